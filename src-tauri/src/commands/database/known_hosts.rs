@@ -0,0 +1,54 @@
+use crate::models::ssh::{ImportKnownHostsResult, KnownHost};
+use crate::state::AppState;
+use std::path::PathBuf;
+use tauri::{Emitter, State};
+
+use super::common::app_result;
+
+/// Get all trusted host keys
+#[tauri::command]
+pub async fn get_known_hosts(state: State<'_, AppState>) -> Result<Vec<KnownHost>, String> {
+    app_result!(state.known_hosts_service.get_known_hosts().await)
+}
+
+/// Remove a trusted host key
+#[tauri::command]
+pub async fn delete_known_host(
+    state: State<'_, AppState>,
+    id: String,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    app_result!(state.known_hosts_service.delete_known_host(&id).await)?;
+    let _ = app_handle.emit("known_host_deleted", &serde_json::json!({ "id": id }));
+    Ok(())
+}
+
+/// Answer a pending `ssh-host-key-verification` event
+#[tauri::command]
+pub async fn respond_host_key_verification(
+    state: State<'_, AppState>,
+    request_id: String,
+    accept: bool,
+) -> Result<(), String> {
+    state
+        .known_hosts_service
+        .respond_host_key_verification(&request_id, accept)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Import host keys from an OpenSSH known_hosts file (defaults to ~/.ssh/known_hosts)
+#[tauri::command]
+pub async fn import_known_hosts(
+    state: State<'_, AppState>,
+    path: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<ImportKnownHostsResult, String> {
+    let result = state
+        .known_hosts_service
+        .import_openssh_known_hosts(path.map(PathBuf::from))
+        .await
+        .map_err(|e| e.to_string())?;
+    let _ = app_handle.emit("known_hosts_imported", &result);
+    Ok(result)
+}
@@ -7,6 +7,9 @@ pub mod auth;
 /// SSH profile and group management commands
 pub mod ssh;
 
+/// Known hosts (host key verification) commands
+pub mod known_hosts;
+
 /// SSH tunnel management commands
 pub mod tunnel;
 
@@ -1,12 +1,16 @@
 use crate::models::ssh::{
     CreateSSHGroupRequest, CreateSSHKeyRequest, CreateSSHProfileRequest, DeleteGroupAction,
-    SSHConfigHost, SSHGroup, SSHKey, SSHProfile, TestSSHConnectionRequest, UpdateSSHGroupRequest,
-    UpdateSSHKeyRequest, UpdateSSHProfileRequest,
+    DeployPublicKeyRequest, DeployPublicKeyResult, ExecExportFormat, GenerateSSHKeyRequest,
+    MultiHostExecEvent, MultiHostExecRequest, MultiHostExecResult, SSHConfigHost, SSHGroup, SSHKey,
+    SSHProfile, TestSSHConnectionRequest, UpdateSSHGroupRequest, UpdateSSHKeyRequest,
+    UpdateSSHProfileRequest,
 };
+use crate::services::ssh::exec;
 use crate::services::ssh_config_parser;
 use crate::state::AppState;
 use serde::Deserialize;
 use tauri::{Emitter, State};
+use tokio::sync::mpsc;
 
 use super::common::app_result;
 
@@ -144,6 +148,20 @@ pub async fn test_ssh_connection(
     app_result!(state.ssh_service.test_ssh_connection(request).await)
 }
 
+/// Answer a pending `ssh-keyboard-interactive` prompt round (None cancels authentication)
+#[tauri::command]
+pub async fn respond_keyboard_interactive(
+    state: State<'_, AppState>,
+    request_id: String,
+    responses: Option<Vec<String>>,
+) -> Result<(), String> {
+    state
+        .keyboard_interactive_service
+        .respond(&request_id, responses)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 /// DTO for delete group action from frontend
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -178,6 +196,19 @@ pub async fn create_ssh_key(
     Ok(key)
 }
 
+/// Generate a new SSH keypair
+#[tauri::command]
+pub async fn generate_ssh_key(
+    state: State<'_, AppState>,
+    request: GenerateSSHKeyRequest,
+    app_handle: tauri::AppHandle,
+) -> Result<SSHKey, String> {
+    let service = state.ssh_key_service.lock().await;
+    let key = app_result!(service.generate_ssh_key(request).await)?;
+    let _ = app_handle.emit("ssh_key_created", &key);
+    Ok(key)
+}
+
 /// Get all SSH keys
 #[tauri::command]
 pub async fn get_ssh_keys(state: State<'_, AppState>) -> Result<Vec<SSHKey>, String> {
@@ -213,6 +244,55 @@ pub async fn delete_ssh_key(
     Ok(())
 }
 
+/// Install a key's public key on a profile's host, like ssh-copy-id
+#[tauri::command]
+pub async fn deploy_public_key(
+    state: State<'_, AppState>,
+    request: DeployPublicKeyRequest,
+    app_handle: tauri::AppHandle,
+) -> Result<DeployPublicKeyResult, String> {
+    let result = app_result!(state.ssh_service.deploy_public_key(request.clone()).await)?;
+    if result.profile_updated {
+        if let Ok(profile) = state.ssh_service.get_ssh_profile(&request.profile_id).await {
+            let _ = app_handle.emit("ssh_profile_updated", &profile);
+        }
+    }
+    Ok(result)
+}
+
+/// Run a command on several profiles or a whole group, streaming output as events
+#[tauri::command]
+pub async fn exec_on_hosts(
+    state: State<'_, AppState>,
+    request: MultiHostExecRequest,
+    app_handle: tauri::AppHandle,
+) -> Result<MultiHostExecResult, String> {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<MultiHostExecEvent>();
+    tokio::spawn(async move {
+        while let Some(event) = receiver.recv().await {
+            let _ = match event {
+                MultiHostExecEvent::Output(output) => {
+                    app_handle.emit("multi-host-exec-output", &output)
+                }
+                MultiHostExecEvent::Finished(finished) => {
+                    app_handle.emit("multi-host-exec-host-finished", &finished)
+                }
+            };
+        }
+    });
+
+    app_result!(state.ssh_service.exec_on_hosts(request, Some(sender)).await)
+}
+
+/// Render a multi-host exec result as JSON or CSV
+#[tauri::command]
+pub async fn export_exec_result(
+    result: MultiHostExecResult,
+    format: ExecExportFormat,
+) -> Result<String, String> {
+    exec::export_result(&result, format).map_err(|e| e.to_string())
+}
+
 /// Count profiles using a specific key
 #[tauri::command]
 pub async fn count_profiles_using_key(
@@ -267,3 +347,14 @@ pub async fn get_ssh_config_hosts(
         Err(e) => Err(e.to_string()),
     }
 }
+
+/// Resolve the effective SSH config settings for a host, like `ssh -G <host>`
+#[tauri::command]
+pub async fn resolve_ssh_config_host(
+    _state: State<'_, AppState>,
+    host: String,
+) -> Result<SSHConfigHost, String> {
+    ssh_config_parser::resolve_ssh_config_host(&host, None)
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -1,5 +1,6 @@
 use crate::models::ssh::{
-    CreateSSHTunnelRequest, SSHTunnel, TunnelStatus, TunnelWithStatus, UpdateSSHTunnelRequest,
+    CreateSSHTunnelRequest, SSHTunnel, TunnelMetrics, TunnelStatusInfo, TunnelWithStatus,
+    UpdateSSHTunnelRequest,
 };
 use crate::state::AppState;
 use tauri::{Emitter, State};
@@ -103,11 +104,35 @@ pub async fn stop_tunnel(
     Ok(())
 }
 
-/// Get tunnel status
+/// Get tunnel status with its restart count and last failure
 #[tauri::command]
 pub async fn get_tunnel_status(
     state: State<'_, AppState>,
     id: String,
-) -> Result<TunnelStatus, String> {
+) -> Result<TunnelStatusInfo, String> {
     tunnel_result!(state.tunnel_service.get_tunnel_status(id).await)
 }
+
+/// Get traffic metrics and active client connections of a running tunnel
+#[tauri::command]
+pub async fn get_tunnel_metrics(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<TunnelMetrics, String> {
+    tunnel_result!(state.tunnel_service.get_tunnel_metrics(id).await)
+}
+
+/// Close one client connection of a running tunnel
+#[tauri::command]
+pub async fn close_tunnel_connection(
+    state: State<'_, AppState>,
+    id: String,
+    connection_id: String,
+) -> Result<(), String> {
+    tunnel_result!(
+        state
+            .tunnel_service
+            .close_tunnel_connection(id, connection_id)
+            .await
+    )
+}
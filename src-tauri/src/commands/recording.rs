@@ -1,4 +1,5 @@
 use crate::models::recording::*;
+use crate::models::terminal::TerminalType;
 use crate::services::recording::*;
 use crate::state::AppState;
 use log::warn;
@@ -12,6 +13,8 @@ pub struct StartRecordingRequest {
     session_name: Option<String>,
     width: Option<u16>,
     height: Option<u16>,
+    #[serde(default)]
+    options: RecordingOptions,
 }
 
 #[tauri::command]
@@ -44,21 +47,35 @@ pub async fn start_recording(
     let cols = request.width.unwrap_or(80);
     let rows = request.height.unwrap_or(24);
 
-    // Determine terminal type - use default for now
-    // In the future, we can get this from terminal info if needed
-    let terminal_type = "Local".to_string();
+    let info = state
+        .terminal_manager
+        .get_terminal_info(request.terminal_id.clone())
+        .await
+        .map_err(|e| e.to_string())?;
+    let terminal_type = match info.config.terminal_type {
+        TerminalType::Local => "Local",
+        TerminalType::SSH | TerminalType::SSHConfig => "SSH",
+    }
+    .to_string();
+    let env = state
+        .terminal_manager
+        .recording_env(&request.terminal_id)
+        .await
+        .map_err(|e| e.to_string())?;
 
     // Create recorder
-    let recorder = SessionRecorder::new(
+    let mut recorder = SessionRecorder::new(
         recording_id.clone(),
         file_path.to_string_lossy().to_string(),
         cols,
         rows,
         Some(name.clone()),
         terminal_type,
+        request.options,
     )
     .await
     .map_err(|e| e.to_string())?;
+    recorder.set_env(env);
 
     recorder.write_header().await.map_err(|e| e.to_string())?;
 
@@ -73,6 +90,34 @@ pub async fn start_recording(
     Ok(recording_id)
 }
 
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddRecordingMarkerRequest {
+    terminal_id: String,
+    label: Option<String>,
+}
+
+/// Add a marker to the terminal's active recording
+#[tauri::command]
+pub async fn add_recording_marker(
+    request: AddRecordingMarkerRequest,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let recorder = state
+        .terminal_manager
+        .recorders
+        .read()
+        .await
+        .get(&request.terminal_id)
+        .cloned()
+        .ok_or_else(|| "No active recording for this terminal".to_string())?;
+
+    recorder
+        .record_marker(request.label.as_deref().unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
+
 #[derive(Debug, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StopRecordingRequest {
@@ -204,6 +249,186 @@ pub async fn export_recording(
     Ok(request.export_path)
 }
 
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditRecordingRequest {
+    recording_id: String,
+    edits: Vec<RecordingEdit>,
+    session_name: Option<String>,
+}
+
+/// Apply edits to a recording, saving the result as a new recording
+#[tauri::command]
+pub async fn edit_recording(
+    request: EditRecordingRequest,
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<SessionRecording, String> {
+    let source = state
+        .database_service
+        .lock()
+        .await
+        .get_session_recording(&request.recording_id)
+        .await
+        .map_err(|e| format!("Failed to get recording: {}", e))?
+        .ok_or_else(|| format!("Recording not found: {}", request.recording_id))?;
+
+    let mut cast = read_cast(&source).await?;
+    for edit in &request.edits {
+        cast.apply(edit).map_err(|e| e.to_string())?;
+    }
+
+    let session_name = request
+        .session_name
+        .unwrap_or_else(|| format!("{} (edited)", source.session_name));
+    let recording =
+        save_derived_recording(&state, &source, &cast, session_name, &[source.id.clone()]).await?;
+
+    let _ = app_handle.emit("recording_saved", &recording);
+
+    Ok(recording)
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConcatRecordingsRequest {
+    recording_ids: Vec<String>,
+    session_name: Option<String>,
+    /// Pause between two recordings, in seconds
+    gap_secs: Option<f64>,
+}
+
+/// Join recordings in the given order into a new recording
+#[tauri::command]
+pub async fn concat_recordings(
+    request: ConcatRecordingsRequest,
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<SessionRecording, String> {
+    if request.recording_ids.len() < 2 {
+        return Err("At least two recordings are needed".to_string());
+    }
+    let gap = request.gap_secs.unwrap_or(1.0);
+    if !gap.is_finite() || gap < 0.0 {
+        return Err("Gap between recordings must not be negative".to_string());
+    }
+
+    let mut sources = Vec::with_capacity(request.recording_ids.len());
+    {
+        let db = state.database_service.lock().await;
+        for recording_id in &request.recording_ids {
+            let source = db
+                .get_session_recording(recording_id)
+                .await
+                .map_err(|e| format!("Failed to get recording: {}", e))?
+                .ok_or_else(|| format!("Recording not found: {}", recording_id))?;
+            sources.push(source);
+        }
+    }
+
+    let mut cast = read_cast(&sources[0]).await?;
+    for source in &sources[1..] {
+        cast.append(read_cast(source).await?, gap);
+    }
+
+    let session_name = request.session_name.unwrap_or_else(|| {
+        sources
+            .iter()
+            .map(|source| source.session_name.as_str())
+            .collect::<Vec<_>>()
+            .join(" + ")
+    });
+    let recording = save_derived_recording(
+        &state,
+        &sources[0],
+        &cast,
+        session_name,
+        &request.recording_ids,
+    )
+    .await?;
+
+    let _ = app_handle.emit("recording_saved", &recording);
+
+    Ok(recording)
+}
+
+async fn read_cast(recording: &SessionRecording) -> Result<Cast, String> {
+    let content = tokio::fs::read_to_string(&recording.file_path)
+        .await
+        .map_err(|e| format!("Failed to read recording file: {}", e))?;
+    Cast::parse(&content).map_err(|e| format!("{}: {}", recording.session_name, e))
+}
+
+/// Write the cast next to the source file and save it as a new recording. The file
+/// is removed again when the recording can't be saved.
+async fn save_derived_recording(
+    state: &AppState,
+    source: &SessionRecording,
+    cast: &Cast,
+    session_name: String,
+    source_ids: &[String],
+) -> Result<SessionRecording, String> {
+    let recording_id = uuid::Uuid::new_v4().to_string();
+    let file_path = std::path::Path::new(&source.file_path)
+        .with_file_name(format!("{}.cast", recording_id))
+        .to_string_lossy()
+        .to_string();
+
+    let content = cast.render().map_err(|e| e.to_string())?;
+    tokio::fs::write(&file_path, &content)
+        .await
+        .map_err(|e| format!("Failed to write recording file: {}", e))?;
+
+    let duration_ms = (cast.duration() * 1000.0).round() as i64;
+    let (width, height) = cast.size();
+    let now = chrono::Utc::now();
+
+    let recording = SessionRecording {
+        id: recording_id,
+        terminal_id: source.terminal_id.clone(),
+        session_name,
+        terminal_type: source.terminal_type.clone(),
+        started_at: source.started_at,
+        ended_at: Some(source.started_at + chrono::Duration::milliseconds(duration_ms)),
+        duration_ms: Some(duration_ms),
+        file_path,
+        file_size: content.len() as i64,
+        width,
+        height,
+        metadata: Some(derived_metadata(source.metadata.as_deref(), source_ids)),
+        created_at: now,
+    };
+
+    let saved = state
+        .database_service
+        .lock()
+        .await
+        .save_session_recording(&recording)
+        .await;
+    if let Err(e) = saved {
+        if let Err(remove_error) = tokio::fs::remove_file(&recording.file_path).await {
+            warn!("Failed to remove unsaved recording file: {}", remove_error);
+        }
+        return Err(format!("Failed to save recording to database: {}", e));
+    }
+
+    Ok(recording)
+}
+
+/// Metadata of the source recording with the recordings it was derived from added
+fn derived_metadata(source_metadata: Option<&str>, source_ids: &[String]) -> String {
+    let mut metadata = source_metadata
+        .and_then(|metadata| {
+            serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(metadata).ok()
+        })
+        .unwrap_or_default();
+    metadata.insert(
+        "sourceRecordings".to_string(),
+        serde_json::json!(source_ids),
+    );
+    serde_json::Value::Object(metadata).to_string()
+}
+
 #[derive(Debug, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ReadCastFileRequest {
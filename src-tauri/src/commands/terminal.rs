@@ -1,9 +1,12 @@
 use crate::error::AppError;
 use crate::models::terminal::{
-    CloseTerminalRequest, CreateLocalTerminalRequest, CreateSshConfigTerminalRequest,
-    CreateSshTerminalRequest, CreateTerminalRequest, CreateTerminalResponse,
-    GetTerminalInfoRequest, LocalConfig, ResizeTerminalRequest, TerminalConfig, TerminalInfo,
-    TerminalType, WriteBatchTerminalRequest, WriteTerminalRequest,
+    AttachPersistentSessionRequest, BroadcastGroup, BroadcastMembersRequest, BroadcastWriteFailure,
+    CloseTerminalRequest, CreateBroadcastGroupRequest, CreateLocalTerminalRequest,
+    CreateSshConfigTerminalRequest, CreateSshTerminalRequest, CreateTerminalRequest,
+    CreateTerminalResponse, DumpScrollbackRequest, DumpScrollbackResult, GetTerminalInfoRequest,
+    LocalConfig, PauseBroadcastMemberRequest, PersistentSessionInfo, ResizeTerminalRequest,
+    ScrollbackConfig, ScrollbackMatch, ScrollbackStats, SearchScrollbackRequest, SessionLogConfig,
+    TerminalConfig, TerminalInfo, TerminalType, WriteBatchTerminalRequest, WriteTerminalRequest,
 };
 use crate::state::AppState;
 use tauri::{AppHandle, State};
@@ -22,11 +25,13 @@ pub async fn create_terminal(
             working_dir: request.working_dir,
             env_vars: None,
             command: request.command,
+            persistent: request.persistent,
         }),
         ssh_profile_id: None,
         ssh_config_host: None,
         ssh_config_password: None,
         terminal_profile_id: request.terminal_profile_id,
+        reconnect_policy: None,
     };
 
     let create_request = CreateTerminalRequest {
@@ -59,6 +64,7 @@ pub async fn create_ssh_terminal(
         ssh_config_host: None,
         ssh_config_password: None,
         terminal_profile_id: None,
+        reconnect_policy: request.reconnect_policy,
     };
 
     let terminal_request = CreateTerminalRequest {
@@ -109,6 +115,7 @@ pub async fn create_ssh_config_terminal(
         ssh_config_host: Some(host.clone()),
         ssh_config_password: request.password,
         terminal_profile_id: None,
+        reconnect_policy: request.reconnect_policy,
     };
 
     let title = request.title.unwrap_or_else(|| {
@@ -130,12 +137,12 @@ pub async fn create_ssh_config_terminal(
         .await
 }
 
-/// Write data to a terminal
+/// Write data to a terminal (and its broadcast group members)
 #[tauri::command]
 pub async fn write_to_terminal(
     request: WriteTerminalRequest,
     app_state: State<'_, AppState>,
-) -> Result<(), AppError> {
+) -> Result<Vec<BroadcastWriteFailure>, AppError> {
     app_state.terminal_manager.write_to_terminal(request).await
 }
 
@@ -144,14 +151,17 @@ pub async fn write_to_terminal(
 pub async fn write_batch_to_terminal(
     request: WriteBatchTerminalRequest,
     app_state: State<'_, AppState>,
-) -> Result<(), AppError> {
+) -> Result<Vec<BroadcastWriteFailure>, AppError> {
+    let mut failures = Vec::new();
     for write_request in request.requests {
-        app_state
-            .terminal_manager
-            .write_to_terminal(write_request)
-            .await?;
+        failures.extend(
+            app_state
+                .terminal_manager
+                .write_to_terminal(write_request)
+                .await?,
+        );
     }
-    Ok(())
+    Ok(failures)
 }
 
 /// Resize a terminal
@@ -175,6 +185,74 @@ pub async fn close_terminal(
         .await
 }
 
+/// Close a persistent terminal but keep its shell running in the session daemon
+#[tauri::command]
+pub async fn detach_terminal(
+    terminal_id: String,
+    app_state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    app_state
+        .terminal_manager
+        .detach_terminal(terminal_id)
+        .await
+}
+
+/// List the shells owned by the session daemon
+#[tauri::command]
+pub async fn list_persistent_sessions(
+    app_state: State<'_, AppState>,
+) -> Result<Vec<PersistentSessionInfo>, AppError> {
+    #[cfg(unix)]
+    return app_state.terminal_manager.list_persistent_sessions().await;
+    #[cfg(not(unix))]
+    {
+        let _ = app_state;
+        Err(persistent_sessions_unsupported())
+    }
+}
+
+/// Reattach to a shell owned by the session daemon
+#[tauri::command]
+pub async fn attach_persistent_session(
+    request: AttachPersistentSessionRequest,
+    app_state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<CreateTerminalResponse, AppError> {
+    #[cfg(unix)]
+    return app_state
+        .terminal_manager
+        .attach_persistent_session(request, Some(app_handle))
+        .await;
+    #[cfg(not(unix))]
+    {
+        let _ = (request, app_state, app_handle);
+        Err(persistent_sessions_unsupported())
+    }
+}
+
+/// Kill a shell owned by the session daemon
+#[tauri::command]
+pub async fn kill_persistent_session(
+    session_id: String,
+    app_state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    #[cfg(unix)]
+    return app_state
+        .terminal_manager
+        .kill_persistent_session(session_id)
+        .await;
+    #[cfg(not(unix))]
+    {
+        let _ = (session_id, app_state);
+        Err(persistent_sessions_unsupported())
+    }
+}
+
+#[cfg(not(unix))]
+fn persistent_sessions_unsupported() -> AppError {
+    AppError::terminal_error("Persistent sessions are only supported on unix platforms")
+}
+
 /// Get information about a specific terminal
 #[tauri::command]
 pub async fn get_terminal_info(
@@ -192,3 +270,145 @@ pub async fn get_terminal_info(
 pub async fn list_terminals(app_state: State<'_, AppState>) -> Result<Vec<TerminalInfo>, AppError> {
     app_state.terminal_manager.list_terminals().await
 }
+
+/// Create an input broadcast group of open terminals
+#[tauri::command]
+pub async fn create_broadcast_group(
+    request: CreateBroadcastGroupRequest,
+    app_state: State<'_, AppState>,
+) -> Result<BroadcastGroup, AppError> {
+    app_state
+        .terminal_manager
+        .create_broadcast_group(request)
+        .await
+}
+
+/// Delete an input broadcast group
+#[tauri::command]
+pub async fn delete_broadcast_group(
+    name: String,
+    app_state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    app_state
+        .terminal_manager
+        .delete_broadcast_group(&name)
+        .await
+}
+
+/// Add terminals to an input broadcast group
+#[tauri::command]
+pub async fn add_broadcast_members(
+    request: BroadcastMembersRequest,
+    app_state: State<'_, AppState>,
+) -> Result<BroadcastGroup, AppError> {
+    app_state
+        .terminal_manager
+        .add_broadcast_members(request)
+        .await
+}
+
+/// Remove terminals from an input broadcast group
+#[tauri::command]
+pub async fn remove_broadcast_members(
+    request: BroadcastMembersRequest,
+    app_state: State<'_, AppState>,
+) -> Result<BroadcastGroup, AppError> {
+    app_state
+        .terminal_manager
+        .remove_broadcast_members(request)
+        .await
+}
+
+/// Pause or resume a member of an input broadcast group
+#[tauri::command]
+pub async fn pause_broadcast_member(
+    request: PauseBroadcastMemberRequest,
+    app_state: State<'_, AppState>,
+) -> Result<BroadcastGroup, AppError> {
+    app_state
+        .terminal_manager
+        .set_broadcast_member_paused(&request.name, &request.terminal_id, request.paused)
+        .await
+}
+
+/// List the input broadcast groups
+#[tauri::command]
+pub async fn list_broadcast_groups(
+    app_state: State<'_, AppState>,
+) -> Result<Vec<BroadcastGroup>, AppError> {
+    Ok(app_state.terminal_manager.list_broadcast_groups().await)
+}
+
+/// Search a terminal's scrollback by regex
+#[tauri::command]
+pub async fn search_terminal_scrollback(
+    request: SearchScrollbackRequest,
+    app_state: State<'_, AppState>,
+) -> Result<Vec<ScrollbackMatch>, AppError> {
+    app_state.terminal_manager.search_scrollback(request).await
+}
+
+/// Save a range of a terminal's scrollback to a file
+#[tauri::command]
+pub async fn dump_terminal_scrollback(
+    request: DumpScrollbackRequest,
+    app_state: State<'_, AppState>,
+) -> Result<DumpScrollbackResult, AppError> {
+    app_state.terminal_manager.dump_scrollback(request).await
+}
+
+/// Get the size of a terminal's scrollback
+#[tauri::command]
+pub async fn get_terminal_scrollback_stats(
+    terminal_id: String,
+    app_state: State<'_, AppState>,
+) -> Result<ScrollbackStats, AppError> {
+    app_state
+        .terminal_manager
+        .get_scrollback_stats(terminal_id)
+        .await
+}
+
+/// Get the scrollback limits
+#[tauri::command]
+pub async fn get_scrollback_config(
+    app_state: State<'_, AppState>,
+) -> Result<ScrollbackConfig, AppError> {
+    Ok(app_state.terminal_manager.get_scrollback_config().await)
+}
+
+/// Set the scrollback limits of new and open terminals
+#[tauri::command]
+pub async fn set_scrollback_config(
+    config: ScrollbackConfig,
+    app_state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    app_state
+        .terminal_manager
+        .set_scrollback_config(config)
+        .await
+}
+
+/// Get the session logging of terminals opened from ~/.ssh/config hosts
+#[tauri::command]
+pub async fn get_ssh_config_session_log(
+    app_state: State<'_, AppState>,
+) -> Result<Option<SessionLogConfig>, AppError> {
+    Ok(app_state
+        .terminal_manager
+        .get_ssh_config_session_log()
+        .await)
+}
+
+/// Set the session logging of terminals opened from ~/.ssh/config hosts
+#[tauri::command]
+pub async fn set_ssh_config_session_log(
+    config: Option<SessionLogConfig>,
+    app_state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    app_state
+        .terminal_manager
+        .set_ssh_config_session_log(config)
+        .await;
+    Ok(())
+}
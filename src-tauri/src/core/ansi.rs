@@ -0,0 +1,152 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+/// Longest line kept before it is split, guards against output without newlines
+const MAX_LINE_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum State {
+    #[default]
+    Ground,
+    Escape,
+    /// Control sequence, ends with a byte in 0x40..=0x7e
+    Csi,
+    /// OSC/DCS/APC style string, ends with BEL or ST (ESC \)
+    String,
+    StringEscape,
+    /// Character set designation, followed by one more byte
+    Charset,
+}
+
+/// Splits terminal output into plain text lines, dropping escape sequences and
+/// control characters. State is kept between calls so sequences may span chunks.
+#[derive(Debug, Default)]
+pub struct AnsiStripper {
+    state: State,
+    line: Vec<u8>,
+    pending_cr: bool,
+}
+
+impl AnsiStripper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed output and return the lines it completed
+    pub fn push(&mut self, data: &[u8]) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        for &byte in data {
+            match self.state {
+                State::Ground => self.ground(byte, &mut lines),
+                State::Escape => {
+                    self.state = match byte {
+                        b'[' => State::Csi,
+                        b']' | b'P' | b'X' | b'^' | b'_' => State::String,
+                        b'(' | b')' | b'*' | b'+' => State::Charset,
+                        _ => State::Ground,
+                    };
+                }
+                State::Csi => {
+                    if (0x40..=0x7e).contains(&byte) {
+                        self.state = State::Ground;
+                    }
+                }
+                State::String => match byte {
+                    0x07 => self.state = State::Ground,
+                    0x1b => self.state = State::StringEscape,
+                    _ => {}
+                },
+                State::StringEscape => {
+                    self.state = if byte == b'\\' {
+                        State::Ground
+                    } else {
+                        State::String
+                    };
+                }
+                State::Charset => self.state = State::Ground,
+            }
+        }
+
+        lines
+    }
+
+    /// Text of the line that has not been terminated yet
+    pub fn current_line(&self) -> String {
+        String::from_utf8_lossy(&self.line).into_owned()
+    }
+
+    fn ground(&mut self, byte: u8, lines: &mut Vec<String>) {
+        if self.pending_cr {
+            self.pending_cr = false;
+            // A carriage return not followed by a newline redraws the line
+            if byte != b'\n' {
+                self.line.clear();
+            }
+        }
+
+        match byte {
+            0x1b => self.state = State::Escape,
+            b'\n' => lines.push(self.take_line()),
+            b'\r' => self.pending_cr = true,
+            0x08 => {
+                // Remove a whole UTF-8 character
+                while let Some(last) = self.line.pop() {
+                    if last & 0xc0 != 0x80 {
+                        break;
+                    }
+                }
+            }
+            b'\t' => self.line.push(byte),
+            0x00..=0x1f | 0x7f => {}
+            _ => {
+                self.line.push(byte);
+                if self.line.len() >= MAX_LINE_BYTES {
+                    lines.push(self.take_line());
+                }
+            }
+        }
+    }
+
+    fn take_line(&mut self) -> String {
+        let line = String::from_utf8_lossy(&self.line).into_owned();
+        self.line.clear();
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strips_escape_sequences_across_chunks() {
+        let mut stripper = AnsiStripper::new();
+        let mut lines = stripper.push(b"\x1b[1;32mgreen\x1b[");
+        lines.extend(stripper.push(b"0m text\r\n\x1b]0;user@host: ~\x07$ ls\r\n"));
+        assert_eq!(lines, vec!["green text", "$ ls"]);
+    }
+
+    #[test]
+    fn test_handles_carriage_return_and_backspace() {
+        let mut stripper = AnsiStripper::new();
+        let lines = stripper.push(b"progress 10%\rprogress 100%\r\nabc\x08d");
+        assert_eq!(lines, vec!["progress 100%"]);
+        assert_eq!(stripper.current_line(), "abd");
+    }
+}
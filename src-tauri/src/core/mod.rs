@@ -16,8 +16,11 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+pub mod ansi;
 pub mod auth_session_manager;
 pub mod graphics;
 pub mod proxy;
+#[cfg(unix)]
+pub mod session_daemon;
 pub mod terminal;
 pub mod title_detector;
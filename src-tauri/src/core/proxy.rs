@@ -17,7 +17,28 @@
  */
 
 use crate::models::ssh::profile::{ProxyConfig, ProxyType};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
 use russh_config::Stream;
+use std::net::IpAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Upper bound for the HTTP proxy response head, to not read forever from a broken proxy
+const MAX_HTTP_RESPONSE_HEAD: usize = 16 * 1024;
+
+const SOCKS4_VERSION: u8 = 0x04;
+const SOCKS4_CMD_CONNECT: u8 = 0x01;
+const SOCKS4_REQUEST_GRANTED: u8 = 0x5a;
+
+const SOCKS5_VERSION: u8 = 0x05;
+const SOCKS5_AUTH_NONE: u8 = 0x00;
+const SOCKS5_AUTH_PASSWORD: u8 = 0x02;
+const SOCKS5_AUTH_NO_ACCEPTABLE: u8 = 0xff;
+const SOCKS5_PASSWORD_AUTH_VERSION: u8 = 0x01;
+const SOCKS5_CMD_CONNECT: u8 = 0x01;
+const SOCKS5_ATYP_IPV4: u8 = 0x01;
+const SOCKS5_ATYP_DOMAIN: u8 = 0x03;
+const SOCKS5_ATYP_IPV6: u8 = 0x04;
 
 /// Error types for proxy connections
 #[derive(Debug, thiserror::Error)]
@@ -26,6 +47,10 @@ pub enum ProxyError {
     ConnectionFailed(String),
     #[error("Invalid proxy configuration: {0}")]
     InvalidConfig(String),
+    #[error("Proxy authentication failed: {0}")]
+    AuthenticationFailed(String),
+    #[error("Proxy protocol error: {0}")]
+    Protocol(String),
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
@@ -55,73 +80,642 @@ pub fn validate_proxy_config(proxy_config: &ProxyConfig) -> Result<(), ProxyErro
     Ok(())
 }
 
-/// Create a proxy command string for russh-config
-pub fn create_proxy_command(
+/// Connect to the target through the proxy. The returned stream is past the proxy
+/// handshake and carries the raw connection to `target_host:target_port`.
+pub async fn create_proxy_stream(
     proxy_config: &ProxyConfig,
     target_host: &str,
     target_port: u16,
-) -> Result<String, ProxyError> {
+) -> Result<TcpStream, ProxyError> {
     validate_proxy_config(proxy_config)?;
 
+    let mut stream = TcpStream::connect((proxy_config.host.as_str(), proxy_config.port))
+        .await
+        .map_err(|e| {
+            ProxyError::ConnectionFailed(format!(
+                "{}:{}: {}",
+                proxy_config.host, proxy_config.port, e
+            ))
+        })?;
+
     match proxy_config.proxy_type {
-        ProxyType::Http => create_http_proxy_command(proxy_config, target_host, target_port),
-        ProxyType::Socks4 => create_socks4_proxy_command(proxy_config, target_host, target_port),
-        ProxyType::Socks5 => create_socks5_proxy_command(proxy_config, target_host, target_port),
+        ProxyType::Http => {
+            http_connect(&mut stream, proxy_config, target_host, target_port).await?
+        }
+        ProxyType::Socks4 => {
+            socks4_connect(&mut stream, proxy_config, target_host, target_port).await?
+        }
+        ProxyType::Socks5 => {
+            socks5_connect(&mut stream, proxy_config, target_host, target_port).await?
+        }
     }
+
+    Ok(stream)
 }
 
-/// Create a Stream for proxy connection using russh-config
-pub async fn create_proxy_stream(
-    proxy_config: &ProxyConfig,
+/// Expand the `%h`, `%p`, `%r` and `%%` tokens of an OpenSSH ProxyCommand
+pub fn expand_proxy_command(
+    template: &str,
+    target_host: &str,
+    target_port: u16,
+    username: &str,
+) -> String {
+    let mut command = String::with_capacity(template.len());
+    let mut chars = template.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            command.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('h') => command.push_str(target_host),
+            Some('p') => command.push_str(&target_port.to_string()),
+            Some('r') => command.push_str(username),
+            Some('%') => command.push('%'),
+            Some(other) => {
+                command.push('%');
+                command.push(other);
+            }
+            None => command.push('%'),
+        }
+    }
+
+    command
+}
+
+/// Run a ProxyCommand through the shell and use its stdin/stdout as the transport
+pub async fn create_proxy_command_stream(
+    template: &str,
     target_host: &str,
     target_port: u16,
+    username: &str,
 ) -> Result<Stream, ProxyError> {
-    let command = create_proxy_command(proxy_config, target_host, target_port)?;
+    let command = expand_proxy_command(template, target_host, target_port, username);
+    if command.trim().is_empty() {
+        return Err(ProxyError::InvalidConfig(
+            "ProxyCommand cannot be empty".to_string(),
+        ));
+    }
 
-    Stream::proxy_command(&command, &[])
-        .await
-        .map_err(|e| ProxyError::ConnectionFailed(format!("Proxy command failed: {}", e)))
+    #[cfg(unix)]
+    let result = Stream::proxy_command("sh", &["-c", &format!("exec {}", command)]).await;
+    #[cfg(windows)]
+    let result = Stream::proxy_command("cmd", &["/C", &command]).await;
+
+    result.map_err(|e| {
+        ProxyError::ConnectionFailed(format!("ProxyCommand '{}' failed: {}", command, e))
+    })
 }
 
-/// Create HTTP CONNECT proxy command
-fn create_http_proxy_command(
+/// HTTP CONNECT handshake, with Basic authentication when a username is set
+async fn http_connect(
+    stream: &mut TcpStream,
     proxy_config: &ProxyConfig,
     target_host: &str,
     target_port: u16,
-) -> Result<String, ProxyError> {
-    if let (Some(username), Some(password)) = (&proxy_config.username, &proxy_config.password) {
-        Ok(format!(
-            "socat - PROXY:{}:{},proxyport={},proxyauth={}:{}",
-            proxy_config.host, target_host, proxy_config.port, username, password
-        ))
+) -> Result<(), ProxyError> {
+    let authority = if target_host.contains(':') {
+        format!("[{}]:{}", target_host, target_port)
     } else {
-        Ok(format!(
-            "nc -X connect -x {}:{} {} {}",
-            proxy_config.host, proxy_config.port, target_host, target_port
-        ))
+        format!("{}:{}", target_host, target_port)
+    };
+
+    let mut request = format!("CONNECT {} HTTP/1.1\r\nHost: {}\r\n", authority, authority);
+    if let Some(username) = &proxy_config.username {
+        let credentials = format!(
+            "{}:{}",
+            username,
+            proxy_config.password.as_deref().unwrap_or_default()
+        );
+        request.push_str(&format!(
+            "Proxy-Authorization: Basic {}\r\n",
+            STANDARD.encode(credentials)
+        ));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    let head = read_http_response_head(stream).await?;
+    let status_line = head.lines().next().unwrap_or_default().to_string();
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok());
+
+    match status {
+        Some(200..=299) => Ok(()),
+        Some(407) => Err(ProxyError::AuthenticationFailed(status_line)),
+        Some(_) => Err(ProxyError::ConnectionFailed(format!(
+            "HTTP proxy refused CONNECT to {}: {}",
+            authority, status_line
+        ))),
+        None => Err(ProxyError::Protocol(format!(
+            "Malformed HTTP proxy response: {}",
+            status_line
+        ))),
+    }
+}
+
+/// Read the response head one byte at a time so no tunnelled data is consumed
+async fn read_http_response_head(stream: &mut TcpStream) -> Result<String, ProxyError> {
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_HTTP_RESPONSE_HEAD {
+            return Err(ProxyError::Protocol(
+                "HTTP proxy response head is too large".to_string(),
+            ));
+        }
+        stream.read_exact(&mut byte).await?;
+        head.push(byte[0]);
     }
+
+    Ok(String::from_utf8_lossy(&head).into_owned())
 }
 
-/// Create SOCKS4 proxy command
-fn create_socks4_proxy_command(
+/// SOCKS4 handshake. Host names are sent as SOCKS4a so the proxy resolves them.
+async fn socks4_connect(
+    stream: &mut TcpStream,
     proxy_config: &ProxyConfig,
     target_host: &str,
     target_port: u16,
-) -> Result<String, ProxyError> {
-    Ok(format!(
-        "nc -X 4 -x {}:{} {} {}",
-        proxy_config.host, proxy_config.port, target_host, target_port
-    ))
+) -> Result<(), ProxyError> {
+    let mut request = vec![SOCKS4_VERSION, SOCKS4_CMD_CONNECT];
+    request.extend_from_slice(&target_port.to_be_bytes());
+
+    let remote_name = match target_host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            request.extend_from_slice(&ip.octets());
+            None
+        }
+        Ok(IpAddr::V6(_)) => {
+            return Err(ProxyError::InvalidConfig(
+                "SOCKS4 proxies do not support IPv6 targets".to_string(),
+            ))
+        }
+        Err(_) => {
+            // SOCKS4a marker address 0.0.0.x with x != 0
+            request.extend_from_slice(&[0, 0, 0, 1]);
+            Some(target_host)
+        }
+    };
+
+    if let Some(username) = &proxy_config.username {
+        request.extend_from_slice(username.as_bytes());
+    }
+    request.push(0);
+    if let Some(name) = remote_name {
+        request.extend_from_slice(name.as_bytes());
+        request.push(0);
+    }
+    stream.write_all(&request).await?;
+
+    let mut reply = [0u8; 8];
+    stream.read_exact(&mut reply).await?;
+
+    match reply[1] {
+        SOCKS4_REQUEST_GRANTED => Ok(()),
+        0x5c | 0x5d => Err(ProxyError::AuthenticationFailed(
+            "SOCKS4 proxy could not verify the user id".to_string(),
+        )),
+        code => Err(ProxyError::ConnectionFailed(format!(
+            "SOCKS4 proxy rejected connection to {}:{} (code 0x{:02x})",
+            target_host, target_port, code
+        ))),
+    }
 }
 
-/// Create SOCKS5 proxy command
-fn create_socks5_proxy_command(
+/// SOCKS5 handshake with optional username/password authentication (RFC 1929).
+/// Host names are sent as domain addresses so the proxy resolves them.
+async fn socks5_connect(
+    stream: &mut TcpStream,
     proxy_config: &ProxyConfig,
     target_host: &str,
     target_port: u16,
-) -> Result<String, ProxyError> {
-    Ok(format!(
-        "nc -X 5 -x {}:{} {} {}",
-        proxy_config.host, proxy_config.port, target_host, target_port
-    ))
+) -> Result<(), ProxyError> {
+    let greeting: &[u8] = if proxy_config.username.is_some() {
+        &[SOCKS5_VERSION, 2, SOCKS5_AUTH_NONE, SOCKS5_AUTH_PASSWORD]
+    } else {
+        &[SOCKS5_VERSION, 1, SOCKS5_AUTH_NONE]
+    };
+    stream.write_all(greeting).await?;
+
+    let mut choice = [0u8; 2];
+    stream.read_exact(&mut choice).await?;
+    if choice[0] != SOCKS5_VERSION {
+        return Err(ProxyError::Protocol(format!(
+            "Unexpected SOCKS version {} in proxy reply",
+            choice[0]
+        )));
+    }
+
+    match choice[1] {
+        SOCKS5_AUTH_NONE => {}
+        SOCKS5_AUTH_PASSWORD => socks5_password_auth(stream, proxy_config).await?,
+        SOCKS5_AUTH_NO_ACCEPTABLE => {
+            return Err(ProxyError::AuthenticationFailed(
+                "SOCKS5 proxy accepted none of the offered authentication methods".to_string(),
+            ))
+        }
+        method => {
+            return Err(ProxyError::Protocol(format!(
+                "SOCKS5 proxy selected unsupported authentication method 0x{:02x}",
+                method
+            )))
+        }
+    }
+
+    let mut request = vec![SOCKS5_VERSION, SOCKS5_CMD_CONNECT, 0];
+    match target_host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            request.push(SOCKS5_ATYP_IPV4);
+            request.extend_from_slice(&ip.octets());
+        }
+        Ok(IpAddr::V6(ip)) => {
+            request.push(SOCKS5_ATYP_IPV6);
+            request.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            let name = target_host.as_bytes();
+            if name.len() > u8::MAX as usize {
+                return Err(ProxyError::InvalidConfig(
+                    "Target host name is too long for SOCKS5".to_string(),
+                ));
+            }
+            request.push(SOCKS5_ATYP_DOMAIN);
+            request.push(name.len() as u8);
+            request.extend_from_slice(name);
+        }
+    }
+    request.extend_from_slice(&target_port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await?;
+    if reply[1] != 0 {
+        return Err(ProxyError::ConnectionFailed(format!(
+            "SOCKS5 proxy rejected connection to {}:{}: {}",
+            target_host,
+            target_port,
+            socks5_reply_message(reply[1])
+        )));
+    }
+
+    // Skip the bound address and port
+    let address_len = match reply[3] {
+        SOCKS5_ATYP_IPV4 => 4,
+        SOCKS5_ATYP_IPV6 => 16,
+        SOCKS5_ATYP_DOMAIN => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len).await?;
+            len[0] as usize
+        }
+        atyp => {
+            return Err(ProxyError::Protocol(format!(
+                "SOCKS5 proxy replied with unknown address type 0x{:02x}",
+                atyp
+            )))
+        }
+    };
+    let mut bound = vec![0u8; address_len + 2];
+    stream.read_exact(&mut bound).await?;
+
+    Ok(())
+}
+
+/// Username/password sub-negotiation (RFC 1929)
+async fn socks5_password_auth(
+    stream: &mut TcpStream,
+    proxy_config: &ProxyConfig,
+) -> Result<(), ProxyError> {
+    let username = proxy_config.username.as_deref().unwrap_or_default();
+    let password = proxy_config.password.as_deref().unwrap_or_default();
+    if username.len() > u8::MAX as usize || password.len() > u8::MAX as usize {
+        return Err(ProxyError::InvalidConfig(
+            "SOCKS5 username and password must be at most 255 bytes".to_string(),
+        ));
+    }
+
+    let mut request = vec![SOCKS5_PASSWORD_AUTH_VERSION, username.len() as u8];
+    request.extend_from_slice(username.as_bytes());
+    request.push(password.len() as u8);
+    request.extend_from_slice(password.as_bytes());
+    stream.write_all(&request).await?;
+
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).await?;
+    if reply[1] != 0 {
+        return Err(ProxyError::AuthenticationFailed(
+            "SOCKS5 proxy rejected the username or password".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn socks5_reply_message(code: u8) -> &'static str {
+    match code {
+        0x01 => "general failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_expand_proxy_command() {
+        assert_eq!(
+            expand_proxy_command("ssh -W %h:%p -l %r bastion", "10.0.0.5", 2222, "deploy"),
+            "ssh -W 10.0.0.5:2222 -l deploy bastion"
+        );
+        assert_eq!(
+            expand_proxy_command("printf '100%%' %x %", "h", 22, "u"),
+            "printf '100%' %x %"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_proxy_command_stream_uses_command_io() {
+        let mut stream = create_proxy_command_stream("cat", "example.com", 22, "root")
+            .await
+            .unwrap();
+        stream.write_all(b"SSH-2.0-test\r\n").await.unwrap();
+        let mut echoed = [0u8; 14];
+        stream.read_exact(&mut echoed).await.unwrap();
+        assert_eq!(&echoed, b"SSH-2.0-test\r\n");
+    }
+
+    fn proxy_config(proxy_type: ProxyType, port: u16, auth: Option<(&str, &str)>) -> ProxyConfig {
+        ProxyConfig {
+            proxy_type,
+            host: "127.0.0.1".to_string(),
+            port,
+            username: auth.map(|(user, _)| user.to_string()),
+            password: auth.map(|(_, pass)| pass.to_string()),
+        }
+    }
+
+    /// Start a stand-in proxy: `handshake` checks the request and answers it, after which
+    /// the connection echoes everything back like a tunnelled target would.
+    async fn spawn_stand_in_proxy<F, Fut>(handshake: F) -> u16
+    where
+        F: FnOnce(TcpStream) -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = Option<TcpStream>> + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            if let Some(mut socket) = handshake(socket).await {
+                let mut buf = [0u8; 64];
+                while let Ok(n) = socket.read(&mut buf).await {
+                    if n == 0 || socket.write_all(&buf[..n]).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        port
+    }
+
+    async fn assert_tunnel_works(stream: &mut TcpStream) {
+        stream.write_all(b"SSH-2.0-test\r\n").await.unwrap();
+        let mut echoed = [0u8; 14];
+        stream.read_exact(&mut echoed).await.unwrap();
+        assert_eq!(&echoed, b"SSH-2.0-test\r\n");
+    }
+
+    async fn read_until_nul(socket: &mut TcpStream) -> Vec<u8> {
+        let mut out = Vec::new();
+        loop {
+            let byte = socket.read_u8().await.unwrap();
+            if byte == 0 {
+                return out;
+            }
+            out.push(byte);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_connect_with_basic_auth() {
+        let port = spawn_stand_in_proxy(|mut socket| async move {
+            let head = read_http_response_head(&mut socket).await.unwrap();
+            assert!(head.starts_with("CONNECT example.com:22 HTTP/1.1\r\n"));
+            // base64("alice:s3cret")
+            assert!(head.contains("Proxy-Authorization: Basic YWxpY2U6czNjcmV0\r\n"));
+            socket
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                .await
+                .unwrap();
+            Some(socket)
+        })
+        .await;
+
+        let config = proxy_config(ProxyType::Http, port, Some(("alice", "s3cret")));
+        let mut stream = create_proxy_stream(&config, "example.com", 22)
+            .await
+            .unwrap();
+        assert_tunnel_works(&mut stream).await;
+    }
+
+    #[tokio::test]
+    async fn test_http_connect_auth_required() {
+        let port = spawn_stand_in_proxy(|mut socket| async move {
+            read_http_response_head(&mut socket).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n")
+                .await
+                .unwrap();
+            None
+        })
+        .await;
+
+        let config = proxy_config(ProxyType::Http, port, None);
+        let result = create_proxy_stream(&config, "example.com", 22).await;
+        assert!(matches!(result, Err(ProxyError::AuthenticationFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_socks4_ipv4_target() {
+        let port = spawn_stand_in_proxy(|mut socket| async move {
+            let mut header = [0u8; 8];
+            socket.read_exact(&mut header).await.unwrap();
+            assert_eq!(header, [4, 1, 0, 22, 10, 0, 0, 5]);
+            assert_eq!(read_until_nul(&mut socket).await, b"");
+            socket
+                .write_all(&[0, 0x5a, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+            Some(socket)
+        })
+        .await;
+
+        let config = proxy_config(ProxyType::Socks4, port, None);
+        let mut stream = create_proxy_stream(&config, "10.0.0.5", 22).await.unwrap();
+        assert_tunnel_works(&mut stream).await;
+    }
+
+    #[tokio::test]
+    async fn test_socks4a_sends_host_name() {
+        let port = spawn_stand_in_proxy(|mut socket| async move {
+            let mut header = [0u8; 8];
+            socket.read_exact(&mut header).await.unwrap();
+            assert_eq!(header, [4, 1, 0x08, 0xae, 0, 0, 0, 1]);
+            assert_eq!(read_until_nul(&mut socket).await, b"bob");
+            assert_eq!(read_until_nul(&mut socket).await, b"internal.example");
+            socket
+                .write_all(&[0, 0x5a, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+            Some(socket)
+        })
+        .await;
+
+        let config = proxy_config(ProxyType::Socks4, port, Some(("bob", "")));
+        let mut stream = create_proxy_stream(&config, "internal.example", 2222)
+            .await
+            .unwrap();
+        assert_tunnel_works(&mut stream).await;
+    }
+
+    #[tokio::test]
+    async fn test_socks4_rejected() {
+        let port = spawn_stand_in_proxy(|mut socket| async move {
+            let mut header = [0u8; 8];
+            socket.read_exact(&mut header).await.unwrap();
+            read_until_nul(&mut socket).await;
+            read_until_nul(&mut socket).await;
+            socket
+                .write_all(&[0, 0x5b, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+            None
+        })
+        .await;
+
+        let config = proxy_config(ProxyType::Socks4, port, None);
+        let result = create_proxy_stream(&config, "internal.example", 22).await;
+        assert!(matches!(result, Err(ProxyError::ConnectionFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_socks5_password_auth_and_remote_dns() {
+        let port = spawn_stand_in_proxy(|mut socket| async move {
+            let mut greeting = [0u8; 4];
+            socket.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting, [5, 2, 0, 2]);
+            socket.write_all(&[5, 2]).await.unwrap();
+
+            let mut auth = [0u8; 13];
+            socket.read_exact(&mut auth).await.unwrap();
+            assert_eq!(&auth, b"\x01\x05alice\x05pass1");
+            socket.write_all(&[1, 0]).await.unwrap();
+
+            let mut request = [0u8; 5];
+            socket.read_exact(&mut request).await.unwrap();
+            assert_eq!(request, [5, 1, 0, 3, 11]);
+            let mut name_and_port = [0u8; 13];
+            socket.read_exact(&mut name_and_port).await.unwrap();
+            assert_eq!(&name_and_port, b"example.com\x00\x16");
+
+            socket
+                .write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0x1f, 0x90])
+                .await
+                .unwrap();
+            Some(socket)
+        })
+        .await;
+
+        let config = proxy_config(ProxyType::Socks5, port, Some(("alice", "pass1")));
+        let mut stream = create_proxy_stream(&config, "example.com", 22)
+            .await
+            .unwrap();
+        assert_tunnel_works(&mut stream).await;
+    }
+
+    #[tokio::test]
+    async fn test_socks5_no_auth_ipv6_target() {
+        let port = spawn_stand_in_proxy(|mut socket| async move {
+            let mut greeting = [0u8; 3];
+            socket.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting, [5, 1, 0]);
+            socket.write_all(&[5, 0]).await.unwrap();
+
+            let mut request = [0u8; 22];
+            socket.read_exact(&mut request).await.unwrap();
+            assert_eq!(&request[..4], &[5, 1, 0, 4]);
+            assert_eq!(request[19], 1);
+            assert_eq!(&request[20..], &[0, 22]);
+
+            // Bound address given as a domain name this time
+            socket
+                .write_all(&[5, 0, 0, 3, 4, b'p', b'r', b'o', b'x', 0, 0])
+                .await
+                .unwrap();
+            Some(socket)
+        })
+        .await;
+
+        let config = proxy_config(ProxyType::Socks5, port, None);
+        let mut stream = create_proxy_stream(&config, "::1", 22).await.unwrap();
+        assert_tunnel_works(&mut stream).await;
+    }
+
+    #[tokio::test]
+    async fn test_socks5_bad_credentials() {
+        let port = spawn_stand_in_proxy(|mut socket| async move {
+            let mut greeting = [0u8; 4];
+            socket.read_exact(&mut greeting).await.unwrap();
+            socket.write_all(&[5, 2]).await.unwrap();
+            let mut auth = [0u8; 11];
+            socket.read_exact(&mut auth).await.unwrap();
+            socket.write_all(&[1, 1]).await.unwrap();
+            None
+        })
+        .await;
+
+        let config = proxy_config(ProxyType::Socks5, port, Some(("alice", "bad")));
+        let result = create_proxy_stream(&config, "example.com", 22).await;
+        assert!(matches!(result, Err(ProxyError::AuthenticationFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_socks5_connection_refused() {
+        let port = spawn_stand_in_proxy(|mut socket| async move {
+            let mut greeting = [0u8; 3];
+            socket.read_exact(&mut greeting).await.unwrap();
+            socket.write_all(&[5, 0]).await.unwrap();
+            let mut request = [0u8; 10];
+            socket.read_exact(&mut request).await.unwrap();
+            socket
+                .write_all(&[5, 5, 0, 1, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+            None
+        })
+        .await;
+
+        let config = proxy_config(ProxyType::Socks5, port, None);
+        match create_proxy_stream(&config, "192.168.1.10", 22).await {
+            Err(ProxyError::ConnectionFailed(message)) => {
+                assert!(message.contains("connection refused"))
+            }
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
 }
@@ -0,0 +1,199 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Session daemon: a background process that owns persistent local shells so they
+//! survive app restarts. The app talks to it over a unix socket with one JSON message
+//! per line and one connection per attached terminal.
+
+pub mod scrollback;
+pub mod server;
+
+use crate::error::AppError;
+use crate::models::terminal::{LocalConfig, PersistentSessionInfo};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::UnixStream;
+
+/// Command line argument that starts the binary as the session daemon
+pub const DAEMON_ARG: &str = "--session-daemon";
+
+const SOCKET_NAME: &str = "kerminal-sessions.sock";
+
+/// How long to wait for a freshly spawned daemon to accept connections
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Message sent from the app to the daemon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum DaemonRequest {
+    /// Start a shell and attach this connection to it
+    Spawn {
+        session_id: String,
+        config: LocalConfig,
+        cols: u16,
+        rows: u16,
+    },
+    /// Attach this connection to a running shell, replaying its scrollback
+    Attach {
+        session_id: String,
+    },
+    /// Base64 encoded input for the attached shell
+    Input {
+        data: String,
+    },
+    Resize {
+        cols: u16,
+        rows: u16,
+    },
+    Kill {
+        session_id: String,
+    },
+    List,
+}
+
+/// Message sent from the daemon to the app
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum DaemonMessage {
+    /// Base64 encoded recent output, sent once after spawning or attaching
+    Attached {
+        session_id: String,
+        scrollback: String,
+    },
+    /// Base64 encoded shell output
+    Output {
+        data: String,
+    },
+    Exited {
+        exit_code: Option<i32>,
+    },
+    Sessions {
+        sessions: Vec<PersistentSessionInfo>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Path of the daemon socket, in the runtime directory when the platform has one
+pub fn socket_path() -> PathBuf {
+    dirs::runtime_dir()
+        .or_else(|| dirs::data_dir().map(|dir| dir.join("com.klpod221.kerminal")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join(SOCKET_NAME)
+}
+
+/// Connect to the session daemon, starting it first if it is not running
+pub async fn connect() -> Result<UnixStream, AppError> {
+    let path = socket_path();
+    if let Ok(stream) = UnixStream::connect(&path).await {
+        return Ok(stream);
+    }
+
+    spawn_daemon()?;
+
+    let deadline = tokio::time::Instant::now() + STARTUP_TIMEOUT;
+    loop {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        match UnixStream::connect(&path).await {
+            Ok(stream) => return Ok(stream),
+            Err(e) if tokio::time::Instant::now() >= deadline => {
+                return Err(AppError::terminal_error(format!(
+                    "Session daemon did not start at {}: {}",
+                    path.display(),
+                    e
+                )));
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+/// Start the current executable as a detached session daemon
+fn spawn_daemon() -> Result<(), AppError> {
+    use std::os::unix::process::CommandExt;
+    use std::process::{Command, Stdio};
+
+    let exe = std::env::current_exe()?;
+    let mut child = Command::new(exe)
+        .arg(DAEMON_ARG)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        // Own process group so signals aimed at the app do not reach the daemon
+        .process_group(0)
+        .spawn()
+        .map_err(|e| AppError::terminal_error(format!("Failed to start session daemon: {}", e)))?;
+
+    // Reap the daemon if it exits while the app is still running
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+
+    Ok(())
+}
+
+/// Write one message as a JSON line
+pub async fn send_message<W, T>(writer: &mut W, message: &T) -> Result<(), AppError>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let mut line = serde_json::to_vec(message)
+        .map_err(|e| AppError::internal_error(format!("Failed to encode message: {}", e)))?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Read one JSON line message, `None` once the peer has closed the connection
+pub async fn read_message<R, T>(reader: &mut R) -> Result<Option<T>, AppError>
+where
+    R: AsyncBufRead + Unpin,
+    T: DeserializeOwned,
+{
+    let mut line = String::new();
+    if reader.read_line(&mut line).await? == 0 {
+        return Ok(None);
+    }
+    serde_json::from_str(&line)
+        .map(Some)
+        .map_err(|e| AppError::terminal_error(format!("Invalid session daemon message: {}", e)))
+}
+
+pub fn encode(data: &[u8]) -> String {
+    STANDARD.encode(data)
+}
+
+pub fn decode(data: &str) -> Result<Vec<u8>, AppError> {
+    STANDARD
+        .decode(data)
+        .map_err(|e| AppError::terminal_error(format!("Invalid session daemon data: {}", e)))
+}
@@ -0,0 +1,93 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::VecDeque;
+
+/// Recent output of a daemon session, replayed when the app reattaches
+pub struct ScrollbackRing {
+    buffer: VecDeque<u8>,
+    capacity: usize,
+}
+
+impl ScrollbackRing {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buffer: VecDeque::with_capacity(capacity.min(64 * 1024)),
+            capacity,
+        }
+    }
+
+    /// Append output, dropping the oldest bytes once the ring is full. The ring then
+    /// starts at a line boundary so the replay does not begin inside an escape sequence.
+    pub fn push(&mut self, data: &[u8]) {
+        self.buffer.extend(data);
+        let overflow = self.buffer.len().saturating_sub(self.capacity);
+        if overflow == 0 {
+            return;
+        }
+
+        let at_line_start = self.buffer.drain(..overflow).last() == Some(b'\n');
+        if !at_line_start {
+            if let Some(newline) = self.buffer.iter().position(|&b| b == b'\n') {
+                self.buffer.drain(..=newline);
+            }
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<u8> {
+        self.buffer.iter().copied().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_everything_below_capacity() {
+        let mut ring = ScrollbackRing::new(32);
+        ring.push(b"hello ");
+        ring.push(b"world\n");
+        assert_eq!(ring.snapshot(), b"hello world\n");
+    }
+
+    #[test]
+    fn test_drops_oldest_output_up_to_a_line_boundary() {
+        let mut ring = ScrollbackRing::new(16);
+        ring.push(b"first line\n");
+        ring.push(b"second\nthird");
+        assert_eq!(ring.snapshot(), b"second\nthird");
+
+        let mut ring = ScrollbackRing::new(5);
+        ring.push(b"ab\n");
+        ring.push(b"cdef\n");
+        assert_eq!(ring.snapshot(), b"cdef\n");
+
+        let mut ring = ScrollbackRing::new(4);
+        ring.push(b"abcdefgh");
+        assert_eq!(ring.len(), 4);
+    }
+}
@@ -0,0 +1,575 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::scrollback::ScrollbackRing;
+use super::{
+    decode, encode, read_message, send_message, socket_path, DaemonMessage, DaemonRequest,
+};
+use crate::core::terminal::local::shell_command;
+use crate::error::AppError;
+use crate::models::terminal::{LocalConfig, PersistentSessionInfo};
+use log::warn;
+use portable_pty::{ChildKiller, MasterPty, PtySize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use tokio::io::BufReader;
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+
+/// Output kept per session for replay on reattach
+const SCROLLBACK_BYTES: usize = 1024 * 1024;
+
+/// The daemon exits once it has had no sessions and no clients for this long
+const IDLE_SHUTDOWN: Duration = Duration::from_secs(60);
+
+/// Output chunks buffered per attached client before it starts missing output
+const OUTPUT_CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone)]
+enum SessionOutput {
+    Data(Vec<u8>),
+    Exited(Option<i32>),
+}
+
+/// A shell owned by the daemon
+struct Session {
+    info: PersistentSessionInfo,
+    writer: Mutex<Box<dyn Write + Send>>,
+    master: Mutex<Box<dyn MasterPty + Send>>,
+    killer: Mutex<Box<dyn ChildKiller + Send + Sync>>,
+    scrollback: Mutex<ScrollbackRing>,
+    output: broadcast::Sender<SessionOutput>,
+}
+
+impl Session {
+    fn push_output(&self, data: &[u8]) {
+        // Held while sending so an attaching client sees each chunk exactly once
+        let mut scrollback = lock(&self.scrollback);
+        scrollback.push(data);
+        let _ = self.output.send(SessionOutput::Data(data.to_vec()));
+    }
+
+    /// Recent output plus a receiver for everything after it
+    fn subscribe(&self) -> (Vec<u8>, broadcast::Receiver<SessionOutput>) {
+        let scrollback = lock(&self.scrollback);
+        (scrollback.snapshot(), self.output.subscribe())
+    }
+
+    fn write(&self, data: &[u8]) -> Result<(), AppError> {
+        let mut writer = lock(&self.writer);
+        writer.write_all(data)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn resize(&self, cols: u16, rows: u16) -> Result<(), AppError> {
+        lock(&self.master)
+            .resize(PtySize {
+                rows,
+                cols,
+                pixel_width: 0,
+                pixel_height: 0,
+            })
+            .map_err(|e| AppError::pty_error(e.to_string()))
+    }
+
+    fn kill(&self) -> Result<(), AppError> {
+        lock(&self.killer).kill()?;
+        Ok(())
+    }
+
+    fn info(&self) -> PersistentSessionInfo {
+        PersistentSessionInfo {
+            attached_clients: self.output.receiver_count(),
+            ..self.info.clone()
+        }
+    }
+}
+
+/// Per-connection state: the session this client is attached to, if any
+#[derive(Default)]
+struct Attachment {
+    session: Option<Arc<Session>>,
+    forward_task: Option<JoinHandle<()>>,
+}
+
+impl Attachment {
+    fn session(&self) -> Result<&Arc<Session>, AppError> {
+        self.session
+            .as_ref()
+            .ok_or_else(|| AppError::terminal_error("Not attached to a session".to_string()))
+    }
+
+    /// Replay the session's scrollback to the client and stream its output from then on
+    fn attach(&mut self, session: Arc<Session>, messages: &mpsc::UnboundedSender<DaemonMessage>) {
+        self.detach();
+
+        let (scrollback, mut output) = session.subscribe();
+        let _ = messages.send(DaemonMessage::Attached {
+            session_id: session.info.session_id.clone(),
+            scrollback: encode(&scrollback),
+        });
+
+        let messages = messages.clone();
+        let session_id = session.info.session_id.clone();
+        self.forward_task = Some(tokio::spawn(async move {
+            loop {
+                let message = match output.recv().await {
+                    Ok(SessionOutput::Data(data)) => DaemonMessage::Output {
+                        data: encode(&data),
+                    },
+                    Ok(SessionOutput::Exited(exit_code)) => {
+                        let _ = messages.send(DaemonMessage::Exited { exit_code });
+                        break;
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(
+                            "Client of session {} fell behind, {} output chunks dropped",
+                            session_id, skipped
+                        );
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if messages.send(message).is_err() {
+                    break;
+                }
+            }
+        }));
+        self.session = Some(session);
+    }
+
+    fn detach(&mut self) {
+        if let Some(task) = self.forward_task.take() {
+            task.abort();
+        }
+        self.session = None;
+    }
+}
+
+/// Owns the persistent shells and serves clients on the daemon socket
+pub struct SessionDaemon {
+    sessions: Mutex<HashMap<String, Arc<Session>>>,
+    connections: AtomicUsize,
+}
+
+impl SessionDaemon {
+    pub(crate) fn new() -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+            connections: AtomicUsize::new(0),
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        self.connections.load(Ordering::SeqCst) == 0 && lock(&self.sessions).is_empty()
+    }
+
+    fn session(&self, session_id: &str) -> Result<Arc<Session>, AppError> {
+        lock(&self.sessions)
+            .get(session_id)
+            .cloned()
+            .ok_or_else(|| AppError::not_found(format!("Session {} not found", session_id)))
+    }
+
+    fn list(&self) -> Vec<PersistentSessionInfo> {
+        let mut sessions: Vec<_> = lock(&self.sessions).values().map(|s| s.info()).collect();
+        sessions.sort_by_key(|s| s.created_at);
+        sessions
+    }
+
+    /// Start a shell and a reader thread that feeds its scrollback and clients
+    fn spawn_session(
+        self: &Arc<Self>,
+        session_id: String,
+        config: &LocalConfig,
+        cols: u16,
+        rows: u16,
+    ) -> Result<Arc<Session>, AppError> {
+        if lock(&self.sessions).contains_key(&session_id) {
+            return Err(AppError::validation_error(format!(
+                "Session {} already exists",
+                session_id
+            )));
+        }
+
+        let pty_pair = portable_pty::native_pty_system()
+            .openpty(PtySize {
+                rows,
+                cols,
+                pixel_width: 0,
+                pixel_height: 0,
+            })
+            .map_err(|e| AppError::pty_error(e.to_string()))?;
+
+        let (shell, cmd) = shell_command(config);
+        let mut child = pty_pair
+            .slave
+            .spawn_command(cmd)
+            .map_err(|e| AppError::pty_error(e.to_string()))?;
+        let writer = pty_pair
+            .master
+            .take_writer()
+            .map_err(|e| AppError::pty_error(e.to_string()))?;
+        let mut reader = pty_pair
+            .master
+            .try_clone_reader()
+            .map_err(|e| AppError::pty_error(e.to_string()))?;
+
+        let (output, _) = broadcast::channel(OUTPUT_CHANNEL_CAPACITY);
+        let session = Arc::new(Session {
+            info: PersistentSessionInfo {
+                session_id: session_id.clone(),
+                shell,
+                working_dir: config.working_dir.clone(),
+                pid: child.process_id(),
+                created_at: chrono::Utc::now(),
+                attached_clients: 0,
+            },
+            writer: Mutex::new(writer),
+            master: Mutex::new(pty_pair.master),
+            killer: Mutex::new(child.clone_killer()),
+            scrollback: Mutex::new(ScrollbackRing::new(SCROLLBACK_BYTES)),
+            output,
+        });
+        lock(&self.sessions).insert(session_id.clone(), session.clone());
+
+        let daemon = self.clone();
+        let reader_session = session.clone();
+        std::thread::spawn(move || {
+            let mut buffer = [0u8; 65536];
+            loop {
+                match reader.read(&mut buffer) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => reader_session.push_output(&buffer[..n]),
+                }
+            }
+
+            let exit_code = child.wait().ok().map(|status| status.exit_code() as i32);
+            lock(&daemon.sessions).remove(&session_id);
+            let _ = reader_session.output.send(SessionOutput::Exited(exit_code));
+        });
+
+        Ok(session)
+    }
+
+    /// Serve a client connection until it closes
+    pub(crate) fn accept(self: &Arc<Self>, stream: UnixStream) {
+        self.connections.fetch_add(1, Ordering::SeqCst);
+        tokio::spawn(self.clone().serve(stream));
+    }
+
+    async fn serve(self: Arc<Self>, stream: UnixStream) {
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+
+        let (messages, mut outgoing) = mpsc::unbounded_channel::<DaemonMessage>();
+        let write_task = tokio::spawn(async move {
+            while let Some(message) = outgoing.recv().await {
+                if send_message(&mut writer, &message).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut attachment = Attachment::default();
+        loop {
+            let request = match read_message::<_, DaemonRequest>(&mut reader).await {
+                Ok(Some(request)) => request,
+                Ok(None) => break,
+                Err(e) => {
+                    let _ = messages.send(DaemonMessage::Error {
+                        message: e.to_string(),
+                    });
+                    break;
+                }
+            };
+
+            if let Err(e) = self.handle(request, &mut attachment, &messages) {
+                let _ = messages.send(DaemonMessage::Error {
+                    message: e.to_string(),
+                });
+            }
+        }
+
+        // Closing the connection detaches the client, the shell keeps running
+        attachment.detach();
+        drop(messages);
+        let _ = write_task.await;
+        self.connections.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    fn handle(
+        self: &Arc<Self>,
+        request: DaemonRequest,
+        attachment: &mut Attachment,
+        messages: &mpsc::UnboundedSender<DaemonMessage>,
+    ) -> Result<(), AppError> {
+        match request {
+            DaemonRequest::Spawn {
+                session_id,
+                config,
+                cols,
+                rows,
+            } => {
+                let session = self.spawn_session(session_id, &config, cols, rows)?;
+                attachment.attach(session, messages);
+            }
+            DaemonRequest::Attach { session_id } => {
+                let session = self.session(&session_id)?;
+                attachment.attach(session, messages);
+            }
+            DaemonRequest::Input { data } => attachment.session()?.write(&decode(&data)?)?,
+            DaemonRequest::Resize { cols, rows } => attachment.session()?.resize(cols, rows)?,
+            DaemonRequest::Kill { session_id } => self.session(&session_id)?.kill()?,
+            DaemonRequest::List => {
+                let _ = messages.send(DaemonMessage::Sessions {
+                    sessions: self.list(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Run the session daemon until it has been idle for a while
+pub async fn run() -> Result<(), AppError> {
+    let path = socket_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    if UnixStream::connect(&path).await.is_ok() {
+        return Err(AppError::terminal_error(format!(
+            "Session daemon already running at {}",
+            path.display()
+        )));
+    }
+    // Left behind by a daemon that did not shut down cleanly
+    let _ = std::fs::remove_file(&path);
+
+    let listener = UnixListener::bind(&path)?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+
+    let daemon = Arc::new(SessionDaemon::new());
+    let mut idle_check = tokio::time::interval(IDLE_SHUTDOWN);
+    idle_check.tick().await;
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => daemon.accept(stream),
+                Err(e) => warn!("Failed to accept session daemon client: {}", e),
+            },
+            _ = idle_check.tick() => {
+                if daemon.is_idle() {
+                    break;
+                }
+            }
+        }
+    }
+
+    let _ = std::fs::remove_file(&path);
+    Ok(())
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+
+    struct Client {
+        reader: BufReader<OwnedReadHalf>,
+        writer: OwnedWriteHalf,
+    }
+
+    impl Client {
+        fn connect(daemon: &Arc<SessionDaemon>) -> Self {
+            let (client, server) = UnixStream::pair().unwrap();
+            daemon.accept(server);
+            let (reader, writer) = client.into_split();
+            Self {
+                reader: BufReader::new(reader),
+                writer,
+            }
+        }
+
+        async fn send(&mut self, request: DaemonRequest) {
+            send_message(&mut self.writer, &request).await.unwrap();
+        }
+
+        async fn receive(&mut self) -> DaemonMessage {
+            tokio::time::timeout(Duration::from_secs(5), read_message(&mut self.reader))
+                .await
+                .expect("daemon did not answer")
+                .unwrap()
+                .expect("daemon closed the connection")
+        }
+
+        /// Read output until it contains `needle`
+        async fn expect_output(&mut self, needle: &str) {
+            let mut output = String::new();
+            while !output.contains(needle) {
+                match self.receive().await {
+                    DaemonMessage::Output { data } => {
+                        output.push_str(&String::from_utf8_lossy(&decode(&data).unwrap()))
+                    }
+                    other => panic!("unexpected message {:?}", other),
+                }
+            }
+        }
+    }
+
+    fn shell_config() -> LocalConfig {
+        LocalConfig {
+            shell: Some("/bin/sh".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_session_outlives_client_and_replays_scrollback() {
+        let daemon = Arc::new(SessionDaemon::new());
+
+        let mut client = Client::connect(&daemon);
+        client
+            .send(DaemonRequest::Spawn {
+                session_id: "s1".to_string(),
+                config: shell_config(),
+                cols: 80,
+                rows: 24,
+            })
+            .await;
+        assert!(matches!(
+            client.receive().await,
+            DaemonMessage::Attached { session_id, .. } if session_id == "s1"
+        ));
+
+        // The expansion only shows up in the output, not in the echoed input
+        client
+            .send(DaemonRequest::Input {
+                data: encode(b"echo marker-$((40 + 2))\n"),
+            })
+            .await;
+        client.expect_output("marker-42").await;
+        drop(client);
+
+        let mut client = Client::connect(&daemon);
+        client
+            .send(DaemonRequest::Attach {
+                session_id: "s1".to_string(),
+            })
+            .await;
+        match client.receive().await {
+            DaemonMessage::Attached { scrollback, .. } => {
+                let scrollback = decode(&scrollback).unwrap();
+                assert!(String::from_utf8_lossy(&scrollback).contains("marker-42"));
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+
+        client.send(DaemonRequest::List).await;
+        match client.receive().await {
+            DaemonMessage::Sessions { sessions } => {
+                assert_eq!(sessions.len(), 1);
+                assert_eq!(sessions[0].session_id, "s1");
+                assert_eq!(sessions[0].attached_clients, 1);
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+
+        client
+            .send(DaemonRequest::Kill {
+                session_id: "s1".to_string(),
+            })
+            .await;
+        loop {
+            match client.receive().await {
+                DaemonMessage::Exited { .. } => break,
+                DaemonMessage::Output { .. } => {}
+                other => panic!("unexpected message {:?}", other),
+            }
+        }
+        assert!(daemon.list().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_unknown_and_duplicate_sessions() {
+        let daemon = Arc::new(SessionDaemon::new());
+        let mut client = Client::connect(&daemon);
+
+        client
+            .send(DaemonRequest::Attach {
+                session_id: "missing".to_string(),
+            })
+            .await;
+        assert!(matches!(
+            client.receive().await,
+            DaemonMessage::Error { .. }
+        ));
+
+        client
+            .send(DaemonRequest::Input {
+                data: encode(b"ls\n"),
+            })
+            .await;
+        assert!(matches!(
+            client.receive().await,
+            DaemonMessage::Error { .. }
+        ));
+
+        let spawn = DaemonRequest::Spawn {
+            session_id: "s1".to_string(),
+            config: shell_config(),
+            cols: 80,
+            rows: 24,
+        };
+        client.send(spawn.clone()).await;
+        assert!(matches!(
+            client.receive().await,
+            DaemonMessage::Attached { .. }
+        ));
+
+        let mut other = Client::connect(&daemon);
+        other.send(spawn).await;
+        assert!(matches!(other.receive().await, DaemonMessage::Error { .. }));
+
+        daemon.session("s1").unwrap().kill().unwrap();
+        drop(client);
+        drop(other);
+
+        // Idle once the shell has exited and both clients are gone
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !daemon.is_idle() {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
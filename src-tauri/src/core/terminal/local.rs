@@ -18,6 +18,7 @@
 
 use crate::core::title_detector::TitleDetector;
 use crate::error::AppError;
+use crate::models::recording::RecordingEnv;
 use crate::models::terminal::{LocalConfig, TerminalConfig, TerminalExited, TerminalState};
 use log::{error, warn};
 use portable_pty::{Child, CommandBuilder, MasterPty, PtySize};
@@ -68,65 +69,7 @@ impl LocalTerminal {
             })
             .map_err(|e| AppError::pty_error(e.to_string()))?;
 
-        let shell = self
-            .local_config
-            .shell
-            .clone()
-            .or_else(|| std::env::var("SHELL").ok())
-            .unwrap_or_else(|| {
-                if cfg!(windows) {
-                    "cmd.exe".to_string()
-                } else {
-                    let preferred_shells =
-                        ["/bin/zsh", "/usr/bin/zsh", "/bin/bash", "/usr/bin/bash"];
-                    for shell_path in &preferred_shells {
-                        if std::path::Path::new(shell_path).exists() {
-                            return shell_path.to_string();
-                        }
-                    }
-                    "/bin/sh".to_string()
-                }
-            });
-
-        let mut cmd = CommandBuilder::new(&shell);
-
-        if shell.contains("zsh") {
-            cmd.arg("-l");
-        } else if shell.contains("bash") {
-            cmd.arg("-l");
-        }
-
-        if let Some(command) = &self.local_config.command {
-            if !command.is_empty() {
-                if shell.contains("zsh") || shell.contains("bash") || shell.ends_with("/sh") {
-                    let new_cmd = format!("{}; exec {} -l", command, shell);
-                    cmd.arg("-c");
-                    cmd.arg(&new_cmd);
-                } else {
-                    cmd.arg("-c");
-                    cmd.arg(command);
-                }
-            }
-        }
-
-        if let Some(working_dir) = &self.local_config.working_dir {
-            if !working_dir.is_empty() {
-                cmd.cwd(working_dir);
-            }
-        }
-
-        cmd.env("TERM", "xterm-256color");
-        cmd.env("COLORTERM", "truecolor");
-
-        for (key, value) in std::env::vars() {
-            cmd.env(&key, &value);
-        }
-
-        if let Some(env_vars) = &self.local_config.env_vars {
-            for (key, value) in env_vars {
-                cmd.env(key, value);
-            }
-        }
+        let (_, cmd) = shell_command(&self.local_config);
 
         let child = pty_pair
             .slave
@@ -208,6 +151,35 @@ impl LocalTerminal {
         &self.config
     }
 
+    /// TERM and SHELL the shell runs with
+    pub fn recording_env(&self) -> RecordingEnv {
+        local_recording_env(&self.local_config)
+    }
+
+    /// Whether the PTY reads a line without echoing it, the way programs read passwords.
+    /// Line editors like readline turn echo off as well, but also leave canonical mode.
+    #[cfg(unix)]
+    pub fn reads_secret(&self) -> Option<bool> {
+        let (master, _) = self.pty_pair.as_ref()?;
+        let fd = master.as_raw_fd()?;
+
+        let mut termios = std::mem::MaybeUninit::<libc::termios>::uninit();
+        // SAFETY: fd is the PTY master owned by this terminal and termios is only read
+        // after tcgetattr filled it in
+        let termios = unsafe {
+            if libc::tcgetattr(fd, termios.as_mut_ptr()) != 0 {
+                return None;
+            }
+            termios.assume_init()
+        };
+        Some(termios.c_lflag & libc::ICANON != 0 && termios.c_lflag & libc::ECHO == 0)
+    }
+
+    #[cfg(not(unix))]
+    pub fn reads_secret(&self) -> Option<bool> {
+        None
+    }
+
     /// Start reading from terminal and send output to the provided sender
     pub async fn start_read_loop(
         &mut self,
@@ -215,6 +187,7 @@ impl LocalTerminal {
         title_sender: Option<mpsc::UnboundedSender<String>>,
         exit_sender: Option<mpsc::UnboundedSender<TerminalExited>>,
         _latency_sender: Option<mpsc::UnboundedSender<crate::models::terminal::TerminalLatency>>,
+        _state_sender: Option<mpsc::UnboundedSender<crate::models::terminal::TerminalStateChanged>>,
     ) -> Result<(), AppError> {
         if let Some((pty_master, _)) = &mut self.pty_pair {
             let mut reader = pty_master
@@ -276,3 +249,81 @@ impl LocalTerminal {
         }
     }
 }
+
+/// Build the login shell command of a local terminal, returning the resolved shell path
+pub(crate) fn shell_command(local_config: &LocalConfig) -> (String, CommandBuilder) {
+    let shell = local_config
+        .shell
+        .clone()
+        .or_else(|| std::env::var("SHELL").ok())
+        .unwrap_or_else(|| {
+            if cfg!(windows) {
+                "cmd.exe".to_string()
+            } else {
+                let preferred_shells = ["/bin/zsh", "/usr/bin/zsh", "/bin/bash", "/usr/bin/bash"];
+                for shell_path in &preferred_shells {
+                    if std::path::Path::new(shell_path).exists() {
+                        return shell_path.to_string();
+                    }
+                }
+                "/bin/sh".to_string()
+            }
+        });
+
+    let mut cmd = CommandBuilder::new(&shell);
+
+    if shell.contains("zsh") {
+        cmd.arg("-l");
+    } else if shell.contains("bash") {
+        cmd.arg("-l");
+    }
+
+    if let Some(command) = &local_config.command {
+        if !command.is_empty() {
+            if shell.contains("zsh") || shell.contains("bash") || shell.ends_with("/sh") {
+                let new_cmd = format!("{}; exec {} -l", command, shell);
+                cmd.arg("-c");
+                cmd.arg(&new_cmd);
+            } else {
+                cmd.arg("-c");
+                cmd.arg(command);
+            }
+        }
+    }
+
+    if let Some(working_dir) = &local_config.working_dir {
+        if !working_dir.is_empty() {
+            cmd.cwd(working_dir);
+        }
+    }
+
+    cmd.env("TERM", "xterm-256color");
+    cmd.env("COLORTERM", "truecolor");
+
+    for (key, value) in std::env::vars() {
+        cmd.env(&key, &value);
+    }
+
+    if let Some(env_vars) = &local_config.env_vars {
+        for (key, value) in env_vars {
+            cmd.env(key, value);
+        }
+    }
+
+    (shell, cmd)
+}
+
+/// TERM and SHELL of a local shell, resolved the same way `shell_command` sets them
+pub(crate) fn local_recording_env(local_config: &LocalConfig) -> RecordingEnv {
+    let (shell, _) = shell_command(local_config);
+    let term = local_config
+        .env_vars
+        .as_ref()
+        .and_then(|env_vars| env_vars.get("TERM").cloned())
+        .or_else(|| std::env::var("TERM").ok())
+        .unwrap_or_else(|| "xterm-256color".to_string());
+    RecordingEnv {
+        term: Some(term),
+        shell: Some(shell),
+    }
+}
@@ -17,17 +17,26 @@
  */
 
 pub mod local;
+#[cfg(unix)]
+pub mod persistent;
 pub mod ssh;
 
 use crate::database::service::DatabaseService;
 use crate::error::AppError;
+use crate::models::recording::RecordingEnv;
 use crate::models::terminal::{TerminalConfig, TerminalExited, TerminalState, TerminalType};
+use crate::services::ssh::SSHConnectionPool;
+use crate::services::ssh_config_parser::parse_ssh_config;
+use futures::future::{self, BoxFuture, FutureExt};
 use std::sync::Arc;
 use tokio::sync::{mpsc, Mutex};
 
 /// Unified terminal wrapper that can handle both local and SSH terminals
 pub enum TerminalWrapper {
     Local(Box<local::LocalTerminal>),
+    /// Local shell owned by the session daemon
+    #[cfg(unix)]
+    Persistent(Box<persistent::PersistentTerminal>),
     Ssh(Box<ssh::SSHTerminal>),
 }
 
@@ -36,6 +45,8 @@ impl TerminalWrapper {
     pub async fn connect(&mut self) -> Result<(), AppError> {
         match self {
             TerminalWrapper::Local(terminal) => terminal.connect().await,
+            #[cfg(unix)]
+            TerminalWrapper::Persistent(terminal) => terminal.connect().await,
             TerminalWrapper::Ssh(terminal) => terminal.connect().await,
         }
     }
@@ -47,6 +58,8 @@ impl TerminalWrapper {
     ) -> Result<(), AppError> {
         match self {
             TerminalWrapper::Local(terminal) => terminal.connect().await,
+            #[cfg(unix)]
+            TerminalWrapper::Persistent(terminal) => terminal.connect().await,
             TerminalWrapper::Ssh(terminal) => {
                 terminal.connect_with_resolved_data(resolved_key).await
             }
@@ -57,14 +70,29 @@ impl TerminalWrapper {
     pub async fn disconnect(&mut self) -> Result<(), AppError> {
         match self {
             TerminalWrapper::Local(terminal) => terminal.disconnect().await,
+            #[cfg(unix)]
+            TerminalWrapper::Persistent(terminal) => terminal.disconnect().await,
             TerminalWrapper::Ssh(terminal) => terminal.disconnect().await,
         }
     }
 
+    /// Close the connection but leave the shell running (persistent terminals only)
+    pub async fn detach(&mut self) -> Result<(), AppError> {
+        match self {
+            #[cfg(unix)]
+            TerminalWrapper::Persistent(terminal) => terminal.detach().await,
+            _ => Err(AppError::terminal_error(
+                "Only persistent local terminals can be detached".to_string(),
+            )),
+        }
+    }
+
     /// Write data to the terminal
     pub async fn write(&mut self, data: &[u8]) -> Result<(), AppError> {
         match self {
             TerminalWrapper::Local(terminal) => terminal.write(data).await,
+            #[cfg(unix)]
+            TerminalWrapper::Persistent(terminal) => terminal.write(data).await,
             TerminalWrapper::Ssh(terminal) => terminal.write(data).await,
         }
     }
@@ -73,6 +101,8 @@ impl TerminalWrapper {
     pub async fn resize(&mut self, cols: u16, rows: u16) -> Result<(), AppError> {
         match self {
             TerminalWrapper::Local(terminal) => terminal.resize(cols, rows).await,
+            #[cfg(unix)]
+            TerminalWrapper::Persistent(terminal) => terminal.resize(cols, rows).await,
             TerminalWrapper::Ssh(terminal) => terminal.resize(cols, rows).await,
         }
     }
@@ -81,6 +111,8 @@ impl TerminalWrapper {
     pub fn get_state(&self) -> TerminalState {
         match self {
             TerminalWrapper::Local(terminal) => terminal.get_state(),
+            #[cfg(unix)]
+            TerminalWrapper::Persistent(terminal) => terminal.get_state(),
             TerminalWrapper::Ssh(terminal) => terminal.get_state(),
         }
     }
@@ -89,10 +121,37 @@ impl TerminalWrapper {
     pub fn get_config(&self) -> &TerminalConfig {
         match self {
             TerminalWrapper::Local(terminal) => terminal.get_config(),
+            #[cfg(unix)]
+            TerminalWrapper::Persistent(terminal) => terminal.get_config(),
             TerminalWrapper::Ssh(terminal) => terminal.get_config(),
         }
     }
 
+    /// TERM and SHELL of the process behind the terminal, for recording headers.
+    /// Looking up a remote shell takes a round trip, so the lookup is returned to be
+    /// awaited once the terminal is no longer locked.
+    pub fn recording_env(&self) -> BoxFuture<'static, RecordingEnv> {
+        match self {
+            TerminalWrapper::Local(terminal) => future::ready(terminal.recording_env()).boxed(),
+            #[cfg(unix)]
+            TerminalWrapper::Persistent(terminal) => {
+                future::ready(terminal.recording_env()).boxed()
+            }
+            TerminalWrapper::Ssh(terminal) => terminal.recording_env(),
+        }
+    }
+
+    /// Whether the terminal is reading input without echoing it, e.g. at a password
+    /// prompt. `None` when the terminal mode can't be read, like for remote PTYs.
+    pub fn reads_secret(&self) -> Option<bool> {
+        match self {
+            TerminalWrapper::Local(terminal) => terminal.reads_secret(),
+            #[cfg(unix)]
+            TerminalWrapper::Persistent(_) => None,
+            TerminalWrapper::Ssh(_) => None,
+        }
+    }
+
     /// Start reading from terminal and send output to the provided sender
     pub async fn start_read_loop(
         &mut self,
@@ -100,16 +159,41 @@ impl TerminalWrapper {
         title_sender: Option<mpsc::UnboundedSender<String>>,
         exit_sender: Option<mpsc::UnboundedSender<TerminalExited>>,
         latency_sender: Option<mpsc::UnboundedSender<crate::models::terminal::TerminalLatency>>,
+        state_sender: Option<mpsc::UnboundedSender<crate::models::terminal::TerminalStateChanged>>,
     ) -> Result<(), AppError> {
         match self {
             TerminalWrapper::Local(terminal) => {
                 terminal
-                    .start_read_loop(sender, title_sender, exit_sender, latency_sender)
+                    .start_read_loop(
+                        sender,
+                        title_sender,
+                        exit_sender,
+                        latency_sender,
+                        state_sender,
+                    )
+                    .await
+            }
+            #[cfg(unix)]
+            TerminalWrapper::Persistent(terminal) => {
+                terminal
+                    .start_read_loop(
+                        sender,
+                        title_sender,
+                        exit_sender,
+                        latency_sender,
+                        state_sender,
+                    )
                     .await
             }
             TerminalWrapper::Ssh(terminal) => {
                 terminal
-                    .start_read_loop(sender, title_sender, exit_sender, latency_sender)
+                    .start_read_loop(
+                        sender,
+                        title_sender,
+                        exit_sender,
+                        latency_sender,
+                        state_sender,
+                    )
                     .await
             }
         }
@@ -125,10 +209,19 @@ impl TerminalFactory {
         id: String,
         config: TerminalConfig,
         database_service: Option<Arc<Mutex<DatabaseService>>>,
+        connection_pool: Arc<SSHConnectionPool>,
     ) -> Result<TerminalWrapper, AppError> {
         match config.terminal_type {
             TerminalType::Local => {
                 let local_config = config.local_config.clone().unwrap_or_default();
+                if local_config.persistent {
+                    #[cfg(unix)]
+                    return Ok(TerminalWrapper::Persistent(Box::new(
+                        persistent::PersistentTerminal::new(id, config, local_config),
+                    )));
+                    #[cfg(not(unix))]
+                    log::warn!("Persistent terminals need the unix session daemon, starting a regular shell");
+                }
                 Ok(TerminalWrapper::Local(Box::new(local::LocalTerminal::new(
                     id,
                     config,
@@ -160,7 +253,7 @@ impl TerminalFactory {
                     id,
                     config,
                     ssh_profile,
-                    Some(database_service),
+                    connection_pool,
                 )?)))
             }
             TerminalType::SSHConfig => {
@@ -171,16 +264,18 @@ impl TerminalFactory {
                 })?;
 
                 let password = config.ssh_config_password.clone();
+                // Other hosts are only needed to resolve ProxyJump aliases
+                let config_hosts = parse_ssh_config(None).await.unwrap_or_default();
 
                 let ssh_profile = ssh_config_host
-                    .to_temporary_profile(password)
+                    .to_temporary_profile(password, &config_hosts)
                     .map_err(|e| AppError::Config(format!("Failed to create profile: {}", e)))?;
 
                 Ok(TerminalWrapper::Ssh(Box::new(ssh::SSHTerminal::new(
                     id,
                     config,
                     ssh_profile,
-                    database_service,
+                    connection_pool,
                 )?)))
             }
         }
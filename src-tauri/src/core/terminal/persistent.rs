@@ -0,0 +1,362 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::core::session_daemon::{self, DaemonMessage, DaemonRequest};
+use crate::core::title_detector::TitleDetector;
+use crate::error::AppError;
+use crate::models::recording::RecordingEnv;
+use crate::models::terminal::{
+    LocalConfig, TerminalConfig, TerminalExited, TerminalLatency, TerminalState,
+    TerminalStateChanged,
+};
+use log::warn;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::io::BufReader;
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::UnixStream;
+use tokio::sync::{mpsc, Mutex};
+
+/// Local terminal whose shell is owned by the session daemon, so it outlives the app
+pub struct PersistentTerminal {
+    id: String,
+    config: TerminalConfig,
+    local_config: LocalConfig,
+    /// Attach to the existing daemon session with this terminal's ID instead of spawning one
+    reattach: bool,
+    state: TerminalState,
+    writer: Option<Arc<Mutex<OwnedWriteHalf>>>,
+    reader: Option<BufReader<OwnedReadHalf>>,
+    /// Output the daemon replayed on attach, sent before the live output
+    scrollback: Vec<u8>,
+    detached: Arc<AtomicBool>,
+    title_detector: TitleDetector,
+}
+
+impl PersistentTerminal {
+    /// Create a terminal that spawns a new daemon session on connect
+    pub fn new(id: String, config: TerminalConfig, local_config: LocalConfig) -> Self {
+        Self {
+            id,
+            config,
+            local_config,
+            reattach: false,
+            state: TerminalState::Disconnected,
+            writer: None,
+            reader: None,
+            scrollback: Vec::new(),
+            detached: Arc::new(AtomicBool::new(false)),
+            title_detector: TitleDetector::new(),
+        }
+    }
+
+    /// Create a terminal that reattaches to the running daemon session `session_id`
+    pub fn reattach(session_id: String, config: TerminalConfig) -> Self {
+        let local_config = config.local_config.clone().unwrap_or_default();
+        Self {
+            reattach: true,
+            ..Self::new(session_id, config, local_config)
+        }
+    }
+
+    /// Connect to the session daemon and spawn or attach the session
+    pub async fn connect(&mut self) -> Result<(), AppError> {
+        self.state = TerminalState::Connecting;
+        let stream = session_daemon::connect().await?;
+        self.attach_stream(stream).await
+    }
+
+    /// Spawn or attach the session over a connection to the daemon
+    async fn attach_stream(&mut self, stream: UnixStream) -> Result<(), AppError> {
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+
+        let request = if self.reattach {
+            DaemonRequest::Attach {
+                session_id: self.id.clone(),
+            }
+        } else {
+            DaemonRequest::Spawn {
+                session_id: self.id.clone(),
+                config: self.local_config.clone(),
+                cols: 80,
+                rows: 24,
+            }
+        };
+        session_daemon::send_message(&mut writer, &request).await?;
+
+        match session_daemon::read_message(&mut reader).await? {
+            Some(DaemonMessage::Attached { scrollback, .. }) => {
+                self.scrollback = session_daemon::decode(&scrollback)?;
+            }
+            Some(DaemonMessage::Error { message }) => {
+                self.state = TerminalState::Disconnected;
+                return Err(AppError::terminal_error(message));
+            }
+            _ => {
+                self.state = TerminalState::Disconnected;
+                return Err(AppError::terminal_error(
+                    "Unexpected response from session daemon".to_string(),
+                ));
+            }
+        }
+
+        self.writer = Some(Arc::new(Mutex::new(writer)));
+        self.reader = Some(reader);
+        self.state = TerminalState::Connected;
+        Ok(())
+    }
+
+    /// Kill the daemon session
+    pub async fn disconnect(&mut self) -> Result<(), AppError> {
+        let result = self
+            .send(DaemonRequest::Kill {
+                session_id: self.id.clone(),
+            })
+            .await;
+        self.writer = None;
+        self.state = TerminalState::Disconnected;
+        result
+    }
+
+    /// Drop the connection to the daemon, leaving the session running for a later reattach
+    pub async fn detach(&mut self) -> Result<(), AppError> {
+        self.detached.store(true, Ordering::SeqCst);
+        if let Some(writer) = self.writer.take() {
+            use tokio::io::AsyncWriteExt;
+            let _ = writer.lock().await.shutdown().await;
+        }
+        self.reader = None;
+        self.state = TerminalState::Disconnected;
+        Ok(())
+    }
+
+    async fn send(&self, request: DaemonRequest) -> Result<(), AppError> {
+        let Some(writer) = &self.writer else {
+            return Err(AppError::terminal_error(
+                "Terminal not connected".to_string(),
+            ));
+        };
+        session_daemon::send_message(&mut *writer.lock().await, &request).await
+    }
+
+    /// Write data to the terminal
+    pub async fn write(&mut self, data: &[u8]) -> Result<(), AppError> {
+        self.send(DaemonRequest::Input {
+            data: session_daemon::encode(data),
+        })
+        .await
+    }
+
+    /// Resize the terminal
+    pub async fn resize(&mut self, cols: u16, rows: u16) -> Result<(), AppError> {
+        self.send(DaemonRequest::Resize { cols, rows }).await
+    }
+
+    /// Get current state of the terminal
+    pub fn get_state(&self) -> TerminalState {
+        self.state.clone()
+    }
+
+    /// Get terminal configuration
+    pub fn get_config(&self) -> &TerminalConfig {
+        &self.config
+    }
+
+    /// TERM and SHELL the daemon starts the shell with
+    pub fn recording_env(&self) -> RecordingEnv {
+        super::local::local_recording_env(&self.local_config)
+    }
+
+    /// Replay the scrollback, then forward daemon output until the session ends
+    pub async fn start_read_loop(
+        &mut self,
+        sender: mpsc::UnboundedSender<Vec<u8>>,
+        title_sender: Option<mpsc::UnboundedSender<String>>,
+        exit_sender: Option<mpsc::UnboundedSender<TerminalExited>>,
+        _latency_sender: Option<mpsc::UnboundedSender<TerminalLatency>>,
+        _state_sender: Option<mpsc::UnboundedSender<TerminalStateChanged>>,
+    ) -> Result<(), AppError> {
+        let Some(mut reader) = self.reader.take() else {
+            return Err(AppError::terminal_error(
+                "Terminal not connected".to_string(),
+            ));
+        };
+
+        let mut title_detector = std::mem::take(&mut self.title_detector);
+        let scrollback = std::mem::take(&mut self.scrollback);
+        let terminal_id = self.id.clone();
+        let detached = self.detached.clone();
+
+        tokio::spawn(async move {
+            let mut forward = |data: Vec<u8>| {
+                if let Some(title_sender) = &title_sender {
+                    if let Some(new_title) = title_detector.process_output(&data) {
+                        let _ = title_sender.send(new_title);
+                    }
+                }
+                sender.send(data).is_ok()
+            };
+
+            if !scrollback.is_empty() && !forward(scrollback) {
+                return;
+            }
+
+            let (exit_code, reason) = loop {
+                match session_daemon::read_message(&mut reader).await {
+                    Ok(Some(DaemonMessage::Output { data })) => {
+                        match session_daemon::decode(&data) {
+                            Ok(data) => {
+                                if !forward(data) {
+                                    return;
+                                }
+                            }
+                            Err(e) => warn!("Terminal {}: {}", terminal_id, e),
+                        }
+                    }
+                    Ok(Some(DaemonMessage::Exited { exit_code })) => {
+                        break (exit_code, "user-closed")
+                    }
+                    Ok(Some(DaemonMessage::Error { message })) => {
+                        warn!(
+                            "Session daemon error for terminal {}: {}",
+                            terminal_id, message
+                        );
+                    }
+                    Ok(Some(_)) => {}
+                    Ok(None) | Err(_) => break (None, "daemon-lost"),
+                }
+            };
+
+            if detached.load(Ordering::SeqCst) {
+                return;
+            }
+            if let Some(exit_sender) = &exit_sender {
+                let _ = exit_sender.send(TerminalExited {
+                    terminal_id: terminal_id.clone(),
+                    exit_code,
+                    reason: Some(reason.to_string()),
+                });
+            }
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::session_daemon::server::SessionDaemon;
+    use crate::models::terminal::TerminalType;
+    use std::time::Duration;
+
+    fn config() -> TerminalConfig {
+        TerminalConfig {
+            terminal_type: TerminalType::Local,
+            local_config: Some(LocalConfig {
+                shell: Some("/bin/sh".to_string()),
+                persistent: true,
+                ..Default::default()
+            }),
+            ssh_profile_id: None,
+            terminal_profile_id: None,
+            ssh_config_host: None,
+            ssh_config_password: None,
+            reconnect_policy: None,
+        }
+    }
+
+    async fn connect(daemon: &Arc<SessionDaemon>, terminal: &mut PersistentTerminal) {
+        let (client, server) = UnixStream::pair().unwrap();
+        daemon.accept(server);
+        terminal.attach_stream(client).await.unwrap();
+    }
+
+    /// Collect output until it contains `needle`
+    async fn expect_output(output: &mut mpsc::UnboundedReceiver<Vec<u8>>, needle: &str) {
+        let mut received = String::new();
+        while !received.contains(needle) {
+            let data = tokio::time::timeout(Duration::from_secs(5), output.recv())
+                .await
+                .expect("no output from the session")
+                .expect("output channel closed");
+            received.push_str(&String::from_utf8_lossy(&data));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_detach_and_reattach_keeps_the_shell() {
+        let daemon = Arc::new(SessionDaemon::new());
+        let config = config();
+
+        let mut terminal = PersistentTerminal::new(
+            "t1".to_string(),
+            config.clone(),
+            config.local_config.clone().unwrap(),
+        );
+        connect(&daemon, &mut terminal).await;
+        assert!(matches!(terminal.get_state(), TerminalState::Connected));
+
+        let (sender, mut output) = mpsc::unbounded_channel();
+        let (exit_sender, mut exits) = mpsc::unbounded_channel();
+        terminal
+            .start_read_loop(sender, None, Some(exit_sender), None, None)
+            .await
+            .unwrap();
+        terminal.write(b"echo marker-$((40 + 2))\n").await.unwrap();
+        expect_output(&mut output, "marker-42").await;
+
+        // Detaching leaves the shell running and reports no exit
+        terminal.detach().await.unwrap();
+        let exited = tokio::time::timeout(Duration::from_secs(5), exits.recv())
+            .await
+            .unwrap();
+        assert!(exited.is_none());
+
+        let mut terminal = PersistentTerminal::reattach("t1".to_string(), config);
+        connect(&daemon, &mut terminal).await;
+
+        let (sender, mut output) = mpsc::unbounded_channel();
+        let (exit_sender, mut exits) = mpsc::unbounded_channel();
+        terminal
+            .start_read_loop(sender, None, Some(exit_sender), None, None)
+            .await
+            .unwrap();
+        expect_output(&mut output, "marker-42").await;
+
+        terminal.disconnect().await.unwrap();
+        let exited = tokio::time::timeout(Duration::from_secs(5), exits.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(exited.terminal_id, "t1");
+        assert_eq!(exited.reason.as_deref(), Some("user-closed"));
+    }
+
+    #[tokio::test]
+    async fn test_reattach_to_missing_session_fails() {
+        let daemon = Arc::new(SessionDaemon::new());
+        let mut terminal = PersistentTerminal::reattach("missing".to_string(), config());
+
+        let (client, server) = UnixStream::pair().unwrap();
+        daemon.accept(server);
+        assert!(terminal.attach_stream(client).await.is_err());
+        assert!(matches!(terminal.get_state(), TerminalState::Disconnected));
+    }
+}
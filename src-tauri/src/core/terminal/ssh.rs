@@ -16,176 +16,79 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use crate::core::proxy::create_proxy_stream;
 use crate::error::AppError;
+use crate::models::recording::RecordingEnv;
 use crate::models::ssh::key::ResolvedSSHKey;
-use crate::models::ssh::{AuthData, SSHProfile};
-use crate::models::terminal::{TerminalConfig, TerminalState};
-use async_trait::async_trait;
-use log::warn;
-use russh::client::{DisconnectReason, Handle, Handler, Session};
-use russh::{client::Msg, Channel, ChannelId, Disconnect};
-use russh_keys::key::PublicKey;
-use std::path::Path;
+use crate::models::ssh::SSHProfile;
+use crate::models::terminal::{
+    ReconnectPolicy, TerminalConfig, TerminalExited, TerminalLatency, TerminalState,
+    TerminalStateChanged,
+};
+use crate::services::ssh::connection_pool::{PooledSession, SSHConnectionPool};
+use crate::services::ssh::exec::{run_command, run_with_timeout, OutputSink};
+use crate::services::ssh::x11::X11_AUTH_PROTOCOL;
+use futures::future::{BoxFuture, FutureExt};
+use log::{info, warn};
+use russh::{client::Msg, Channel, ChannelMsg};
 use std::sync::Arc;
-use tokio::sync::{mpsc, Mutex};
-
-/// SSH client handler implementation
-#[derive(Clone)]
-pub struct ClientHandler {
-    output_sender: Arc<Mutex<Option<mpsc::UnboundedSender<Vec<u8>>>>>,
-    exit_sender: Arc<Mutex<Option<mpsc::UnboundedSender<crate::models::terminal::TerminalExited>>>>,
-    terminal_id: Arc<Mutex<String>>,
-}
-
-impl ClientHandler {
-    fn new(terminal_id: String) -> Self {
-        Self {
-            output_sender: Arc::new(Mutex::new(None)),
-            exit_sender: Arc::new(Mutex::new(None)),
-            terminal_id: Arc::new(Mutex::new(terminal_id)),
-        }
-    }
-
-    async fn set_output_sender(&self, sender: mpsc::UnboundedSender<Vec<u8>>) {
-        *self.output_sender.lock().await = Some(sender);
-    }
-
-    async fn set_exit_sender(
-        &self,
-        sender: mpsc::UnboundedSender<crate::models::terminal::TerminalExited>,
-    ) {
-        *self.exit_sender.lock().await = Some(sender);
-    }
-}
-
-#[async_trait]
-impl Handler for ClientHandler {
-    type Error = russh::Error;
-
-    async fn check_server_key(
-        &mut self,
-        _server_public_key: &PublicKey,
-    ) -> Result<bool, Self::Error> {
-        Ok(true)
-    }
-
-    async fn data(
-        &mut self,
-        _channel: ChannelId,
-        data: &[u8],
-        _session: &mut Session,
-    ) -> Result<(), Self::Error> {
-        if let Some(sender) = self.output_sender.lock().await.as_ref() {
-            let _ = sender.send(data.to_vec());
-        }
-        Ok(())
-    }
-
-    async fn extended_data(
-        &mut self,
-        _channel: ChannelId,
-        _code: u32,
-        data: &[u8],
-        _session: &mut Session,
-    ) -> Result<(), Self::Error> {
-        if let Some(sender) = self.output_sender.lock().await.as_ref() {
-            let _ = sender.send(data.to_vec());
-        }
-        Ok(())
-    }
-
-    async fn channel_eof(
-        &mut self,
-        _channel: ChannelId,
-        _session: &mut Session,
-    ) -> Result<(), Self::Error> {
-        if let Some(sender) = self.output_sender.lock().await.as_ref() {
-            let eof_msg = b"[SSH: Connection closed by remote host]\r\n";
-            let _ = sender.send(eof_msg.to_vec());
-        }
-
-        if let Some(sender) = self.exit_sender.lock().await.as_ref() {
-            let terminal_id = self.terminal_id.lock().await;
-            let exit_event = crate::models::terminal::TerminalExited {
-                terminal_id: terminal_id.clone(),
-                exit_code: Some(0),
-                reason: Some("user-closed".to_string()),
-            };
-            let _ = sender.send(exit_event);
-        }
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 
-        Ok(())
-    }
+/// PTY size until the frontend sends the real one
+const DEFAULT_PTY_SIZE: (u16, u16) = (80, 24);
 
-    async fn channel_close(
-        &mut self,
-        _channel: ChannelId,
-        _session: &mut Session,
-    ) -> Result<(), Self::Error> {
-        Ok(())
-    }
+/// Terminal type requested for the remote PTY
+const PTY_TERM: &str = "xterm-256color";
 
-    async fn disconnected(
-        &mut self,
-        reason: DisconnectReason<Self::Error>,
-    ) -> Result<(), Self::Error> {
-        let (message, exit_code, reason_str) = match &reason {
-            DisconnectReason::ReceivedDisconnect(disconnect) => {
-                let msg = if disconnect.message.is_empty() {
-                    "[SSH: Connection disconnected by server]\r\n".to_string()
-                } else {
-                    format!(
-                        "[SSH: Connection disconnected - {}]\r\n",
-                        disconnect.message
-                    )
-                };
-                (msg, Some(1), "server-disconnect")
-            }
-            DisconnectReason::Error(e) => {
-                let msg = if format!("{:?}", e).contains("timeout")
-                    || format!("{:?}", e).contains("Timeout")
-                {
-                    "[SSH: Connection timeout - No response from server]\r\n".to_string()
-                } else {
-                    format!("[SSH: Connection error - {}]\r\n", e)
-                };
-                (msg, Some(1), "connection-error")
-            }
-        };
+/// How long to wait for the remote login shell lookup
+const SHELL_LOOKUP_TIMEOUT: Duration = Duration::from_secs(5);
 
-        let output_sender = self.output_sender.lock().await;
-        if let Some(sender) = output_sender.as_ref() {
-            let _ = sender.send(message.as_bytes().to_vec());
-        }
+/// Requests from the terminal to the task owning the shell channel
+enum ChannelCommand {
+    Data(Vec<u8>),
+    Resize { cols: u16, rows: u16 },
+    Close,
+}
 
-        let exit_sender = self.exit_sender.lock().await;
-        if let Some(sender) = exit_sender.as_ref() {
-            let terminal_id = self.terminal_id.lock().await;
-            let exit_event = crate::models::terminal::TerminalExited {
-                terminal_id: terminal_id.clone(),
-                exit_code,
-                reason: Some(reason_str.to_string()),
-            };
-            let _ = sender.send(exit_event);
-        }
+/// Output of the shell channel
+enum ChannelEvent {
+    Output(Vec<u8>),
+    State(TerminalState),
+    Exited {
+        message: String,
+        exit_code: Option<i32>,
+        reason: &'static str,
+    },
+}
 
-        match reason {
-            DisconnectReason::ReceivedDisconnect(_) => Ok(()),
-            DisconnectReason::Error(e) => Err(e),
-        }
-    }
+/// Why a shell channel stopped
+enum ChannelEnd {
+    /// The terminal closed it
+    Closed,
+    /// The remote shell exited or the server closed the channel
+    Remote { exit_status: Option<u32> },
+    /// The connection underneath went away
+    Lost,
 }
 
-/// SSH terminal implementation using russh
+type SharedState = Arc<std::sync::Mutex<TerminalState>>;
+type ConnectionSlot = Arc<std::sync::Mutex<Option<PooledSession>>>;
+
+/// SSH terminal implementation using russh.
+///
+/// The connection itself is leased from the shared pool; the terminal only owns its
+/// shell channel, so closing the terminal leaves the connection to other users. When the
+/// connection drops, the channel task reconnects according to the reconnect policy.
 pub struct SSHTerminal {
+    id: String,
     config: TerminalConfig,
     ssh_profile: SSHProfile,
-    state: TerminalState,
-    session: Option<Arc<Handle<ClientHandler>>>,
-    channel: Option<Channel<Msg>>,
-    handler: Arc<ClientHandler>,
-    database_service: Option<Arc<tokio::sync::Mutex<crate::database::service::DatabaseService>>>,
+    state: SharedState,
+    connection_pool: Arc<SSHConnectionPool>,
+    connection: ConnectionSlot,
+    commands: Option<mpsc::UnboundedSender<ChannelCommand>>,
+    events: Option<mpsc::UnboundedReceiver<ChannelEvent>>,
 }
 
 impl SSHTerminal {
@@ -194,19 +97,17 @@ impl SSHTerminal {
         id: String,
         config: TerminalConfig,
         ssh_profile: SSHProfile,
-        database_service: Option<
-            Arc<tokio::sync::Mutex<crate::database::service::DatabaseService>>,
-        >,
+        connection_pool: Arc<SSHConnectionPool>,
     ) -> Result<Self, AppError> {
-        let handler = Arc::new(ClientHandler::new(id.clone()));
         Ok(SSHTerminal {
+            id,
             config,
             ssh_profile,
-            state: TerminalState::Disconnected,
-            session: None,
-            channel: None,
-            handler,
-            database_service,
+            state: Arc::new(std::sync::Mutex::new(TerminalState::Disconnected)),
+            connection_pool,
+            connection: Arc::new(std::sync::Mutex::new(None)),
+            commands: None,
+            events: None,
         })
     }
 
@@ -218,677 +119,529 @@ impl SSHTerminal {
     /// Connect to the SSH server with optionally resolved key data
     pub async fn connect_with_resolved_data(
         &mut self,
-        resolved_key: Option<crate::models::ssh::key::ResolvedSSHKey>,
+        resolved_key: Option<ResolvedSSHKey>,
     ) -> Result<(), AppError> {
-        self.state = TerminalState::Connecting;
+        self.set_state(TerminalState::Connecting);
 
-        let keepalive_interval = if self.ssh_profile.keep_alive {
-            Some(std::time::Duration::from_secs(15))
-        } else {
-            None
+        let (connection, channel) = match open_shell(
+            &self.connection_pool,
+            &self.ssh_profile,
+            resolved_key.clone(),
+            DEFAULT_PTY_SIZE,
+        )
+        .await
+        {
+            Ok(opened) => opened,
+            Err(e) => {
+                self.set_state(TerminalState::Disconnected);
+                return Err(e);
+            }
         };
 
-        let inactivity_timeout = self
-            .ssh_profile
-            .timeout
-            .map(|t| std::time::Duration::from_secs(t as u64));
-
-        let mut config = russh::client::Config {
-            inactivity_timeout,
-            keepalive_interval,
-            keepalive_max: 10,
-            ..<russh::client::Config as Default>::default()
+        if let Ok(mut slot) = self.connection.lock() {
+            *slot = Some(connection);
+        }
+
+        let (command_sender, command_receiver) = mpsc::unbounded_channel();
+        let (event_sender, event_receiver) = mpsc::unbounded_channel();
+        let task = ShellTask {
+            profile: self.ssh_profile.clone(),
+            resolved_key,
+            connection_pool: self.connection_pool.clone(),
+            policy: self.config.reconnect_policy.clone().unwrap_or_default(),
+            connection: self.connection.clone(),
+            state: self.state.clone(),
+            size: DEFAULT_PTY_SIZE,
+            events: event_sender,
         };
+        tokio::spawn(task.run(channel, command_receiver));
+
+        self.commands = Some(command_sender);
+        self.events = Some(event_receiver);
+        self.set_state(TerminalState::Connected);
 
-        config.window_size = 2097152;
-        config.maximum_packet_size = 32768;
+        Ok(())
+    }
 
-        let config = Arc::new(config);
+    /// Disconnect from the SSH terminal. Only the shell channel is closed, the pooled
+    /// connection stays open until it is idle.
+    pub async fn disconnect(&mut self) -> Result<(), AppError> {
+        if let Some(commands) = self.commands.take() {
+            let _ = commands.send(ChannelCommand::Close);
+        }
 
-        let handler = (*self.handler).clone();
+        self.events = None;
+        if let Ok(mut slot) = self.connection.lock() {
+            *slot = None;
+        }
+        self.set_state(TerminalState::Disconnected);
+        Ok(())
+    }
 
-        // Clone jump_hosts to avoid borrow checker issues
-        let jump_hosts_cloned = self.ssh_profile.jump_hosts.clone();
+    fn set_state(&self, state: TerminalState) {
+        if let Ok(mut current) = self.state.lock() {
+            *current = state;
+        }
+    }
 
-        // Check if we need to connect via jump hosts
-        let mut session = if let Some(jump_hosts) = &jump_hosts_cloned {
-            if !jump_hosts.is_empty() {
-                self.connect_via_jump_hosts(
-                    jump_hosts,
-                    config.clone(),
-                    handler,
-                    resolved_key.clone(),
-                )
-                .await?
-            } else {
-                self.connect_direct(config, handler).await?
-            }
-        } else {
-            self.connect_direct(config, handler).await?
+    /// Send a request to the channel task, marking the terminal disconnected if it is gone
+    fn send_command(&mut self, command: ChannelCommand) -> Result<(), AppError> {
+        let Some(commands) = &self.commands else {
+            return Err(AppError::terminal_error(
+                "SSH terminal not connected".to_string(),
+            ));
         };
 
-        // Authenticate with the final host (only if we didn't go through jump hosts,
-        // as jump host path handles its own authentication)
-        if jump_hosts_cloned.is_none()
-            || jump_hosts_cloned
-                .as_ref()
-                .map(|jh| jh.is_empty())
-                .unwrap_or(true)
-        {
-            self.authenticate_with_resolved_data(&mut session, resolved_key)
-                .await?;
+        if commands.send(command).is_err() {
+            self.set_state(TerminalState::Disconnected);
+            return Err(AppError::terminal_error(
+                "SSH session is closed".to_string(),
+            ));
         }
+        Ok(())
+    }
 
-        let channel = session.channel_open_session().await.map_err(|e| {
-            self.state = TerminalState::Disconnected;
-            AppError::terminal_error(format!("Failed to open SSH channel: {}", e))
-        })?;
-
-        let _ = channel
-            .request_pty(
-                false,
-                "xterm-256color",
-                80,
-                24,
-                0,
-                0,
-                &[
-                    (russh::Pty::TTY_OP_ISPEED, 38400),
-                    (russh::Pty::TTY_OP_OSPEED, 38400),
-                ],
-            )
-            .await;
+    /// Write data to the SSH terminal
+    pub async fn write(&mut self, data: &[u8]) -> Result<(), AppError> {
+        self.send_command(ChannelCommand::Data(data.to_vec()))
+    }
 
-        // Handle command, working directory, and environment variables
-        let mut command_parts: Vec<String> = Vec::new();
+    /// Resize the SSH terminal
+    pub async fn resize(&mut self, cols: u16, rows: u16) -> Result<(), AppError> {
+        self.send_command(ChannelCommand::Resize { cols, rows })
+    }
 
-        // Inject environment variables via export commands
-        if let Some(env) = &self.ssh_profile.env {
-            for (key, value) in env {
-                // Escape single quotes in value
-                let escaped_value = value.replace("'", "'\\''");
-                command_parts.push(format!("export {}='{}'", key, escaped_value));
-            }
-        }
+    /// Get current state of the terminal
+    pub fn get_state(&self) -> TerminalState {
+        self.state
+            .lock()
+            .map(|state| state.clone())
+            .unwrap_or(TerminalState::Disconnected)
+    }
 
-        if let Some(wd) = &self.ssh_profile.working_dir {
-            if !wd.is_empty() {
-                command_parts.push(format!("cd \"{}\"", wd));
-            }
-        }
+    /// Get terminal configuration
+    pub fn get_config(&self) -> &TerminalConfig {
+        &self.config
+    }
 
-        if let Some(cmd) = &self.ssh_profile.command {
-            if !cmd.is_empty() {
-                command_parts.push(cmd.clone());
-            }
-        }
+    /// TERM and SHELL of the remote session. The login shell is looked up with an exec
+    /// channel on the terminal's connection.
+    ///
+    /// The returned lookup borrows nothing from the terminal, so callers can await the
+    /// remote exec without holding the terminal lock.
+    pub fn recording_env(&self) -> BoxFuture<'static, RecordingEnv> {
+        let term = self
+            .ssh_profile
+            .env
+            .as_ref()
+            .and_then(|env| env.get("TERM").cloned())
+            .unwrap_or_else(|| PTY_TERM.to_string());
+
+        let connection = self.connection.lock().ok().and_then(|slot| slot.clone());
+        let sink = OutputSink {
+            exec_id: self.id.clone(),
+            profile_id: self.ssh_profile.base.id.clone(),
+            events: None,
+        };
 
-        if !command_parts.is_empty() {
-            // Join parts with && to ensure sequence
-            let mut full_command = command_parts.join(" && ");
-            // Append shell execution to keep session open
-            full_command.push_str("; exec ${SHELL:-bash} -l");
+        async move {
+            let shell = match connection {
+                Some(connection) => {
+                    let cancel = CancellationToken::new();
+                    let lookup = run_command(&connection, "printf '%s' \"$SHELL\"", &sink, &cancel);
+                    match run_with_timeout(SHELL_LOOKUP_TIMEOUT, &cancel, lookup).await {
+                        Some(Ok(output)) if !output.stdout.trim().is_empty() => {
+                            Some(output.stdout.trim().to_string())
+                        }
+                        Some(Err(e)) => {
+                            warn!("Failed to look up remote shell of {}: {}", sink.exec_id, e);
+                            None
+                        }
+                        _ => None,
+                    }
+                }
+                None => None,
+            };
 
-            let _ = channel.exec(false, full_command.as_bytes()).await;
-        } else {
-            let _ = channel.request_shell(false).await;
+            RecordingEnv {
+                term: Some(term),
+                shell,
+            }
         }
-
-        self.session = Some(Arc::new(session));
-        self.channel = Some(channel);
-        self.state = TerminalState::Connected;
-
-        Ok(())
+        .boxed()
     }
 
-    /// Authenticate with the SSH server
-    pub async fn authenticate_with_resolved_data(
+    /// Start reading from SSH terminal and send output to the provided sender
+    pub async fn start_read_loop(
         &mut self,
-        session: &mut Handle<ClientHandler>,
-        resolved_key: Option<ResolvedSSHKey>,
+        sender: mpsc::UnboundedSender<Vec<u8>>,
+        _title_sender: Option<mpsc::UnboundedSender<String>>,
+        exit_sender: Option<mpsc::UnboundedSender<TerminalExited>>,
+        latency_sender: Option<mpsc::UnboundedSender<TerminalLatency>>,
+        state_sender: Option<mpsc::UnboundedSender<TerminalStateChanged>>,
     ) -> Result<(), AppError> {
-        let username = &self.ssh_profile.username;
-
-        match &self.ssh_profile.auth_data {
-            AuthData::Password { password } => {
-                let result = session
-                    .authenticate_password(username, password)
-                    .await
-                    .map_err(|e| {
-                        AppError::authentication_failed(format!(
-                            "Password authentication error for user '{}': {}",
-                            username, e
-                        ))
-                    })?;
-
-                if !result {
-                    return Err(AppError::authentication_failed(format!(
-                        "Password authentication failed for user '{}'",
-                        username
-                    )));
-                }
-            }
-            AuthData::KeyReference { key_id } => {
-                let key_data = resolved_key.ok_or_else(|| {
-                    AppError::authentication_failed(format!(
-                        "No resolved key data provided for KeyReference {}",
-                        key_id
-                    ))
-                })?;
-
-                let key = if Path::new(&key_data.private_key).exists() {
-                    russh_keys::load_secret_key(
-                        &key_data.private_key,
-                        key_data.passphrase.as_deref(),
-                    )
-                    .map_err(|e| {
-                        AppError::authentication_failed(format!("Failed to load SSH key: {}", e))
-                    })?
-                } else {
-                    russh_keys::decode_secret_key(
-                        &key_data.private_key,
-                        key_data.passphrase.as_deref(),
-                    )
-                    .map_err(|e| {
-                        AppError::authentication_failed(format!("Failed to parse SSH key: {}", e))
-                    })?
-                };
-
-                let result = session
-                    .authenticate_publickey(username, Arc::new(key))
-                    .await
-                    .map_err(|e| {
-                        AppError::authentication_failed(format!(
-                            "SSH key authentication error for user '{}': {}",
-                            username, e
-                        ))
-                    })?;
-
-                if !result {
-                    return Err(AppError::authentication_failed(format!(
-                        "SSH key authentication failed for user '{}'",
-                        username
-                    )));
+        if let Some(mut events) = self.events.take() {
+            let terminal_id = self.id.clone();
+            tokio::spawn(async move {
+                while let Some(event) = events.recv().await {
+                    match event {
+                        ChannelEvent::Output(data) => {
+                            let _ = sender.send(data);
+                        }
+                        ChannelEvent::State(state) => {
+                            if let Some(state_sender) = &state_sender {
+                                let _ = state_sender.send(TerminalStateChanged {
+                                    terminal_id: terminal_id.clone(),
+                                    state,
+                                });
+                            }
+                        }
+                        ChannelEvent::Exited {
+                            message,
+                            exit_code,
+                            reason,
+                        } => {
+                            let _ = sender.send(message.into_bytes());
+                            if let Some(exit_sender) = &exit_sender {
+                                let _ = exit_sender.send(TerminalExited {
+                                    terminal_id: terminal_id.clone(),
+                                    exit_code,
+                                    reason: Some(reason.to_string()),
+                                });
+                            }
+                            break;
+                        }
+                    }
                 }
+            });
+        }
 
-                if let Some(db_service) = &self.database_service {
-                    let db = db_service.lock().await;
-                    if let Err(e) = db.mark_key_used(key_id).await {
-                        warn!("Warning: Failed to mark SSH key {} as used: {}", key_id, e);
+        // Spawn latency measurement task
+        if let (Some(latency_sender), Some(commands)) = (latency_sender, &self.commands) {
+            let connection = self.connection.clone();
+            let commands = commands.clone();
+            let terminal_id = self.id.clone();
+
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(std::time::Duration::from_secs(5));
+                loop {
+                    interval.tick().await;
+
+                    // Stop once the shell channel is gone so the lease is released
+                    if commands.is_closed() {
+                        break;
+                    }
+
+                    // The lease changes when the terminal reconnects
+                    let Some(connection) = connection.lock().ok().and_then(|slot| slot.clone())
+                    else {
+                        continue;
+                    };
+
+                    let start = std::time::Instant::now();
+                    // Use channel_open_session as a ping mechanism
+                    // It involves a round-trip to the server
+                    if let Ok(channel) = connection.open_session_channel().await {
+                        let latency = start.elapsed().as_millis() as u64;
+                        // Close the channel immediately
+                        let _ = channel.close().await;
+
+                        let event = TerminalLatency {
+                            terminal_id: terminal_id.clone(),
+                            latency_ms: latency,
+                        };
+                        if latency_sender.send(event).is_err() {
+                            break;
+                        }
                     }
                 }
-            }
-            AuthData::Certificate {
-                certificate,
-                private_key,
-                key_type: _,
-                validity_period: _,
-            } => {
-                let key = if Path::new(private_key).exists() {
-                    russh_keys::load_secret_key(private_key, None).map_err(|e| {
-                        AppError::authentication_failed(format!(
-                            "Failed to load private key: {}",
-                            e
-                        ))
-                    })?
-                } else {
-                    russh_keys::decode_secret_key(private_key, None).map_err(|e| {
-                        AppError::authentication_failed(format!(
-                            "Failed to parse private key: {}",
-                            e
-                        ))
-                    })?
-                };
-
-                let _cert = if Path::new(certificate).exists() {
-                    russh_keys::load_public_key(certificate).map_err(|e| {
-                        AppError::authentication_failed(format!(
-                            "Failed to load certificate: {}",
-                            e
-                        ))
-                    })?
-                } else {
-                    russh_keys::parse_public_key_base64(certificate).map_err(|e| {
-                        AppError::authentication_failed(format!(
-                            "Failed to parse certificate: {}",
-                            e
-                        ))
-                    })?
-                };
-
-                let result = session
-                    .authenticate_publickey(username, Arc::new(key))
-                    .await
-                    .map_err(|e| {
-                        AppError::authentication_failed(format!(
-                            "Certificate authentication error for user '{}': {}",
-                            username, e
-                        ))
-                    })?;
-
-                if !result {
-                    return Err(AppError::authentication_failed(format!(
-                        "Certificate authentication failed for user '{}'",
-                        username
-                    )));
-                }
-            }
+            });
         }
 
         Ok(())
     }
+}
 
-    /// Connect directly to the target (with optional proxy)
-    async fn connect_direct(
-        &mut self,
-        config: Arc<russh::client::Config>,
-        handler: ClientHandler,
-    ) -> Result<Handle<ClientHandler>, AppError> {
-        if let Some(proxy_config) = &self.ssh_profile.proxy {
-            let stream =
-                create_proxy_stream(proxy_config, &self.ssh_profile.host, self.ssh_profile.port)
-                    .await
-                    .map_err(|e| {
-                        self.state = TerminalState::Disconnected;
-                        AppError::connection_failed(format!(
-                            "Failed to create proxy connection: {}",
-                            e
-                        ))
-                    })?;
-
-            russh::client::connect_stream(config, stream, handler)
-                .await
-                .map_err(|e| {
-                    self.state = TerminalState::Disconnected;
-                    AppError::connection_failed(format!(
-                        "Failed to connect to SSH server {}:{}: {}",
-                        self.ssh_profile.host, self.ssh_profile.port, e
-                    ))
-                })
-        } else {
-            russh::client::connect(
-                config,
-                (self.ssh_profile.host.as_str(), self.ssh_profile.port),
-                handler,
+/// Lease a connection for the profile (walking its jump chain) and start a shell on it
+async fn open_shell(
+    connection_pool: &SSHConnectionPool,
+    profile: &SSHProfile,
+    resolved_key: Option<ResolvedSSHKey>,
+    (cols, rows): (u16, u16),
+) -> Result<(PooledSession, Channel<Msg>), AppError> {
+    let connection = connection_pool.acquire(profile, resolved_key).await?;
+
+    let channel = connection
+        .open_session_channel()
+        .await
+        .map_err(|e| AppError::terminal_error(e.to_string()))?;
+
+    // The connection's handler was built with the same flag, as it is part of the pool key
+    if profile.forward_agent && connection.forwards_agent() {
+        if let Err(e) = channel.agent_forward(false).await {
+            warn!("Failed to request agent forwarding: {}", e);
+        }
+    }
+
+    if let Some(x11) = connection.x11_forwarder().filter(|_| profile.forward_x11) {
+        if let Err(e) = channel
+            .request_x11(
+                false,
+                false,
+                X11_AUTH_PROTOCOL,
+                x11.cookie_hex(),
+                x11.screen(),
             )
             .await
-            .map_err(|e| {
-                self.state = TerminalState::Disconnected;
-                AppError::connection_failed(format!(
-                    "Failed to connect to SSH server {}:{}: {}",
-                    self.ssh_profile.host, self.ssh_profile.port, e
-                ))
-            })
+        {
+            warn!("Failed to request X11 forwarding: {}", e);
         }
     }
 
-    /// Connect to the final target via jump hosts
-    async fn connect_via_jump_hosts(
-        &mut self,
-        jump_hosts: &[crate::models::ssh::profile::JumpHostConfig],
-        config: Arc<russh::client::Config>,
-        handler: ClientHandler,
-        _resolved_key: Option<ResolvedSSHKey>,
-    ) -> Result<Handle<ClientHandler>, AppError> {
-        // We need to chain connections through jump hosts
-        // Support profile_id references and inline config
-
-        let db_service = self.database_service.as_ref().ok_or_else(|| {
-            AppError::Config("Database service required for jump host resolution".to_string())
-        })?;
-
-        // Collect all jump profiles we need
-        let mut jump_profiles = Vec::new();
-        for jh in jump_hosts {
-            if let Some(profile_id) = &jh.profile_id {
-                // Get the profile with decrypted password
-                let profile = {
-                    let db = db_service.lock().await;
-                    db.get_ssh_profile(profile_id).await.map_err(|e| {
-                        AppError::Config(format!(
-                            "Failed to get jump host profile '{}': {}",
-                            profile_id, e
-                        ))
-                    })?
-                }; // Lock released here
-                jump_profiles.push(profile);
-            } else if let (Some(host), Some(port), Some(username)) =
-                (&jh.host, jh.port, &jh.username)
-            {
-                // Create a temporary profile from inline config
-                let mut temp_profile = crate::models::ssh::SSHProfile::new(
-                    "temp".to_string(),
-                    format!("jump-{}", host),
-                    host.clone(),
-                    port,
-                    username.clone(),
-                );
-                if let Some(auth_method) = &jh.auth_method {
-                    temp_profile.auth_method = auth_method.clone();
-                }
-                if let Some(auth_data) = &jh.auth_data {
-                    temp_profile.auth_data = auth_data.clone();
-                }
-                jump_profiles.push(temp_profile);
-            } else {
-                return Err(AppError::Config(
-                    "Invalid jump host config: need either profile_id or inline config".to_string(),
-                ));
-            }
-        }
+    let _ = channel
+        .request_pty(
+            false,
+            PTY_TERM,
+            cols as u32,
+            rows as u32,
+            0,
+            0,
+            &[
+                (russh::Pty::TTY_OP_ISPEED, 38400),
+                (russh::Pty::TTY_OP_OSPEED, 38400),
+            ],
+        )
+        .await;
+
+    // Handle command, working directory, and environment variables
+    let mut command_parts: Vec<String> = Vec::new();
 
-        if jump_profiles.is_empty() {
-            return self.connect_direct(config, handler).await;
+    // Inject environment variables via export commands
+    if let Some(env) = &profile.env {
+        for (key, value) in env {
+            // Escape single quotes in value
+            let escaped_value = value.replace("'", "'\\''");
+            command_parts.push(format!("export {}='{}'", key, escaped_value));
         }
+    }
 
-        // Connect to the first jump host
-        let first_jump = &jump_profiles[0];
-        let jump_handler = ClientHandler::new(format!("jump-{}", first_jump.host));
+    if let Some(wd) = &profile.working_dir {
+        if !wd.is_empty() {
+            command_parts.push(format!("cd \"{}\"", wd));
+        }
+    }
 
-        let mut current_session: Handle<ClientHandler> = russh::client::connect(
-            config.clone(),
-            (first_jump.host.as_str(), first_jump.port),
-            jump_handler,
-        )
-        .await
-        .map_err(|e| {
-            self.state = TerminalState::Disconnected;
-            AppError::connection_failed(format!(
-                "Failed to connect to first jump host {}:{}: {}",
-                first_jump.host, first_jump.port, e
-            ))
-        })?;
-
-        // Authenticate with first jump host
-        self.authenticate_jump_host(&mut current_session, first_jump)
-            .await?;
-
-        // Chain through remaining jump hosts
-        for i in 1..jump_profiles.len() {
-            let next_jump = &jump_profiles[i];
-            let jump_handler = ClientHandler::new(format!("jump-{}", next_jump.host));
-
-            // Open a direct TCP/IP channel to the next jump host
-            let channel = current_session
-                .channel_open_direct_tcpip(&next_jump.host, next_jump.port as u32, "127.0.0.1", 0)
-                .await
-                .map_err(|e| {
-                    self.state = TerminalState::Disconnected;
-                    AppError::connection_failed(format!(
-                        "Failed to forward to jump host {}:{}: {}",
-                        next_jump.host, next_jump.port, e
-                    ))
-                })?;
-
-            // Connect through the forwarded channel
-            current_session =
-                russh::client::connect_stream(config.clone(), channel.into_stream(), jump_handler)
-                    .await
-                    .map_err(|e| {
-                        self.state = TerminalState::Disconnected;
-                        AppError::connection_failed(format!(
-                            "Failed to establish SSH through channel to {}:{}: {}",
-                            next_jump.host, next_jump.port, e
-                        ))
-                    })?;
-
-            // Authenticate with this jump host
-            self.authenticate_jump_host(&mut current_session, next_jump)
-                .await?;
+    if let Some(cmd) = &profile.command {
+        if !cmd.is_empty() {
+            command_parts.push(cmd.clone());
         }
+    }
 
-        // Now forward to the final target
-        let channel = current_session
-            .channel_open_direct_tcpip(
-                &self.ssh_profile.host,
-                self.ssh_profile.port as u32,
-                "127.0.0.1",
-                0,
-            )
-            .await
-            .map_err(|e| {
-                self.state = TerminalState::Disconnected;
-                AppError::connection_failed(format!(
-                    "Failed to forward to final target {}:{}: {}",
-                    self.ssh_profile.host, self.ssh_profile.port, e
-                ))
-            })?;
-
-        // Connect to final target through the channel
-        let mut final_session =
-            russh::client::connect_stream(config, channel.into_stream(), handler)
-                .await
-                .map_err(|e| {
-                    self.state = TerminalState::Disconnected;
-                    AppError::connection_failed(format!(
-                        "Failed to establish SSH to final target {}:{}: {}",
-                        self.ssh_profile.host, self.ssh_profile.port, e
-                    ))
-                })?;
-
-        // Authenticate with the final target
-        self.authenticate_with_resolved_data(&mut final_session, None)
-            .await?;
-
-        Ok(final_session)
+    if !command_parts.is_empty() {
+        // Join parts with && to ensure sequence
+        let mut full_command = command_parts.join(" && ");
+        // Append shell execution to keep session open
+        full_command.push_str("; exec ${SHELL:-bash} -l");
+
+        let _ = channel.exec(false, full_command.as_bytes()).await;
+    } else {
+        let _ = channel.request_shell(false).await;
     }
 
-    /// Authenticate with a jump host
-    async fn authenticate_jump_host(
-        &self,
-        session: &mut Handle<ClientHandler>,
-        profile: &crate::models::ssh::SSHProfile,
-    ) -> Result<(), AppError> {
-        use crate::models::ssh::AuthData;
-
-        let username = &profile.username;
-
-        match &profile.auth_data {
-            AuthData::Password { password } => {
-                let result = session
-                    .authenticate_password(username, password)
-                    .await
-                    .map_err(|e| {
-                        AppError::authentication_failed(format!(
-                            "Jump host password auth error for '{}@{}': {}",
-                            username, profile.host, e
-                        ))
-                    })?;
-
-                if !result {
-                    return Err(AppError::authentication_failed(format!(
-                        "Jump host password auth failed for '{}@{}'",
-                        username, profile.host
-                    )));
+    Ok((connection, channel))
+}
+
+/// Owns the shell channel: applies writes and resizes from the terminal, reports output
+/// and state changes back to it, and replaces the channel when the connection is lost
+struct ShellTask {
+    profile: SSHProfile,
+    resolved_key: Option<ResolvedSSHKey>,
+    connection_pool: Arc<SSHConnectionPool>,
+    policy: ReconnectPolicy,
+    connection: ConnectionSlot,
+    state: SharedState,
+    /// Last known PTY size, used when the shell is reopened
+    size: (u16, u16),
+    events: mpsc::UnboundedSender<ChannelEvent>,
+}
+
+impl ShellTask {
+    async fn run(
+        mut self,
+        mut channel: Channel<Msg>,
+        mut commands: mpsc::UnboundedReceiver<ChannelCommand>,
+    ) {
+        loop {
+            match self.pump(&mut channel, &mut commands).await {
+                ChannelEnd::Closed => return,
+                ChannelEnd::Remote { exit_status } => {
+                    self.set_state(TerminalState::Disconnected);
+                    let _ = self.events.send(ChannelEvent::Exited {
+                        message: "[SSH: Connection closed by remote host]\r\n".to_string(),
+                        exit_code: Some(exit_status.unwrap_or(0) as i32),
+                        reason: "remote-closed",
+                    });
+                    return;
                 }
+                ChannelEnd::Lost => match self.reconnect(&mut commands).await {
+                    Some(reopened) => channel = reopened,
+                    None => return,
+                },
             }
-            AuthData::KeyReference { key_id } => {
-                // Resolve the key from database
-                if let Some(db_service) = &self.database_service {
-                    let db = db_service.lock().await;
-                    let key = db.get_ssh_key(key_id).await.map_err(|e| {
-                        AppError::authentication_failed(format!(
-                            "Failed to get jump host key: {}",
-                            e
-                        ))
-                    })?;
-                    let secret_key =
-                        russh_keys::decode_secret_key(&key.private_key, key.passphrase.as_deref())
-                            .map_err(|e| {
-                                AppError::authentication_failed(format!(
-                                    "Failed to parse jump host key: {}",
-                                    e
-                                ))
-                            })?;
-
-                    let result = session
-                        .authenticate_publickey(username, Arc::new(secret_key))
-                        .await
-                        .map_err(|e| {
-                            AppError::authentication_failed(format!(
-                                "Jump host key auth error for '{}@{}': {}",
-                                username, profile.host, e
-                            ))
-                        })?;
-
-                    if !result {
-                        return Err(AppError::authentication_failed(format!(
-                            "Jump host key auth failed for '{}@{}'",
-                            username, profile.host
-                        )));
+        }
+    }
+
+    /// Move data between the terminal and the channel until one side ends
+    async fn pump(
+        &mut self,
+        channel: &mut Channel<Msg>,
+        commands: &mut mpsc::UnboundedReceiver<ChannelCommand>,
+    ) -> ChannelEnd {
+        let mut exit_status: Option<u32> = None;
+        let mut remote_eof = false;
+
+        loop {
+            tokio::select! {
+                command = commands.recv() => match command {
+                    Some(ChannelCommand::Data(data)) => {
+                        if let Err(e) = channel.data(&data[..]).await {
+                            warn!("Failed to write to SSH channel: {}", e);
+                        }
                     }
-                } else {
-                    return Err(AppError::Config(
-                        "Database service required for key auth".to_string(),
-                    ));
-                }
-            }
-            AuthData::Certificate { private_key, .. } => {
-                let secret_key = russh_keys::decode_secret_key(private_key, None).map_err(|e| {
-                    AppError::authentication_failed(format!(
-                        "Failed to parse jump host certificate key: {}",
-                        e
-                    ))
-                })?;
-
-                let result = session
-                    .authenticate_publickey(username, Arc::new(secret_key))
-                    .await
-                    .map_err(|e| {
-                        AppError::authentication_failed(format!(
-                            "Jump host certificate auth error for '{}@{}': {}",
-                            username, profile.host, e
-                        ))
-                    })?;
-
-                if !result {
-                    return Err(AppError::authentication_failed(format!(
-                        "Jump host certificate auth failed for '{}@{}'",
-                        username, profile.host
-                    )));
-                }
+                    Some(ChannelCommand::Resize { cols, rows }) => {
+                        self.size = (cols, rows);
+                        if let Err(e) = channel.window_change(cols as u32, rows as u32, 0, 0).await {
+                            warn!("Failed to resize SSH terminal: {}", e);
+                        }
+                    }
+                    Some(ChannelCommand::Close) | None => {
+                        let _ = channel.eof().await;
+                        let _ = channel.close().await;
+                        return ChannelEnd::Closed;
+                    }
+                },
+                msg = channel.wait() => match msg {
+                    Some(ChannelMsg::Data { data }) => {
+                        let _ = self.events.send(ChannelEvent::Output(data.to_vec()));
+                    }
+                    Some(ChannelMsg::ExtendedData { data, .. }) => {
+                        let _ = self.events.send(ChannelEvent::Output(data.to_vec()));
+                    }
+                    Some(ChannelMsg::ExitStatus { exit_status: status }) => {
+                        exit_status = Some(status);
+                    }
+                    Some(ChannelMsg::Eof) => {
+                        remote_eof = true;
+                    }
+                    // The server closing the channel is an orderly end of the shell
+                    Some(ChannelMsg::Close) => return ChannelEnd::Remote { exit_status },
+                    Some(_) => {}
+                    None if remote_eof || exit_status.is_some() => {
+                        return ChannelEnd::Remote { exit_status }
+                    }
+                    // The channel vanished without a close, so the connection is gone
+                    None => return ChannelEnd::Lost,
+                },
             }
         }
-
-        Ok(())
     }
 
-    /// Disconnect from the SSH terminal
-    pub async fn disconnect(&mut self) -> Result<(), AppError> {
-        if let Some(channel) = self.channel.take() {
-            let _ = channel.eof().await;
-            let _ = channel.close().await;
-        }
+    /// Reopen the shell on a fresh connection with exponential backoff. Returns None when
+    /// the terminal was closed meanwhile or every attempt failed, after reporting the exit.
+    async fn reconnect(
+        &mut self,
+        commands: &mut mpsc::UnboundedReceiver<ChannelCommand>,
+    ) -> Option<Channel<Msg>> {
+        // Release the dead connection so the pool does not hand it out again
+        let lost = self.connection.lock().ok().and_then(|mut slot| slot.take());
+        let (message, reason) = match lost.as_ref().and_then(|c| c.connection_loss()) {
+            Some(loss) => (loss.message, loss.reason),
+            None => ("Connection lost".to_string(), "network-lost"),
+        };
+        drop(lost);
 
-        if let Some(session) = self.session.take() {
-            let _ = session
-                .disconnect(Disconnect::ByApplication, "", "en")
-                .await;
+        if !self.policy.enabled || self.policy.max_attempts == 0 {
+            self.exit_lost(&message, reason);
+            return None;
         }
 
-        self.state = TerminalState::Disconnected;
-        Ok(())
-    }
-
-    /// Write data to the SSH terminal
-    pub async fn write(&mut self, data: &[u8]) -> Result<(), AppError> {
-        if let Some(session) = &self.session {
-            if session.is_closed() {
-                self.state = TerminalState::Disconnected;
-                return Err(AppError::terminal_error(
-                    "SSH session is closed".to_string(),
-                ));
+        let max_attempts = self.policy.max_attempts;
+        for attempt in 1..=max_attempts {
+            self.set_state(TerminalState::Reconnecting {
+                attempt,
+                max_attempts,
+            });
+            self.output(format!(
+                "\r\n[SSH: {} - reconnecting (attempt {}/{})...]\r\n",
+                message, attempt, max_attempts
+            ));
+
+            // Keep serving the terminal while waiting out the backoff
+            let backoff = tokio::time::sleep(self.policy.delay(attempt));
+            tokio::pin!(backoff);
+            loop {
+                tokio::select! {
+                    _ = &mut backoff => break,
+                    command = commands.recv() => match command {
+                        Some(ChannelCommand::Resize { cols, rows }) => self.size = (cols, rows),
+                        // Input typed while disconnected has nowhere to go
+                        Some(ChannelCommand::Data(_)) => {}
+                        Some(ChannelCommand::Close) | None => {
+                            self.set_state(TerminalState::Disconnected);
+                            return None;
+                        }
+                    },
+                }
             }
-        }
 
-        if let Some(channel) = &mut self.channel {
-            channel.data(data).await.map_err(|e| {
-                self.state = TerminalState::Disconnected;
-                AppError::terminal_error(format!("Failed to write to SSH channel: {}", e))
-            })?;
-            Ok(())
-        } else {
-            Err(AppError::terminal_error(
-                "SSH terminal not connected".to_string(),
-            ))
-        }
-    }
-    /// Resize the SSH terminal
-    pub async fn resize(&mut self, cols: u16, rows: u16) -> Result<(), AppError> {
-        if let Some(channel) = &mut self.channel {
-            channel
-                .window_change(cols as u32, rows as u32, 0, 0)
-                .await
-                .map_err(|e| {
-                    self.state = TerminalState::Disconnected;
-                    AppError::terminal_error(format!("Failed to resize SSH terminal: {}", e))
-                })?;
-            Ok(())
-        } else {
-            Err(AppError::terminal_error(
-                "SSH terminal not connected".to_string(),
-            ))
+            match open_shell(
+                &self.connection_pool,
+                &self.profile,
+                self.resolved_key.clone(),
+                self.size,
+            )
+            .await
+            {
+                Ok((connection, channel)) => {
+                    info!(
+                        "Reconnected SSH terminal to {}@{} after {} attempt(s)",
+                        self.profile.username, self.profile.host, attempt
+                    );
+                    if let Ok(mut slot) = self.connection.lock() {
+                        *slot = Some(connection);
+                    }
+                    self.set_state(TerminalState::Connected);
+                    self.output("[SSH: Reconnected]\r\n".to_string());
+                    return Some(channel);
+                }
+                Err(e) => {
+                    warn!("SSH reconnect attempt {} failed: {}", attempt, e);
+                    self.output(format!("[SSH: Reconnect failed - {}]\r\n", e));
+                }
+            }
         }
-    }
 
-    /// Get current state of the terminal
-    pub fn get_state(&self) -> TerminalState {
-        self.state.clone()
+        self.exit_lost(&message, reason);
+        None
     }
 
-    /// Get terminal configuration
-    pub fn get_config(&self) -> &TerminalConfig {
-        &self.config
+    fn exit_lost(&self, message: &str, reason: &'static str) {
+        self.set_state(TerminalState::Disconnected);
+        let _ = self.events.send(ChannelEvent::Exited {
+            message: format!("[SSH: {}]\r\n", message),
+            exit_code: Some(1),
+            reason,
+        });
     }
 
-    /// Start reading from SSH terminal and send output to the provided sender
-    pub async fn start_read_loop(
-        &mut self,
-        sender: mpsc::UnboundedSender<Vec<u8>>,
-        _title_sender: Option<mpsc::UnboundedSender<String>>,
-        exit_sender: Option<mpsc::UnboundedSender<crate::models::terminal::TerminalExited>>,
-        latency_sender: Option<mpsc::UnboundedSender<crate::models::terminal::TerminalLatency>>,
-    ) -> Result<(), AppError> {
-        self.handler.set_output_sender(sender).await;
-        if let Some(exit_sender) = exit_sender {
-            self.handler.set_exit_sender(exit_sender).await;
-        }
-
-        // Spawn latency measurement task
-        if let Some(latency_sender) = latency_sender {
-            if let Some(session) = &self.session {
-                let session_handle = session.clone();
-                let terminal_id = self.handler.terminal_id.lock().await.clone();
-
-                tokio::spawn(async move {
-                    let mut interval = tokio::time::interval(std::time::Duration::from_secs(5));
-                    loop {
-                        interval.tick().await;
-
-                        let start = std::time::Instant::now();
-                        // Use channel_open_session as a ping mechanism
-                        // It involves a round-trip to the server
-                        match session_handle.channel_open_session().await {
-                            Ok(channel) => {
-                                let latency = start.elapsed().as_millis() as u64;
-                                // Close the channel immediately
-                                let _ = channel.close().await;
-
-                                let event = crate::models::terminal::TerminalLatency {
-                                    terminal_id: terminal_id.clone(),
-                                    latency_ms: latency,
-                                };
-                                if latency_sender.send(event).is_err() {
-                                    break;
-                                }
-                            }
-                            Err(_) => {}
-                        }
-                    }
-                });
-            }
+    fn set_state(&self, state: TerminalState) {
+        if let Ok(mut current) = self.state.lock() {
+            *current = state.clone();
         }
+        let _ = self.events.send(ChannelEvent::State(state));
+    }
 
-        Ok(())
+    fn output(&self, message: String) {
+        let _ = self.events.send(ChannelEvent::Output(message.into_bytes()));
     }
 }
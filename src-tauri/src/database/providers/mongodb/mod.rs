@@ -70,6 +70,7 @@ impl MongoDBProvider {
             "ssh_groups",
             "ssh_keys",
             "ssh_tunnels",
+            "ssh_known_hosts",
             "saved_commands",
             "saved_command_groups",
         ];
@@ -72,6 +72,9 @@ impl MySQLProvider {
                 timeout INT,
                 keep_alive BOOLEAN NOT NULL DEFAULT TRUE,
                 compression BOOLEAN NOT NULL DEFAULT FALSE,
+                forward_agent BOOLEAN NOT NULL DEFAULT FALSE,
+                forward_x11 BOOLEAN NOT NULL DEFAULT FALSE,
+                session_log TEXT,
                 proxy TEXT,
                 created_at DATETIME NOT NULL,
                 updated_at DATETIME NOT NULL,
@@ -127,7 +130,13 @@ impl MySQLProvider {
                 local_port INT NOT NULL,
                 remote_host VARCHAR(255),
                 remote_port INT,
+                local_socket_path TEXT,
+                remote_socket_path TEXT,
                 auto_start BOOLEAN NOT NULL DEFAULT FALSE,
+                restart_policy TEXT,
+                health_probe TEXT,
+                socks_auth TEXT,
+                access_control TEXT,
                 created_at DATETIME NOT NULL,
                 updated_at DATETIME NOT NULL,
                 device_id VARCHAR(255) NOT NULL,
@@ -139,6 +148,25 @@ impl MySQLProvider {
             ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4
             "#,
             r#"
+            CREATE TABLE IF NOT EXISTS ssh_known_hosts (
+                id VARCHAR(36) PRIMARY KEY,
+                host VARCHAR(255) NOT NULL,
+                port INT NOT NULL,
+                jump_chain TEXT,
+                key_type VARCHAR(100) NOT NULL,
+                public_key TEXT NOT NULL,
+                fingerprint VARCHAR(255) NOT NULL,
+                comment TEXT,
+                created_at DATETIME NOT NULL,
+                updated_at DATETIME NOT NULL,
+                device_id VARCHAR(255) NOT NULL,
+                version BIGINT NOT NULL DEFAULT 1,
+                sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced',
+                INDEX idx_ssh_known_hosts_host_port (host, port),
+                INDEX idx_ssh_known_hosts_updated_at (updated_at)
+            ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4
+            "#,
+            r#"
             CREATE TABLE IF NOT EXISTS saved_commands (
                 id VARCHAR(36) PRIMARY KEY,
                 name VARCHAR(255) NOT NULL,
@@ -182,6 +210,58 @@ impl MySQLProvider {
                 .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
         }
 
+        // Add forward_agent column migration
+        sqlx::query(
+            "ALTER TABLE ssh_profiles ADD COLUMN forward_agent BOOLEAN NOT NULL DEFAULT FALSE",
+        )
+        .execute(&*pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+        // Add forward_x11 column migration
+        sqlx::query(
+            "ALTER TABLE ssh_profiles ADD COLUMN forward_x11 BOOLEAN NOT NULL DEFAULT FALSE",
+        )
+        .execute(&*pool)
+        .await
+        .ok();
+
+        // Add session_log column migration
+        sqlx::query("ALTER TABLE ssh_profiles ADD COLUMN session_log TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+
+        // Add tunnel restart policy and health probe columns migration
+        sqlx::query("ALTER TABLE ssh_tunnels ADD COLUMN restart_policy TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+        sqlx::query("ALTER TABLE ssh_tunnels ADD COLUMN health_probe TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+
+        // Add SOCKS authentication and access control columns migration
+        sqlx::query("ALTER TABLE ssh_tunnels ADD COLUMN socks_auth TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+        sqlx::query("ALTER TABLE ssh_tunnels ADD COLUMN access_control TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+
+        // Add unix socket forwarding columns migration
+        sqlx::query("ALTER TABLE ssh_tunnels ADD COLUMN local_socket_path TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+        sqlx::query("ALTER TABLE ssh_tunnels ADD COLUMN remote_socket_path TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+
         Ok(())
     }
 }
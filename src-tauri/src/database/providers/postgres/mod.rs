@@ -73,6 +73,9 @@ impl PostgreSQLProvider {
                 timeout INTEGER,
                 keep_alive BOOLEAN NOT NULL DEFAULT TRUE,
                 compression BOOLEAN NOT NULL DEFAULT FALSE,
+                forward_agent BOOLEAN NOT NULL DEFAULT FALSE,
+                forward_x11 BOOLEAN NOT NULL DEFAULT FALSE,
+                session_log TEXT,
                 proxy TEXT,
                 created_at TEXT NOT NULL,
                 updated_at TEXT NOT NULL,
@@ -123,7 +126,13 @@ impl PostgreSQLProvider {
                 local_port INTEGER NOT NULL,
                 remote_host VARCHAR(255),
                 remote_port INTEGER,
+                local_socket_path TEXT,
+                remote_socket_path TEXT,
                 auto_start BOOLEAN NOT NULL DEFAULT FALSE,
+                restart_policy TEXT,
+                health_probe TEXT,
+                socks_auth TEXT,
+                access_control TEXT,
                 created_at TEXT NOT NULL,
                 updated_at TEXT NOT NULL,
                 device_id VARCHAR(255) NOT NULL,
@@ -133,6 +142,23 @@ impl PostgreSQLProvider {
             )
             "#,
             r#"
+            CREATE TABLE IF NOT EXISTS ssh_known_hosts (
+                id VARCHAR(36) PRIMARY KEY,
+                host VARCHAR(255) NOT NULL,
+                port INTEGER NOT NULL,
+                jump_chain TEXT,
+                key_type VARCHAR(100) NOT NULL,
+                public_key TEXT NOT NULL,
+                fingerprint VARCHAR(255) NOT NULL,
+                comment TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                device_id VARCHAR(255) NOT NULL,
+                version BIGINT NOT NULL DEFAULT 1,
+                sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced'
+            )
+            "#,
+            r#"
             CREATE TABLE IF NOT EXISTS saved_commands (
                 id VARCHAR(36) PRIMARY KEY,
                 name VARCHAR(255) NOT NULL,
@@ -181,6 +207,8 @@ impl PostgreSQLProvider {
             "CREATE INDEX IF NOT EXISTS idx_ssh_keys_updated_at ON ssh_keys (updated_at)",
             "CREATE INDEX IF NOT EXISTS idx_ssh_tunnels_profile_id ON ssh_tunnels (profile_id)",
             "CREATE INDEX IF NOT EXISTS idx_ssh_tunnels_updated_at ON ssh_tunnels (updated_at)",
+            "CREATE INDEX IF NOT EXISTS idx_ssh_known_hosts_host_port ON ssh_known_hosts (host, port)",
+            "CREATE INDEX IF NOT EXISTS idx_ssh_known_hosts_updated_at ON ssh_known_hosts (updated_at)",
             "CREATE INDEX IF NOT EXISTS idx_saved_commands_group_id ON saved_commands (group_id)",
             "CREATE INDEX IF NOT EXISTS idx_saved_commands_updated_at ON saved_commands (updated_at)",
             "CREATE INDEX IF NOT EXISTS idx_saved_command_groups_updated_at ON saved_command_groups (updated_at)",
@@ -193,6 +221,58 @@ impl PostgreSQLProvider {
                 .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
         }
 
+        // Add forward_agent column migration
+        sqlx::query(
+            "ALTER TABLE ssh_profiles ADD COLUMN forward_agent BOOLEAN NOT NULL DEFAULT FALSE",
+        )
+        .execute(&*pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+        // Add forward_x11 column migration
+        sqlx::query(
+            "ALTER TABLE ssh_profiles ADD COLUMN forward_x11 BOOLEAN NOT NULL DEFAULT FALSE",
+        )
+        .execute(&*pool)
+        .await
+        .ok();
+
+        // Add session_log column migration
+        sqlx::query("ALTER TABLE ssh_profiles ADD COLUMN session_log TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+
+        // Add tunnel restart policy and health probe columns migration
+        sqlx::query("ALTER TABLE ssh_tunnels ADD COLUMN restart_policy TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+        sqlx::query("ALTER TABLE ssh_tunnels ADD COLUMN health_probe TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+
+        // Add SOCKS authentication and access control columns migration
+        sqlx::query("ALTER TABLE ssh_tunnels ADD COLUMN socks_auth TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+        sqlx::query("ALTER TABLE ssh_tunnels ADD COLUMN access_control TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+
+        // Add unix socket forwarding columns migration
+        sqlx::query("ALTER TABLE ssh_tunnels ADD COLUMN local_socket_path TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+        sqlx::query("ALTER TABLE ssh_tunnels ADD COLUMN remote_socket_path TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+
         Ok(())
     }
 }
@@ -0,0 +1,125 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use sqlx::Row;
+
+use crate::{
+    database::error::{DatabaseError, DatabaseResult},
+    models::ssh::KnownHost,
+};
+
+use super::SQLiteProvider;
+
+pub async fn save_known_host(provider: &SQLiteProvider, model: &KnownHost) -> DatabaseResult<()> {
+    let pool = provider.get_pool()?;
+    let pool = pool.read().await;
+
+    sqlx::query(
+        r#"
+        INSERT OR REPLACE INTO ssh_known_hosts (
+            id, host, port, jump_chain, key_type, public_key, fingerprint, comment,
+            created_at, updated_at, device_id, version, sync_status
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    "#,
+    )
+    .bind(&model.base.id)
+    .bind(&model.host)
+    .bind(model.port as i32)
+    .bind(&model.jump_chain)
+    .bind(&model.key_type)
+    .bind(&model.public_key)
+    .bind(&model.fingerprint)
+    .bind(&model.comment)
+    .bind(model.base.created_at.to_rfc3339())
+    .bind(model.base.updated_at.to_rfc3339())
+    .bind(&model.base.device_id)
+    .bind(model.base.version as i64)
+    .bind(serde_json::to_string(&model.base.sync_status).unwrap())
+    .execute(&*pool)
+    .await
+    .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Helper to parse a KnownHost from a SQLite row
+fn parse_known_host(row: &sqlx::sqlite::SqliteRow) -> DatabaseResult<KnownHost> {
+    Ok(KnownHost {
+        base: crate::models::base::BaseModel {
+            id: row.get("id"),
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))
+                .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?
+                .with_timezone(&chrono::Utc),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))
+                .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?
+                .with_timezone(&chrono::Utc),
+            device_id: row.get("device_id"),
+            version: row.get::<i64, _>("version") as u64,
+            sync_status: serde_json::from_str(&row.get::<String, _>("sync_status"))
+                .unwrap_or(crate::database::traits::SyncStatus::Synced),
+        },
+        host: row.get("host"),
+        port: row.get::<i32, _>("port") as u16,
+        jump_chain: row.get("jump_chain"),
+        key_type: row.get("key_type"),
+        public_key: row.get("public_key"),
+        fingerprint: row.get("fingerprint"),
+        comment: row.get("comment"),
+    })
+}
+
+pub async fn find_known_host_by_id(
+    provider: &SQLiteProvider,
+    id: &str,
+) -> DatabaseResult<Option<KnownHost>> {
+    let pool = provider.get_pool()?;
+    let pool = pool.read().await;
+
+    let row = sqlx::query("SELECT * FROM ssh_known_hosts WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    row.as_ref().map(parse_known_host).transpose()
+}
+
+pub async fn find_all_known_hosts(provider: &SQLiteProvider) -> DatabaseResult<Vec<KnownHost>> {
+    let pool = provider.get_pool()?;
+    let pool = pool.read().await;
+
+    let rows = sqlx::query("SELECT * FROM ssh_known_hosts ORDER BY host, port")
+        .fetch_all(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    rows.iter().map(parse_known_host).collect()
+}
+
+pub async fn delete_known_host(provider: &SQLiteProvider, id: &str) -> DatabaseResult<()> {
+    let pool = provider.get_pool()?;
+    let pool = pool.read().await;
+
+    sqlx::query("DELETE FROM ssh_known_hosts WHERE id = ?")
+        .bind(id)
+        .execute(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(())
+}
@@ -18,6 +18,7 @@
 
 mod auth;
 mod command;
+mod known_host;
 mod ssh;
 pub mod sync_ops;
 mod terminal;
@@ -99,6 +100,9 @@ impl Database for SQLiteProvider {
                 timeout INTEGER,
                 keep_alive BOOLEAN NOT NULL DEFAULT true,
                 compression BOOLEAN NOT NULL DEFAULT false,
+                forward_agent BOOLEAN NOT NULL DEFAULT false,
+                forward_x11 BOOLEAN NOT NULL DEFAULT false,
+                session_log TEXT,
                 command TEXT,
                 working_dir TEXT,
                 env TEXT,
@@ -179,7 +183,13 @@ impl Database for SQLiteProvider {
                 local_port INTEGER NOT NULL,
                 remote_host TEXT,
                 remote_port INTEGER,
+                local_socket_path TEXT,
+                remote_socket_path TEXT,
                 auto_start BOOLEAN NOT NULL DEFAULT false,
+                restart_policy TEXT,
+                health_probe TEXT,
+                socks_auth TEXT,
+                access_control TEXT,
                 created_at TEXT NOT NULL,
                 updated_at TEXT NOT NULL,
                 device_id TEXT NOT NULL,
@@ -203,6 +213,39 @@ impl Database for SQLiteProvider {
         .await
         .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
 
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS ssh_known_hosts (
+                id TEXT PRIMARY KEY,
+                host TEXT NOT NULL,
+                port INTEGER NOT NULL,
+                jump_chain TEXT,
+                key_type TEXT NOT NULL,
+                public_key TEXT NOT NULL,
+                fingerprint TEXT NOT NULL,
+                comment TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                device_id TEXT NOT NULL,
+                version INTEGER NOT NULL DEFAULT 1,
+                sync_status TEXT NOT NULL DEFAULT 'Clean'
+            )
+        "#,
+        )
+        .execute(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_ssh_known_hosts_host_port
+            ON ssh_known_hosts(host, port)
+        "#,
+        )
+        .execute(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
         sqlx::query(
             r#"
             CREATE TABLE IF NOT EXISTS master_passwords (
@@ -426,6 +469,7 @@ impl Database for SQLiteProvider {
                 color TEXT,
                 command TEXT,
                 is_default BOOLEAN NOT NULL DEFAULT 0,
+                session_log TEXT,
                 created_at INTEGER NOT NULL,
                 updated_at INTEGER NOT NULL
             )
@@ -469,6 +513,62 @@ impl Database for SQLiteProvider {
             .await
             .ok();
 
+        // Add forward_agent column migration
+        sqlx::query(
+            "ALTER TABLE ssh_profiles ADD COLUMN forward_agent BOOLEAN NOT NULL DEFAULT false",
+        )
+        .execute(&*pool)
+        .await
+        .ok();
+
+        // Add forward_x11 column migration
+        sqlx::query(
+            "ALTER TABLE ssh_profiles ADD COLUMN forward_x11 BOOLEAN NOT NULL DEFAULT false",
+        )
+        .execute(&*pool)
+        .await
+        .ok();
+
+        // Add session_log columns migration
+        sqlx::query("ALTER TABLE ssh_profiles ADD COLUMN session_log TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+        sqlx::query("ALTER TABLE terminal_profiles ADD COLUMN session_log TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+
+        // Add tunnel restart policy and health probe columns migration
+        sqlx::query("ALTER TABLE ssh_tunnels ADD COLUMN restart_policy TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+        sqlx::query("ALTER TABLE ssh_tunnels ADD COLUMN health_probe TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+
+        // Add SOCKS authentication and access control columns migration
+        sqlx::query("ALTER TABLE ssh_tunnels ADD COLUMN socks_auth TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+        sqlx::query("ALTER TABLE ssh_tunnels ADD COLUMN access_control TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+
+        // Add tunnel unix socket endpoint columns migration
+        sqlx::query("ALTER TABLE ssh_tunnels ADD COLUMN local_socket_path TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+        sqlx::query("ALTER TABLE ssh_tunnels ADD COLUMN remote_socket_path TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+
         Ok(())
     }
 
@@ -775,6 +875,28 @@ impl SQLiteProvider {
         terminal::find_default_terminal_profile(self).await
     }
 
+    pub async fn save_known_host(
+        &self,
+        model: &crate::models::ssh::KnownHost,
+    ) -> DatabaseResult<()> {
+        known_host::save_known_host(self, model).await
+    }
+
+    pub async fn find_known_host_by_id(
+        &self,
+        id: &str,
+    ) -> DatabaseResult<Option<crate::models::ssh::KnownHost>> {
+        known_host::find_known_host_by_id(self, id).await
+    }
+
+    pub async fn find_all_known_hosts(&self) -> DatabaseResult<Vec<crate::models::ssh::KnownHost>> {
+        known_host::find_all_known_hosts(self).await
+    }
+
+    pub async fn delete_known_host(&self, id: &str) -> DatabaseResult<()> {
+        known_host::delete_known_host(self, id).await
+    }
+
     pub async fn get_all_external_databases(
         &self,
     ) -> DatabaseResult<Vec<crate::models::sync::external_db::ExternalDatabaseConfig>> {
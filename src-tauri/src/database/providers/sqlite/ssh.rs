@@ -33,9 +33,9 @@ pub async fn save_ssh_profile(provider: &SQLiteProvider, model: &SSHProfile) ->
         r#"
         INSERT INTO ssh_profiles (
             id, name, host, port, username, group_id, auth_method, auth_data, jump_hosts,
-            description, color, timeout, keep_alive, compression, command, working_dir, env, created_at, updated_at,
+            description, color, timeout, keep_alive, compression, forward_agent, forward_x11, session_log, command, working_dir, env, created_at, updated_at,
             device_id, version, sync_status
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         ON CONFLICT(id) DO UPDATE SET
             name = excluded.name,
             host = excluded.host,
@@ -50,6 +50,9 @@ pub async fn save_ssh_profile(provider: &SQLiteProvider, model: &SSHProfile) ->
             timeout = excluded.timeout,
             keep_alive = excluded.keep_alive,
             compression = excluded.compression,
+            forward_agent = excluded.forward_agent,
+            forward_x11 = excluded.forward_x11,
+            session_log = excluded.session_log,
             command = excluded.command,
             working_dir = excluded.working_dir,
             env = excluded.env,
@@ -73,6 +76,14 @@ pub async fn save_ssh_profile(provider: &SQLiteProvider, model: &SSHProfile) ->
     .bind(model.timeout.map(|t| t as i32))
     .bind(model.keep_alive)
     .bind(model.compression)
+    .bind(model.forward_agent)
+    .bind(model.forward_x11)
+    .bind(
+        model
+            .session_log
+            .as_ref()
+            .map(|log| serde_json::to_string(log).unwrap_or_default()),
+    )
     .bind(&model.command)
     .bind(&model.working_dir)
     .bind(serde_json::to_string(&model.env).unwrap_or_default())
@@ -96,7 +107,7 @@ pub async fn find_ssh_profile_by_id(
     let pool = pool.read().await;
 
     let row = sqlx::query(
-        "SELECT id, name, host, port, username, group_id, auth_method, auth_data, jump_hosts, description, color, timeout, keep_alive, compression, command, working_dir, env, created_at, updated_at, device_id, version, sync_status FROM ssh_profiles WHERE id = ?"
+        "SELECT id, name, host, port, username, group_id, auth_method, auth_data, jump_hosts, description, color, timeout, keep_alive, compression, forward_agent, forward_x11, session_log, command, working_dir, env, created_at, updated_at, device_id, version, sync_status FROM ssh_profiles WHERE id = ?"
     )
     .bind(id)
     .fetch_optional(&*pool)
@@ -134,7 +145,13 @@ pub async fn find_ssh_profile_by_id(
             timeout: row.get::<Option<i32>, _>("timeout").map(|t| t as u32),
             keep_alive: row.get("keep_alive"),
             compression: row.get("compression"),
+            forward_agent: row.get("forward_agent"),
+            forward_x11: row.get("forward_x11"),
+            session_log: row
+                .get::<Option<String>, _>("session_log")
+                .and_then(|s| serde_json::from_str(&s).ok()),
             proxy: None,
+            proxy_command: None,
             jump_hosts: row
                 .get::<Option<String>, _>("jump_hosts")
                 .and_then(|s| serde_json::from_str(&s).ok()),
@@ -155,7 +172,7 @@ pub async fn find_all_ssh_profiles(provider: &SQLiteProvider) -> DatabaseResult<
     let pool = pool.read().await;
 
     let rows = sqlx::query(
-        "SELECT id, name, host, port, username, group_id, auth_method, auth_data, jump_hosts, description, color, timeout, keep_alive, compression, command, working_dir, env, created_at, updated_at, device_id, version, sync_status FROM ssh_profiles ORDER BY name"
+        "SELECT id, name, host, port, username, group_id, auth_method, auth_data, jump_hosts, description, color, timeout, keep_alive, compression, forward_agent, forward_x11, session_log, command, working_dir, env, created_at, updated_at, device_id, version, sync_status FROM ssh_profiles ORDER BY name"
     )
     .fetch_all(&*pool)
     .await
@@ -193,7 +210,13 @@ pub async fn find_all_ssh_profiles(provider: &SQLiteProvider) -> DatabaseResult<
             timeout: row.get::<Option<i32>, _>("timeout").map(|t| t as u32),
             keep_alive: row.get("keep_alive"),
             compression: row.get("compression"),
+            forward_agent: row.get("forward_agent"),
+            forward_x11: row.get("forward_x11"),
+            session_log: row
+                .get::<Option<String>, _>("session_log")
+                .and_then(|s| serde_json::from_str(&s).ok()),
             proxy: None,
+            proxy_command: None,
             jump_hosts: row
                 .get::<Option<String>, _>("jump_hosts")
                 .and_then(|s| serde_json::from_str(&s).ok()),
@@ -32,12 +32,17 @@ pub async fn save_terminal_profile(
     let pool = pool_arc.read().await;
 
     let env_json = serde_json::to_string(&profile.env)?;
+    let session_log_json = profile
+        .session_log
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()?;
 
     sqlx::query(
         r#"
         INSERT OR REPLACE INTO terminal_profiles (
-            id, name, shell, working_dir, env, icon, color, command, is_default, created_at, updated_at
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            id, name, shell, working_dir, env, icon, color, command, is_default, session_log, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
     )
     .bind(&profile.id)
@@ -49,6 +54,7 @@ pub async fn save_terminal_profile(
     .bind(&profile.color)
     .bind(&profile.command)
     .bind(profile.is_default)
+    .bind(session_log_json)
     .bind(profile.created_at)
     .bind(profile.updated_at)
     .execute(&*pool)
@@ -73,6 +79,11 @@ fn parse_terminal_profile(row: &sqlx::sqlite::SqliteRow) -> TerminalProfile {
         color: row.try_get("color").ok(),
         command: row.try_get("command").ok(),
         is_default: row.try_get("is_default").unwrap_or(false),
+        session_log: row
+            .try_get::<Option<String>, _>("session_log")
+            .ok()
+            .flatten()
+            .and_then(|s| serde_json::from_str(&s).ok()),
         created_at: row.try_get("created_at").unwrap_or_default(),
         updated_at: row.try_get("updated_at").unwrap_or_default(),
     }
@@ -87,7 +98,7 @@ pub async fn find_terminal_profile_by_id(
 
     let row = sqlx::query(
         r#"
-        SELECT id, name, shell, working_dir, env, icon, color, command, is_default, created_at, updated_at
+        SELECT id, name, shell, working_dir, env, icon, color, command, is_default, session_log, created_at, updated_at
         FROM terminal_profiles
         WHERE id = ?
         "#,
@@ -108,7 +119,7 @@ pub async fn find_all_terminal_profiles(
 
     let rows = sqlx::query(
         r#"
-        SELECT id, name, shell, working_dir, env, icon, color, command, is_default, created_at, updated_at
+        SELECT id, name, shell, working_dir, env, icon, color, command, is_default, session_log, created_at, updated_at
         FROM terminal_profiles
         ORDER BY name ASC
         "#,
@@ -129,7 +140,7 @@ pub async fn find_default_terminal_profile(
 
     let row = sqlx::query(
         r#"
-        SELECT id, name, shell, working_dir, env, icon, color, command, is_default, created_at, updated_at
+        SELECT id, name, shell, working_dir, env, icon, color, command, is_default, session_log, created_at, updated_at
         FROM terminal_profiles
         WHERE is_default = 1
         LIMIT 1
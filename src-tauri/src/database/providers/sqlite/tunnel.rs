@@ -33,9 +33,10 @@ pub async fn save_ssh_tunnel(provider: &SQLiteProvider, model: &SSHTunnel) -> Da
         r#"
         INSERT OR REPLACE INTO ssh_tunnels (
             id, name, description, profile_id, tunnel_type, local_host, local_port,
-            remote_host, remote_port, auto_start, created_at, updated_at,
-            device_id, version, sync_status
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            remote_host, remote_port, local_socket_path, remote_socket_path, auto_start,
+            restart_policy, health_probe, socks_auth, access_control,
+            created_at, updated_at, device_id, version, sync_status
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
     "#,
     )
     .bind(&model.base.id)
@@ -47,7 +48,23 @@ pub async fn save_ssh_tunnel(provider: &SQLiteProvider, model: &SSHTunnel) -> Da
     .bind(model.local_port as i32)
     .bind(&model.remote_host)
     .bind(model.remote_port.map(|p| p as i32))
+    .bind(&model.local_socket_path)
+    .bind(&model.remote_socket_path)
     .bind(model.auto_start)
+    .bind(serde_json::to_string(&model.restart_policy).unwrap_or_default())
+    .bind(
+        model
+            .health_probe
+            .as_ref()
+            .map(|probe| serde_json::to_string(probe).unwrap_or_default()),
+    )
+    .bind(
+        model
+            .socks_auth
+            .as_ref()
+            .map(|auth| serde_json::to_string(auth).unwrap_or_default()),
+    )
+    .bind(serde_json::to_string(&model.access_control).unwrap_or_default())
     .bind(model.base.created_at.to_rfc3339())
     .bind(model.base.updated_at.to_rfc3339())
     .bind(&model.base.device_id)
@@ -101,7 +118,23 @@ pub async fn find_ssh_tunnel_by_id(
             local_port: row.get::<i32, _>("local_port") as u16,
             remote_host: row.get("remote_host"),
             remote_port: row.get::<Option<i32>, _>("remote_port").map(|p| p as u16),
+            local_socket_path: row.get("local_socket_path"),
+            remote_socket_path: row.get("remote_socket_path"),
             auto_start: row.get("auto_start"),
+            restart_policy: row
+                .get::<Option<String>, _>("restart_policy")
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            health_probe: row
+                .get::<Option<String>, _>("health_probe")
+                .and_then(|s| serde_json::from_str(&s).ok()),
+            socks_auth: row
+                .get::<Option<String>, _>("socks_auth")
+                .and_then(|s| serde_json::from_str(&s).ok()),
+            access_control: row
+                .get::<Option<String>, _>("access_control")
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
             status: crate::models::ssh::TunnelStatus::default(),
             error_message: None,
         };
@@ -149,7 +182,23 @@ pub async fn find_all_ssh_tunnels(provider: &SQLiteProvider) -> DatabaseResult<V
             local_port: row.get::<i32, _>("local_port") as u16,
             remote_host: row.get("remote_host"),
             remote_port: row.get::<Option<i32>, _>("remote_port").map(|p| p as u16),
+            local_socket_path: row.get("local_socket_path"),
+            remote_socket_path: row.get("remote_socket_path"),
             auto_start: row.get("auto_start"),
+            restart_policy: row
+                .get::<Option<String>, _>("restart_policy")
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            health_probe: row
+                .get::<Option<String>, _>("health_probe")
+                .and_then(|s| serde_json::from_str(&s).ok()),
+            socks_auth: row
+                .get::<Option<String>, _>("socks_auth")
+                .and_then(|s| serde_json::from_str(&s).ok()),
+            access_control: row
+                .get::<Option<String>, _>("access_control")
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
             status: crate::models::ssh::TunnelStatus::default(),
             error_message: None,
         };
@@ -199,7 +248,23 @@ pub async fn find_auto_start_ssh_tunnels(
             local_port: row.get::<i32, _>("local_port") as u16,
             remote_host: row.get("remote_host"),
             remote_port: row.get::<Option<i32>, _>("remote_port").map(|p| p as u16),
+            local_socket_path: row.get("local_socket_path"),
+            remote_socket_path: row.get("remote_socket_path"),
             auto_start: row.get("auto_start"),
+            restart_policy: row
+                .get::<Option<String>, _>("restart_policy")
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            health_probe: row
+                .get::<Option<String>, _>("health_probe")
+                .and_then(|s| serde_json::from_str(&s).ok()),
+            socks_auth: row
+                .get::<Option<String>, _>("socks_auth")
+                .and_then(|s| serde_json::from_str(&s).ok()),
+            access_control: row
+                .get::<Option<String>, _>("access_control")
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
             status: crate::models::ssh::TunnelStatus::default(),
             error_message: None,
         };
@@ -819,11 +819,23 @@ impl DatabaseService {
         let mut tunnel = tunnel;
         tunnel.description = request.description;
         tunnel.auto_start = request.auto_start.unwrap_or(false);
+        tunnel.restart_policy = request.restart_policy.unwrap_or_default();
+        tunnel.health_probe = request.health_probe;
+        tunnel.socks_auth = request.socks_auth;
+        tunnel.access_control = request.access_control.unwrap_or_default();
+        tunnel.local_socket_path = request.local_socket_path;
+        tunnel.remote_socket_path = request.remote_socket_path;
 
         tunnel.validate().map_err(DatabaseError::ValidationError)?;
 
+        let mut stored = tunnel.clone();
+        if stored.has_encrypted_data() {
+            let mp_manager = self.master_password_manager.read().await;
+            stored.encrypt_fields(&*mp_manager)?;
+        }
+
         let local_db = self.local_db.read().await;
-        local_db.save_ssh_tunnel(&tunnel).await?;
+        local_db.save_ssh_tunnel(&stored).await?;
 
         Ok(tunnel)
     }
@@ -831,16 +843,24 @@ impl DatabaseService {
     /// Get all SSH tunnels
     pub async fn get_ssh_tunnels(&self) -> DatabaseResult<Vec<crate::models::ssh::SSHTunnel>> {
         let local_db = self.local_db.read().await;
-        local_db.find_all_ssh_tunnels().await
+        let tunnels = local_db.find_all_ssh_tunnels().await?;
+        self.decrypt_ssh_tunnels(tunnels).await
     }
 
     /// Get SSH tunnel by ID
     pub async fn get_ssh_tunnel(&self, id: &str) -> DatabaseResult<crate::models::ssh::SSHTunnel> {
         let local_db = self.local_db.read().await;
-        local_db
+        let mut tunnel = local_db
             .find_ssh_tunnel_by_id(id)
             .await?
-            .ok_or_else(|| DatabaseError::NotFound(format!("SSH tunnel {} not found", id)))
+            .ok_or_else(|| DatabaseError::NotFound(format!("SSH tunnel {} not found", id)))?;
+
+        if tunnel.has_encrypted_data() {
+            let mp_manager = self.master_password_manager.read().await;
+            tunnel.decrypt_fields(&*mp_manager)?;
+        }
+
+        Ok(tunnel)
     }
 
     /// Get SSH tunnels that have auto-start enabled
@@ -848,7 +868,25 @@ impl DatabaseService {
         &self,
     ) -> DatabaseResult<Vec<crate::models::ssh::SSHTunnel>> {
         let local_db = self.local_db.read().await;
-        local_db.find_auto_start_ssh_tunnels().await
+        let tunnels = local_db.find_auto_start_ssh_tunnels().await?;
+        self.decrypt_ssh_tunnels(tunnels).await
+    }
+
+    /// Decrypt the SOCKS credentials of loaded tunnels
+    async fn decrypt_ssh_tunnels(
+        &self,
+        mut tunnels: Vec<crate::models::ssh::SSHTunnel>,
+    ) -> DatabaseResult<Vec<crate::models::ssh::SSHTunnel>> {
+        if tunnels.iter().any(|tunnel| tunnel.has_encrypted_data()) {
+            let mp_manager = self.master_password_manager.read().await;
+            for tunnel in tunnels
+                .iter_mut()
+                .filter(|tunnel| tunnel.has_encrypted_data())
+            {
+                tunnel.decrypt_fields(&*mp_manager)?;
+            }
+        }
+        Ok(tunnels)
     }
 
     /// Update SSH tunnel
@@ -863,6 +901,12 @@ impl DatabaseService {
             .await?
             .ok_or_else(|| DatabaseError::NotFound(format!("SSH tunnel {} not found", id)))?;
 
+        // Decrypt first so an unchanged SOCKS password is not encrypted twice
+        if tunnel.has_encrypted_data() {
+            let mp_manager = self.master_password_manager.read().await;
+            tunnel.decrypt_fields(&*mp_manager)?;
+        }
+
         if let Some(name) = request.name {
             tunnel.name = name;
         }
@@ -888,15 +932,39 @@ impl DatabaseService {
         if let Some(remote_port) = request.remote_port {
             tunnel.remote_port = Some(remote_port);
         }
+        if let Some(local_socket_path) = request.local_socket_path {
+            tunnel.local_socket_path = local_socket_path;
+        }
+        if let Some(remote_socket_path) = request.remote_socket_path {
+            tunnel.remote_socket_path = remote_socket_path;
+        }
         if let Some(auto_start) = request.auto_start {
             tunnel.auto_start = auto_start;
         }
+        if let Some(restart_policy) = request.restart_policy {
+            tunnel.restart_policy = restart_policy;
+        }
+        if let Some(health_probe) = request.health_probe {
+            tunnel.health_probe = health_probe;
+        }
+        if let Some(socks_auth) = request.socks_auth {
+            tunnel.socks_auth = socks_auth;
+        }
+        if let Some(access_control) = request.access_control {
+            tunnel.access_control = access_control;
+        }
 
         tunnel.validate().map_err(DatabaseError::ValidationError)?;
 
         tunnel.base.touch();
 
-        local_db.save_ssh_tunnel(&tunnel).await?;
+        let mut stored = tunnel.clone();
+        if stored.has_encrypted_data() {
+            let mp_manager = self.master_password_manager.read().await;
+            stored.encrypt_fields(&*mp_manager)?;
+        }
+
+        local_db.save_ssh_tunnel(&stored).await?;
 
         Ok(tunnel)
     }
@@ -907,6 +975,31 @@ impl DatabaseService {
         local_db.delete_ssh_tunnel(id).await
     }
 
+    /// Save a trusted host key
+    pub async fn save_known_host(
+        &self,
+        known_host: &crate::models::ssh::KnownHost,
+    ) -> DatabaseResult<()> {
+        let local_db = self.local_db.read().await;
+        local_db.save_known_host(known_host).await
+    }
+
+    /// Get all trusted host keys
+    pub async fn get_known_hosts(&self) -> DatabaseResult<Vec<crate::models::ssh::KnownHost>> {
+        let local_db = self.local_db.read().await;
+        local_db.find_all_known_hosts().await
+    }
+
+    /// Delete a trusted host key
+    pub async fn delete_known_host(&self, id: &str) -> DatabaseResult<()> {
+        let local_db = self.local_db.read().await;
+        local_db
+            .find_known_host_by_id(id)
+            .await?
+            .ok_or_else(|| DatabaseError::NotFound(format!("Known host {} not found", id)))?;
+        local_db.delete_known_host(id).await
+    }
+
     /// Move all profiles from one group to another
     async fn move_profiles_to_group(
         &self,
@@ -1196,6 +1289,7 @@ impl DatabaseService {
             color: request.color,
             command: request.command.filter(|s| !s.is_empty()),
             is_default: false,
+            session_log: request.session_log,
             created_at: Utc::now().timestamp_millis(),
             updated_at: Utc::now().timestamp_millis(),
         };
@@ -1265,6 +1359,10 @@ impl DatabaseService {
             };
         }
 
+        if let Some(session_log) = request.session_log {
+            profile.session_log = Some(session_log);
+        }
+
         profile.updated_at = Utc::now().timestamp_millis();
 
         local_db.save_terminal_profile(&profile).await?;
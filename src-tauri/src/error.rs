@@ -31,6 +31,8 @@ pub enum AppError {
     ConflictResolutionRequired,
     MasterPasswordRequired,
     TerminalNotFound(String),
+    /// Server presented a host key different from the trusted one
+    HostKeyMismatch(String),
 }
 
 impl fmt::Display for AppError {
@@ -50,6 +52,7 @@ impl fmt::Display for AppError {
             AppError::ConflictResolutionRequired => write!(f, "Conflict resolution required"),
             AppError::MasterPasswordRequired => write!(f, "Master password required"),
             AppError::TerminalNotFound(id) => write!(f, "Terminal not found: {}", id),
+            AppError::HostKeyMismatch(msg) => write!(f, "Host key verification failed: {}", msg),
         }
     }
 }
@@ -27,6 +27,26 @@ mod services;
 mod setup;
 mod state;
 
+#[cfg(unix)]
+pub use crate::core::session_daemon::DAEMON_ARG;
+
+/// Entry point of the session daemon process that owns persistent local shells
+#[cfg(unix)]
+pub fn run_session_daemon() {
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            log::error!("Failed to start session daemon runtime: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(e) = runtime.block_on(crate::core::session_daemon::server::run()) {
+        log::error!("Session daemon stopped: {}", e);
+        std::process::exit(1);
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn main() {
     tauri::Builder::default()
@@ -46,8 +66,25 @@ pub fn main() {
             commands::terminal::create_ssh_config_terminal,
             commands::terminal::write_to_terminal,
             commands::terminal::write_batch_to_terminal,
+            commands::terminal::create_broadcast_group,
+            commands::terminal::delete_broadcast_group,
+            commands::terminal::add_broadcast_members,
+            commands::terminal::remove_broadcast_members,
+            commands::terminal::pause_broadcast_member,
+            commands::terminal::list_broadcast_groups,
+            commands::terminal::search_terminal_scrollback,
+            commands::terminal::dump_terminal_scrollback,
+            commands::terminal::get_terminal_scrollback_stats,
+            commands::terminal::get_scrollback_config,
+            commands::terminal::set_scrollback_config,
+            commands::terminal::get_ssh_config_session_log,
+            commands::terminal::set_ssh_config_session_log,
             commands::terminal::resize_terminal,
             commands::terminal::close_terminal,
+            commands::terminal::detach_terminal,
+            commands::terminal::list_persistent_sessions,
+            commands::terminal::attach_persistent_session,
+            commands::terminal::kill_persistent_session,
             commands::terminal::get_terminal_info,
             commands::terminal::list_terminals,
             commands::system::get_user_hostname,
@@ -78,7 +115,12 @@ pub fn main() {
             commands::database::ssh::move_profile_to_group,
             commands::database::ssh::duplicate_ssh_profile,
             commands::database::ssh::test_ssh_connection,
+            commands::database::ssh::respond_keyboard_interactive,
             commands::database::ssh::create_ssh_key,
+            commands::database::ssh::generate_ssh_key,
+            commands::database::ssh::deploy_public_key,
+            commands::database::ssh::exec_on_hosts,
+            commands::database::ssh::export_exec_result,
             commands::database::ssh::get_ssh_keys,
             commands::database::ssh::update_ssh_key,
             commands::database::ssh::delete_ssh_key,
@@ -88,6 +130,11 @@ pub fn main() {
             commands::database::ssh::clear_connection_pool,
             commands::database::ssh::get_connection_pool_size,
             commands::database::ssh::get_ssh_config_hosts,
+            commands::database::ssh::resolve_ssh_config_host,
+            commands::database::known_hosts::get_known_hosts,
+            commands::database::known_hosts::delete_known_host,
+            commands::database::known_hosts::respond_host_key_verification,
+            commands::database::known_hosts::import_known_hosts,
             commands::database::tunnel::create_tunnel,
             commands::database::tunnel::get_tunnels,
             commands::database::tunnel::get_tunnel,
@@ -96,6 +143,8 @@ pub fn main() {
             commands::database::tunnel::start_tunnel,
             commands::database::tunnel::stop_tunnel,
             commands::database::tunnel::get_tunnel_status,
+            commands::database::tunnel::get_tunnel_metrics,
+            commands::database::tunnel::close_tunnel_connection,
             commands::database::saved_command::create_saved_command,
             commands::database::saved_command::get_saved_commands,
             commands::database::saved_command::get_saved_command,
@@ -133,9 +182,12 @@ pub fn main() {
             commands::database::sync::register_device,
             commands::recording::start_recording,
             commands::recording::stop_recording,
+            commands::recording::add_recording_marker,
             commands::recording::list_recordings,
             commands::recording::delete_recording,
             commands::recording::export_recording,
+            commands::recording::edit_recording,
+            commands::recording::concat_recordings,
             commands::recording::read_cast_file,
             commands::sftp::sftp_connect,
             commands::sftp::sftp_disconnect,
@@ -28,3 +28,77 @@ pub struct AsciicastHeader {
     pub title: Option<String>,
     pub env: Option<serde_json::Value>,
 }
+
+/// Header of an asciicast v3 file, where the terminal size moves under `term`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AsciicastV3Header {
+    pub version: u8,
+    pub term: AsciicastTerm,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AsciicastTerm {
+    pub cols: u16,
+    pub rows: u16,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub term_type: Option<String>,
+}
+
+/// TERM and SHELL of the process behind a recorded terminal
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecordingEnv {
+    pub term: Option<String>,
+    pub shell: Option<String>,
+}
+
+/// What a recording captures besides output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingOptions {
+    /// Asciicast format version, 2 or 3
+    #[serde(default = "default_asciicast_version")]
+    pub version: u8,
+    /// Record typed input as "i" events, masked at password prompts
+    #[serde(default)]
+    pub record_input: bool,
+}
+
+impl Default for RecordingOptions {
+    fn default() -> Self {
+        Self {
+            version: default_asciicast_version(),
+            record_input: false,
+        }
+    }
+}
+
+fn default_asciicast_version() -> u8 {
+    2
+}
+
+/// One step of a recording edit, applied in order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum RecordingEdit {
+    /// Shorten pauses longer than the limit, like `asciinema rec -i`
+    CapIdle { max_idle_secs: f64 },
+    /// Play faster (or slower, below 1)
+    Speed { factor: f64 },
+    /// Remove the events between the two times and close the gap
+    Cut { start_secs: f64, end_secs: f64 },
+    /// Replace regex matches in the output and input streams
+    Redact {
+        patterns: Vec<String>,
+        replacement: Option<String>,
+    },
+}
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// Event payload warning that a profile's OpenSSH certificate is expired or about to expire
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateExpiryWarning {
+    pub profile_id: String,
+    pub profile_name: String,
+    pub host: String,
+    pub valid_to: String,
+    pub expired: bool,
+    /// Seconds until the certificate expires, negative once it has
+    pub expires_in_seconds: i64,
+}
@@ -1,8 +1,12 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-use super::profile::{AuthData, AuthMethod, KeyType, SSHProfile};
+use super::profile::{AuthData, AuthMethod, JumpHostConfig, KeyType, SSHProfile};
 use crate::database::traits::SyncStatus;
 
+/// Identity files (in ~/.ssh) OpenSSH tries when a host configures none, in its order
+const DEFAULT_IDENTITY_FILES: [&str; 3] = ["id_rsa", "id_ecdsa", "id_ed25519"];
+
 /**
  * Represents a host entry from SSH config file (~/.ssh/config)
  * This is read-only data that reflects the user's SSH configuration
@@ -22,9 +26,17 @@ pub struct SSHConfigHost {
     /// Username for connection
     pub user: Option<String>,
 
-    /// Path to identity file (private key)
+    /// Path to identity file (private key), the first of `identity_files`
     pub identity_file: Option<String>,
 
+    /// All IdentityFile entries in the order OpenSSH tries them
+    #[serde(default)]
+    pub identity_files: Vec<String>,
+
+    /// Only use the configured identity files, not the agent's keys
+    #[serde(default)]
+    pub identities_only: Option<bool>,
+
     /// ProxyJump configuration
     pub proxy_jump: Option<String>,
 
@@ -34,6 +46,30 @@ pub struct SSHConfigHost {
     /// Whether to use agent forwarding
     pub forward_agent: Option<bool>,
 
+    /// Whether to forward X11 connections
+    #[serde(default)]
+    pub forward_x11: Option<bool>,
+
+    /// ServerAliveInterval in seconds (0 disables keepalives)
+    #[serde(default)]
+    pub server_alive_interval: Option<u32>,
+
+    /// ServerAliveCountMax
+    #[serde(default)]
+    pub server_alive_count_max: Option<u32>,
+
+    /// ConnectTimeout in seconds
+    #[serde(default)]
+    pub connect_timeout: Option<u32>,
+
+    /// Whether to request compression
+    #[serde(default)]
+    pub compression: Option<bool>,
+
+    /// Effective settings like `ssh -G` prints them, keyed by lowercase keyword
+    #[serde(default)]
+    pub options: BTreeMap<String, Vec<String>>,
+
     /// Additional options from config
     pub other_options: Option<String>,
 }
@@ -43,8 +79,13 @@ impl SSHConfigHost {
      * Convert SSHConfigHost to a temporary SSHProfile for connection
      * This creates a profile that's not stored in the database
      * @param password - Optional password for authentication (used when no identity file)
+     * @param config_hosts - All hosts of the SSH config, used to resolve ProxyJump aliases
      */
-    pub fn to_temporary_profile(&self, password: Option<String>) -> Result<SSHProfile, String> {
+    pub fn to_temporary_profile(
+        &self,
+        password: Option<String>,
+        config_hosts: &[SSHConfigHost],
+    ) -> Result<SSHProfile, String> {
         use chrono::Utc;
         use uuid::Uuid;
 
@@ -55,8 +96,65 @@ impl SSHConfigHost {
             .clone()
             .ok_or_else(|| "Username is required".to_string())?;
 
-        let (auth_method, auth_data) = if let Some(identity_file) = &self.identity_file {
-            // Has key file - use certificate auth
+        let (auth_method, auth_data) = self
+            .resolve_auth(password)?
+            .ok_or_else(|| "Password is required for authentication".to_string())?;
+
+        // Like OpenSSH, ProxyJump wins over ProxyCommand when both are set
+        let jump_hosts = self.jump_host_configs(config_hosts)?;
+        let proxy_command = if jump_hosts.is_none() {
+            self.proxy_command
+                .clone()
+                .filter(|command| !command.trim().eq_ignore_ascii_case("none"))
+        } else {
+            None
+        };
+
+        Ok(SSHProfile {
+            base: BaseModel {
+                id: Uuid::new_v4().to_string(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                device_id: "temp".to_string(),
+                version: 0,
+                sync_status: SyncStatus::Synced,
+            },
+            name: self.name.clone(),
+            host: self.hostname.clone(),
+            port: self.port,
+            username,
+            group_id: None,
+            auth_method,
+            auth_data,
+            timeout: Some(self.connect_timeout.unwrap_or(30)),
+            keep_alive: self.server_alive_interval != Some(0),
+            compression: self.compression.unwrap_or(false),
+            forward_agent: self.forward_agent.unwrap_or(false),
+            forward_x11: self.forward_x11.unwrap_or(false),
+            session_log: None,
+            proxy: None,
+            proxy_command,
+            jump_hosts,
+            color: None,
+            description: Some(format!("Temporary profile from SSH config: {}", self.name)),
+            command: None,
+            working_dir: None,
+            env: None,
+        })
+    }
+
+    /**
+     * Pick the authentication for this host the way OpenSSH orders it: identity files,
+     * the running ssh-agent (unless IdentitiesOnly), then the password. Several
+     * candidates are tried in turn. None when there is nothing to try.
+     */
+    fn resolve_auth(
+        &self,
+        password: Option<String>,
+    ) -> Result<Option<(AuthMethod, AuthData)>, String> {
+        let mut factors: Vec<(AuthMethod, AuthData)> = Vec::new();
+
+        for identity_file in &self.identity_files {
             let expanded_path = if identity_file.starts_with("~/") {
                 let home = std::env::var("HOME")
                     .map_err(|_| "Cannot determine HOME directory".to_string())?;
@@ -65,6 +163,11 @@ impl SSHConfigHost {
                 identity_file.clone()
             };
 
+            // OpenSSH silently skips identity files that do not exist
+            if !std::path::Path::new(&expanded_path).exists() {
+                continue;
+            }
+
             let private_key = std::fs::read_to_string(&expanded_path)
                 .map_err(|e| format!("Failed to read identity file '{}': {}", expanded_path, e))?;
 
@@ -78,50 +181,128 @@ impl SSHConfigHost {
                 KeyType::RSA
             };
 
-            (
+            // Like OpenSSH, a certificate next to the key is offered with it
+            let certificate =
+                std::fs::read_to_string(format!("{}-cert.pub", expanded_path)).unwrap_or_default();
+
+            factors.push((
                 AuthMethod::Certificate,
                 AuthData::Certificate {
-                    certificate: String::new(),
+                    certificate,
                     private_key,
                     key_type,
                     validity_period: None,
                 },
-            )
+            ));
+        }
+
+        let agent_allowed = !self.identities_only.unwrap_or(false) || factors.is_empty();
+        if agent_allowed && std::env::var_os("SSH_AUTH_SOCK").is_some() {
+            factors.push((AuthMethod::Agent, AuthData::Agent {}));
+        }
+
+        if let Some(pwd) = password {
+            factors.push((AuthMethod::Password, AuthData::Password { password: pwd }));
+        }
+
+        if factors.len() > 1 {
+            Ok(Some((
+                AuthMethod::MultiFactor,
+                AuthData::MultiFactor {
+                    factors: factors.into_iter().map(|(_, data)| data).collect(),
+                },
+            )))
         } else {
-            // No key file - use password auth
-            let pwd =
-                password.ok_or_else(|| "Password is required for authentication".to_string())?;
+            Ok(factors.pop())
+        }
+    }
 
-            (AuthMethod::Password, AuthData::Password { password: pwd })
+    /**
+     * Turn ProxyJump (comma separated `[user@]host[:port]` hops) into jump host configs.
+     * Hops naming another config host use its HostName, Port, User and IdentityFile.
+     * Like OpenSSH, hops without an IdentityFile try the default identity files and the
+     * ssh-agent; keyboard-interactive is only the last resort when neither exists.
+     */
+    pub fn jump_host_configs(
+        &self,
+        config_hosts: &[SSHConfigHost],
+    ) -> Result<Option<Vec<JumpHostConfig>>, String> {
+        let Some(proxy_jump) = &self.proxy_jump else {
+            return Ok(None);
         };
+        if proxy_jump.trim().eq_ignore_ascii_case("none") {
+            return Ok(None);
+        }
 
-        Ok(SSHProfile {
-            base: BaseModel {
-                id: Uuid::new_v4().to_string(),
-                created_at: Utc::now(),
-                updated_at: Utc::now(),
-                device_id: "temp".to_string(),
-                version: 0,
-                sync_status: SyncStatus::Synced,
-            },
-            name: self.name.clone(),
-            host: self.hostname.clone(),
-            port: self.port,
-            username,
-            group_id: None,
-            auth_method,
-            auth_data,
-            timeout: Some(30),
-            keep_alive: true,
-            compression: false,
-            proxy: None,
-            jump_hosts: None,
-            color: None,
-            description: Some(format!("Temporary profile from SSH config: {}", self.name)),
-            command: None,
-            working_dir: None,
-            env: None,
-        })
+        let mut hops = Vec::new();
+        for spec in proxy_jump
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+        {
+            let (user, host, port) = parse_jump_spec(spec)?;
+            let alias = config_hosts.iter().find(|h| h.name == host);
+
+            let username = user
+                .or_else(|| alias.and_then(|h| h.user.clone()))
+                .or_else(|| std::env::var("USER").ok())
+                .or_else(|| std::env::var("USERNAME").ok())
+                .or_else(|| self.user.clone())
+                .ok_or_else(|| format!("Username is required for jump host '{}'", spec))?;
+
+            let mut hop = alias.cloned().unwrap_or_else(|| SSHConfigHost::bare(&host));
+            if hop.identity_files.is_empty() {
+                if let Some(ssh_dir) = dirs::home_dir().map(|home| home.join(".ssh")) {
+                    hop.identity_files = DEFAULT_IDENTITY_FILES
+                        .iter()
+                        .map(|name| ssh_dir.join(name).to_string_lossy().into_owned())
+                        .collect();
+                }
+            }
+            let resolved_auth = hop.resolve_auth(None)?;
+            let (auth_method, auth_data) = resolved_auth.unwrap_or((
+                AuthMethod::KeyboardInteractive,
+                AuthData::KeyboardInteractive {},
+            ));
+
+            hops.push(JumpHostConfig {
+                profile_id: None,
+                host: Some(alias.map(|h| h.hostname.clone()).unwrap_or(host)),
+                port: Some(port.or(alias.map(|h| h.port)).unwrap_or(22)),
+                username: Some(username),
+                auth_method: Some(auth_method),
+                auth_data: Some(auth_data),
+            });
+        }
+
+        if hops.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(hops))
+        }
+    }
+
+    /// Host entry with nothing but a name, for ProxyJump hops that are not in the config
+    fn bare(host: &str) -> Self {
+        SSHConfigHost {
+            name: host.to_string(),
+            hostname: host.to_string(),
+            port: 22,
+            user: None,
+            identity_file: None,
+            identity_files: Vec::new(),
+            identities_only: None,
+            proxy_jump: None,
+            proxy_command: None,
+            forward_agent: None,
+            forward_x11: None,
+            server_alive_interval: None,
+            server_alive_count_max: None,
+            connect_timeout: None,
+            compression: None,
+            options: BTreeMap::new(),
+            other_options: None,
+        }
     }
 
     /**
@@ -129,6 +310,43 @@ impl SSHConfigHost {
      * Returns true if no identity file is configured
      */
     pub fn requires_password(&self) -> bool {
-        self.identity_file.is_none()
+        self.identity_files.is_empty()
     }
 }
+
+/**
+ * Parse a single ProxyJump hop: `[ssh://][user@]host[:port]`, with IPv6 hosts in brackets
+ */
+fn parse_jump_spec(spec: &str) -> Result<(Option<String>, String, Option<u16>), String> {
+    let spec = spec.strip_prefix("ssh://").unwrap_or(spec);
+    let (user, host_port) = match spec.rsplit_once('@') {
+        Some((user, rest)) => (Some(user.to_string()), rest),
+        None => (None, spec),
+    };
+
+    let (host, port) = if let Some(rest) = host_port.strip_prefix('[') {
+        let (host, after) = rest
+            .split_once(']')
+            .ok_or_else(|| format!("Invalid ProxyJump host '{}'", spec))?;
+        (host, after.strip_prefix(':'))
+    } else {
+        match host_port.split_once(':') {
+            // More than one colon is a bare IPv6 address without a port
+            Some((host, port)) if !port.contains(':') => (host, Some(port)),
+            _ => (host_port, None),
+        }
+    };
+
+    if host.is_empty() {
+        return Err(format!("Invalid ProxyJump host '{}'", spec));
+    }
+
+    let port = port
+        .map(|p| {
+            p.parse::<u16>()
+                .map_err(|_| format!("Invalid port in ProxyJump host '{}'", spec))
+        })
+        .transpose()?;
+
+    Ok((user, host.to_string(), port))
+}
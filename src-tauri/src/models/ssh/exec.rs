@@ -0,0 +1,99 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Request to run one command on several hosts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiHostExecRequest {
+    /// Id used in the events of this run, generated when not given
+    pub exec_id: Option<String>,
+    #[serde(default)]
+    pub profile_ids: Vec<String>,
+    /// Run on every profile of the group (in addition to `profile_ids`)
+    pub group_id: Option<String>,
+    pub command: String,
+    /// Hosts running at the same time
+    pub concurrency: Option<usize>,
+    /// Per-host limit covering connect, authentication and the command itself
+    pub timeout_secs: Option<u64>,
+}
+
+/// How the command ended on a host
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum HostExecStatus {
+    /// The command ran and exited, see the exit status
+    Completed,
+    /// Connecting or running took longer than the timeout
+    TimedOut,
+    /// Connecting, authenticating or starting the command failed
+    Failed,
+}
+
+/// Outcome of the command on one host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostExecResult {
+    pub profile_id: String,
+    pub profile_name: String,
+    pub host: String,
+    pub status: HostExecStatus,
+    pub exit_status: Option<u32>,
+    pub stdout: String,
+    pub stderr: String,
+    /// Output beyond the capture limit was dropped (it was still streamed)
+    pub truncated: bool,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// Outcome of a multi-host run, in the order the hosts were requested
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiHostExecResult {
+    pub exec_id: String,
+    pub command: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub hosts: Vec<HostExecResult>,
+}
+
+/// Output stream of a host
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecOutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Event payload with partial output of a host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostExecOutput {
+    pub exec_id: String,
+    pub profile_id: String,
+    pub stream: ExecOutputStream,
+    pub data: String,
+}
+
+/// Event payload sent as soon as a host is done
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostExecFinished {
+    pub exec_id: String,
+    pub result: HostExecResult,
+}
+
+/// Progress of a multi-host run
+#[derive(Debug, Clone)]
+pub enum MultiHostExecEvent {
+    Output(HostExecOutput),
+    Finished(HostExecFinished),
+}
+
+/// Export format of a multi-host run
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecExportFormat {
+    Json,
+    Csv,
+}
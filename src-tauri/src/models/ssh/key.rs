@@ -78,8 +78,17 @@ impl SSHKey {
         }
     }
 
-    /// Calculate SHA256 fingerprint from key data
+    /// Calculate SHA256 fingerprint from key data. OpenSSH public keys get the same
+    /// `SHA256:...` fingerprint ssh-keygen shows.
     pub fn calculate_fingerprint(private_key: &str, public_key: &Option<String>) -> String {
+        if let Some(fingerprint) = public_key
+            .as_deref()
+            .and_then(|key| ssh_key::PublicKey::from_openssh(key.trim()).ok())
+            .map(|key| key.fingerprint(ssh_key::HashAlg::Sha256).to_string())
+        {
+            return fingerprint;
+        }
+
         let key_data = if let Some(pub_key) = public_key {
             pub_key.as_bytes()
         } else {
@@ -248,6 +257,43 @@ impl CreateSSHKeyRequest {
     }
 }
 
+/// Request to generate a new keypair in the key manager
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateSSHKeyRequest {
+    pub name: String,
+    pub key_type: KeyType,
+    /// ECDSA curve (256, 384, 521) or RSA modulus (3072, 4096) size
+    pub bits: Option<u32>,
+    pub passphrase: Option<String>,
+    pub comment: Option<String>,
+    pub description: Option<String>,
+    /// Also write the keypair to this path (and `.pub`) in OpenSSH format
+    pub export_path: Option<String>,
+}
+
+/// Request to install a stored key's public key on a profile's host (ssh-copy-id)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeployPublicKeyRequest {
+    pub profile_id: String,
+    pub key_id: String,
+    /// Switch the profile to this key once logging in with it works
+    #[serde(default)]
+    pub switch_profile_auth: bool,
+}
+
+/// Result of deploying a public key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeployPublicKeyResult {
+    pub authorized_keys_path: String,
+    /// The key was already in authorized_keys and was left alone
+    pub already_present: bool,
+    /// The profile now authenticates with the key
+    pub profile_updated: bool,
+}
+
 /// Request to update SSH key
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+/// A single prompt of a keyboard-interactive round
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyboardInteractivePrompt {
+    pub prompt: String,
+    /// Whether the answer may be shown while typing (false for passwords and OTP codes)
+    pub echo: bool,
+}
+
+/// Event payload asking the user to answer a keyboard-interactive round
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyboardInteractiveRequest {
+    pub request_id: String,
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    /// Name and instructions sent by the server, often empty
+    pub name: String,
+    pub instructions: String,
+    pub prompts: Vec<KeyboardInteractivePrompt>,
+}
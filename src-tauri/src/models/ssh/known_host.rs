@@ -0,0 +1,199 @@
+use base64::{
+    engine::general_purpose::{STANDARD, STANDARD_NO_PAD},
+    Engine as _,
+};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    database::{
+        error::DatabaseResult,
+        traits::{Encryptable, EncryptionService},
+    },
+    impl_syncable,
+    models::base::BaseModel,
+};
+
+/// Prefix used by OpenSSH for hashed host names (`HashKnownHosts yes`)
+const HASHED_HOST_PREFIX: &str = "|1|";
+
+/// Trusted host key for an SSH endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownHost {
+    /// Base model with sync metadata
+    #[serde(flatten)]
+    pub base: BaseModel,
+
+    /// Host name or address, or a hashed `|1|salt|hash` entry imported from OpenSSH
+    pub host: String,
+    /// Port of the endpoint (ignored for hashed entries, the port is part of the hash)
+    pub port: u16,
+
+    /// Jump hosts used to reach the endpoint (`host:port,host:port`), None when direct.
+    /// Entries without a chain are trusted for any route to the endpoint.
+    pub jump_chain: Option<String>,
+
+    /// Key algorithm (e.g. `ssh-ed25519`)
+    pub key_type: String,
+    /// Base64 encoded public key blob
+    pub public_key: String,
+    /// OpenSSH style fingerprint (`SHA256:...`)
+    pub fingerprint: String,
+
+    /// Free form comment, e.g. where the entry was imported from
+    pub comment: Option<String>,
+}
+
+impl KnownHost {
+    /// Create a new known host entry
+    pub fn new(
+        device_id: String,
+        host: String,
+        port: u16,
+        jump_chain: Option<String>,
+        key_type: String,
+        public_key: String,
+    ) -> Self {
+        let fingerprint = Self::calculate_fingerprint(&public_key);
+
+        Self {
+            base: BaseModel::new(device_id),
+            host,
+            port,
+            jump_chain,
+            key_type,
+            public_key,
+            fingerprint,
+            comment: None,
+        }
+    }
+
+    /// Calculate the OpenSSH SHA256 fingerprint of a base64 encoded key blob
+    pub fn calculate_fingerprint(public_key: &str) -> String {
+        let blob = STANDARD
+            .decode(public_key.trim())
+            .unwrap_or_else(|_| public_key.as_bytes().to_vec());
+
+        let mut hasher = Sha256::new();
+        hasher.update(&blob);
+        format!("SHA256:{}", STANDARD_NO_PAD.encode(hasher.finalize()))
+    }
+
+    /// Whether the host name is stored hashed
+    pub fn is_hashed(&self) -> bool {
+        self.host.starts_with(HASHED_HOST_PREFIX)
+    }
+
+    /// Check whether this entry applies to the given endpoint and jump chain
+    pub fn matches(&self, host: &str, port: u16, jump_chain: Option<&str>) -> bool {
+        if let Some(chain) = &self.jump_chain {
+            if Some(chain.as_str()) != jump_chain {
+                return false;
+            }
+        }
+
+        if self.is_hashed() {
+            return hashed_host_matches(&self.host, &known_hosts_name(host, port));
+        }
+
+        self.port == port && self.host.eq_ignore_ascii_case(host)
+    }
+
+    /// Whether the entry holds exactly this key
+    pub fn has_key(&self, key_type: &str, public_key: &str) -> bool {
+        normalize_key_type(&self.key_type) == normalize_key_type(key_type)
+            && self.public_key.trim() == public_key.trim()
+    }
+}
+
+/// Map signature algorithm names to the key type stored in known_hosts
+/// (RSA keys are negotiated as `rsa-sha2-*` but recorded as `ssh-rsa`)
+pub fn normalize_key_type(key_type: &str) -> &str {
+    match key_type {
+        "rsa-sha2-256" | "rsa-sha2-512" => "ssh-rsa",
+        other => other,
+    }
+}
+
+/// Host name as written in known_hosts: `host` for port 22, `[host]:port` otherwise
+pub fn known_hosts_name(host: &str, port: u16) -> String {
+    if port == 22 {
+        host.to_string()
+    } else {
+        format!("[{}]:{}", host, port)
+    }
+}
+
+/// Check a `|1|salt|hash` entry against a host name (HMAC-SHA1 keyed by the salt)
+pub fn hashed_host_matches(hashed: &str, name: &str) -> bool {
+    let Some(rest) = hashed.strip_prefix(HASHED_HOST_PREFIX) else {
+        return false;
+    };
+    let Some((salt, hash)) = rest.split_once('|') else {
+        return false;
+    };
+    let (Ok(salt), Ok(hash)) = (STANDARD.decode(salt), STANDARD.decode(hash)) else {
+        return false;
+    };
+
+    let Ok(mut mac) = Hmac::<Sha1>::new_from_slice(&salt) else {
+        return false;
+    };
+    mac.update(name.to_lowercase().as_bytes());
+    mac.verify_slice(&hash).is_ok()
+}
+
+impl_syncable!(KnownHost, "ssh_known_hosts");
+
+impl Encryptable for KnownHost {
+    fn encrypted_fields() -> Vec<&'static str> {
+        vec![]
+    }
+
+    fn encrypt_fields(
+        &mut self,
+        _encryption_service: &dyn EncryptionService,
+    ) -> DatabaseResult<()> {
+        Ok(())
+    }
+
+    fn decrypt_fields(
+        &mut self,
+        _encryption_service: &dyn EncryptionService,
+    ) -> DatabaseResult<()> {
+        Ok(())
+    }
+
+    fn has_encrypted_data(&self) -> bool {
+        false
+    }
+
+    fn encryption_device_id(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Event payload asking the user to trust an unknown host key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostKeyVerificationRequest {
+    pub request_id: String,
+    pub host: String,
+    pub port: u16,
+    pub jump_chain: Option<String>,
+    pub key_type: String,
+    pub fingerprint: String,
+    /// Key types already trusted for this endpoint (non-empty when the server offers a new algorithm)
+    pub known_key_types: Vec<String>,
+}
+
+/// Result of importing an OpenSSH known_hosts file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportKnownHostsResult {
+    pub imported: usize,
+    pub skipped: usize,
+}
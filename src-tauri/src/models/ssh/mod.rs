@@ -1,17 +1,33 @@
+pub mod certificate;
 pub mod config_host;
+pub mod exec;
 pub mod group;
 pub mod key;
+pub mod keyboard_interactive;
+pub mod known_host;
 pub mod profile;
 pub mod tunnel;
 
+pub use certificate::CertificateExpiryWarning;
 pub use config_host::SSHConfigHost;
+pub use exec::{
+    ExecExportFormat, ExecOutputStream, HostExecFinished, HostExecOutput, HostExecResult,
+    HostExecStatus, MultiHostExecEvent, MultiHostExecRequest, MultiHostExecResult,
+};
 pub use group::{CreateSSHGroupRequest, DeleteGroupAction, SSHGroup, UpdateSSHGroupRequest};
-pub use key::{CreateSSHKeyRequest, SSHKey, UpdateSSHKeyRequest};
+pub use key::{
+    CreateSSHKeyRequest, DeployPublicKeyRequest, DeployPublicKeyResult, GenerateSSHKeyRequest,
+    SSHKey, UpdateSSHKeyRequest,
+};
+pub use keyboard_interactive::{KeyboardInteractivePrompt, KeyboardInteractiveRequest};
+pub use known_host::{HostKeyVerificationRequest, ImportKnownHostsResult, KnownHost};
 pub use profile::{
     AuthData, CreateSSHProfileRequest, SSHProfile, TestSSHConnectionRequest,
     UpdateSSHProfileRequest,
 };
 pub use tunnel::{
-    CreateSSHTunnelRequest, SSHTunnel, TunnelStatus, TunnelType, TunnelWithStatus,
-    UpdateSSHTunnelRequest,
+    CreateSSHTunnelRequest, RestartMode, SSHTunnel, TunnelAccessControl, TunnelConnectionInfo,
+    TunnelEndpoint, TunnelFailure, TunnelHealthCheck, TunnelHealthProbe, TunnelMetrics,
+    TunnelRestartPolicy, TunnelSocksAuth, TunnelStatus, TunnelStatusInfo, TunnelType,
+    TunnelWithStatus, UpdateSSHTunnelRequest,
 };
@@ -6,7 +6,7 @@ use crate::{
         traits::{Encryptable, EncryptionService},
     },
     impl_syncable,
-    models::base::BaseModel,
+    models::{base::BaseModel, terminal::SessionLogConfig},
 };
 
 /// SSH Profile with flexible authentication methods
@@ -35,9 +35,26 @@ pub struct SSHProfile {
     pub keep_alive: bool,
     pub compression: bool,
 
+    /// Forward the local ssh-agent to interactive sessions
+    #[serde(default)]
+    pub forward_agent: bool,
+
+    /// Forward X11 connections from interactive sessions to the local display
+    #[serde(default)]
+    pub forward_x11: bool,
+
+    /// Plain-text logging of terminal sessions to this host
+    #[serde(default)]
+    pub session_log: Option<SessionLogConfig>,
+
     /// Proxy settings
     pub proxy: Option<ProxyConfig>,
 
+    /// Command whose stdin/stdout is used as the transport (ProxyCommand from
+    /// ~/.ssh/config hosts, not stored)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_command: Option<String>,
+
     /// Jump hosts for SSH chaining (ProxyJump)
     /// Supports multi-hop: connections go through each jump host in order
     pub jump_hosts: Option<Vec<JumpHostConfig>>,
@@ -64,6 +81,12 @@ pub enum AuthMethod {
     KeyReference,
     /// Certificate-based authentication
     Certificate,
+    /// Identities held by the local ssh-agent (SSH_AUTH_SOCK)
+    Agent,
+    /// Server driven prompts (OTP codes, push approvals), answered in the UI
+    KeyboardInteractive,
+    /// Several methods in a row for servers requiring more than one (e.g. publickey,password)
+    MultiFactor,
 }
 
 /// Proxy configuration
@@ -137,16 +160,29 @@ pub enum AuthData {
         #[serde(rename = "validityPeriod")]
         validity_period: Option<CertificateValidity>,
     },
+    /// Authenticate with the local ssh-agent, no secrets are stored
+    Agent {},
+    /// Keyboard-interactive, every prompt is answered by the user
+    KeyboardInteractive {},
+    /// Methods tried in order; each one may be a partial success that the next completes
+    MultiFactor { factors: Vec<AuthData> },
 }
 
 /// Certificate validity information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CertificateValidity {
+    /// RFC 3339 timestamp, or "always" when the certificate has no start
     pub valid_from: String,
+    /// RFC 3339 timestamp, or "forever" when the certificate does not expire
     pub valid_to: String,
     pub serial: Option<String>,
     pub ca_fingerprint: Option<String>,
+    #[serde(default)]
+    pub key_id: Option<String>,
+    /// Users the certificate is valid for, empty means any
+    #[serde(default)]
+    pub principals: Vec<String>,
 }
 
 /// SSH key types
@@ -175,7 +211,11 @@ impl SSHProfile {
             timeout: Some(30),
             keep_alive: true,
             compression: false,
+            forward_agent: false,
+            forward_x11: false,
+            session_log: None,
             proxy: None,
+            proxy_command: None,
             jump_hosts: None,
             color: None,
             description: None,
@@ -209,7 +249,42 @@ impl Encryptable for SSHProfile {
     }
 
     fn encrypt_fields(&mut self, encryption_service: &dyn EncryptionService) -> DatabaseResult<()> {
-        match &mut self.auth_data {
+        self.auth_data.encrypt_secrets(encryption_service)
+    }
+
+    fn decrypt_fields(&mut self, encryption_service: &dyn EncryptionService) -> DatabaseResult<()> {
+        let device_id = self.base.device_id.clone();
+        self.auth_data
+            .decrypt_secrets(encryption_service, &device_id)
+    }
+
+    fn has_encrypted_data(&self) -> bool {
+        self.auth_data.has_secrets()
+    }
+
+    fn encryption_device_id(&self) -> Option<&str> {
+        Some(&self.base.device_id)
+    }
+}
+
+impl AuthData {
+    /// Whether this auth data holds secrets that are encrypted at rest
+    pub fn has_secrets(&self) -> bool {
+        match self {
+            AuthData::Password { .. } => true,
+            AuthData::KeyReference { .. } => false,
+            AuthData::Certificate { .. } => true,
+            AuthData::Agent {} => false,
+            AuthData::KeyboardInteractive {} => false,
+            AuthData::MultiFactor { factors } => factors.iter().any(|f| f.has_secrets()),
+        }
+    }
+
+    fn encrypt_secrets(
+        &mut self,
+        encryption_service: &dyn EncryptionService,
+    ) -> DatabaseResult<()> {
+        match self {
             AuthData::Password { password } => {
                 let encrypted = tokio::task::block_in_place(|| {
                     tokio::runtime::Handle::current().block_on(async {
@@ -220,7 +295,9 @@ impl Encryptable for SSHProfile {
                 })?;
                 *password = encrypted;
             }
-            AuthData::KeyReference { .. } => {}
+            AuthData::KeyReference { .. }
+            | AuthData::Agent {}
+            | AuthData::KeyboardInteractive {} => {}
             AuthData::Certificate {
                 certificate,
                 private_key,
@@ -243,14 +320,22 @@ impl Encryptable for SSHProfile {
                 *certificate = encrypted_cert;
                 *private_key = encrypted_key;
             }
+            AuthData::MultiFactor { factors } => {
+                for factor in factors {
+                    factor.encrypt_secrets(encryption_service)?;
+                }
+            }
         }
         Ok(())
     }
 
-    fn decrypt_fields(&mut self, encryption_service: &dyn EncryptionService) -> DatabaseResult<()> {
-        match &mut self.auth_data {
+    fn decrypt_secrets(
+        &mut self,
+        encryption_service: &dyn EncryptionService,
+        device_id: &str,
+    ) -> DatabaseResult<()> {
+        match self {
             AuthData::Password { password } => {
-                let device_id = self.base.device_id.clone();
                 let decrypted = tokio::task::block_in_place(|| {
                     tokio::runtime::Handle::current().block_on(async {
                         match encryption_service
@@ -260,7 +345,7 @@ impl Encryptable for SSHProfile {
                             Ok(data) => Ok(data),
                             Err(_) => {
                                 encryption_service
-                                    .decrypt_string(password, Some(&device_id))
+                                    .decrypt_string(password, Some(device_id))
                                     .await
                             }
                         }
@@ -268,13 +353,14 @@ impl Encryptable for SSHProfile {
                 })?;
                 *password = decrypted;
             }
-            AuthData::KeyReference { .. } => {}
+            AuthData::KeyReference { .. }
+            | AuthData::Agent {}
+            | AuthData::KeyboardInteractive {} => {}
             AuthData::Certificate {
                 certificate,
                 private_key,
                 ..
             } => {
-                let device_id = self.base.device_id.clone();
                 let decrypted_cert = tokio::task::block_in_place(|| {
                     tokio::runtime::Handle::current().block_on(async {
                         match encryption_service
@@ -284,7 +370,7 @@ impl Encryptable for SSHProfile {
                             Ok(data) => Ok(data),
                             Err(_) => {
                                 encryption_service
-                                    .decrypt_string(certificate, Some(&device_id))
+                                    .decrypt_string(certificate, Some(device_id))
                                     .await
                             }
                         }
@@ -299,7 +385,7 @@ impl Encryptable for SSHProfile {
                             Ok(data) => Ok(data),
                             Err(_) => {
                                 encryption_service
-                                    .decrypt_string(private_key, Some(&device_id))
+                                    .decrypt_string(private_key, Some(device_id))
                                     .await
                             }
                         }
@@ -308,21 +394,26 @@ impl Encryptable for SSHProfile {
                 *certificate = decrypted_cert;
                 *private_key = decrypted_key;
             }
+            AuthData::MultiFactor { factors } => {
+                for factor in factors {
+                    factor.decrypt_secrets(encryption_service, device_id)?;
+                }
+            }
         }
         Ok(())
     }
 
-    fn has_encrypted_data(&self) -> bool {
-        match &self.auth_data {
-            AuthData::Password { .. } => true,
-            AuthData::KeyReference { .. } => false,
-            AuthData::Certificate { .. } => true,
+    /// Short human readable name of the method, used in error messages
+    pub fn method_label(&self) -> &'static str {
+        match self {
+            AuthData::Password { .. } => "Password",
+            AuthData::KeyReference { .. } => "SSH key",
+            AuthData::Certificate { .. } => "Certificate",
+            AuthData::Agent {} => "SSH agent",
+            AuthData::KeyboardInteractive {} => "Keyboard-interactive",
+            AuthData::MultiFactor { .. } => "Multi-factor",
         }
     }
-
-    fn encryption_device_id(&self) -> Option<&str> {
-        Some(&self.base.device_id)
-    }
 }
 
 /// Request to create new SSH profile
@@ -339,6 +430,9 @@ pub struct CreateSSHProfileRequest {
     pub timeout: Option<u32>,
     pub keep_alive: Option<bool>,
     pub compression: Option<bool>,
+    pub forward_agent: Option<bool>,
+    pub forward_x11: Option<bool>,
+    pub session_log: Option<SessionLogConfig>,
     pub proxy: Option<ProxyConfig>,
     pub jump_hosts: Option<Vec<JumpHostConfig>>,
     pub color: Option<String>,
@@ -364,6 +458,9 @@ impl CreateSSHProfileRequest {
         profile.timeout = self.timeout.or(Some(30));
         profile.keep_alive = self.keep_alive.unwrap_or(true);
         profile.compression = self.compression.unwrap_or(false);
+        profile.forward_agent = self.forward_agent.unwrap_or(false);
+        profile.forward_x11 = self.forward_x11.unwrap_or(false);
+        profile.session_log = self.session_log;
         profile.jump_hosts = self.jump_hosts;
         profile.color = self.color;
         profile.description = self.description;
@@ -376,7 +473,7 @@ impl CreateSSHProfileRequest {
 }
 
 /// Request to update SSH profile
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateSSHProfileRequest {
     pub name: Option<String>,
@@ -391,6 +488,10 @@ pub struct UpdateSSHProfileRequest {
     pub timeout: Option<Option<u32>>,
     pub keep_alive: Option<bool>,
     pub compression: Option<bool>,
+    pub forward_agent: Option<bool>,
+    pub forward_x11: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_optional_field")]
+    pub session_log: Option<Option<SessionLogConfig>>,
     #[serde(default, deserialize_with = "deserialize_optional_field")]
     pub jump_hosts: Option<Option<Vec<JumpHostConfig>>>, // None = no change, Some(None) = remove
     #[serde(default, deserialize_with = "deserialize_optional_field")]
@@ -406,7 +507,9 @@ pub struct UpdateSSHProfileRequest {
 }
 
 /// Deserialize optional field: missing = None, null = Some(None), value = Some(Some(value))
-fn deserialize_optional_field<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+pub(crate) fn deserialize_optional_field<'de, T, D>(
+    deserializer: D,
+) -> Result<Option<Option<T>>, D::Error>
 where
     T: serde::Deserialize<'de>,
     D: serde::Deserializer<'de>,
@@ -445,7 +548,11 @@ impl TestSSHConnectionRequest {
             timeout: self.timeout,
             keep_alive: self.keep_alive,
             compression: self.compression,
+            forward_agent: false,
+            forward_x11: false,
+            session_log: None,
             proxy: self.proxy,
+            proxy_command: None,
             jump_hosts: self.jump_hosts,
             color: None,
             description: None,
@@ -488,6 +595,15 @@ impl UpdateSSHProfileRequest {
         if let Some(compression) = self.compression {
             profile.compression = compression;
         }
+        if let Some(forward_agent) = self.forward_agent {
+            profile.forward_agent = forward_agent;
+        }
+        if let Some(forward_x11) = self.forward_x11 {
+            profile.forward_x11 = forward_x11;
+        }
+        if let Some(session_log) = self.session_log {
+            profile.session_log = session_log;
+        }
         if let Some(jump_hosts) = self.jump_hosts {
             profile.jump_hosts = jump_hosts;
         }
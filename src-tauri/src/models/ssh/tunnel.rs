@@ -9,6 +9,8 @@ use crate::{
     models::base::BaseModel,
 };
 
+use super::profile::deserialize_optional_field;
+
 /// SSH Tunnel for port forwarding and SOCKS proxy
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -33,9 +35,33 @@ pub struct SSHTunnel {
     pub remote_host: Option<String>,
     pub remote_port: Option<u16>,
 
+    /// Unix socket used instead of the local host and port, for Local and Remote tunnels
+    #[serde(default)]
+    pub local_socket_path: Option<String>,
+
+    /// Unix socket on the SSH server used instead of the remote host and port
+    #[serde(default)]
+    pub remote_socket_path: Option<String>,
+
     /// Auto-start configuration
     pub auto_start: bool,
 
+    /// What happens when the tunnel stops on its own
+    #[serde(default)]
+    pub restart_policy: TunnelRestartPolicy,
+
+    /// Active check that the tunnel still forwards, failures restart it
+    #[serde(default)]
+    pub health_probe: Option<TunnelHealthProbe>,
+
+    /// Credentials SOCKS clients must send, only for Dynamic tunnels
+    #[serde(default)]
+    pub socks_auth: Option<TunnelSocksAuth>,
+
+    /// Destinations proxy clients may connect to, only for Dynamic and HttpProxy tunnels
+    #[serde(default)]
+    pub access_control: TunnelAccessControl,
+
     /// Runtime status (not persisted, used for UI)
     #[serde(skip)]
     pub status: TunnelStatus,
@@ -58,6 +84,24 @@ pub enum TunnelType {
     /// Dynamic port forwarding (-D)
     /// SOCKS5 proxy via SSH server
     Dynamic,
+    /// HTTP proxy via SSH server, for CONNECT and plain http:// requests
+    HttpProxy,
+}
+
+/// One end of a forward: a TCP address or a unix socket
+#[derive(Debug, Clone, PartialEq)]
+pub enum TunnelEndpoint {
+    Tcp { host: String, port: u16 },
+    Socket { path: String },
+}
+
+impl std::fmt::Display for TunnelEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TunnelEndpoint::Tcp { host, port } => write!(f, "{}:{}", host, port),
+            TunnelEndpoint::Socket { path } => f.write_str(path),
+        }
+    }
 }
 
 /// Runtime status of tunnel
@@ -69,9 +113,349 @@ pub enum TunnelStatus {
     Stopped,
     Starting,
     Running,
+    /// Waiting to restart after a failure
+    Reconnecting,
     Error,
 }
 
+/// When a tunnel is restarted after it stops without being asked to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum RestartMode {
+    #[default]
+    Never,
+    /// Restart after errors, up to `max_restarts` in a row
+    OnFailure,
+    /// Restart whenever the tunnel ends, without a limit
+    Always,
+}
+
+/// How a tunnel restarts after its SSH session dies
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TunnelRestartPolicy {
+    pub mode: RestartMode,
+    /// Restarts in a row before giving up, unlimited when unset
+    pub max_restarts: Option<u32>,
+    /// Delay before the first restart, doubled for every further one
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for TunnelRestartPolicy {
+    fn default() -> Self {
+        Self {
+            mode: RestartMode::Never,
+            max_restarts: Some(10),
+            initial_delay_ms: 1000,
+            max_delay_ms: 60_000,
+        }
+    }
+}
+
+impl TunnelRestartPolicy {
+    /// Backoff before the given restart (starting at 1)
+    pub fn delay(&self, restart: u32) -> std::time::Duration {
+        let factor = 1u64 << restart.saturating_sub(1).min(32);
+        let delay = self
+            .initial_delay_ms
+            .saturating_mul(factor)
+            .min(self.max_delay_ms);
+        std::time::Duration::from_millis(delay)
+    }
+}
+
+/// What the health probe checks
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum TunnelHealthCheck {
+    /// Open a connection to the forwarded port or socket
+    TcpConnect,
+    /// Run a command on the SSH server, healthy when it exits with 0
+    Exec { command: String },
+}
+
+/// Periodic health check of a running tunnel
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TunnelHealthProbe {
+    pub check: TunnelHealthCheck,
+    pub interval_secs: u64,
+    pub timeout_secs: u64,
+    /// Failed checks in a row before the tunnel counts as broken
+    pub failure_threshold: u32,
+}
+
+/// Username and password of a SOCKS5 proxy (RFC 1929). SOCKS4 clients cannot send a
+/// password, so they are refused while this is set.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TunnelSocksAuth {
+    pub username: String,
+    pub password: String,
+}
+
+/// Allow and deny lists of proxy destinations. Entries are host names, `*.domain`
+/// wildcards, IP addresses, CIDRs or `*` for everything. Host names are checked
+/// against addresses and CIDRs by what they resolve to locally; the SSH server may
+/// resolve them differently, so the tunnel connects to the address that was checked.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TunnelAccessControl {
+    /// Destinations that may be reached, anything when empty
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Destinations that are refused, even when allowed
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+impl TunnelAccessControl {
+    /// Whether the host is a name that has to be resolved before `allows` can check it
+    /// against the address and CIDR entries
+    pub fn needs_resolution(&self, host: &str) -> bool {
+        ip_literal(host).is_none()
+            && self.allow.iter().chain(&self.deny).any(|rule| {
+                matches!(
+                    DestinationPattern::parse(rule),
+                    Ok(DestinationPattern::Network(..))
+                )
+            })
+    }
+
+    /// Whether a client may connect to the host. `addresses` are what a host name
+    /// resolved to: a denied network refuses the name if it holds any of them, and
+    /// the allowed networks admit it only if each of them is in one of those
+    /// networks. A name that did not resolve is refused while any denied network
+    /// exists.
+    pub fn allows(&self, host: &str, addresses: &[std::net::IpAddr]) -> bool {
+        let destination = match ip_literal(host) {
+            Some(address) => Destination {
+                name: None,
+                addresses: vec![address],
+            },
+            None => Destination {
+                name: Some(
+                    trim_brackets(host)
+                        .trim_end_matches('.')
+                        .to_ascii_lowercase(),
+                ),
+                addresses: addresses.to_vec(),
+            },
+        };
+        let patterns = |rules: &[String]| -> Vec<DestinationPattern> {
+            rules
+                .iter()
+                .filter_map(|rule| DestinationPattern::parse(rule).ok())
+                .collect()
+        };
+        let deny = patterns(&self.deny);
+        let allow = patterns(&self.allow);
+
+        let unresolved = destination.addresses.is_empty()
+            && deny
+                .iter()
+                .any(|pattern| matches!(pattern, DestinationPattern::Network(..)));
+        let denied = unresolved || deny.iter().any(|pattern| pattern.denies(&destination));
+        let allowed = self.allow.is_empty()
+            || allow
+                .iter()
+                .any(|pattern| pattern.matches_name(&destination))
+            || (!destination.addresses.is_empty()
+                && destination
+                    .addresses
+                    .iter()
+                    .all(|address| allow.iter().any(|pattern| pattern.contains(*address))));
+        !denied && allowed
+    }
+
+    /// Check that every entry can be parsed
+    pub fn validate(&self) -> Result<(), String> {
+        for rule in self.allow.iter().chain(&self.deny) {
+            DestinationPattern::parse(rule)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parsed entry of a `TunnelAccessControl` list
+enum DestinationPattern {
+    Any,
+    Network(std::net::IpAddr, u8),
+    Host(String),
+    /// `*.domain`, stored as `.domain`
+    Subdomain(String),
+}
+
+impl DestinationPattern {
+    fn parse(rule: &str) -> Result<Self, String> {
+        let rule = rule.trim();
+        if rule.is_empty() {
+            return Err("Access control entries cannot be empty".to_string());
+        }
+        if rule == "*" {
+            return Ok(Self::Any);
+        }
+
+        if let Some((address, prefix)) = rule.split_once('/') {
+            let invalid = || format!("Invalid CIDR '{}'", rule);
+            let address: std::net::IpAddr = address.parse().map_err(|_| invalid())?;
+            let prefix: u8 = prefix.parse().map_err(|_| invalid())?;
+            let max_prefix = if address.is_ipv4() { 32 } else { 128 };
+            if prefix > max_prefix {
+                return Err(invalid());
+            }
+            return Ok(Self::Network(address, prefix));
+        }
+        if let Ok(address) = trim_brackets(rule).parse::<std::net::IpAddr>() {
+            let prefix = if address.is_ipv4() { 32 } else { 128 };
+            return Ok(Self::Network(address, prefix));
+        }
+
+        let host = rule.to_ascii_lowercase();
+        match host.strip_prefix("*.") {
+            Some(domain) if !domain.is_empty() && !domain.contains('*') => {
+                Ok(Self::Subdomain(format!(".{}", domain)))
+            }
+            None if !host.contains('*') => Ok(Self::Host(host)),
+            _ => Err(format!("Invalid access control entry '{}'", rule)),
+        }
+    }
+
+    /// Whether a deny entry covers the destination, by its name or any of its addresses
+    fn denies(&self, destination: &Destination) -> bool {
+        match self {
+            Self::Network(..) => destination
+                .addresses
+                .iter()
+                .any(|address| self.contains(*address)),
+            _ => self.matches_name(destination),
+        }
+    }
+
+    /// Whether a network entry holds the address
+    fn contains(&self, address: std::net::IpAddr) -> bool {
+        match self {
+            Self::Network(network, prefix) => in_network(address, *network, *prefix),
+            _ => false,
+        }
+    }
+
+    fn matches_name(&self, destination: &Destination) -> bool {
+        let name = destination.name.as_deref();
+        match self {
+            Self::Any => true,
+            Self::Network(..) => false,
+            Self::Host(host) => name == Some(host.as_str()),
+            Self::Subdomain(suffix) => name.is_some_and(|name| name.ends_with(suffix.as_str())),
+        }
+    }
+}
+
+/// Host of a proxy request, as a name with the addresses it resolved to or as an address
+struct Destination {
+    name: Option<String>,
+    addresses: Vec<std::net::IpAddr>,
+}
+
+/// The address a host stands for without a lookup. Besides the usual notations this
+/// takes the shorthand IPv4 forms resolvers accept, like `10.1`, `167772161` or
+/// `0x0a.0.0.1`, so they can't pass for names.
+pub fn ip_literal(host: &str) -> Option<std::net::IpAddr> {
+    let host = trim_brackets(host).trim_end_matches('.');
+    if let Ok(address) = host.parse() {
+        return Some(address);
+    }
+
+    let parts = host
+        .split('.')
+        .map(|part| {
+            let (digits, radix) =
+                if let Some(hex) = part.strip_prefix("0x").or_else(|| part.strip_prefix("0X")) {
+                    (hex, 16)
+                } else if part.len() > 1 && part.starts_with('0') {
+                    (&part[1..], 8)
+                } else {
+                    (part, 10)
+                };
+            if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+                return None;
+            }
+            u32::from_str_radix(digits, radix).ok()
+        })
+        .collect::<Option<Vec<u32>>>()?;
+    if parts.is_empty() || parts.len() > 4 {
+        return None;
+    }
+
+    // Every part but the last is one byte, the last one fills the remaining bytes
+    let (last, leading) = parts.split_last()?;
+    if leading.iter().any(|part| *part > 0xFF) {
+        return None;
+    }
+    let last_bits = 8 * (4 - leading.len() as u32);
+    if last_bits < 32 && *last >= 1 << last_bits {
+        return None;
+    }
+    let address = leading
+        .iter()
+        .enumerate()
+        .fold(*last, |address, (index, part)| {
+            address | part << (24 - 8 * index as u32)
+        });
+    Some(std::net::IpAddr::V4(address.into()))
+}
+
+fn trim_brackets(host: &str) -> &str {
+    host.strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(host)
+}
+
+fn in_network(address: std::net::IpAddr, network: std::net::IpAddr, prefix: u8) -> bool {
+    use std::net::IpAddr;
+
+    // IPv4-mapped IPv6 addresses count as their IPv4 address
+    let address = match address {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(address),
+        v4 => v4,
+    };
+    match (address, network) {
+        (IpAddr::V4(address), IpAddr::V4(network)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(address) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(address), IpAddr::V6(network)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(address) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Why a tunnel last stopped on its own
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TunnelFailure {
+    pub reason: String,
+    pub failed_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Runtime status of a tunnel with its restart history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TunnelStatusInfo {
+    pub status: TunnelStatus,
+    pub error_message: Option<String>,
+    /// Restarts since the tunnel was started
+    pub restart_count: u32,
+    pub last_failure: Option<TunnelFailure>,
+}
+
 impl SSHTunnel {
     /// Create a new SSH tunnel
     pub fn new(
@@ -94,23 +478,61 @@ impl SSHTunnel {
             local_port,
             remote_host,
             remote_port,
+            local_socket_path: None,
+            remote_socket_path: None,
             auto_start: false,
+            restart_policy: TunnelRestartPolicy::default(),
+            health_probe: None,
+            socks_auth: None,
+            access_control: TunnelAccessControl::default(),
             status: TunnelStatus::default(),
             error_message: None,
         }
     }
 
+    /// Local end of the tunnel
+    pub fn local_endpoint(&self) -> TunnelEndpoint {
+        match &self.local_socket_path {
+            Some(path) => TunnelEndpoint::Socket { path: path.clone() },
+            None => TunnelEndpoint::Tcp {
+                host: self.local_host.clone(),
+                port: self.local_port,
+            },
+        }
+    }
+
+    /// Remote end of a Local or Remote tunnel
+    pub fn remote_endpoint(&self) -> TunnelEndpoint {
+        match &self.remote_socket_path {
+            Some(path) => TunnelEndpoint::Socket { path: path.clone() },
+            None => TunnelEndpoint::Tcp {
+                host: self.remote_host.clone().unwrap_or_default(),
+                port: self.remote_port.unwrap_or(22),
+            },
+        }
+    }
+
     /// Validate tunnel configuration
     pub fn validate(&self) -> Result<(), String> {
         if self.name.trim().is_empty() {
             return Err("Tunnel name cannot be empty".to_string());
         }
 
-        if self.local_port == 0 {
+        if self.local_port == 0 && self.local_socket_path.is_none() {
             return Err("Local port cannot be 0".to_string());
         }
 
+        for path in [&self.local_socket_path, &self.remote_socket_path]
+            .into_iter()
+            .flatten()
+        {
+            if !path.starts_with('/') {
+                return Err(format!("Socket path '{}' must be absolute", path));
+            }
+        }
+
         match self.tunnel_type {
+            TunnelType::Local | TunnelType::Remote if self.remote_socket_path.is_some() => {}
             TunnelType::Local | TunnelType::Remote => {
                 if self.remote_host.is_none() || self.remote_port.is_none() {
                     return Err(
@@ -129,13 +551,53 @@ impl SSHTunnel {
                     }
                 }
             }
-            TunnelType::Dynamic => {}
+            TunnelType::Dynamic | TunnelType::HttpProxy => {
+                if self.local_socket_path.is_some() || self.remote_socket_path.is_some() {
+                    return Err(
+                        "Socket paths are only available for Local and Remote tunnels".to_string(),
+                    );
+                }
+            }
         }
 
         if self.profile_id.trim().is_empty() {
             return Err("Profile ID cannot be empty".to_string());
         }
 
+        if self.tunnel_type != TunnelType::Dynamic && self.socks_auth.is_some() {
+            return Err("SOCKS authentication is only available for Dynamic tunnels".to_string());
+        }
+        if !matches!(
+            self.tunnel_type,
+            TunnelType::Dynamic | TunnelType::HttpProxy
+        ) && self.access_control != TunnelAccessControl::default()
+        {
+            return Err(
+                "Access control is only available for Dynamic and HttpProxy tunnels".to_string(),
+            );
+        }
+        if let Some(auth) = &self.socks_auth {
+            // RFC 1929 sends both with a one byte length
+            let valid_length = |value: &str| (1..=255).contains(&value.len());
+            if !valid_length(&auth.username) || !valid_length(&auth.password) {
+                return Err(
+                    "SOCKS username and password must be between 1 and 255 bytes".to_string(),
+                );
+            }
+        }
+        self.access_control.validate()?;
+
+        if let Some(probe) = &self.health_probe {
+            if probe.interval_secs == 0 || probe.timeout_secs == 0 {
+                return Err("Health probe interval and timeout must be positive".to_string());
+            }
+            if let TunnelHealthCheck::Exec { command } = &probe.check {
+                if command.trim().is_empty() {
+                    return Err("Health probe command cannot be empty".to_string());
+                }
+            }
+        }
+
         Ok(())
     }
 }
@@ -152,7 +614,13 @@ pub struct CreateSSHTunnelRequest {
     pub local_port: u16,
     pub remote_host: Option<String>,
     pub remote_port: Option<u16>,
+    pub local_socket_path: Option<String>,
+    pub remote_socket_path: Option<String>,
     pub auto_start: Option<bool>,
+    pub restart_policy: Option<TunnelRestartPolicy>,
+    pub health_probe: Option<TunnelHealthProbe>,
+    pub socks_auth: Option<TunnelSocksAuth>,
+    pub access_control: Option<TunnelAccessControl>,
 }
 
 /// Request to update an existing SSH tunnel
@@ -167,32 +635,65 @@ pub struct UpdateSSHTunnelRequest {
     pub local_port: Option<u16>,
     pub remote_host: Option<String>,
     pub remote_port: Option<u16>,
+    #[serde(default, deserialize_with = "deserialize_optional_field")]
+    pub local_socket_path: Option<Option<String>>, // None = no change, Some(None) = remove
+    #[serde(default, deserialize_with = "deserialize_optional_field")]
+    pub remote_socket_path: Option<Option<String>>, // None = no change, Some(None) = remove
     pub auto_start: Option<bool>,
+    pub restart_policy: Option<TunnelRestartPolicy>,
+    #[serde(default, deserialize_with = "deserialize_optional_field")]
+    pub health_probe: Option<Option<TunnelHealthProbe>>, // None = no change, Some(None) = remove
+    #[serde(default, deserialize_with = "deserialize_optional_field")]
+    pub socks_auth: Option<Option<TunnelSocksAuth>>, // None = no change, Some(None) = remove
+    pub access_control: Option<TunnelAccessControl>,
 }
 
 impl_syncable!(SSHTunnel, "ssh_tunnels");
 
 impl Encryptable for SSHTunnel {
     fn encrypted_fields() -> Vec<&'static str> {
-        vec![] // No encrypted fields
+        vec!["socks_auth"]
     }
 
-    fn encrypt_fields(
-        &mut self,
-        _encryption_service: &dyn EncryptionService,
-    ) -> DatabaseResult<()> {
+    fn encrypt_fields(&mut self, encryption_service: &dyn EncryptionService) -> DatabaseResult<()> {
+        if let Some(auth) = &mut self.socks_auth {
+            let encrypted = tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(async {
+                    encryption_service
+                        .encrypt_string(&auth.password, Some("__shared__"))
+                        .await
+                })
+            })?;
+            auth.password = encrypted;
+        }
         Ok(())
     }
 
-    fn decrypt_fields(
-        &mut self,
-        _encryption_service: &dyn EncryptionService,
-    ) -> DatabaseResult<()> {
+    fn decrypt_fields(&mut self, encryption_service: &dyn EncryptionService) -> DatabaseResult<()> {
+        let device_id = self.base.device_id.clone();
+        if let Some(auth) = &mut self.socks_auth {
+            let decrypted = tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(async {
+                    match encryption_service
+                        .decrypt_string(&auth.password, Some("__shared__"))
+                        .await
+                    {
+                        Ok(data) => Ok(data),
+                        Err(_) => {
+                            encryption_service
+                                .decrypt_string(&auth.password, Some(&device_id))
+                                .await
+                        }
+                    }
+                })
+            })?;
+            auth.password = decrypted;
+        }
         Ok(())
     }
 
     fn has_encrypted_data(&self) -> bool {
-        false
+        self.socks_auth.is_some()
     }
 
     fn encryption_device_id(&self) -> Option<&str> {
@@ -264,6 +765,10 @@ pub struct TunnelWithStatus {
     pub tunnel: SSHTunnel,
     pub status: TunnelStatus,
     pub error_message: Option<String>,
+    /// Restarts of the current run under the restart policy
+    #[serde(default)]
+    pub restart_count: u32,
+    pub last_failure: Option<TunnelFailure>,
 }
 
 impl From<SSHTunnel> for TunnelWithStatus {
@@ -271,7 +776,37 @@ impl From<SSHTunnel> for TunnelWithStatus {
         Self {
             status: tunnel.status.clone(),
             error_message: tunnel.error_message.clone(),
+            restart_count: 0,
+            last_failure: None,
             tunnel,
         }
     }
 }
+
+/// Live traffic of a running tunnel. "In" is data from the remote side to the local
+/// client, "out" the other direction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TunnelMetrics {
+    pub tunnel_id: String,
+    pub active_connections: usize,
+    pub total_connections: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub bytes_in_per_sec: f64,
+    pub bytes_out_per_sec: f64,
+    pub connections: Vec<TunnelConnectionInfo>,
+}
+
+/// A client connection proxied by a tunnel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TunnelConnectionInfo {
+    pub id: String,
+    pub peer_address: String,
+    /// Where the connection goes, the requested address for SOCKS connections
+    pub target: Option<String>,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+}
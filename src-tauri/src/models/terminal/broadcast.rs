@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+/// Named set of terminals whose input is mirrored to each other
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BroadcastGroup {
+    pub name: String,
+    pub members: Vec<BroadcastMember>,
+}
+
+/// Terminal in a broadcast group
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BroadcastMember {
+    pub terminal_id: String,
+    /// A paused member neither mirrors its input nor receives the input of others
+    pub paused: bool,
+}
+
+/// Request to create a broadcast group
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateBroadcastGroupRequest {
+    pub name: String,
+    #[serde(default)]
+    pub terminal_ids: Vec<String>,
+}
+
+/// Request to add terminals to or remove them from a broadcast group
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BroadcastMembersRequest {
+    pub name: String,
+    pub terminal_ids: Vec<String>,
+}
+
+/// Request to pause or resume a member of a broadcast group
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PauseBroadcastMemberRequest {
+    pub name: String,
+    pub terminal_id: String,
+    pub paused: bool,
+}
+
+/// Mirrored write that failed for one terminal (the other members were still written)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BroadcastWriteFailure {
+    pub terminal_id: String,
+    pub error: String,
+}
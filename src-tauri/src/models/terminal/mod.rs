@@ -1,11 +1,27 @@
+pub mod broadcast;
+pub mod persistent;
 pub mod profile;
 pub mod requests;
+pub mod scrollback;
+pub mod session_log;
 pub mod terminal;
 
+pub use broadcast::{
+    BroadcastGroup, BroadcastMember, BroadcastMembersRequest, BroadcastWriteFailure,
+    CreateBroadcastGroupRequest, PauseBroadcastMemberRequest,
+};
+pub use persistent::{AttachPersistentSessionRequest, PersistentSessionInfo};
 pub use requests::*;
+pub use scrollback::{
+    DumpScrollbackRequest, DumpScrollbackResult, ScrollbackConfig, ScrollbackMatch,
+    ScrollbackStats, SearchScrollbackRequest,
+};
+
+pub use session_log::{LogRotation, SessionLogConfig};
 
 pub use terminal::{
-    CreateTerminalRequest, CreateTerminalResponse, LocalConfig, ResizeTerminalRequest,
-    TerminalConfig, TerminalData, TerminalExited, TerminalInfo, TerminalLatency, TerminalState,
-    TerminalTitleChanged, TerminalType, WriteBatchTerminalRequest, WriteTerminalRequest,
+    CreateTerminalRequest, CreateTerminalResponse, LocalConfig, ReconnectPolicy,
+    ResizeTerminalRequest, TerminalConfig, TerminalData, TerminalExited, TerminalInfo,
+    TerminalLatency, TerminalState, TerminalStateChanged, TerminalTitleChanged, TerminalType,
+    WriteBatchTerminalRequest, WriteTerminalRequest,
 };
@@ -0,0 +1,24 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A local shell owned by the session daemon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistentSessionInfo {
+    /// Also the terminal ID the session is attached under
+    pub session_id: String,
+    pub shell: String,
+    pub working_dir: Option<String>,
+    pub pid: Option<u32>,
+    pub created_at: DateTime<Utc>,
+    /// Number of app instances currently attached to the session
+    pub attached_clients: usize,
+}
+
+/// Request for reattaching to a session daemon shell
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachPersistentSessionRequest {
+    pub session_id: String,
+    pub title: Option<String>,
+}
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use super::session_log::SessionLogConfig;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TerminalProfile {
@@ -13,6 +15,8 @@ pub struct TerminalProfile {
     pub color: Option<String>,
     pub command: Option<String>,
     pub is_default: bool,
+    #[serde(default)]
+    pub session_log: Option<SessionLogConfig>,
     pub created_at: i64,
     pub updated_at: i64,
 }
@@ -27,6 +31,8 @@ pub struct CreateTerminalProfileRequest {
     pub icon: Option<String>,
     pub color: Option<String>,
     pub command: Option<String>,
+    #[serde(default)]
+    pub session_log: Option<SessionLogConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,4 +45,6 @@ pub struct UpdateTerminalProfileRequest {
     pub icon: Option<String>,
     pub color: Option<String>,
     pub command: Option<String>,
+    #[serde(default)]
+    pub session_log: Option<SessionLogConfig>,
 }
@@ -1,10 +1,14 @@
 use serde::{Deserialize, Serialize};
 
+use super::terminal::ReconnectPolicy;
+
 /// Request for creating a new SSH terminal
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateSshTerminalRequest {
     pub profile_id: String,
+    #[serde(default)]
+    pub reconnect_policy: Option<ReconnectPolicy>,
 }
 
 /// Request for creating a new SSH terminal from SSH config host
@@ -14,6 +18,8 @@ pub struct CreateSshConfigTerminalRequest {
     pub host_name: String,
     pub title: Option<String>,
     pub password: Option<String>,
+    #[serde(default)]
+    pub reconnect_policy: Option<ReconnectPolicy>,
 }
 
 /// Request for creating a local terminal
@@ -25,6 +31,8 @@ pub struct CreateLocalTerminalRequest {
     pub title: Option<String>,
     pub terminal_profile_id: Option<String>,
     pub command: Option<String>,
+    #[serde(default)]
+    pub persistent: bool,
 }
 
 /// Request for closing a terminal
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+/// Limits of the scrollback the backend keeps per terminal
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScrollbackConfig {
+    pub max_lines: usize,
+    pub max_bytes: usize,
+}
+
+impl Default for ScrollbackConfig {
+    fn default() -> Self {
+        Self {
+            max_lines: 10_000,
+            max_bytes: 8 * 1024 * 1024,
+        }
+    }
+}
+
+/// Size of a terminal's scrollback, lines are numbered from the start of the session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScrollbackStats {
+    pub terminal_id: String,
+    /// Number of the oldest line still kept
+    pub first_line: u64,
+    pub line_count: usize,
+    pub bytes: usize,
+}
+
+/// Request for searching a terminal's scrollback
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchScrollbackRequest {
+    pub terminal_id: String,
+    /// Regular expression matched against ANSI-stripped lines
+    pub pattern: String,
+    #[serde(default)]
+    pub case_insensitive: bool,
+    /// Lines included before and after each match
+    #[serde(default)]
+    pub context_lines: usize,
+    pub max_results: Option<usize>,
+}
+
+/// A scrollback line matching a search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScrollbackMatch {
+    pub line_number: u64,
+    pub line: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+/// Request for writing a range of a terminal's scrollback to a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DumpScrollbackRequest {
+    pub terminal_id: String,
+    pub path: String,
+    /// First and last line to write (inclusive), the whole scrollback when not set
+    pub start_line: Option<u64>,
+    pub end_line: Option<u64>,
+}
+
+/// Result of dumping scrollback to a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DumpScrollbackResult {
+    pub path: String,
+    pub line_count: usize,
+    pub bytes: usize,
+}
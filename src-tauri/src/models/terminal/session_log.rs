@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+/// Plain-text session logging settings of an SSH or terminal profile
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionLogConfig {
+    pub enabled: bool,
+    /// Log file path, `{profile}`, `{date}` and `{terminal_id}` are replaced. Relative
+    /// paths are resolved against the app's `logs` directory.
+    pub path_template: String,
+    /// Write the raw output stream instead of ANSI-stripped text
+    #[serde(default)]
+    pub raw: bool,
+    /// Prefix every line with the local time it was written
+    #[serde(default)]
+    pub timestamps: bool,
+    #[serde(default)]
+    pub rotation: LogRotation,
+}
+
+/// When a session log file is closed and a new one started
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum LogRotation {
+    #[default]
+    None,
+    Daily,
+    Size {
+        max_bytes: u64,
+    },
+}
@@ -20,6 +20,9 @@ pub struct LocalConfig {
     pub working_dir: Option<String>,
     pub env_vars: Option<HashMap<String, String>>,
     pub command: Option<String>,
+    /// Run the shell in the session daemon so it survives app restarts (unix only)
+    #[serde(default)]
+    pub persistent: bool,
 }
 
 /// Terminal configuration that can be either Local or SSH with profile ID
@@ -32,6 +35,43 @@ pub struct TerminalConfig {
     pub terminal_profile_id: Option<String>,
     pub ssh_config_host: Option<SSHConfigHost>,
     pub ssh_config_password: Option<String>,
+    /// Reconnect behaviour of SSH terminals, the default policy when not set
+    #[serde(default)]
+    pub reconnect_policy: Option<ReconnectPolicy>,
+}
+
+/// How an SSH terminal reconnects after losing its connection
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconnectPolicy {
+    pub enabled: bool,
+    pub max_attempts: u32,
+    /// Delay before the first attempt, doubled for every further attempt
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_attempts: 5,
+            initial_delay_ms: 1000,
+            max_delay_ms: 30_000,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Backoff before the given attempt (starting at 1)
+    pub fn delay(&self, attempt: u32) -> std::time::Duration {
+        let factor = 1u64 << attempt.saturating_sub(1).min(32);
+        let delay = self
+            .initial_delay_ms
+            .saturating_mul(factor)
+            .min(self.max_delay_ms);
+        std::time::Duration::from_millis(delay)
+    }
 }
 
 /// Represents the current state of a terminal
@@ -39,6 +79,12 @@ pub struct TerminalConfig {
 pub enum TerminalState {
     Connecting,
     Connected,
+    /// Connection was lost and attempt `attempt` of `max_attempts` is in progress
+    #[serde(rename_all = "camelCase")]
+    Reconnecting {
+        attempt: u32,
+        max_attempts: u32,
+    },
     Disconnected,
     Error(String),
 }
@@ -117,6 +163,14 @@ pub struct TerminalExited {
     pub reason: Option<String>,
 }
 
+/// Event when the connection state of a terminal changes (e.g. while reconnecting)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalStateChanged {
+    pub terminal_id: String,
+    pub state: TerminalState,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TerminalLatency {
@@ -16,7 +16,7 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use crate::core::proxy::create_proxy_stream;
+use crate::core::proxy::{create_proxy_command_stream, create_proxy_stream};
 use crate::error::AppError;
 use crate::models::history::{
     CommandHistoryEntry, ExportHistoryRequest, GetTerminalHistoryRequest, SearchHistoryRequest,
@@ -24,7 +24,9 @@ use crate::models::history::{
 };
 use crate::models::ssh::{AuthData, SSHProfile};
 use crate::models::terminal::TerminalType;
-use crate::services::ssh::SSHService;
+use crate::services::ssh::agent::authenticate_with_agent;
+use crate::services::ssh::client_config::{client_config, with_connect_timeout};
+use crate::services::ssh::{HostKeyVerifier, SSHService};
 use crate::services::terminal::TerminalManager;
 use chrono::{DateTime, Utc};
 use russh::client::{Handler, Session};
@@ -153,8 +155,11 @@ impl HistoryManager {
                         })?;
 
                 let password = terminal_info.config.ssh_config_password.clone();
+                let config_hosts = crate::services::ssh_config_parser::parse_ssh_config(None)
+                    .await
+                    .unwrap_or_default();
                 let temp_profile = ssh_config_host
-                    .to_temporary_profile(password)
+                    .to_temporary_profile(password, &config_hosts)
                     .map_err(|e| AppError::Config(format!("Failed to create profile: {}", e)))?;
 
                 self.load_remote_history_from_profile(&temp_profile).await
@@ -399,25 +404,49 @@ impl HistoryManager {
         profile: &SSHProfile,
     ) -> Result<Vec<CommandHistoryEntry>, AppError> {
         // Create temporary SSH session to execute command
-        let config = Arc::new(russh::client::Config::default());
-        let handler = RemoteCommandHandler::new();
-
-        let mut session = if let Some(proxy_config) = &profile.proxy {
-            let stream = create_proxy_stream(proxy_config, &profile.host, profile.port)
+        let config = client_config(profile);
+        let verifier = HostKeyVerifier::new(
+            self.ssh_service.known_hosts(),
+            &profile.host,
+            profile.port,
+            None,
+        );
+        let handler = RemoteCommandHandler::new(verifier.clone());
+
+        let connect = async {
+            if let Some(proxy_command) = &profile.proxy_command {
+                let stream = create_proxy_command_stream(
+                    proxy_command,
+                    &profile.host,
+                    profile.port,
+                    &profile.username,
+                )
                 .await
-                .map_err(|e| {
-                    AppError::connection_failed(format!("Failed to create proxy connection: {}", e))
-                })?;
-            russh::client::connect_stream(config, stream, handler).await
-        } else {
-            russh::client::connect(config, (&profile.host as &str, profile.port), handler).await
-        }
-        .map_err(|e| {
-            AppError::connection_failed(format!(
-                "Failed to connect to SSH server {}:{}: {}",
-                profile.host, profile.port, e
-            ))
-        })?;
+                .map_err(|e| AppError::connection_failed(e.to_string()))?;
+                russh::client::connect_stream(config, stream, handler).await
+            } else if let Some(proxy_config) = &profile.proxy {
+                let stream = create_proxy_stream(proxy_config, &profile.host, profile.port)
+                    .await
+                    .map_err(|e| {
+                        AppError::connection_failed(format!(
+                            "Failed to create proxy connection: {}",
+                            e
+                        ))
+                    })?;
+                russh::client::connect_stream(config, stream, handler).await
+            } else {
+                russh::client::connect(config, (&profile.host as &str, profile.port), handler).await
+            }
+            .map_err(|e| {
+                verifier.take_failure().unwrap_or_else(|| {
+                    AppError::connection_failed(format!(
+                        "Failed to connect to SSH server {}:{}: {}",
+                        profile.host, profile.port, e
+                    ))
+                })
+            })
+        };
+        let mut session = with_connect_timeout(profile, verifier.checking(), connect).await?;
 
         // Authenticate
         match &profile.auth_data {
@@ -448,6 +477,18 @@ impl HistoryManager {
                     .await;
                 return Ok(Vec::new());
             }
+            AuthData::Agent {} => {
+                let authenticated =
+                    authenticate_with_agent(&mut session, &profile.username).await?;
+                if !authenticated {
+                    let _ = session
+                        .disconnect(russh::Disconnect::ByApplication, "", "en")
+                        .await;
+                    return Err(AppError::authentication_failed(
+                        "SSH agent authentication failed".to_string(),
+                    ));
+                }
+            }
             AuthData::Certificate { .. } => {
                 // Certificate auth not implemented yet
                 let _ = session
@@ -455,6 +496,13 @@ impl HistoryManager {
                     .await;
                 return Ok(Vec::new());
             }
+            AuthData::KeyboardInteractive {} | AuthData::MultiFactor { .. } => {
+                // Background history fetches should not prompt the user
+                let _ = session
+                    .disconnect(russh::Disconnect::ByApplication, "", "en")
+                    .await;
+                return Ok(Vec::new());
+            }
         }
 
         // Execute command to get history
@@ -568,11 +616,13 @@ impl HistoryManager {
 
 /// Handler for executing remote commands
 #[derive(Clone)]
-struct RemoteCommandHandler;
+struct RemoteCommandHandler {
+    host_key_verifier: HostKeyVerifier,
+}
 
 impl RemoteCommandHandler {
-    fn new() -> Self {
-        Self
+    fn new(host_key_verifier: HostKeyVerifier) -> Self {
+        Self { host_key_verifier }
     }
 }
 
@@ -582,9 +632,9 @@ impl Handler for RemoteCommandHandler {
 
     async fn check_server_key(
         &mut self,
-        _server_public_key: &PublicKey,
+        server_public_key: &PublicKey,
     ) -> Result<bool, Self::Error> {
-        Ok(true)
+        Ok(self.host_key_verifier.check(server_public_key).await)
     }
 
     async fn data(
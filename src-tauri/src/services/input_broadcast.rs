@@ -0,0 +1,227 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashMap;
+
+use crate::error::AppError;
+use crate::models::terminal::{BroadcastGroup, BroadcastMember};
+
+/// Input broadcast groups of the open terminals, like tmux synchronize-panes across tabs
+#[derive(Debug, Default)]
+pub struct InputBroadcast {
+    groups: HashMap<String, BroadcastGroup>,
+}
+
+impl InputBroadcast {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a group with the given members
+    pub fn create_group(
+        &mut self,
+        name: &str,
+        terminal_ids: &[String],
+    ) -> Result<BroadcastGroup, AppError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AppError::validation_error(
+                "Broadcast group name must not be empty",
+            ));
+        }
+        if self.groups.contains_key(name) {
+            return Err(AppError::validation_error(format!(
+                "Broadcast group '{}' already exists",
+                name
+            )));
+        }
+
+        let mut group = BroadcastGroup {
+            name: name.to_string(),
+            members: Vec::new(),
+        };
+        add_members(&mut group, terminal_ids);
+        self.groups.insert(name.to_string(), group.clone());
+        Ok(group)
+    }
+
+    /// Delete a group, the terminals themselves stay open
+    pub fn delete_group(&mut self, name: &str) -> Result<(), AppError> {
+        self.groups
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| group_not_found(name))
+    }
+
+    pub fn add_members(
+        &mut self,
+        name: &str,
+        terminal_ids: &[String],
+    ) -> Result<BroadcastGroup, AppError> {
+        let group = self.group_mut(name)?;
+        add_members(group, terminal_ids);
+        Ok(group.clone())
+    }
+
+    pub fn remove_members(
+        &mut self,
+        name: &str,
+        terminal_ids: &[String],
+    ) -> Result<BroadcastGroup, AppError> {
+        let group = self.group_mut(name)?;
+        group
+            .members
+            .retain(|member| !terminal_ids.contains(&member.terminal_id));
+        Ok(group.clone())
+    }
+
+    /// Pause or resume a member without removing it from the group
+    pub fn set_paused(
+        &mut self,
+        name: &str,
+        terminal_id: &str,
+        paused: bool,
+    ) -> Result<BroadcastGroup, AppError> {
+        let group = self.group_mut(name)?;
+        let member = group
+            .members
+            .iter_mut()
+            .find(|member| member.terminal_id == terminal_id)
+            .ok_or_else(|| {
+                AppError::not_found(format!(
+                    "Terminal {} is not in broadcast group '{}'",
+                    terminal_id, name
+                ))
+            })?;
+        member.paused = paused;
+        Ok(group.clone())
+    }
+
+    pub fn groups(&self) -> Vec<BroadcastGroup> {
+        let mut groups: Vec<BroadcastGroup> = self.groups.values().cloned().collect();
+        groups.sort_by(|a, b| a.name.cmp(&b.name));
+        groups
+    }
+
+    /// Terminals that input written to `terminal_id` is mirrored to: the active members of
+    /// every group where it is an active member itself, without duplicates
+    pub fn mirror_targets(&self, terminal_id: &str) -> Vec<String> {
+        let mut targets: Vec<String> = Vec::new();
+
+        for group in self.groups.values() {
+            let is_active_member = group
+                .members
+                .iter()
+                .any(|member| member.terminal_id == terminal_id && !member.paused);
+            if !is_active_member {
+                continue;
+            }
+
+            for member in &group.members {
+                if member.paused
+                    || member.terminal_id == terminal_id
+                    || targets.contains(&member.terminal_id)
+                {
+                    continue;
+                }
+                targets.push(member.terminal_id.clone());
+            }
+        }
+
+        targets
+    }
+
+    /// Drop a closed terminal from every group
+    pub fn remove_terminal(&mut self, terminal_id: &str) {
+        for group in self.groups.values_mut() {
+            group
+                .members
+                .retain(|member| member.terminal_id != terminal_id);
+        }
+    }
+
+    fn group_mut(&mut self, name: &str) -> Result<&mut BroadcastGroup, AppError> {
+        self.groups
+            .get_mut(name)
+            .ok_or_else(|| group_not_found(name))
+    }
+}
+
+fn add_members(group: &mut BroadcastGroup, terminal_ids: &[String]) {
+    for terminal_id in terminal_ids {
+        if !group.members.iter().any(|m| &m.terminal_id == terminal_id) {
+            group.members.push(BroadcastMember {
+                terminal_id: terminal_id.clone(),
+                paused: false,
+            });
+        }
+    }
+}
+
+fn group_not_found(name: &str) -> AppError {
+    AppError::not_found(format!("Broadcast group '{}' not found", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_mirror_targets_skip_paused_members() {
+        let mut broadcast = InputBroadcast::new();
+        broadcast
+            .create_group("web", &ids(&["t1", "t2", "t3"]))
+            .unwrap();
+
+        assert_eq!(broadcast.mirror_targets("t1"), ids(&["t2", "t3"]));
+
+        broadcast.set_paused("web", "t2", true).unwrap();
+        assert_eq!(broadcast.mirror_targets("t1"), ids(&["t3"]));
+        // A paused member's own input is not mirrored either
+        assert!(broadcast.mirror_targets("t2").is_empty());
+
+        broadcast.set_paused("web", "t2", false).unwrap();
+        assert_eq!(broadcast.mirror_targets("t3"), ids(&["t1", "t2"]));
+        assert!(broadcast.mirror_targets("t4").is_empty());
+    }
+
+    #[test]
+    fn test_members_across_groups_are_deduplicated() {
+        let mut broadcast = InputBroadcast::new();
+        broadcast.create_group("a", &ids(&["t1", "t2"])).unwrap();
+        broadcast
+            .create_group("b", &ids(&["t1", "t2", "t3"]))
+            .unwrap();
+        assert!(broadcast.create_group("a", &[]).is_err());
+
+        let mut targets = broadcast.mirror_targets("t1");
+        targets.sort();
+        assert_eq!(targets, ids(&["t2", "t3"]));
+
+        broadcast.remove_members("b", &ids(&["t3"])).unwrap();
+        assert_eq!(broadcast.mirror_targets("t1"), ids(&["t2"]));
+
+        broadcast.remove_terminal("t2");
+        assert!(broadcast.mirror_targets("t1").is_empty());
+        assert!(broadcast.delete_group("missing").is_err());
+    }
+}
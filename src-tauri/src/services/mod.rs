@@ -19,12 +19,18 @@
 pub mod auth;
 
 pub mod history;
+pub mod input_broadcast;
 pub mod recording;
 pub mod saved_command;
+pub mod scrollback;
+pub mod session_log;
 pub mod sftp;
 pub mod ssh;
 pub mod ssh_config_parser;
 pub mod sync;
 pub mod terminal;
 pub mod tunnel;
+pub mod tunnel_http;
+pub mod tunnel_socks;
+pub mod tunnel_traffic;
 pub mod updater;
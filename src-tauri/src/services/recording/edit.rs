@@ -0,0 +1,454 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Post-processing of asciicast files: idle capping, speed changes, cuts,
+//! concatenation and redaction.
+
+use crate::error::AppError;
+use crate::models::recording::RecordingEdit;
+use crate::services::recording::recorder::round_time;
+use regex::Regex;
+use serde_json::{Map, Value};
+
+/// Replacement for redacted text when none is given
+const DEFAULT_REDACTION: &str = "[REDACTED]";
+
+/// A parsed asciicast file, v2 or v3, with event times in seconds since the start
+#[derive(Debug, Clone)]
+pub struct Cast {
+    pub header: Map<String, Value>,
+    pub events: Vec<CastEvent>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CastEvent {
+    pub time: f64,
+    pub code: String,
+    pub data: String,
+}
+
+impl Cast {
+    pub fn parse(content: &str) -> Result<Self, AppError> {
+        let mut lines = content.lines().enumerate();
+        let header: Map<String, Value> = match lines.next() {
+            Some((_, line)) => serde_json::from_str(line).map_err(|e| {
+                AppError::serialization_error(format!("Invalid cast header: {}", e))
+            })?,
+            None => return Err(AppError::validation_error("Cast file is empty")),
+        };
+
+        let version = header_version(&header);
+        if !matches!(version, 2 | 3) {
+            return Err(AppError::validation_error(format!(
+                "Unsupported asciicast version {}",
+                version
+            )));
+        }
+
+        let mut events = Vec::new();
+        let mut time = 0.0;
+        for (index, line) in lines {
+            let line = line.trim();
+            // v3 allows comment lines
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (event_time, code, data): (f64, String, String) = serde_json::from_str(line)
+                .map_err(|e| {
+                    AppError::serialization_error(format!(
+                        "Invalid cast event on line {}: {}",
+                        index + 1,
+                        e
+                    ))
+                })?;
+            // v3 stores the interval since the previous event
+            time = if version == 3 {
+                time + event_time
+            } else {
+                event_time
+            };
+            events.push(CastEvent { time, code, data });
+        }
+
+        Ok(Self { header, events })
+    }
+
+    /// Render the cast in the format version of its header
+    pub fn render(&self) -> Result<String, AppError> {
+        let version = self.version();
+        let mut content = serde_json::to_string(&self.header)
+            .map_err(|e| AppError::serialization_error(e.to_string()))?;
+        content.push('\n');
+
+        let mut previous = 0.0;
+        for event in &self.events {
+            let time = if version == 3 {
+                event.time - previous
+            } else {
+                event.time
+            };
+            previous = event.time;
+            let line = serde_json::to_string(&(round_time(time), &event.code, &event.data))
+                .map_err(|e| AppError::serialization_error(e.to_string()))?;
+            content.push_str(&line);
+            content.push('\n');
+        }
+
+        Ok(content)
+    }
+
+    pub fn version(&self) -> u8 {
+        header_version(&self.header)
+    }
+
+    /// Terminal size in the header, as (cols, rows)
+    pub fn size(&self) -> (u16, u16) {
+        let (cols, rows) = if self.version() == 3 {
+            let term = self.header.get("term");
+            (
+                term.and_then(|term| term.get("cols")),
+                term.and_then(|term| term.get("rows")),
+            )
+        } else {
+            (self.header.get("width"), self.header.get("height"))
+        };
+        let dimension = |value: Option<&Value>, default: u16| {
+            value
+                .and_then(Value::as_u64)
+                .and_then(|value| u16::try_from(value).ok())
+                .unwrap_or(default)
+        };
+        (dimension(cols, 80), dimension(rows, 24))
+    }
+
+    /// Seconds until the last event
+    pub fn duration(&self) -> f64 {
+        self.events.last().map(|event| event.time).unwrap_or(0.0)
+    }
+
+    pub fn apply(&mut self, edit: &RecordingEdit) -> Result<(), AppError> {
+        match edit {
+            RecordingEdit::CapIdle { max_idle_secs } => {
+                if max_idle_secs.is_nan() || *max_idle_secs <= 0.0 {
+                    return Err(AppError::validation_error(
+                        "Idle time limit must be positive",
+                    ));
+                }
+                self.cap_idle(*max_idle_secs);
+            }
+            RecordingEdit::Speed { factor } => {
+                if !factor.is_finite() || *factor <= 0.0 {
+                    return Err(AppError::validation_error("Speed factor must be positive"));
+                }
+                self.speed(*factor);
+            }
+            RecordingEdit::Cut {
+                start_secs,
+                end_secs,
+            } => {
+                if start_secs.is_nan()
+                    || *start_secs < 0.0
+                    || end_secs.is_nan()
+                    || end_secs <= start_secs
+                {
+                    return Err(AppError::validation_error(format!(
+                        "Invalid cut range {}s to {}s",
+                        start_secs, end_secs
+                    )));
+                }
+                self.cut(*start_secs, *end_secs);
+            }
+            RecordingEdit::Redact {
+                patterns,
+                replacement,
+            } => {
+                let patterns = patterns
+                    .iter()
+                    .map(|pattern| {
+                        Regex::new(pattern).map_err(|e| {
+                            AppError::validation_error(format!(
+                                "Invalid redaction pattern '{}': {}",
+                                pattern, e
+                            ))
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let replacement = replacement.as_deref().unwrap_or(DEFAULT_REDACTION);
+                self.redact("o", &patterns, replacement);
+                self.redact("i", &patterns, replacement);
+            }
+        }
+        Ok(())
+    }
+
+    /// Shorten every pause to at most `max_idle` seconds
+    pub fn cap_idle(&mut self, max_idle: f64) {
+        let mut previous = 0.0;
+        let mut time = 0.0;
+        for event in &mut self.events {
+            let pause = (event.time - previous).max(0.0);
+            previous = event.time;
+            time += pause.min(max_idle);
+            event.time = time;
+        }
+    }
+
+    pub fn speed(&mut self, factor: f64) {
+        for event in &mut self.events {
+            event.time /= factor;
+        }
+    }
+
+    /// Drop the events in `start..end` and move the later ones back by the cut length.
+    /// Markers and the last resize of the range are kept at the cut point, so players
+    /// still follow the terminal size and can jump to the markers.
+    pub fn cut(&mut self, start: f64, end: f64) {
+        let mut resize = None;
+        let mut markers = Vec::new();
+        let mut events = Vec::with_capacity(self.events.len());
+        for mut event in self.events.drain(..) {
+            if event.time < start {
+                events.push(event);
+            } else if event.time < end {
+                event.time = start;
+                match event.code.as_str() {
+                    "r" => resize = Some(event),
+                    "m" => markers.push(event),
+                    _ => {}
+                }
+            } else {
+                event.time -= end - start;
+                events.extend(resize.take());
+                events.append(&mut markers);
+                events.push(event);
+            }
+        }
+        events.extend(resize);
+        events.append(&mut markers);
+        self.events = events;
+    }
+
+    /// Append another cast after this one, `gap` seconds after its last event. A resize
+    /// event is inserted when the other cast has a different terminal size.
+    pub fn append(&mut self, other: Cast, gap: f64) {
+        let offset = self.duration() + gap;
+
+        let current_size = self
+            .events
+            .iter()
+            .rev()
+            .find(|event| event.code == "r")
+            .and_then(|event| parse_size(&event.data))
+            .unwrap_or_else(|| self.size());
+        let (cols, rows) = other.size();
+        if current_size != (cols, rows) {
+            self.events.push(CastEvent {
+                time: offset,
+                code: "r".to_string(),
+                data: format!("{}x{}", cols, rows),
+            });
+        }
+
+        self.events
+            .extend(other.events.into_iter().map(|event| CastEvent {
+                time: event.time + offset,
+                ..event
+            }));
+    }
+
+    /// Replace pattern matches in the stream of events with the given code. The events
+    /// are joined first, so matches split across output chunks are found too.
+    pub fn redact(&mut self, code: &str, patterns: &[Regex], replacement: &str) {
+        let indices: Vec<usize> = self
+            .events
+            .iter()
+            .enumerate()
+            .filter(|(_, event)| event.code == code)
+            .map(|(index, _)| index)
+            .collect();
+
+        let mut stream = String::new();
+        let mut bounds = Vec::with_capacity(indices.len());
+        for &index in &indices {
+            let start = stream.len();
+            stream.push_str(&self.events[index].data);
+            bounds.push((start, stream.len()));
+        }
+
+        let mut matches: Vec<(usize, usize)> = patterns
+            .iter()
+            .flat_map(|pattern| pattern.find_iter(&stream))
+            .map(|found| (found.start(), found.end()))
+            .filter(|(start, end)| start < end)
+            .collect();
+        matches.sort_unstable();
+
+        let mut merged: Vec<(usize, usize)> = Vec::new();
+        for (start, end) in matches {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        if merged.is_empty() {
+            return;
+        }
+
+        for (&index, &(start, end)) in indices.iter().zip(&bounds) {
+            let overlapping = merged
+                .iter()
+                .filter(|(found_start, found_end)| *found_start < end && *found_end > start);
+            let mut data = String::new();
+            let mut position = start;
+            for &(found_start, found_end) in overlapping {
+                if found_start > position {
+                    data.push_str(&stream[position..found_start]);
+                }
+                // The replacement goes where the match starts, the rest of it is dropped
+                if found_start >= start {
+                    data.push_str(replacement);
+                }
+                position = position.max(found_end.min(end));
+            }
+            if position < end {
+                data.push_str(&stream[position..end]);
+            }
+            self.events[index].data = data;
+        }
+    }
+}
+
+fn header_version(header: &Map<String, Value>) -> u8 {
+    header
+        .get("version")
+        .and_then(Value::as_u64)
+        .and_then(|version| u8::try_from(version).ok())
+        .unwrap_or(0)
+}
+
+/// Parse the "COLSxROWS" data of a resize event
+fn parse_size(data: &str) -> Option<(u16, u16)> {
+    let (cols, rows) = data.split_once('x')?;
+    Some((cols.trim().parse().ok()?, rows.trim().parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(time: f64, code: &str, data: &str) -> CastEvent {
+        CastEvent {
+            time,
+            code: code.to_string(),
+            data: data.to_string(),
+        }
+    }
+
+    fn cast(events: Vec<CastEvent>) -> Cast {
+        let header = serde_json::json!({ "version": 2, "width": 80, "height": 24 });
+        Cast {
+            header: header.as_object().cloned().unwrap(),
+            events,
+        }
+    }
+
+    #[test]
+    fn test_parses_and_renders_v3_intervals() {
+        let content = "{\"version\":3,\"term\":{\"cols\":100,\"rows\":30}}\n\
+                       [0.5,\"o\",\"a\"]\n# comment\n[1.0,\"o\",\"b\"]\n";
+        let parsed = Cast::parse(content).unwrap();
+        assert_eq!(parsed.size(), (100, 30));
+        assert_eq!(parsed.events[1].time, 1.5);
+        let rendered = parsed.render().unwrap();
+        let events: Vec<&str> = rendered.lines().skip(1).collect();
+        assert_eq!(events, vec!["[0.5,\"o\",\"a\"]", "[1.0,\"o\",\"b\"]"]);
+    }
+
+    #[test]
+    fn test_caps_idle_time_and_cuts_ranges() {
+        let mut recording = cast(vec![
+            event(1.0, "o", "a"),
+            event(10.0, "o", "b"),
+            event(10.5, "o", "c"),
+            event(12.0, "o", "d"),
+        ]);
+        recording.cap_idle(2.0);
+        let times: Vec<f64> = recording.events.iter().map(|e| e.time).collect();
+        assert_eq!(times, vec![1.0, 3.0, 3.5, 5.0]);
+
+        recording.cut(3.0, 4.0);
+        let remaining: Vec<(f64, &str)> = recording
+            .events
+            .iter()
+            .map(|e| (e.time, e.data.as_str()))
+            .collect();
+        assert_eq!(remaining, vec![(1.0, "a"), (4.0, "d")]);
+    }
+
+    #[test]
+    fn test_cut_keeps_last_resize_and_markers() {
+        let mut recording = cast(vec![
+            event(1.0, "o", "a"),
+            event(2.0, "r", "100x30"),
+            event(2.5, "m", "deploy"),
+            event(3.0, "r", "120x40"),
+            event(3.5, "o", "b"),
+            event(6.0, "o", "c"),
+        ]);
+        recording.cut(2.0, 5.0);
+        assert_eq!(
+            recording.events,
+            vec![
+                event(1.0, "o", "a"),
+                event(2.0, "r", "120x40"),
+                event(2.0, "m", "deploy"),
+                event(3.0, "o", "c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_redacts_matches_split_across_events() {
+        let mut recording = cast(vec![
+            event(0.0, "o", "token=ab"),
+            event(0.5, "i", "ls\r"),
+            event(1.0, "o", "cd12 ok"),
+        ]);
+        let patterns = [Regex::new("token=[a-z0-9]+").unwrap()];
+        recording.redact("o", &patterns, "***");
+        assert_eq!(recording.events[0].data, "***");
+        assert_eq!(recording.events[1].data, "ls\r");
+        assert_eq!(recording.events[2].data, " ok");
+    }
+
+    #[test]
+    fn test_appends_with_resize_when_sizes_differ() {
+        let mut first = cast(vec![event(2.0, "o", "a")]);
+        let mut second = cast(vec![event(1.0, "o", "b")]);
+        second.header.insert("width".to_string(), 120.into());
+        first.append(second, 0.5);
+        assert_eq!(
+            first.events,
+            vec![
+                event(2.0, "o", "a"),
+                event(2.5, "r", "120x24"),
+                event(3.5, "o", "b"),
+            ]
+        );
+    }
+}
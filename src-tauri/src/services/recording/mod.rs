@@ -16,6 +16,8 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+pub mod edit;
 pub mod recorder;
 
+pub use edit::Cast;
 pub use recorder::SessionRecorder;
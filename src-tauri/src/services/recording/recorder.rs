@@ -16,6 +16,7 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use crate::core::ansi::AnsiStripper;
 use crate::error::AppError;
 use crate::models::recording::*;
 use chrono::{DateTime, Utc};
@@ -25,6 +26,28 @@ use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
 use tokio::sync::Mutex;
 
+/// Words that suggest the current output line is a prompt for a secret. Only a
+/// heuristic for terminals whose echo state can't be read, and it misses prompts in
+/// other languages.
+const SECRET_PROMPTS: &[&str] = &[
+    "password",
+    "passphrase",
+    "passcode",
+    "pin",
+    "verification code",
+    "one-time",
+    "otp",
+];
+
+/// Character typed input is replaced with while a secret is entered
+const MASK_CHAR: char = '*';
+
+struct CastWriter {
+    file: File,
+    /// Seconds since start of the previous event, v3 events store the interval since it
+    last_event: f64,
+}
+
 pub struct SessionRecorder {
     pub recording_id: String,
     pub session_name: String,
@@ -32,8 +55,10 @@ pub struct SessionRecorder {
     start_time: Instant,
     pub started_at: DateTime<Utc>,
     pub file_path: String,
-    file_writer: Arc<Mutex<File>>,
+    file_writer: Arc<Mutex<CastWriter>>,
     pub header: AsciicastHeader,
+    pub options: RecordingOptions,
+    input_mask: std::sync::Mutex<InputMask>,
     is_recording: Arc<tokio::sync::RwLock<bool>>,
 }
 
@@ -45,20 +70,28 @@ impl SessionRecorder {
         height: u16,
         title: Option<String>,
         terminal_type: String,
+        options: RecordingOptions,
     ) -> Result<Self, AppError> {
+        if !matches!(options.version, 2 | 3) {
+            return Err(AppError::validation_error(format!(
+                "Unsupported asciicast version {}",
+                options.version
+            )));
+        }
+
         let file = File::create(&file_path)
             .await
             .map_err(|e| AppError::General(format!("Failed to create recording file: {}", e)))?;
 
         let header = AsciicastHeader {
-            version: 2,
+            version: options.version,
             width,
             height,
             timestamp: Some(chrono::Utc::now().timestamp()),
             title: title.clone(),
-            env: Some(serde_json::json!({
-                "TERM": "xterm-256color",
-                "SHELL": std::env::var("SHELL").unwrap_or_default()
+            env: Some(env_json(RecordingEnv {
+                term: None,
+                shell: std::env::var("SHELL").ok(),
             })),
         };
 
@@ -69,21 +102,61 @@ impl SessionRecorder {
             start_time: Instant::now(),
             started_at: Utc::now(),
             file_path,
-            file_writer: Arc::new(Mutex::new(file)),
+            file_writer: Arc::new(Mutex::new(CastWriter {
+                file,
+                last_event: 0.0,
+            })),
             header,
+            options,
+            input_mask: std::sync::Mutex::new(InputMask::default()),
             is_recording: Arc::new(tokio::sync::RwLock::new(true)),
         })
     }
 
+    /// Use the TERM and SHELL of the recorded process in the header, before it is written
+    pub fn set_env(&mut self, env: RecordingEnv) {
+        self.header.env = Some(env_json(env));
+    }
+
     pub async fn write_header(&self) -> Result<(), AppError> {
         let mut writer = self.file_writer.lock().await;
-        let header_json = serde_json::to_string(&self.header)
-            .map_err(|e| AppError::serialization_error(e.to_string()))?;
+        let header_json = if self.header.version == 3 {
+            let term = self
+                .header
+                .env
+                .as_ref()
+                .and_then(|env| env.get("TERM"))
+                .and_then(|term| term.as_str())
+                .map(str::to_string);
+            let env = self.header.env.clone().map(|mut env| {
+                // v3 keeps TERM in the term object
+                if let Some(env) = env.as_object_mut() {
+                    env.remove("TERM");
+                }
+                env
+            });
+            serde_json::to_string(&AsciicastV3Header {
+                version: 3,
+                term: AsciicastTerm {
+                    cols: self.header.width,
+                    rows: self.header.height,
+                    term_type: term,
+                },
+                timestamp: self.header.timestamp,
+                title: self.header.title.clone(),
+                env,
+            })
+        } else {
+            serde_json::to_string(&self.header)
+        }
+        .map_err(|e| AppError::serialization_error(e.to_string()))?;
         writer
+            .file
             .write_all(header_json.as_bytes())
             .await
             .map_err(|e| AppError::General(format!("IO error: {}", e)))?;
         writer
+            .file
             .write_all(b"\n")
             .await
             .map_err(|e| AppError::General(format!("IO error: {}", e)))?;
@@ -91,22 +164,60 @@ impl SessionRecorder {
     }
 
     pub async fn record_output(&self, data: &[u8]) -> Result<(), AppError> {
+        if self.options.record_input {
+            if let Ok(mut mask) = self.input_mask.lock() {
+                mask.observe_output(data);
+            }
+        }
+        self.write_event("o", &String::from_utf8_lossy(data)).await
+    }
+
+    /// Record typed input, when enabled, masking it while a password is entered.
+    /// `secret` is whether the terminal read the input with echo off, when known;
+    /// otherwise the input is masked when the output looks like a password prompt.
+    pub async fn record_input(&self, data: &[u8], secret: Option<bool>) -> Result<(), AppError> {
+        if !self.options.record_input {
+            return Ok(());
+        }
+        let input = String::from_utf8_lossy(data);
+        let input = match self.input_mask.lock() {
+            Ok(mut mask) => mask.apply(&input, secret),
+            // Never write input unmasked when the prompt state is unknown
+            Err(_) => mask_all(&input),
+        };
+        self.write_event("i", &input).await
+    }
+
+    /// Record a terminal resize so players follow the new size
+    pub async fn record_resize(&self, cols: u16, rows: u16) -> Result<(), AppError> {
+        self.write_event("r", &format!("{}x{}", cols, rows)).await
+    }
+
+    /// Record a marker players can jump to
+    pub async fn record_marker(&self, label: &str) -> Result<(), AppError> {
+        self.write_event("m", label).await
+    }
+
+    async fn write_event(&self, code: &str, data: &str) -> Result<(), AppError> {
         if !*self.is_recording.read().await {
             return Ok(());
         }
 
+        let mut writer = self.file_writer.lock().await;
+        // Taken under the lock so events are written in time order
         let elapsed = self.start_time.elapsed().as_secs_f64();
-        let data_str = String::from_utf8_lossy(data);
+        let time = event_time(self.header.version, elapsed, writer.last_event);
+        writer.last_event = elapsed;
 
-        let event = serde_json::json!([elapsed, "o", data_str]);
+        let event = serde_json::json!([time, code, data]);
         let event_line = format!(
             "{}\n",
             serde_json::to_string(&event)
                 .map_err(|e| AppError::serialization_error(e.to_string()))?
         );
 
-        let mut writer = self.file_writer.lock().await;
         writer
+            .file
             .write_all(event_line.as_bytes())
             .await
             .map_err(|e| AppError::General(format!("IO error: {}", e)))?;
@@ -118,6 +229,7 @@ impl SessionRecorder {
         *self.is_recording.write().await = false;
         let mut writer = self.file_writer.lock().await;
         writer
+            .file
             .flush()
             .await
             .map_err(|e| AppError::General(format!("IO error: {}", e)))?;
@@ -128,3 +240,135 @@ impl SessionRecorder {
         self.start_time.elapsed().as_millis() as i64
     }
 }
+
+fn env_json(env: RecordingEnv) -> serde_json::Value {
+    serde_json::json!({
+        "TERM": env.term.unwrap_or_else(|| "xterm-256color".to_string()),
+        "SHELL": env.shell.unwrap_or_default()
+    })
+}
+
+/// Event time as stored in the file: seconds since start in v2, since the previous event in v3
+fn event_time(version: u8, elapsed: f64, last_event: f64) -> f64 {
+    let time = if version == 3 {
+        (elapsed - last_event).max(0.0)
+    } else {
+        elapsed
+    };
+    round_time(time)
+}
+
+/// Microsecond precision, like asciinema writes
+pub(super) fn round_time(time: f64) -> f64 {
+    (time * 1_000_000.0).round() / 1_000_000.0
+}
+
+/// Tells when typed input is a secret. The terminal's echo state decides when it is
+/// known; otherwise the line the cursor is on is matched against prompt words, which
+/// is a best-effort guess. Programs turn echo off at password prompts, so the prompt
+/// stays the last thing printed while the secret is typed.
+#[derive(Default)]
+struct InputMask {
+    line: AnsiStripper,
+    masking: bool,
+    /// Set once a secret was submitted, until the terminal prints something again
+    submitted: bool,
+}
+
+impl InputMask {
+    fn observe_output(&mut self, data: &[u8]) {
+        self.line.push(data);
+        self.submitted = false;
+    }
+
+    fn apply(&mut self, input: &str, secret: Option<bool>) -> String {
+        match secret {
+            Some(secret) => self.masking = secret,
+            None => {
+                if !self.masking && !self.submitted && is_secret_prompt(&self.line.current_line()) {
+                    self.masking = true;
+                }
+            }
+        }
+
+        let mut result = String::with_capacity(input.len());
+        for c in input.chars() {
+            if !self.masking {
+                result.push(c);
+            } else if c == '\r' || c == '\n' {
+                self.masking = false;
+                self.submitted = true;
+                result.push(c);
+            } else if c.is_control() {
+                result.push(c);
+            } else {
+                result.push(MASK_CHAR);
+            }
+        }
+        result
+    }
+}
+
+fn mask_all(input: &str) -> String {
+    input
+        .chars()
+        .map(|c| if c.is_control() { c } else { MASK_CHAR })
+        .collect()
+}
+
+/// Whether a prompt line looks like it asks for a password or similar secret
+fn is_secret_prompt(line: &str) -> bool {
+    let line = line.trim_end().to_lowercase();
+    if !line.ends_with(':') {
+        return false;
+    }
+    line.split(|c: char| !c.is_alphanumeric() && c != '-')
+        .any(|word| SECRET_PROMPTS.contains(&word))
+        || SECRET_PROMPTS
+            .iter()
+            .filter(|prompt| prompt.contains(' '))
+            .any(|prompt| line.contains(prompt))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_masks_input_typed_at_password_prompts() {
+        let mut mask = InputMask::default();
+        mask.observe_output(b"$ sudo ls\r\n[sudo] password for user: ");
+        assert_eq!(mask.apply("hunter2", None), "*******");
+        assert_eq!(mask.apply("!\rls\r", None), "*\rls\r");
+
+        // The prompt is still the current line until the shell prints again
+        assert_eq!(mask.apply("pwd", None), "pwd");
+
+        mask.observe_output(b"\r\n$ ");
+        assert_eq!(mask.apply("echo pin", None), "echo pin");
+        assert!(is_secret_prompt("Enter PIN for token:"));
+        assert!(is_secret_prompt("Verification code: "));
+        assert!(!is_secret_prompt("Spinning up containers:"));
+        assert!(!is_secret_prompt("password reset done"));
+    }
+
+    #[test]
+    fn test_echo_state_overrides_prompt_words() {
+        let mut mask = InputMask::default();
+
+        // A prompt without any of the words, read with echo off
+        mask.observe_output(b"Mot de passe : ");
+        assert_eq!(mask.apply("secret\rls", Some(true)), "******\rls");
+
+        // Echo on wins over a prompt that looks like a password prompt
+        mask.observe_output(b"\r\nNew password hint: ");
+        assert_eq!(mask.apply("dog", Some(false)), "dog");
+    }
+
+    #[test]
+    fn test_v3_events_store_intervals() {
+        assert_eq!(event_time(2, 1.5, 1.0), 1.5);
+        assert_eq!(event_time(3, 1.5, 1.0), 0.5);
+        assert_eq!(event_time(3, 2.000_000_4, 2.0), 0.0);
+    }
+}
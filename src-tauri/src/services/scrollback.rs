@@ -0,0 +1,201 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::core::ansi::AnsiStripper;
+use crate::error::AppError;
+use crate::models::terminal::{ScrollbackConfig, ScrollbackMatch};
+use regex::Regex;
+use std::collections::VecDeque;
+
+/// Bounded ANSI-stripped history of a terminal's output, kept independently of the
+/// frontend buffer so it can be searched and saved
+pub struct ScrollbackBuffer {
+    config: ScrollbackConfig,
+    stripper: AnsiStripper,
+    lines: VecDeque<String>,
+    /// Lines dropped so far, the oldest kept line is number `evicted + 1`
+    evicted: u64,
+    bytes: usize,
+}
+
+impl ScrollbackBuffer {
+    pub fn new(config: ScrollbackConfig) -> Self {
+        Self {
+            config,
+            stripper: AnsiStripper::new(),
+            lines: VecDeque::new(),
+            evicted: 0,
+            bytes: 0,
+        }
+    }
+
+    /// Append raw terminal output
+    pub fn push(&mut self, data: &[u8]) {
+        for line in self.stripper.push(data) {
+            self.bytes += line.len();
+            self.lines.push_back(line);
+        }
+        self.trim();
+    }
+
+    /// Apply new limits, dropping the oldest lines if the buffer is now over them
+    pub fn set_config(&mut self, config: ScrollbackConfig) {
+        self.config = config;
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.lines.len() > self.config.max_lines || self.bytes > self.config.max_bytes {
+            let Some(line) = self.lines.pop_front() else {
+                break;
+            };
+            self.bytes -= line.len();
+            self.evicted += 1;
+        }
+    }
+
+    /// Number of the oldest line still kept
+    pub fn first_line(&self) -> u64 {
+        self.evicted + 1
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Kept lines, including the unterminated line at the prompt
+    fn all_lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self.lines.iter().cloned().collect();
+        let current = self.stripper.current_line();
+        if !current.is_empty() {
+            lines.push(current);
+        }
+        lines
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.lines.len() + usize::from(!self.stripper.current_line().is_empty())
+    }
+
+    /// Copy of the kept lines, so they can be searched without holding the buffer
+    /// while output keeps coming in
+    pub fn snapshot(&self) -> ScrollbackSnapshot {
+        ScrollbackSnapshot {
+            first_line: self.first_line(),
+            lines: self.all_lines(),
+        }
+    }
+
+    /// Lines `start..=end` (clamped to what is kept), the whole buffer when not given
+    pub fn range(&self, start: Option<u64>, end: Option<u64>) -> Result<Vec<String>, AppError> {
+        let first = self.first_line();
+        let start = start.unwrap_or(first).max(first);
+        let end = end.unwrap_or(u64::MAX);
+        if start > end {
+            return Err(AppError::validation_error(format!(
+                "Invalid scrollback range {}..{}",
+                start, end
+            )));
+        }
+
+        Ok(self
+            .all_lines()
+            .into_iter()
+            .skip((start - first) as usize)
+            .take(usize::try_from(end - start + 1).unwrap_or(usize::MAX))
+            .collect())
+    }
+}
+
+/// Lines of a scrollback buffer at one point in time
+pub struct ScrollbackSnapshot {
+    first_line: u64,
+    lines: Vec<String>,
+}
+
+impl ScrollbackSnapshot {
+    /// Find lines matching `regex` with up to `context` lines around each match
+    pub fn search(
+        &self,
+        regex: &Regex,
+        context: usize,
+        max_results: usize,
+    ) -> Vec<ScrollbackMatch> {
+        let lines = &self.lines;
+
+        lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| regex.is_match(line))
+            .take(max_results)
+            .map(|(index, line)| ScrollbackMatch {
+                line_number: self.first_line + index as u64,
+                line: line.clone(),
+                before: lines[index.saturating_sub(context)..index].to_vec(),
+                after: lines[index + 1..(index + 1 + context).min(lines.len())].to_vec(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(max_lines: usize, max_bytes: usize) -> ScrollbackConfig {
+        ScrollbackConfig {
+            max_lines,
+            max_bytes,
+        }
+    }
+
+    #[test]
+    fn test_evicts_oldest_lines_and_keeps_numbering() {
+        let mut buffer = ScrollbackBuffer::new(config(3, 1024));
+        buffer.push(b"one\r\ntwo\r\nthree\r\nfour\r\n$ ");
+
+        assert_eq!(buffer.first_line(), 2);
+        assert_eq!(buffer.line_count(), 4);
+        assert_eq!(
+            buffer.range(Some(3), Some(4)).unwrap(),
+            vec!["three", "four"]
+        );
+        assert_eq!(buffer.range(None, None).unwrap().last().unwrap(), "$ ");
+
+        buffer.set_config(config(10, 8));
+        assert_eq!(buffer.range(None, None).unwrap(), vec!["four", "$ "]);
+        assert!(buffer.range(Some(9), Some(5)).is_err());
+    }
+
+    #[test]
+    fn test_search_returns_context_lines() {
+        let mut buffer = ScrollbackBuffer::new(ScrollbackConfig::default());
+        buffer.push(b"a\nb\n\x1b[31merror: boom\x1b[0m\nc\nd\n");
+
+        let snapshot = buffer.snapshot();
+
+        // Output after the snapshot is not searched
+        buffer.push(b"error: later\n");
+        let matches = snapshot.search(&Regex::new("error").unwrap(), 1, 10);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].line_number, 3);
+        assert_eq!(matches[0].line, "error: boom");
+        assert_eq!(matches[0].before, vec!["b"]);
+        assert_eq!(matches[0].after, vec!["c"]);
+    }
+}
@@ -0,0 +1,286 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::core::ansi::AnsiStripper;
+use crate::error::AppError;
+use crate::models::terminal::{LogRotation, SessionLogConfig};
+use chrono::{Local, NaiveDate};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+/// Continuous plain-text log of a terminal's output, configured per profile
+pub struct SessionLogger {
+    config: SessionLogConfig,
+    profile: String,
+    terminal_id: String,
+    state: Mutex<LogFile>,
+    write_failed: AtomicBool,
+}
+
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    bytes: u64,
+    date: NaiveDate,
+    stripper: AnsiStripper,
+    /// Raw mode: whether the next byte starts a line and needs a timestamp
+    at_line_start: bool,
+}
+
+impl SessionLogger {
+    /// Open (or append to) the log file for a new terminal
+    pub async fn start(
+        config: SessionLogConfig,
+        profile: &str,
+        terminal_id: &str,
+    ) -> Result<Self, AppError> {
+        let date = Local::now().date_naive();
+        let path = resolve_path(&render_path(
+            &config.path_template,
+            profile,
+            date,
+            terminal_id,
+        ));
+        let (file, bytes) = open_log(&path).await?;
+
+        Ok(Self {
+            config,
+            profile: profile.to_string(),
+            terminal_id: terminal_id.to_string(),
+            state: Mutex::new(LogFile {
+                path,
+                file,
+                bytes,
+                date,
+                stripper: AnsiStripper::new(),
+                at_line_start: true,
+            }),
+            write_failed: AtomicBool::new(false),
+        })
+    }
+
+    /// Note that writing the log failed, true only for the first failure so it is
+    /// reported once instead of for every chunk of output
+    pub fn mark_failed(&self) -> bool {
+        !self.write_failed.swap(true, Ordering::SeqCst)
+    }
+
+    /// Log a chunk of raw terminal output
+    pub async fn log_output(&self, data: &[u8]) -> Result<(), AppError> {
+        let mut state = self.state.lock().await;
+        self.rotate_if_needed(&mut state).await?;
+
+        let stamp = self.config.timestamps.then(timestamp);
+        let output = if self.config.raw {
+            stamp_raw(data, &mut state.at_line_start, stamp.as_deref())
+        } else {
+            let lines = state.stripper.push(data);
+            format_lines(&lines, stamp.as_deref())
+        };
+
+        if !output.is_empty() {
+            state.file.write_all(&output).await?;
+            state.bytes += output.len() as u64;
+        }
+        Ok(())
+    }
+
+    /// Write the unterminated last line and flush the file
+    pub async fn stop(&self) -> Result<(), AppError> {
+        let mut state = self.state.lock().await;
+        if !self.config.raw {
+            let current = state.stripper.current_line();
+            if !current.is_empty() {
+                let stamp = self.config.timestamps.then(timestamp);
+                let output = format_lines(&[current], stamp.as_deref());
+                state.file.write_all(&output).await?;
+            }
+        }
+        state.file.flush().await?;
+        Ok(())
+    }
+
+    async fn rotate_if_needed(&self, state: &mut LogFile) -> Result<(), AppError> {
+        let today = Local::now().date_naive();
+        let rotate = match self.config.rotation {
+            LogRotation::None => false,
+            LogRotation::Daily => today != state.date,
+            LogRotation::Size { max_bytes } => state.bytes >= max_bytes,
+        };
+        if !rotate {
+            return Ok(());
+        }
+
+        state.file.flush().await?;
+        let path = resolve_path(&render_path(
+            &self.config.path_template,
+            &self.profile,
+            today,
+            &self.terminal_id,
+        ));
+        // Same file name (size rotation, or no {date} in the template): move the old log aside
+        if path == state.path {
+            let suffix = Local::now().format("%Y%m%d-%H%M%S%.3f");
+            let mut rotated = state.path.clone().into_os_string();
+            rotated.push(format!(".{}", suffix));
+            tokio::fs::rename(&state.path, &rotated).await?;
+        }
+
+        let (file, bytes) = open_log(&path).await?;
+        state.file = file;
+        state.bytes = bytes;
+        state.date = today;
+        state.path = path;
+        Ok(())
+    }
+}
+
+/// Fill in the `{profile}`, `{date}` and `{terminal_id}` placeholders of a path template
+pub fn render_path(template: &str, profile: &str, date: NaiveDate, terminal_id: &str) -> PathBuf {
+    let profile: String = profile
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '@' | ' ') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    PathBuf::from(
+        template
+            .replace("{profile}", &profile)
+            .replace("{date}", &date.format("%Y-%m-%d").to_string())
+            .replace("{terminal_id}", terminal_id),
+    )
+}
+
+/// Expand `~` and resolve relative paths against the app's `logs` directory
+fn resolve_path(path: &Path) -> PathBuf {
+    if let Ok(rest) = path.strip_prefix("~") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest);
+        }
+    }
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+
+    dirs::data_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("com.klpod221.kerminal")
+        .join("logs")
+        .join(path)
+}
+
+async fn open_log(path: &Path) -> Result<(File, u64), AppError> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .map_err(|e| {
+            AppError::General(format!(
+                "Failed to open session log {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+    let bytes = file.metadata().await?.len();
+    Ok((file, bytes))
+}
+
+fn timestamp() -> String {
+    Local::now().format(TIMESTAMP_FORMAT).to_string()
+}
+
+fn format_lines(lines: &[String], stamp: Option<&str>) -> Vec<u8> {
+    let mut output = Vec::new();
+    for line in lines {
+        if let Some(stamp) = stamp {
+            output.extend_from_slice(format!("[{}] ", stamp).as_bytes());
+        }
+        output.extend_from_slice(line.as_bytes());
+        output.push(b'\n');
+    }
+    output
+}
+
+/// Copy raw output, inserting the timestamp at the start of every line
+fn stamp_raw(data: &[u8], at_line_start: &mut bool, stamp: Option<&str>) -> Vec<u8> {
+    let Some(stamp) = stamp else {
+        return data.to_vec();
+    };
+
+    let prefix = format!("[{}] ", stamp);
+    let mut output = Vec::with_capacity(data.len() + prefix.len());
+    for &byte in data {
+        if *at_line_start {
+            output.extend_from_slice(prefix.as_bytes());
+            *at_line_start = false;
+        }
+        output.push(byte);
+        if byte == b'\n' {
+            *at_line_start = true;
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renders_path_template() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 9).unwrap();
+        let path = render_path(
+            "/var/log/kerminal/{profile}/{date}-{terminal_id}.log",
+            "prod/db 1",
+            date,
+            "abc",
+        );
+        assert_eq!(
+            path,
+            PathBuf::from("/var/log/kerminal/prod_db 1/2026-03-09-abc.log")
+        );
+    }
+
+    #[test]
+    fn test_stamps_raw_output_per_line_across_chunks() {
+        let mut at_line_start = true;
+        let mut output = stamp_raw(b"one\r\ntw", &mut at_line_start, Some("T"));
+        output.extend(stamp_raw(b"o\r\n", &mut at_line_start, Some("T")));
+        assert_eq!(output, b"[T] one\r\n[T] two\r\n");
+        assert!(at_line_start);
+
+        assert_eq!(
+            format_lines(&["a".to_string()], Some("T")),
+            b"[T] a\n".to_vec()
+        );
+    }
+}
@@ -21,40 +21,22 @@ use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::{Mutex, RwLock};
 
-use crate::core::proxy::create_proxy_stream;
 use crate::models::sftp::search::SearchResult;
 use crate::models::sftp::{error::SFTPError, file_entry::FileEntry, FileType};
 use crate::models::ssh::AuthData;
+use crate::services::ssh::connection_pool::PooledSession;
 use crate::services::ssh::{SSHKeyService, SSHService};
 
 use crate::services::sftp::channel_stream::ChannelStream;
 use anyhow::Result;
-use async_trait::async_trait;
 use chrono::Utc;
-use russh::client::Config;
-use russh_keys::key::PublicKey;
 use russh_sftp::client::SftpSession;
 
-/// Simple handler for SFTP connections
-#[derive(Clone)]
-pub struct SFTPClientHandler;
-
-#[async_trait]
-impl russh::client::Handler for SFTPClientHandler {
-    type Error = russh::Error;
-
-    async fn check_server_key(
-        &mut self,
-        _server_public_key: &PublicKey,
-    ) -> Result<bool, Self::Error> {
-        Ok(true)
-    }
-}
-
 /// Internal SFTP session data
 pub struct SFTPSessionData {
     pub sftp: SftpSession,
-    pub client: Arc<russh::client::Handle<SFTPClientHandler>>,
+    /// Lease on the pooled SSH connection the SFTP channel runs on
+    pub connection: PooledSession,
     last_used: chrono::DateTime<Utc>,
 }
 
@@ -95,136 +77,38 @@ impl SFTPService {
             }
         }
 
-        // Create SSH session
-        let keepalive_interval = if profile.keep_alive {
-            Some(std::time::Duration::from_secs(15))
-        } else {
-            None
-        };
-
-        let inactivity_timeout = profile
-            .timeout
-            .map(|t| std::time::Duration::from_secs(t as u64));
-
-        let mut config = Config {
-            inactivity_timeout,
-            keepalive_interval,
-            keepalive_max: 10,
-            ..Default::default()
-        };
-
-        config.window_size = 2097152;
-        config.maximum_packet_size = 32768;
-
-        let config = Arc::new(config);
-        let handler = SFTPClientHandler;
-
-        let mut session = if let Some(proxy_config) = &profile.proxy {
-            let stream = create_proxy_stream(proxy_config, &profile.host, profile.port)
-                .await
-                .map_err(|e| SFTPError::SessionFailed {
-                    message: format!("Failed to create proxy connection: {}", e),
-                })?;
-
-            russh::client::connect_stream(config, stream, handler)
-                .await
-                .map_err(|e| SFTPError::SessionFailed {
-                    message: format!("Failed to connect via proxy: {}", e),
-                })?
-        } else {
-            russh::client::connect(config, (&profile.host as &str, profile.port), handler)
-                .await
-                .map_err(|e| SFTPError::SessionFailed {
-                    message: format!(
-                        "Failed to connect to {}:{}: {}",
-                        profile.host, profile.port, e
-                    ),
-                })?
-        };
-
-        // Authenticate
-        let authenticated = match &profile.auth_data {
-            AuthData::Password { password } => session
-                .authenticate_password(&profile.username, password)
-                .await
-                .map_err(|e| SFTPError::SessionFailed {
-                    message: format!("Password authentication failed: {}", e),
-                })?,
+        // Get an authenticated connection from the shared pool
+        let resolved_key = match &profile.auth_data {
             AuthData::KeyReference { key_id } => {
                 let key_service = self.ssh_key_service.lock().await;
-                let resolved_key = key_service
-                    .resolve_key_for_auth(key_id)
-                    .await
-                    .map_err(|e| SFTPError::SessionFailed {
-                        message: format!("Failed to resolve SSH key: {}", e),
-                    })?;
-
-                let key = if Path::new(&resolved_key.private_key).exists() {
-                    russh_keys::load_secret_key(
-                        &resolved_key.private_key,
-                        resolved_key.passphrase.as_deref(),
-                    )
-                    .map_err(|e| SFTPError::SessionFailed {
-                        message: format!("Failed to load SSH key: {}", e),
-                    })?
-                } else {
-                    russh_keys::decode_secret_key(
-                        &resolved_key.private_key,
-                        resolved_key.passphrase.as_deref(),
-                    )
-                    .map_err(|e| SFTPError::SessionFailed {
-                        message: format!("Failed to parse SSH key: {}", e),
-                    })?
-                };
-
-                session
-                    .authenticate_publickey(&profile.username, Arc::new(key))
-                    .await
-                    .map_err(|e| SFTPError::SessionFailed {
-                        message: format!("SSH key authentication failed: {}", e),
-                    })?
-            }
-            AuthData::Certificate {
-                certificate: _,
-                private_key,
-                ..
-            } => {
-                let key = if Path::new(private_key).exists() {
-                    russh_keys::load_secret_key(private_key, None).map_err(|e| {
-                        SFTPError::SessionFailed {
-                            message: format!("Failed to load certificate key: {}", e),
-                        }
-                    })?
-                } else {
-                    russh_keys::decode_secret_key(private_key, None).map_err(|e| {
-                        SFTPError::SessionFailed {
-                            message: format!("Failed to parse certificate key: {}", e),
-                        }
-                    })?
-                };
-
-                session
-                    .authenticate_publickey(&profile.username, Arc::new(key))
-                    .await
-                    .map_err(|e| SFTPError::SessionFailed {
-                        message: format!("Certificate authentication failed: {}", e),
-                    })?
+                Some(
+                    key_service
+                        .resolve_key_for_auth(key_id)
+                        .await
+                        .map_err(|e| SFTPError::SessionFailed {
+                            message: format!("Failed to resolve SSH key: {}", e),
+                        })?,
+                )
             }
+            _ => None,
         };
 
-        if !authenticated {
-            return Err(SFTPError::SessionFailed {
-                message: "Authentication failed".to_string(),
-            });
-        }
+        let connection = self
+            .ssh_service
+            .connection_pool()
+            .acquire(&profile, resolved_key)
+            .await
+            .map_err(|e| SFTPError::SessionFailed {
+                message: e.to_string(),
+            })?;
 
         // Open SFTP channel
         let channel =
-            session
-                .channel_open_session()
+            connection
+                .open_session_channel()
                 .await
                 .map_err(|e| SFTPError::SessionFailed {
-                    message: format!("Failed to open SSH channel: {}", e),
+                    message: e.to_string(),
                 })?;
 
         // Request SFTP subsystem
@@ -246,7 +130,7 @@ impl SFTPService {
         let now = Utc::now();
         let session_data = SFTPSessionData {
             sftp,
-            client: Arc::new(session),
+            connection,
             last_used: now,
         };
 
@@ -728,20 +612,21 @@ impl SFTPService {
     ) -> Result<Vec<SearchResult>, SFTPError> {
         let session_data = self.get_session(&session_id).await?;
 
-        // Clone client handle to avoid holding lock during search
-        let client = {
+        // Clone the connection lease to avoid holding lock during search
+        let connection = {
             let mut data = session_data.lock().await;
             data.last_used = Utc::now();
-            data.client.clone()
+            data.connection.clone()
         };
 
         // Open a new channel for the search command
-        let mut channel = client
-            .channel_open_session()
-            .await
-            .map_err(|e| SFTPError::Other {
-                message: format!("Failed to open channel for search: {}", e),
-            })?;
+        let mut channel =
+            connection
+                .open_session_channel()
+                .await
+                .map_err(|e| SFTPError::Other {
+                    message: format!("Failed to open channel for search: {}", e),
+                })?;
 
         // Escape query to prevent command injection
         // This is a basic escaping, ideally we'd use a robust shell escaping library
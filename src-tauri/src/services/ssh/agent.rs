@@ -0,0 +1,263 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::path::{Path, PathBuf};
+
+use log::{debug, warn};
+use russh::client::{Handle, Handler, Msg};
+use russh::Channel;
+use russh_keys::key::PublicKey;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::error::AppError;
+
+/// Environment variable pointing at the local ssh-agent socket
+pub const SSH_AUTH_SOCK: &str = "SSH_AUTH_SOCK";
+
+/// Resolve the local agent socket from `SSH_AUTH_SOCK`
+pub fn agent_socket_path() -> Result<PathBuf, AppError> {
+    match std::env::var_os(SSH_AUTH_SOCK) {
+        Some(path) if !path.is_empty() => Ok(PathBuf::from(path)),
+        _ => Err(AppError::authentication_failed(
+            "SSH agent is not available: SSH_AUTH_SOCK is not set".to_string(),
+        )),
+    }
+}
+
+/// Connect to the agent listening on the given socket
+#[cfg(unix)]
+pub async fn connect_agent(
+    path: &Path,
+) -> Result<russh_keys::agent::client::AgentClient<tokio::net::UnixStream>, AppError> {
+    russh_keys::agent::client::AgentClient::connect_uds(path)
+        .await
+        .map_err(|e| {
+            AppError::authentication_failed(format!(
+                "Failed to connect to SSH agent at {}: {}",
+                path.display(),
+                e
+            ))
+        })
+}
+
+/// List the identities held by the agent listening on the given socket
+#[cfg(unix)]
+pub async fn list_identities(path: &Path) -> Result<Vec<PublicKey>, AppError> {
+    let mut agent = connect_agent(path).await?;
+    agent.request_identities().await.map_err(|e| {
+        AppError::authentication_failed(format!("Failed to list SSH agent identities: {}", e))
+    })
+}
+
+#[cfg(not(unix))]
+pub async fn list_identities(_path: &Path) -> Result<Vec<PublicKey>, AppError> {
+    Err(AppError::authentication_failed(
+        "SSH agent authentication is not supported on this platform".to_string(),
+    ))
+}
+
+/// Authenticate with the local agent, trying each of its identities in turn.
+/// Returns false when the server rejected every identity.
+pub async fn authenticate_with_agent<H: Handler>(
+    session: &mut Handle<H>,
+    username: &str,
+) -> Result<bool, AppError> {
+    let path = agent_socket_path()?;
+    authenticate_with_agent_at(session, username, &path).await
+}
+
+#[cfg(unix)]
+async fn authenticate_with_agent_at<H: Handler>(
+    session: &mut Handle<H>,
+    username: &str,
+    path: &Path,
+) -> Result<bool, AppError> {
+    let mut agent = connect_agent(path).await?;
+    let identities = agent.request_identities().await.map_err(|e| {
+        AppError::authentication_failed(format!("Failed to list SSH agent identities: {}", e))
+    })?;
+
+    if identities.is_empty() {
+        return Err(AppError::authentication_failed(
+            "SSH agent has no identities loaded".to_string(),
+        ));
+    }
+
+    for key in identities {
+        let fingerprint = key.fingerprint();
+        let (returned_agent, result) = session.authenticate_future(username, key, agent).await;
+        agent = returned_agent;
+
+        match result {
+            Ok(true) => return Ok(true),
+            Ok(false) => debug!("Server rejected agent identity {}", fingerprint),
+            Err(e) => warn!(
+                "SSH agent failed to sign with identity {}: {}",
+                fingerprint, e
+            ),
+        }
+    }
+
+    Ok(false)
+}
+
+#[cfg(not(unix))]
+async fn authenticate_with_agent_at<H: Handler>(
+    _session: &mut Handle<H>,
+    _username: &str,
+    _path: &Path,
+) -> Result<bool, AppError> {
+    Err(AppError::authentication_failed(
+        "SSH agent authentication is not supported on this platform".to_string(),
+    ))
+}
+
+/// Serve an agent channel opened by the server (agent forwarding) from the local agent
+pub fn spawn_agent_forward(channel: Channel<Msg>) {
+    tokio::spawn(async move {
+        let result = match agent_socket_path() {
+            Ok(path) => bridge_agent(channel.into_stream(), &path).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            warn!("Agent forwarding failed: {}", e);
+        }
+    });
+}
+
+/// Copy agent protocol traffic between a stream and the local agent socket until either side closes
+#[cfg(unix)]
+async fn bridge_agent<S>(mut stream: S, path: &Path) -> Result<(), AppError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut agent = tokio::net::UnixStream::connect(path).await.map_err(|e| {
+        AppError::connection_failed(format!(
+            "Failed to connect to SSH agent at {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+
+    tokio::io::copy_bidirectional(&mut stream, &mut agent)
+        .await
+        .map_err(|e| AppError::connection_failed(format!("Agent forwarding error: {}", e)))?;
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn bridge_agent<S>(_stream: S, _path: &Path) -> Result<(), AppError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    Err(AppError::connection_failed(
+        "Agent forwarding is not supported on this platform".to_string(),
+    ))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+    use russh_keys::PublicKeyBase64;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::UnixListener;
+
+    const SSH_AGENTC_REQUEST_IDENTITIES: u8 = 11;
+    const SSH_AGENT_IDENTITIES_ANSWER: u8 = 12;
+    const TEST_KEY: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl";
+
+    fn put_string(buf: &mut Vec<u8>, data: &[u8]) {
+        buf.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        buf.extend_from_slice(data);
+    }
+
+    /// Minimal agent that answers identity requests with a single ed25519 key
+    async fn spawn_stand_in_agent() -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("kerminal-agent-{}.sock", uuid::Uuid::new_v4()));
+        let listener = UnixListener::bind(&path).unwrap();
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    loop {
+                        let mut len = [0u8; 4];
+                        if socket.read_exact(&mut len).await.is_err() {
+                            break;
+                        }
+                        let mut request = vec![0u8; u32::from_be_bytes(len) as usize];
+                        if socket.read_exact(&mut request).await.is_err() {
+                            break;
+                        }
+                        assert_eq!(request[0], SSH_AGENTC_REQUEST_IDENTITIES);
+
+                        let mut body = vec![SSH_AGENT_IDENTITIES_ANSWER];
+                        body.extend_from_slice(&1u32.to_be_bytes());
+                        put_string(&mut body, &STANDARD.decode(TEST_KEY).unwrap());
+                        put_string(&mut body, b"stand-in");
+
+                        let mut response = (body.len() as u32).to_be_bytes().to_vec();
+                        response.extend_from_slice(&body);
+                        socket.write_all(&response).await.unwrap();
+                    }
+                });
+            }
+        });
+
+        path
+    }
+
+    #[tokio::test]
+    async fn test_list_identities_from_agent_socket() {
+        let path = spawn_stand_in_agent().await;
+
+        let identities = list_identities(&path).await.unwrap();
+        assert_eq!(identities.len(), 1);
+        assert_eq!(identities[0].public_key_base64(), TEST_KEY);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_bridge_agent_forwards_requests() {
+        let path = spawn_stand_in_agent().await;
+
+        // One end plays the forwarded channel, the other the remote agent client
+        let (channel_end, client_end) = tokio::io::duplex(4096);
+        let bridge_path = path.clone();
+        let bridge = tokio::spawn(async move { bridge_agent(channel_end, &bridge_path).await });
+
+        let mut client = russh_keys::agent::client::AgentClient::connect(client_end);
+        let identities = client.request_identities().await.unwrap();
+        assert_eq!(identities.len(), 1);
+        assert_eq!(identities[0].public_key_base64(), TEST_KEY);
+
+        drop(client);
+        bridge.await.unwrap().unwrap();
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_list_identities_missing_socket() {
+        let path = std::env::temp_dir().join("kerminal-agent-missing.sock");
+        assert!(list_identities(&path).await.is_err());
+    }
+}
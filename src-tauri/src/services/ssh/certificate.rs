@@ -0,0 +1,213 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use chrono::{DateTime, Utc};
+use ssh_key::{Certificate, HashAlg};
+use std::path::Path;
+
+use crate::error::AppError;
+use crate::models::ssh::profile::{AuthData, CertificateValidity};
+use crate::models::ssh::{CertificateExpiryWarning, SSHProfile};
+
+/// Warn about certificates expiring within this many seconds
+const EXPIRY_WARNING_THRESHOLD_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Load an OpenSSH certificate (`*-cert-v01@openssh.com`) from a file path or its contents
+pub fn load_certificate(certificate: &str) -> Result<Certificate, AppError> {
+    let certificate = certificate.trim();
+    let content = if Path::new(certificate).exists() {
+        std::fs::read_to_string(certificate)
+            .map_err(|e| AppError::config_error(format!("Failed to read certificate: {}", e)))?
+    } else {
+        certificate.to_string()
+    };
+
+    Certificate::from_openssh(content.trim())
+        .map_err(|e| AppError::config_error(format!("Invalid OpenSSH certificate: {}", e)))
+}
+
+/// Validity window, serial, principals and signing CA of a certificate
+pub fn certificate_validity(certificate: &Certificate) -> CertificateValidity {
+    let valid_from = match certificate.valid_after() {
+        0 => "always".to_string(),
+        after => format_timestamp(after),
+    };
+    let valid_to = match certificate.valid_before() {
+        u64::MAX => "forever".to_string(),
+        before => format_timestamp(before),
+    };
+    let key_id = certificate.key_id();
+
+    CertificateValidity {
+        valid_from,
+        valid_to,
+        serial: Some(certificate.serial().to_string()),
+        ca_fingerprint: Some(
+            certificate
+                .signature_key()
+                .fingerprint(HashAlg::Sha256)
+                .to_string(),
+        ),
+        key_id: (!key_id.is_empty()).then(|| key_id.to_string()),
+        principals: certificate.valid_principals().to_vec(),
+    }
+}
+
+/// Fill in the validity of every certificate in the auth data, rejecting ones that do
+/// not parse. Called when profiles are saved so the UI can show it without re-parsing.
+pub fn fill_certificate_validity(auth_data: &mut AuthData) -> Result<(), AppError> {
+    match auth_data {
+        AuthData::Certificate {
+            certificate,
+            validity_period,
+            ..
+        } => {
+            *validity_period = if certificate.trim().is_empty() {
+                None
+            } else {
+                Some(certificate_validity(&load_certificate(certificate)?))
+            };
+            Ok(())
+        }
+        AuthData::MultiFactor { factors } => {
+            for factor in factors {
+                fill_certificate_validity(factor)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Expiry warning for the first certificate of the profile that is expired or close to it
+pub fn expiry_warning(profile: &SSHProfile) -> Option<CertificateExpiryWarning> {
+    expiry_warning_at(profile, Utc::now().timestamp())
+}
+
+fn expiry_warning_at(profile: &SSHProfile, now: i64) -> Option<CertificateExpiryWarning> {
+    let factors: Vec<&AuthData> = match &profile.auth_data {
+        AuthData::MultiFactor { factors } => factors.iter().collect(),
+        single => vec![single],
+    };
+
+    factors.into_iter().find_map(|factor| {
+        let AuthData::Certificate {
+            certificate,
+            validity_period,
+            ..
+        } = factor
+        else {
+            return None;
+        };
+
+        // The certificate itself is authoritative, the stored validity may be stale
+        let valid_before = match load_certificate(certificate) {
+            Ok(certificate) => i64::try_from(certificate.valid_before()).ok()?,
+            Err(_) => DateTime::parse_from_rfc3339(&validity_period.as_ref()?.valid_to)
+                .ok()?
+                .timestamp(),
+        };
+
+        let remaining = valid_before - now;
+        (remaining <= EXPIRY_WARNING_THRESHOLD_SECS).then(|| CertificateExpiryWarning {
+            profile_id: profile.base.id.clone(),
+            profile_name: profile.name.clone(),
+            host: profile.host.clone(),
+            valid_to: format_timestamp(valid_before as u64),
+            expired: remaining <= 0,
+            expires_in_seconds: remaining,
+        })
+    })
+}
+
+fn format_timestamp(timestamp: u64) -> String {
+    i64::try_from(timestamp)
+        .ok()
+        .and_then(|ts| DateTime::<Utc>::from_timestamp(ts, 0))
+        .map(|dt| dt.to_rfc3339())
+        .unwrap_or_else(|| "forever".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ssh::profile::KeyType;
+
+    /// ssh-keygen -s ca -I alice@example -n alice,deploy -z 42 -V 20200101000000:20300101000000
+    const CERTIFICATE: &str = "ssh-ed25519-cert-v01@openssh.com AAAAIHNzaC1lZDI1NTE5LWNlcnQtdjAxQG9wZW5zc2guY29tAAAAINkPQb83XLtzFguMGtRSVOy6yKHu689MojIOZe3fO6IFAAAAIAxsAdlcXCOGbY2R2krn6MB7jdgFEkt3L8Jf0ggPdCb2AAAAAAAAACoAAAABAAAADWFsaWNlQGV4YW1wbGUAAAATAAAABWFsaWNlAAAABmRlcGxveQAAAABeC+EAAAAAAHDb2IAAAAAAAAAAggAAABVwZXJtaXQtWDExLWZvcndhcmRpbmcAAAAAAAAAF3Blcm1pdC1hZ2VudC1mb3J3YXJkaW5nAAAAAAAAABZwZXJtaXQtcG9ydC1mb3J3YXJkaW5nAAAAAAAAAApwZXJtaXQtcHR5AAAAAAAAAA5wZXJtaXQtdXNlci1yYwAAAAAAAAAAAAAAMwAAAAtzc2gtZWQyNTUxOQAAACBigF7xqlZIr8tWlHuU65U2k+Bh6a0w5IcUpyjGWzcpDQAAAFMAAAALc3NoLWVkMjU1MTkAAABAxulLuakkoEm3iNYnoK3BSu8e5M5S98dmjaQd11YJHk3VPp0ACmiFLezndeFDpx1T9GR2KZs7OC5sRZYh3Zj/Dg== user";
+
+    fn certificate_profile() -> SSHProfile {
+        let mut profile = SSHProfile::new(
+            "device".to_string(),
+            "cert".to_string(),
+            "example.com".to_string(),
+            22,
+            "alice".to_string(),
+        );
+        profile.auth_data = AuthData::Certificate {
+            certificate: CERTIFICATE.to_string(),
+            private_key: String::new(),
+            key_type: KeyType::Ed25519,
+            validity_period: None,
+        };
+        profile
+    }
+
+    #[test]
+    fn test_certificate_validity() {
+        let validity = certificate_validity(&load_certificate(CERTIFICATE).unwrap());
+
+        assert_eq!(validity.valid_from, "2020-01-01T00:00:00+00:00");
+        assert_eq!(validity.valid_to, "2030-01-01T00:00:00+00:00");
+        assert_eq!(validity.serial.as_deref(), Some("42"));
+        assert_eq!(validity.key_id.as_deref(), Some("alice@example"));
+        assert_eq!(validity.principals, vec!["alice", "deploy"]);
+        assert_eq!(
+            validity.ca_fingerprint.as_deref(),
+            Some("SHA256:1N/XGXDgf86pdCKxDCJKZuEqR7OPSKZvd2S6W170RCY")
+        );
+    }
+
+    #[test]
+    fn test_fill_certificate_validity_rejects_invalid() {
+        let mut auth_data = AuthData::Certificate {
+            certificate: "ssh-ed25519 not-a-certificate".to_string(),
+            private_key: String::new(),
+            key_type: KeyType::Ed25519,
+            validity_period: None,
+        };
+        assert!(fill_certificate_validity(&mut auth_data).is_err());
+    }
+
+    #[test]
+    fn test_expiry_warning() {
+        let profile = certificate_profile();
+        let expires = DateTime::parse_from_rfc3339("2030-01-01T00:00:00Z")
+            .unwrap()
+            .timestamp();
+
+        assert!(expiry_warning_at(&profile, expires - 30 * 24 * 60 * 60).is_none());
+
+        let soon = expiry_warning_at(&profile, expires - 60 * 60).unwrap();
+        assert!(!soon.expired);
+        assert_eq!(soon.expires_in_seconds, 60 * 60);
+
+        let expired = expiry_warning_at(&profile, expires + 1).unwrap();
+        assert!(expired.expired);
+    }
+}
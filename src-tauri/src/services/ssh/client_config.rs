@@ -0,0 +1,218 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use russh::client::Config;
+use russh::{compression, Preferred};
+use std::borrow::Cow;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+use crate::error::AppError;
+use crate::models::ssh::SSHProfile;
+
+/// Interval between keepalive requests for profiles with keep-alive enabled
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+/// Unanswered keepalive requests before the connection is considered lost
+const KEEPALIVE_MAX: usize = 3;
+/// Connect timeout for profiles that do not set one
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Compression algorithms offered when a profile enables compression, zlib first
+const COMPRESSION_PREFERRED: &[compression::Name] = &[
+    compression::ZLIB,
+    compression::ZLIB_LEGACY,
+    compression::NONE,
+];
+const COMPRESSION_DISABLED: &[compression::Name] = &[compression::NONE];
+
+/// russh client configuration for a profile. Every connection (terminals, SFTP, tunnels
+/// and each jump hop) is built from its own profile through this function.
+pub fn client_config(profile: &SSHProfile) -> Arc<Config> {
+    let compression = if profile.compression {
+        COMPRESSION_PREFERRED
+    } else {
+        COMPRESSION_DISABLED
+    };
+
+    let mut config = Config {
+        // Dead connections are detected by keepalives, idle ones must stay open
+        inactivity_timeout: None,
+        keepalive_interval: profile.keep_alive.then_some(KEEPALIVE_INTERVAL),
+        keepalive_max: KEEPALIVE_MAX,
+        preferred: Preferred {
+            compression: Cow::Borrowed(compression),
+            ..Preferred::default()
+        },
+        ..Config::default()
+    };
+
+    config.window_size = 2097152;
+    config.maximum_packet_size = 32768;
+
+    Arc::new(config)
+}
+
+/// Time allowed for reaching the server and completing the SSH handshake
+pub fn connect_timeout(profile: &SSHProfile) -> Duration {
+    match profile.timeout {
+        Some(seconds) if seconds > 0 => Duration::from_secs(seconds as u64),
+        _ => DEFAULT_CONNECT_TIMEOUT,
+    }
+}
+
+/// Run a connection attempt for the profile, failing once its connect timeout elapses.
+///
+/// The clock stops while `paused` is set, so a host key waiting for the user's
+/// confirmation is not cut off by the timeout.
+pub async fn with_connect_timeout<T, F>(
+    profile: &SSHProfile,
+    paused: watch::Receiver<bool>,
+    connect: F,
+) -> Result<T, AppError>
+where
+    F: Future<Output = Result<T, AppError>>,
+{
+    let timeout = connect_timeout(profile);
+    timeout_unless_paused(timeout, paused, connect)
+        .await
+        .unwrap_or_else(|| {
+            Err(AppError::connection_failed(format!(
+                "Connection to {}:{} timed out after {}s",
+                profile.host,
+                profile.port,
+                timeout.as_secs()
+            )))
+        })
+}
+
+/// Await `future` for at most `timeout`, not counting the time `paused` is set.
+/// Returns `None` once the timeout elapses.
+async fn timeout_unless_paused<F: Future>(
+    timeout: Duration,
+    mut paused: watch::Receiver<bool>,
+    future: F,
+) -> Option<F::Output> {
+    tokio::pin!(future);
+    let mut remaining = timeout;
+
+    loop {
+        if *paused.borrow_and_update() {
+            tokio::select! {
+                output = &mut future => return Some(output),
+                changed = paused.changed() => {
+                    if changed.is_err() {
+                        // Nothing can resume the clock anymore
+                        return Some(future.await);
+                    }
+                }
+            }
+            continue;
+        }
+
+        let started = Instant::now();
+        tokio::select! {
+            output = &mut future => return Some(output),
+            _ = tokio::time::sleep(remaining) => return None,
+            changed = paused.changed() => {
+                remaining = remaining.saturating_sub(started.elapsed());
+                if changed.is_err() {
+                    return tokio::time::timeout(remaining, future).await.ok();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile() -> SSHProfile {
+        SSHProfile::new(
+            "device".to_string(),
+            "test".to_string(),
+            "example.com".to_string(),
+            22,
+            "user".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_client_config_maps_profile_settings() {
+        let mut profile = profile();
+        profile.keep_alive = true;
+        profile.compression = true;
+
+        let config = client_config(&profile);
+        assert_eq!(config.keepalive_interval, Some(KEEPALIVE_INTERVAL));
+        assert_eq!(config.inactivity_timeout, None);
+        assert_eq!(config.preferred.compression[0], compression::ZLIB);
+
+        profile.keep_alive = false;
+        profile.compression = false;
+
+        let config = client_config(&profile);
+        assert_eq!(config.keepalive_interval, None);
+        assert_eq!(&config.preferred.compression[..], COMPRESSION_DISABLED);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_stops_while_paused() {
+        let (paused, receiver) = watch::channel(false);
+        let timeout = Duration::from_millis(100);
+
+        // Waiting on the user for longer than the timeout still completes
+        let result = timeout_unless_paused(timeout, receiver.clone(), async {
+            paused.send_replace(true);
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            paused.send_replace(false);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            "connected"
+        })
+        .await;
+        assert_eq!(result, Some("connected"));
+
+        // Time before and after the pause still counts
+        let result = timeout_unless_paused(timeout, receiver, async {
+            tokio::time::sleep(Duration::from_millis(60)).await;
+            paused.send_replace(true);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            paused.send_replace(false);
+            tokio::time::sleep(Duration::from_millis(60)).await;
+            "connected"
+        })
+        .await;
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn test_connect_timeout_falls_back_to_default() {
+        let mut profile = profile();
+        profile.timeout = Some(5);
+        assert_eq!(connect_timeout(&profile), Duration::from_secs(5));
+
+        profile.timeout = Some(0);
+        assert_eq!(connect_timeout(&profile), DEFAULT_CONNECT_TIMEOUT);
+
+        profile.timeout = None;
+        assert_eq!(connect_timeout(&profile), DEFAULT_CONNECT_TIMEOUT);
+    }
+}
@@ -16,47 +16,1354 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use russh::client::{DisconnectReason, Handle, Handler, Msg, Session};
+use russh::{Channel, Disconnect};
+use russh_keys::key::PublicKey;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::path::Path;
+use std::sync::{Arc, Weak};
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{mpsc, Mutex};
+
+use crate::core::proxy::{create_proxy_command_stream, create_proxy_stream};
+use crate::database::service::DatabaseService;
+use crate::error::AppError;
+use crate::models::ssh::key::ResolvedSSHKey;
+use crate::models::ssh::{AuthData, SSHProfile};
+use crate::services::ssh::agent::{authenticate_with_agent, spawn_agent_forward};
+use crate::services::ssh::certificate::{expiry_warning, load_certificate};
+use crate::services::ssh::client_config::{client_config, with_connect_timeout};
+use crate::services::ssh::keyboard_interactive::KeyboardInteractiveService;
+use crate::services::ssh::known_hosts::{jump_chain_key, HostKeyVerifier, KnownHostsService};
+use crate::services::ssh::x11::X11Forwarder;
+
+/// How often idle connections are swept
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Live authenticated SSH connection shared by terminals, SFTP sessions and tunnels
+pub type PooledHandle = Arc<Mutex<Handle<PooledClientHandler>>>;
+
+type LossSlot = Arc<std::sync::Mutex<Option<ConnectionLoss>>>;
+type ConnectionMap = Arc<std::sync::Mutex<HashMap<String, PooledConnection>>>;
+
+/// Receivers of the channels the server opens for remote forwards, keyed by what
+/// the server listens on
+struct ForwardRoutes<C = Channel<Msg>> {
+    routes: Arc<std::sync::Mutex<HashMap<ForwardedListener, mpsc::UnboundedSender<C>>>>,
+}
+
+impl<C> Default for ForwardRoutes<C> {
+    fn default() -> Self {
+        Self {
+            routes: Arc::new(std::sync::Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl<C> Clone for ForwardRoutes<C> {
+    fn clone(&self) -> Self {
+        Self {
+            routes: self.routes.clone(),
+        }
+    }
+}
+
+impl<C> ForwardRoutes<C> {
+    fn add(
+        &self,
+        listener: ForwardedListener,
+        sender: mpsc::UnboundedSender<C>,
+    ) -> Result<(), AppError> {
+        if let Ok(mut routes) = self.routes.lock() {
+            if routes.contains_key(&listener) {
+                return Err(AppError::connection_failed(format!(
+                    "The {} is already forwarded on this connection",
+                    listener
+                )));
+            }
+            routes.insert(listener, sender);
+        }
+        Ok(())
+    }
+
+    fn remove(&self, listener: &ForwardedListener) {
+        if let Ok(mut routes) = self.routes.lock() {
+            routes.remove(listener);
+        }
+    }
+
+    /// Hand a channel to the forward of `listener`, giving it back when no running
+    /// forward takes it
+    fn route(&self, listener: &ForwardedListener, channel: C) -> Result<(), C> {
+        let route = self
+            .routes
+            .lock()
+            .ok()
+            .and_then(|routes| routes.get(listener).cloned());
+        match route {
+            Some(sender) => sender
+                .send(channel)
+                .map_err(|mpsc::error::SendError(channel)| channel),
+            None => Err(channel),
+        }
+    }
+}
+
+/// Port or unix socket the server listens on for a remote forward
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ForwardedListener {
+    Port(u32),
+    Socket(String),
+}
+
+impl std::fmt::Display for ForwardedListener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ForwardedListener::Port(port) => write!(f, "port {}", port),
+            ForwardedListener::Socket(path) => write!(f, "socket {}", path),
+        }
+    }
+}
+
+/// Why a pooled connection went away, for the channels that were using it
+#[derive(Debug, Clone)]
+pub struct ConnectionLoss {
+    pub message: String,
+    /// Exit reason reported to the frontend (`server-disconnect` or `network-lost`)
+    pub reason: &'static str,
+}
+
+/// Client handler of a pooled connection, shared by all channels opened on it
+pub struct PooledClientHandler {
+    host_key_verifier: HostKeyVerifier,
+    forward_agent: bool,
+    x11: Option<Arc<X11Forwarder>>,
+    loss: LossSlot,
+    forward_routes: ForwardRoutes,
+}
+
+#[async_trait]
+impl Handler for PooledClientHandler {
+    type Error = russh::Error;
+
+    async fn check_server_key(
+        &mut self,
+        server_public_key: &PublicKey,
+    ) -> Result<bool, Self::Error> {
+        Ok(self.host_key_verifier.check(server_public_key).await)
+    }
+
+    async fn server_channel_open_agent_forward(
+        &mut self,
+        channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if self.forward_agent {
+            spawn_agent_forward(channel);
+        } else {
+            warn!("Server opened an agent channel but agent forwarding is disabled");
+            let _ = channel.close().await;
+        }
+        Ok(())
+    }
+
+    async fn server_channel_open_x11(
+        &mut self,
+        channel: Channel<Msg>,
+        _originator_address: &str,
+        _originator_port: u32,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if let Some(x11) = &self.x11 {
+            x11.spawn_forward(channel);
+        } else {
+            warn!("Server opened an X11 channel but X11 forwarding is disabled");
+            let _ = channel.close().await;
+        }
+        Ok(())
+    }
+
+    async fn server_channel_open_forwarded_tcpip(
+        &mut self,
+        channel: Channel<Msg>,
+        _connected_address: &str,
+        connected_port: u32,
+        _originator_address: &str,
+        _originator_port: u32,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        route_forwarded_channel(
+            &self.forward_routes,
+            &ForwardedListener::Port(connected_port),
+            channel,
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn server_channel_open_forwarded_streamlocal(
+        &mut self,
+        channel: Channel<Msg>,
+        socket_path: &str,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        route_forwarded_channel(
+            &self.forward_routes,
+            &ForwardedListener::Socket(socket_path.to_string()),
+            channel,
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn disconnected(
+        &mut self,
+        reason: DisconnectReason<Self::Error>,
+    ) -> Result<(), Self::Error> {
+        let loss = match &reason {
+            DisconnectReason::ReceivedDisconnect(disconnect) => ConnectionLoss {
+                message: if disconnect.message.is_empty() {
+                    "Connection disconnected by server".to_string()
+                } else {
+                    format!("Connection disconnected - {}", disconnect.message)
+                },
+                reason: "server-disconnect",
+            },
+            DisconnectReason::Error(e) => ConnectionLoss {
+                message: if format!("{:?}", e).contains("timeout")
+                    || format!("{:?}", e).contains("Timeout")
+                {
+                    "Connection timeout - No response from server".to_string()
+                } else {
+                    format!("Connection error - {}", e)
+                },
+                reason: "network-lost",
+            },
+        };
+
+        if let Ok(mut slot) = self.loss.lock() {
+            *slot = Some(loss);
+        }
+
+        match reason {
+            DisconnectReason::ReceivedDisconnect(_) => Ok(()),
+            DisconnectReason::Error(e) => Err(e),
+        }
+    }
+}
+
+/// Hand a channel the server opened for a remote forward to the tunnel running it
+async fn route_forwarded_channel(
+    routes: &ForwardRoutes,
+    listener: &ForwardedListener,
+    channel: Channel<Msg>,
+) {
+    if let Err(channel) = routes.route(listener, channel) {
+        warn!("Server opened a channel for unforwarded {}", listener);
+        let _ = channel.close().await;
+    }
+}
 
 struct PooledConnection {
+    handle: PooledHandle,
+    loss: LossSlot,
+    x11: Option<Arc<X11Forwarder>>,
+    forward_agent: bool,
+    forward_routes: ForwardRoutes,
+    leases: LeaseCount,
+    /// Lease on the jump host this connection is tunnelled through
+    _via: Option<PooledSession>,
+}
+
+/// Number of live leases on a pooled connection and when it was last used
+struct LeaseCount {
+    refs: usize,
     last_used: Instant,
-    active: bool,
 }
 
+impl LeaseCount {
+    /// Count of a new connection, leased by whoever opened it
+    fn new() -> Self {
+        Self {
+            refs: 1,
+            last_used: Instant::now(),
+        }
+    }
+
+    fn acquire(&mut self) {
+        self.refs += 1;
+        self.last_used = Instant::now();
+    }
+
+    fn release(&mut self) {
+        self.refs = self.refs.saturating_sub(1);
+        self.last_used = Instant::now();
+    }
+
+    fn in_use(&self) -> bool {
+        self.refs > 0
+    }
+
+    /// Whether nothing has used the connection for at least `max_idle_time`
+    fn is_idle(&self, max_idle_time: Duration) -> bool {
+        !self.in_use() && self.last_used.elapsed() >= max_idle_time
+    }
+}
+
+impl PooledConnection {
+    fn is_closed(&self) -> bool {
+        // A locked handle is in use, so it is not closed as far as we can tell
+        self.handle
+            .try_lock()
+            .map(|handle| handle.is_closed())
+            .unwrap_or(false)
+    }
+}
+
+/// Lease on a pooled connection. The connection stays out of idle cleanup while
+/// any lease (or clone of it) is alive.
+pub struct PooledSession {
+    key: String,
+    handle: PooledHandle,
+    loss: LossSlot,
+    x11: Option<Arc<X11Forwarder>>,
+    forward_agent: bool,
+    forward_routes: ForwardRoutes,
+    connections: ConnectionMap,
+}
+
+impl PooledSession {
+    /// Pool key of the connection (profile plus jump chain)
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Shared handle of the connection, for requests that need `&mut Handle`
+    pub fn handle(&self) -> PooledHandle {
+        self.handle.clone()
+    }
+
+    /// Why the connection was lost, once it has been
+    pub fn connection_loss(&self) -> Option<ConnectionLoss> {
+        self.loss.lock().ok().and_then(|slot| slot.clone())
+    }
+
+    /// Whether the connection accepts the agent channels the server opens, which
+    /// sessions must match when they request agent forwarding
+    pub fn forwards_agent(&self) -> bool {
+        self.forward_agent
+    }
+
+    /// X11 forwarding of the connection, when its profile enables it and a display is available
+    pub fn x11_forwarder(&self) -> Option<Arc<X11Forwarder>> {
+        self.x11.clone()
+    }
+
+    /// Open a session channel (shell, exec or subsystem) on the connection
+    pub async fn open_session_channel(&self) -> Result<Channel<Msg>, AppError> {
+        let handle = self.handle.lock().await;
+        handle
+            .channel_open_session()
+            .await
+            .map_err(|e| AppError::connection_failed(format!("Failed to open SSH channel: {}", e)))
+    }
+
+    /// Open a direct-tcpip channel to the given host as seen from the server
+    pub async fn open_direct_tcpip(&self, host: &str, port: u16) -> Result<Channel<Msg>, AppError> {
+        let handle = self.handle.lock().await;
+        handle
+            .channel_open_direct_tcpip(host, port as u32, "127.0.0.1", 0)
+            .await
+            .map_err(|e| {
+                AppError::connection_failed(format!(
+                    "Failed to open forwarding channel to {}:{}: {}",
+                    host, port, e
+                ))
+            })
+    }
+
+    /// Open a direct-streamlocal channel to a unix socket on the server
+    pub async fn open_direct_streamlocal(&self, path: &str) -> Result<Channel<Msg>, AppError> {
+        let handle = self.handle.lock().await;
+        handle
+            .channel_open_direct_streamlocal(path)
+            .await
+            .map_err(|e| {
+                AppError::connection_failed(format!(
+                    "Failed to open forwarding channel to {}: {}",
+                    path, e
+                ))
+            })
+    }
+
+    /// Ask the server to listen on a TCP port, returning the port it listens on (the
+    /// one it picked when `port` is 0) and the channels it opens for each connection
+    pub async fn forward_tcpip(
+        &self,
+        address: &str,
+        port: u32,
+    ) -> Result<(u32, mpsc::UnboundedReceiver<Channel<Msg>>), AppError> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        // A fixed port is routed before the request, so no early connection is missed
+        if port != 0 {
+            self.forward_routes
+                .add(ForwardedListener::Port(port), sender.clone())?;
+        }
+
+        let result = self.handle.lock().await.tcpip_forward(address, port).await;
+        let port = match result {
+            Ok(bound_port) if port == 0 => {
+                self.forward_routes
+                    .add(ForwardedListener::Port(bound_port), sender)?;
+                bound_port
+            }
+            Ok(_) => port,
+            Err(e) => {
+                self.forward_routes.remove(&ForwardedListener::Port(port));
+                return Err(AppError::connection_failed(format!(
+                    "Failed to request remote port forwarding of {}:{}: {}",
+                    address, port, e
+                )));
+            }
+        };
+        Ok((port, receiver))
+    }
+
+    /// Stop a forward started with `forward_tcpip`
+    pub async fn cancel_tcpip_forward(&self, address: &str, port: u32) -> Result<(), AppError> {
+        self.forward_routes.remove(&ForwardedListener::Port(port));
+        let handle = self.handle.lock().await;
+        handle
+            .cancel_tcpip_forward(address, port)
+            .await
+            .map_err(|e| {
+                AppError::connection_failed(format!(
+                    "Failed to cancel remote port forwarding of {}:{}: {}",
+                    address, port, e
+                ))
+            })
+    }
+
+    /// Ask the server to listen on a unix socket, returning the channels it opens
+    /// for each connection to it
+    pub async fn forward_streamlocal(
+        &self,
+        path: &str,
+    ) -> Result<mpsc::UnboundedReceiver<Channel<Msg>>, AppError> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let listener = ForwardedListener::Socket(path.to_string());
+        self.forward_routes.add(listener.clone(), sender)?;
+
+        let result = self.handle.lock().await.streamlocal_forward(path).await;
+        if let Err(e) = result {
+            self.forward_routes.remove(&listener);
+            return Err(AppError::connection_failed(format!(
+                "Failed to request remote socket forwarding of {}: {}",
+                path, e
+            )));
+        }
+        Ok(receiver)
+    }
+
+    /// Stop a forward started with `forward_streamlocal`
+    pub async fn cancel_streamlocal_forward(&self, path: &str) -> Result<(), AppError> {
+        self.forward_routes
+            .remove(&ForwardedListener::Socket(path.to_string()));
+        let handle = self.handle.lock().await;
+        handle.cancel_streamlocal_forward(path).await.map_err(|e| {
+            AppError::connection_failed(format!(
+                "Failed to cancel remote socket forwarding of {}: {}",
+                path, e
+            ))
+        })
+    }
+
+    /// Update the pool entry of this lease, unless it has been replaced by a new connection
+    fn update<F: FnOnce(&mut PooledConnection)>(&self, f: F) {
+        if let Ok(mut connections) = self.connections.lock() {
+            if let Some(connection) = connections.get_mut(&self.key) {
+                if Arc::ptr_eq(&connection.handle, &self.handle) {
+                    f(connection);
+                }
+            }
+        }
+    }
+}
+
+impl Clone for PooledSession {
+    fn clone(&self) -> Self {
+        self.update(|connection| connection.leases.acquire());
+        Self {
+            key: self.key.clone(),
+            handle: self.handle.clone(),
+            loss: self.loss.clone(),
+            x11: self.x11.clone(),
+            forward_agent: self.forward_agent,
+            forward_routes: self.forward_routes.clone(),
+            connections: self.connections.clone(),
+        }
+    }
+}
+
+impl Drop for PooledSession {
+    fn drop(&mut self) {
+        self.update(|connection| connection.leases.release());
+    }
+}
+
+/// Pool of authenticated SSH connections keyed by profile and jump chain, so that
+/// terminals, SFTP and tunnels to the same host share one handshake (and one MFA prompt)
 pub struct SSHConnectionPool {
-    connections: Arc<RwLock<HashMap<String, PooledConnection>>>,
+    connections: ConnectionMap,
+    connect_locks: Mutex<HashMap<String, Arc<Mutex<()>>>>,
     max_idle_time: Duration,
+    database_service: Arc<Mutex<DatabaseService>>,
+    known_hosts: Arc<KnownHostsService>,
+    keyboard_interactive: Arc<KeyboardInteractiveService>,
+    app_handle: Option<AppHandle>,
 }
 
 impl SSHConnectionPool {
-    pub fn new(max_idle_minutes: u64) -> Self {
+    pub fn new(
+        max_idle_minutes: u64,
+        database_service: Arc<Mutex<DatabaseService>>,
+        known_hosts: Arc<KnownHostsService>,
+        keyboard_interactive: Arc<KeyboardInteractiveService>,
+        app_handle: Option<AppHandle>,
+    ) -> Self {
         Self {
-            connections: Arc::new(RwLock::new(HashMap::new())),
+            connections: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            connect_locks: Mutex::new(HashMap::new()),
             max_idle_time: Duration::from_secs(max_idle_minutes * 60),
+            database_service,
+            known_hosts,
+            keyboard_interactive,
+            app_handle,
         }
     }
 
+    /// Periodically close connections that have been unused for longer than the idle limit
+    pub fn start_idle_cleanup(self: &Arc<Self>) {
+        let pool = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+            loop {
+                interval.tick().await;
+                match Weak::upgrade(&pool) {
+                    Some(pool) => pool.cleanup_idle().await,
+                    None => break,
+                }
+            }
+        });
+    }
+
+    /// Get a lease on an authenticated connection for the profile, connecting
+    /// (through its jump hosts) only when no live connection is pooled yet
+    pub async fn acquire(
+        &self,
+        profile: &SSHProfile,
+        resolved_key: Option<ResolvedSSHKey>,
+    ) -> Result<PooledSession, AppError> {
+        let (via, chain) = self.acquire_jump_chain(profile).await?;
+        let key = connection_key(&chain, &profile_key(profile));
+        self.get_or_connect(key, profile, resolved_key.as_ref(), via, &chain)
+            .await
+    }
+
+    /// Check that the profile can connect and authenticate. The target connection is
+    /// made fresh and closed afterwards; jump hosts are taken from the pool.
+    pub async fn test_connection(
+        &self,
+        profile: &SSHProfile,
+        resolved_key: Option<ResolvedSSHKey>,
+    ) -> Result<(), AppError> {
+        let (via, chain) = self.acquire_jump_chain(profile).await?;
+        let (handle, _, _) = self
+            .connect(
+                profile,
+                resolved_key.as_ref(),
+                via.as_ref(),
+                &chain,
+                ForwardRoutes::default(),
+            )
+            .await?;
+        let _ = handle.disconnect(Disconnect::ByApplication, "", "en").await;
+        Ok(())
+    }
+
+    /// Close all pooled connections that are not in use and forget the rest
     pub async fn clear(&self) {
-        let mut pool = self.connections.write().await;
-        pool.clear();
+        let evicted: Vec<(String, PooledConnection)> = match self.connections.lock() {
+            Ok(mut connections) => connections.drain().collect(),
+            Err(_) => return,
+        };
+        self.close_evicted(evicted).await;
     }
 
+    /// Close connections that are closed already or unused for longer than the idle limit
     pub async fn cleanup_idle(&self) {
-        let mut pool = self.connections.write().await;
-        pool.retain(|_, conn| conn.active && conn.last_used.elapsed() < self.max_idle_time);
+        let evicted: Vec<(String, PooledConnection)> = match self.connections.lock() {
+            Ok(mut connections) => {
+                let keys: Vec<String> = connections
+                    .iter()
+                    .filter(|(_, conn)| conn.is_closed() || conn.leases.is_idle(self.max_idle_time))
+                    .map(|(key, _)| key.clone())
+                    .collect();
+                keys.into_iter()
+                    .filter_map(|key| connections.remove(&key).map(|conn| (key, conn)))
+                    .collect()
+            }
+            Err(_) => return,
+        };
+        self.close_evicted(evicted).await;
     }
 
     pub async fn pool_size(&self) -> usize {
-        let pool = self.connections.read().await;
-        pool.len()
+        self.connections
+            .lock()
+            .map(|connections| connections.len())
+            .unwrap_or(0)
+    }
+
+    /// Disconnect evicted connections. Runs outside the map lock since dropping an
+    /// entry releases its jump host lease, which updates the map again.
+    async fn close_evicted(&self, evicted: Vec<(String, PooledConnection)>) {
+        if evicted.is_empty() {
+            return;
+        }
+
+        let mut locks = self.connect_locks.lock().await;
+        for (key, connection) in evicted {
+            locks.remove(&key);
+            if !connection.leases.in_use() {
+                debug!("Closing pooled SSH connection {}", key);
+                let handle = connection.handle.lock().await;
+                let _ = handle.disconnect(Disconnect::ByApplication, "", "en").await;
+            }
+        }
+    }
+
+    /// Acquire a lease on every jump host of the profile, in order. Returns the lease on
+    /// the last hop and the `(key, host, port)` of each hop.
+    async fn acquire_jump_chain(
+        &self,
+        profile: &SSHProfile,
+    ) -> Result<(Option<PooledSession>, Vec<(String, String, u16)>), AppError> {
+        let mut via: Option<PooledSession> = None;
+        let mut chain: Vec<(String, String, u16)> = Vec::new();
+
+        for (hop_key, hop) in self.resolve_jump_hosts(profile).await? {
+            let key = connection_key(&chain, &hop_key);
+            let session = self
+                .get_or_connect(key, &hop, None, via.take(), &chain)
+                .await
+                .map_err(|e| match e {
+                    AppError::Auth(message) => {
+                        AppError::Auth(format!("{} (jump host {})", message, hop.host))
+                    }
+                    other => other,
+                })?;
+            chain.push((hop_key, hop.host.clone(), hop.port));
+            via = Some(session);
+        }
+
+        Ok((via, chain))
+    }
+
+    /// Resolve the jump hosts of a profile (saved profile references or inline config)
+    async fn resolve_jump_hosts(
+        &self,
+        profile: &SSHProfile,
+    ) -> Result<Vec<(String, SSHProfile)>, AppError> {
+        let Some(jump_hosts) = &profile.jump_hosts else {
+            return Ok(Vec::new());
+        };
+
+        let mut hops = Vec::new();
+        for jh in jump_hosts {
+            if let Some(profile_id) = &jh.profile_id {
+                let hop = {
+                    let db = self.database_service.lock().await;
+                    db.get_ssh_profile(profile_id).await.map_err(|e| {
+                        AppError::Config(format!(
+                            "Failed to get jump host profile '{}': {}",
+                            profile_id, e
+                        ))
+                    })?
+                };
+                hops.push((profile_key(&hop), hop));
+            } else if let (Some(host), Some(port), Some(username)) =
+                (&jh.host, jh.port, &jh.username)
+            {
+                // Create a temporary profile from inline config
+                let mut hop = SSHProfile::new(
+                    "temp".to_string(),
+                    format!("jump-{}", host),
+                    host.clone(),
+                    port,
+                    username.clone(),
+                );
+                if let Some(auth_method) = &jh.auth_method {
+                    hop.auth_method = auth_method.clone();
+                }
+                if let Some(auth_data) = &jh.auth_data {
+                    hop.auth_data = auth_data.clone();
+                }
+                let key = format!(
+                    "inline:{}@{}:{}#{:016x}",
+                    username,
+                    host,
+                    port,
+                    settings_digest(&hop)
+                );
+                hops.push((key, hop));
+            } else {
+                return Err(AppError::Config(
+                    "Invalid jump host config: need either profile_id or inline config".to_string(),
+                ));
+            }
+        }
+
+        Ok(hops)
+    }
+
+    /// Take a lease on the pooled connection for `key`, connecting if there is none.
+    /// Concurrent callers for the same key wait for a single handshake.
+    async fn get_or_connect(
+        &self,
+        key: String,
+        profile: &SSHProfile,
+        resolved_key: Option<&ResolvedSSHKey>,
+        via: Option<PooledSession>,
+        chain: &[(String, String, u16)],
+    ) -> Result<PooledSession, AppError> {
+        if let Some(session) = self.checkout(&key) {
+            return Ok(session);
+        }
+
+        let connect_lock = {
+            let mut locks = self.connect_locks.lock().await;
+            locks.entry(key.clone()).or_default().clone()
+        };
+        let _guard = connect_lock.lock().await;
+
+        if let Some(session) = self.checkout(&key) {
+            return Ok(session);
+        }
+
+        let forward_routes = ForwardRoutes::default();
+        let (handle, loss, x11) = self
+            .connect(
+                profile,
+                resolved_key,
+                via.as_ref(),
+                chain,
+                forward_routes.clone(),
+            )
+            .await?;
+        info!("Opened pooled SSH connection {}", key);
+
+        let handle = Arc::new(Mutex::new(handle));
+        insert_unlocked(
+            &self.connections,
+            key.clone(),
+            PooledConnection {
+                handle: handle.clone(),
+                loss: loss.clone(),
+                x11: x11.clone(),
+                forward_agent: profile.forward_agent,
+                forward_routes: forward_routes.clone(),
+                leases: LeaseCount::new(),
+                _via: via,
+            },
+        );
+
+        Ok(PooledSession {
+            key,
+            handle,
+            loss,
+            x11,
+            forward_agent: profile.forward_agent,
+            forward_routes,
+            connections: self.connections.clone(),
+        })
+    }
+
+    /// Lease a live pooled connection, dropping it from the pool if it has closed
+    fn checkout(&self, key: &str) -> Option<PooledSession> {
+        let stale = {
+            let mut connections = self.connections.lock().ok()?;
+            let connection = connections.get_mut(key)?;
+
+            if !connection.is_closed() {
+                connection.leases.acquire();
+                return Some(PooledSession {
+                    key: key.to_string(),
+                    handle: connection.handle.clone(),
+                    loss: connection.loss.clone(),
+                    x11: connection.x11.clone(),
+                    forward_agent: connection.forward_agent,
+                    forward_routes: connection.forward_routes.clone(),
+                    connections: self.connections.clone(),
+                });
+            }
+
+            connections.remove(key)
+        };
+
+        // Dropped here, outside the map lock
+        drop(stale);
+        None
+    }
+
+    /// Open and authenticate a new connection to the profile, either directly, through its
+    /// proxy or proxy command, or through a direct-tcpip channel of the previous jump host
+    async fn connect(
+        &self,
+        profile: &SSHProfile,
+        resolved_key: Option<&ResolvedSSHKey>,
+        via: Option<&PooledSession>,
+        chain: &[(String, String, u16)],
+        forward_routes: ForwardRoutes,
+    ) -> Result<
+        (
+            Handle<PooledClientHandler>,
+            LossSlot,
+            Option<Arc<X11Forwarder>>,
+        ),
+        AppError,
+    > {
+        self.warn_certificate_expiry(profile);
+
+        let verifier = HostKeyVerifier::new(
+            self.known_hosts.clone(),
+            &profile.host,
+            profile.port,
+            jump_chain_key(chain.iter().map(|(_, host, port)| (host.as_str(), *port))),
+        );
+        let loss: LossSlot = Arc::new(std::sync::Mutex::new(None));
+        let x11 = if profile.forward_x11 {
+            match X11Forwarder::from_env().await {
+                Ok(x11) => Some(Arc::new(x11)),
+                Err(e) => {
+                    warn!("X11 forwarding disabled for {}: {}", profile.host, e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let handler = PooledClientHandler {
+            host_key_verifier: verifier.clone(),
+            forward_agent: profile.forward_agent,
+            x11: x11.clone(),
+            loss: loss.clone(),
+            forward_routes,
+        };
+        let config = client_config(profile);
+
+        let connect = async {
+            let result = if let Some(via) = via {
+                let channel = via.open_direct_tcpip(&profile.host, profile.port).await?;
+                russh::client::connect_stream(config, channel.into_stream(), handler).await
+            } else if let Some(proxy_command) = &profile.proxy_command {
+                let stream = create_proxy_command_stream(
+                    proxy_command,
+                    &profile.host,
+                    profile.port,
+                    &profile.username,
+                )
+                .await
+                .map_err(|e| AppError::connection_failed(e.to_string()))?;
+                russh::client::connect_stream(config, stream, handler).await
+            } else if let Some(proxy_config) = &profile.proxy {
+                let stream = create_proxy_stream(proxy_config, &profile.host, profile.port)
+                    .await
+                    .map_err(|e| {
+                        AppError::connection_failed(format!(
+                            "Failed to create proxy connection: {}",
+                            e
+                        ))
+                    })?;
+                russh::client::connect_stream(config, stream, handler).await
+            } else {
+                russh::client::connect(config, (profile.host.as_str(), profile.port), handler).await
+            };
+
+            result.map_err(|e| {
+                verifier.take_failure().unwrap_or_else(|| {
+                    AppError::connection_failed(format!(
+                        "Failed to connect to SSH server {}:{}: {}",
+                        profile.host, profile.port, e
+                    ))
+                })
+            })
+        };
+        let mut session = with_connect_timeout(profile, verifier.checking(), connect).await?;
+
+        if let Err(e) = self
+            .authenticate_profile(&mut session, profile, resolved_key)
+            .await
+        {
+            let _ = session
+                .disconnect(Disconnect::ByApplication, "", "en")
+                .await;
+            return Err(e);
+        }
+
+        Ok((session, loss, x11))
+    }
+
+    /// Authenticate with the auth data of a profile (target or jump host).
+    ///
+    /// Multi-factor profiles try each method in order: servers requiring e.g.
+    /// `publickey,password` reject the first method with partial success and keep
+    /// the session open for the next one.
+    async fn authenticate_profile(
+        &self,
+        session: &mut Handle<PooledClientHandler>,
+        profile: &SSHProfile,
+        resolved_key: Option<&ResolvedSSHKey>,
+    ) -> Result<(), AppError> {
+        let factors: Vec<&AuthData> = match &profile.auth_data {
+            AuthData::MultiFactor { factors } => factors.iter().collect(),
+            single => vec![single],
+        };
+
+        if factors.is_empty() {
+            return Err(AppError::invalid_config(format!(
+                "No authentication methods configured for '{}@{}'",
+                profile.username, profile.host
+            )));
+        }
+
+        for (index, factor) in factors.iter().enumerate() {
+            let more_factors = index + 1 < factors.len();
+            if self
+                .try_auth_factor(session, profile, factor, resolved_key, more_factors)
+                .await?
+            {
+                return Ok(());
+            }
+        }
+
+        let last = factors[factors.len() - 1];
+        if factors.len() == 1 {
+            Err(AppError::authentication_failed(format!(
+                "{} authentication failed for '{}@{}'",
+                last.method_label(),
+                profile.username,
+                profile.host
+            )))
+        } else {
+            Err(AppError::authentication_failed(format!(
+                "Multi-factor authentication failed for '{}@{}': {} was not accepted",
+                profile.username,
+                profile.host,
+                last.method_label()
+            )))
+        }
+    }
+
+    /// Try a single authentication method, returns false when the server did not accept it.
+    /// With `more_factors`, an identity whose key cannot be loaded is skipped instead of
+    /// failing the whole chain.
+    async fn try_auth_factor(
+        &self,
+        session: &mut Handle<PooledClientHandler>,
+        profile: &SSHProfile,
+        factor: &AuthData,
+        resolved_key: Option<&ResolvedSSHKey>,
+        more_factors: bool,
+    ) -> Result<bool, AppError> {
+        let username = &profile.username;
+
+        match factor {
+            AuthData::Password { password } => session
+                .authenticate_password(username, password)
+                .await
+                .map_err(|e| {
+                    AppError::authentication_failed(format!(
+                        "Password authentication error for '{}@{}': {}",
+                        username, profile.host, e
+                    ))
+                }),
+            AuthData::KeyReference { key_id } => {
+                let key_data = match resolved_key {
+                    Some(key_data) => key_data.clone(),
+                    None => self.resolve_key(key_id).await?,
+                };
+
+                let key = if Path::new(&key_data.private_key).exists() {
+                    russh_keys::load_secret_key(
+                        &key_data.private_key,
+                        key_data.passphrase.as_deref(),
+                    )
+                    .map_err(|e| {
+                        AppError::authentication_failed(format!("Failed to load SSH key: {}", e))
+                    })?
+                } else {
+                    russh_keys::decode_secret_key(
+                        &key_data.private_key,
+                        key_data.passphrase.as_deref(),
+                    )
+                    .map_err(|e| {
+                        AppError::authentication_failed(format!("Failed to parse SSH key: {}", e))
+                    })?
+                };
+
+                let result = session
+                    .authenticate_publickey(username, Arc::new(key))
+                    .await
+                    .map_err(|e| {
+                        AppError::authentication_failed(format!(
+                            "SSH key authentication error for '{}@{}': {}",
+                            username, profile.host, e
+                        ))
+                    })?;
+
+                let db = self.database_service.lock().await;
+                if let Err(e) = db.mark_key_used(key_id).await {
+                    warn!("Warning: Failed to mark SSH key {} as used: {}", key_id, e);
+                }
+
+                Ok(result)
+            }
+            AuthData::Certificate {
+                certificate,
+                private_key,
+                key_type: _,
+                validity_period: _,
+            } => {
+                let Some(key) = load_identity_key(private_key, more_factors)? else {
+                    return Ok(false);
+                };
+
+                if certificate.trim().is_empty() {
+                    return session
+                        .authenticate_publickey(username, Arc::new(key))
+                        .await
+                        .map_err(|e| {
+                            AppError::authentication_failed(format!(
+                                "Public key authentication error for '{}@{}': {}",
+                                username, profile.host, e
+                            ))
+                        });
+                }
+
+                let certificate = load_certificate(certificate)?;
+
+                session
+                    .authenticate_openssh_cert(username, Arc::new(key), certificate)
+                    .await
+                    .map_err(|e| {
+                        AppError::authentication_failed(format!(
+                            "Certificate authentication error for '{}@{}': {}",
+                            username, profile.host, e
+                        ))
+                    })
+            }
+            AuthData::Agent {} => authenticate_with_agent(session, username).await,
+            AuthData::KeyboardInteractive {} => {
+                self.keyboard_interactive
+                    .authenticate(session, username, &profile.host, profile.port)
+                    .await
+            }
+            AuthData::MultiFactor { .. } => Err(AppError::invalid_config(
+                "Multi-factor authentication methods cannot be nested".to_string(),
+            )),
+        }
+    }
+
+    /// Let the UI know before connecting with a certificate that is expired or about to be
+    fn warn_certificate_expiry(&self, profile: &SSHProfile) {
+        let Some(warning) = expiry_warning(profile) else {
+            return;
+        };
+
+        warn!(
+            "SSH certificate for '{}' {} {}",
+            profile.name,
+            if warning.expired {
+                "expired"
+            } else {
+                "expires"
+            },
+            warning.valid_to
+        );
+        if let Some(app_handle) = &self.app_handle {
+            if let Err(e) = app_handle.emit("ssh-certificate-expiry", &warning) {
+                warn!("Failed to emit certificate expiry warning: {}", e);
+            }
+        }
+    }
+
+    /// Load a stored SSH key for authentication
+    async fn resolve_key(&self, key_id: &str) -> Result<ResolvedSSHKey, AppError> {
+        let db = self.database_service.lock().await;
+        let key = db.get_ssh_key(key_id).await.map_err(|e| {
+            AppError::authentication_failed(format!("Failed to get SSH key {}: {}", key_id, e))
+        })?;
+
+        Ok(ResolvedSSHKey {
+            private_key: key.private_key,
+            passphrase: key.passphrase,
+        })
     }
 }
 
-impl Default for SSHConnectionPool {
-    fn default() -> Self {
-        Self::new(30)
+/// Identity of a single hop: the saved profile plus the endpoint it points at and the
+/// settings its connection is made with, so an edited profile gets a new connection
+/// while the old one serves its remaining leases until it idles out
+fn profile_key(profile: &SSHProfile) -> String {
+    format!(
+        "{}:{}@{}:{}#{:016x}",
+        profile.base.id,
+        profile.username,
+        profile.host,
+        profile.port,
+        settings_digest(profile)
+    )
+}
+
+/// Digest of the profile settings that are fixed once a connection is made: the
+/// authentication, handler flags, client config and transport
+fn settings_digest(profile: &SSHProfile) -> u64 {
+    use std::hash::{Hash, Hasher};
+
+    let settings = serde_json::json!([
+        profile.auth_method,
+        profile.auth_data,
+        profile.timeout,
+        profile.keep_alive,
+        profile.compression,
+        profile.forward_agent,
+        profile.forward_x11,
+        profile.proxy,
+        profile.proxy_command,
+    ]);
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    settings.to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Load the private key of an identity from a file or inline key material. Like OpenSSH
+/// moving on to the next identity, a key that cannot be loaded (e.g. one that needs a
+/// passphrase) is skipped with `Ok(None)` while other factors remain.
+fn load_identity_key(
+    private_key: &str,
+    more_factors: bool,
+) -> Result<Option<russh_keys::key::KeyPair>, AppError> {
+    let loaded = if Path::new(private_key).exists() {
+        russh_keys::load_secret_key(private_key, None)
+            .map_err(|e| format!("Failed to load private key: {}", e))
+    } else {
+        russh_keys::decode_secret_key(private_key, None)
+            .map_err(|e| format!("Failed to parse private key: {}", e))
+    };
+
+    match loaded {
+        Ok(key) => Ok(Some(key)),
+        Err(e) if more_factors => {
+            warn!("Skipping identity: {}", e);
+            Ok(None)
+        }
+        Err(e) => Err(AppError::authentication_failed(e)),
+    }
+}
+
+/// Insert into a map guarded by a std mutex, dropping the replaced value only once the
+/// lock is released: a replaced connection drops its jump host lease, which locks the
+/// same map again
+fn insert_unlocked<K, V>(map: &std::sync::Mutex<HashMap<K, V>>, key: K, value: V)
+where
+    K: std::hash::Hash + Eq,
+{
+    let replaced = match map.lock() {
+        Ok(mut entries) => entries.insert(key, value),
+        Err(_) => return,
+    };
+    drop(replaced);
+}
+
+/// Pool key of a connection reached through the given jump chain
+fn connection_key(chain: &[(String, String, u16)], target: &str) -> String {
+    chain
+        .iter()
+        .map(|(key, _, _)| key.as_str())
+        .chain(std::iter::once(target))
+        .collect::<Vec<_>>()
+        .join(" > ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_key_includes_jump_chain() {
+        let chain = vec![
+            (
+                "inline:admin@bastion:22".to_string(),
+                "bastion".to_string(),
+                22,
+            ),
+            (
+                "p2:ops@10.0.0.5:2222".to_string(),
+                "10.0.0.5".to_string(),
+                2222,
+            ),
+        ];
+
+        assert_eq!(connection_key(&[], "p1:root@host:22"), "p1:root@host:22");
+        assert_eq!(
+            connection_key(&chain, "p1:root@host:22"),
+            "inline:admin@bastion:22 > p2:ops@10.0.0.5:2222 > p1:root@host:22"
+        );
+    }
+
+    #[test]
+    fn test_profile_key_follows_connection_settings() {
+        let profile = SSHProfile::new(
+            "device".to_string(),
+            "web".to_string(),
+            "example.com".to_string(),
+            22,
+            "root".to_string(),
+        );
+        let key = profile_key(&profile);
+
+        // Settings the connection doesn't depend on share it
+        let mut renamed = profile.clone();
+        renamed.name = "web server".to_string();
+        renamed.color = Some("#ff0000".to_string());
+        assert_eq!(profile_key(&renamed), key);
+
+        let mut agent = profile.clone();
+        agent.forward_agent = true;
+        let mut x11 = profile.clone();
+        x11.forward_x11 = true;
+        let mut compressed = profile.clone();
+        compressed.compression = !profile.compression;
+        for changed in [agent, x11, compressed] {
+            assert_ne!(profile_key(&changed), key);
+        }
+    }
+
+    #[test]
+    fn test_encrypted_identity_falls_through_to_next_factor() {
+        use crate::models::ssh::profile::KeyType;
+        use crate::services::ssh::keygen::generate_keypair;
+
+        let encrypted =
+            generate_keypair(&KeyType::Ed25519, None, Some("secret"), "locked").unwrap();
+        let working = generate_keypair(&KeyType::Ed25519, None, None, "open").unwrap();
+
+        // Tried in order: the encrypted identity is skipped, the next one is used
+        assert!(load_identity_key(&encrypted.private_key, true)
+            .unwrap()
+            .is_none());
+        assert!(load_identity_key(&working.private_key, false)
+            .unwrap()
+            .is_some());
+
+        // As the last factor, the error is reported
+        assert!(load_identity_key(&encrypted.private_key, false).is_err());
+
+        let path = std::env::temp_dir().join(format!("kerminal-identity-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, &encrypted.private_key).unwrap();
+        let skipped = load_identity_key(path.to_str().unwrap(), true).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(skipped.is_none());
+    }
+
+    #[test]
+    fn test_forwarded_channels_reach_their_forward() {
+        let routes = ForwardRoutes::<u32>::default();
+        let socket = ForwardedListener::Socket("/run/app.sock".to_string());
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        routes.add(socket.clone(), sender).unwrap();
+
+        assert_eq!(routes.route(&socket, 1), Ok(()));
+        assert_eq!(receiver.try_recv(), Ok(1));
+
+        // Channels for other sockets or ports are given back
+        let other = ForwardedListener::Socket("/run/other.sock".to_string());
+        assert_eq!(routes.route(&other, 2), Err(2));
+        assert_eq!(routes.route(&ForwardedListener::Port(8080), 3), Err(3));
+
+        // A listener is forwarded once per connection
+        let (duplicate, _) = mpsc::unbounded_channel();
+        assert!(routes.add(socket.clone(), duplicate).is_err());
+
+        // So are those of a stopped forward, until its listener is forwarded again
+        drop(receiver);
+        assert_eq!(routes.route(&socket, 4), Err(4));
+        routes.remove(&socket);
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        routes.add(socket.clone(), sender).unwrap();
+        assert_eq!(routes.route(&socket, 5), Ok(()));
+        assert_eq!(receiver.try_recv(), Ok(5));
+    }
+
+    #[test]
+    fn test_lease_count_and_idle_eviction() {
+        let mut leases = LeaseCount::new();
+        assert!(leases.in_use());
+        assert!(!leases.is_idle(Duration::ZERO));
+
+        leases.acquire();
+        leases.release();
+        assert!(leases.in_use());
+
+        leases.release();
+        assert!(!leases.in_use());
+        assert!(leases.is_idle(Duration::ZERO));
+        assert!(!leases.is_idle(Duration::from_secs(3600)));
+
+        // A stray release never underflows into a huge count
+        leases.release();
+        assert_eq!(leases.refs, 0);
+
+        leases.acquire();
+        assert!(!leases.is_idle(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_replacing_entry_that_locks_the_map_on_drop() {
+        type Map = std::sync::Mutex<HashMap<String, Entry>>;
+
+        /// Stands in for a connection holding a lease on its jump host
+        struct Entry {
+            map: Option<Arc<Map>>,
+        }
+
+        impl Drop for Entry {
+            fn drop(&mut self) {
+                if let Some(map) = &self.map {
+                    map.lock().unwrap().remove("bastion");
+                }
+            }
+        }
+
+        let map: Arc<Map> = Arc::default();
+        insert_unlocked(&map, "bastion".to_string(), Entry { map: None });
+        insert_unlocked(
+            &map,
+            "target".to_string(),
+            Entry {
+                map: Some(map.clone()),
+            },
+        );
+
+        // Would deadlock if the replaced entry were dropped under the lock
+        insert_unlocked(&map, "target".to_string(), Entry { map: None });
+
+        let entries = map.lock().unwrap();
+        assert!(entries.contains_key("target"));
+        assert!(!entries.contains_key("bastion"));
     }
 }
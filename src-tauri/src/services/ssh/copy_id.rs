@@ -0,0 +1,216 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use russh_sftp::client::SftpSession;
+use russh_sftp::protocol::OpenFlags;
+use tokio::io::AsyncWriteExt;
+
+use crate::error::AppError;
+use crate::services::sftp::channel_stream::ChannelStream;
+use crate::services::ssh::connection_pool::PooledSession;
+
+/// Outcome of installing a public key on a host
+pub struct InstalledKey {
+    /// Absolute path of the remote authorized_keys file
+    pub authorized_keys_path: String,
+    /// The key was already authorized, nothing was written
+    pub already_present: bool,
+}
+
+/**
+ * Append a public key to the remote `~/.ssh/authorized_keys` over SFTP, like ssh-copy-id.
+ * Creates `~/.ssh` when missing and makes sure the directory is 0700 and the file 0600,
+ * which sshd requires with StrictModes.
+ *
+ * @param connection - Authenticated connection to the host
+ * @param public_key - Public key in OpenSSH format (`type base64 [comment]`)
+ */
+pub async fn install_public_key(
+    connection: &PooledSession,
+    public_key: &str,
+) -> Result<InstalledKey, AppError> {
+    let public_key = public_key.trim();
+    if public_key_fields(public_key).is_none() {
+        return Err(AppError::invalid_config("Invalid public key format"));
+    }
+
+    let sftp = open_sftp(connection).await?;
+    let result = install_with_sftp(&sftp, public_key).await;
+    let _ = sftp.close().await;
+    result
+}
+
+async fn open_sftp(connection: &PooledSession) -> Result<SftpSession, AppError> {
+    let channel = connection.open_session_channel().await?;
+    channel
+        .request_subsystem(false, "sftp")
+        .await
+        .map_err(|e| {
+            AppError::connection_failed(format!("Failed to request SFTP subsystem: {}", e))
+        })?;
+
+    SftpSession::new(ChannelStream::new(channel))
+        .await
+        .map_err(|e| {
+            AppError::connection_failed(format!("Failed to initialize SFTP session: {}", e))
+        })
+}
+
+async fn install_with_sftp(sftp: &SftpSession, public_key: &str) -> Result<InstalledKey, AppError> {
+    let home = sftp
+        .canonicalize(".")
+        .await
+        .map_err(|e| sftp_error("resolve home directory", e))?;
+    let ssh_dir = format!("{}/.ssh", home.trim_end_matches('/'));
+    let authorized_keys_path = format!("{}/authorized_keys", ssh_dir);
+
+    if !sftp
+        .try_exists(&ssh_dir)
+        .await
+        .map_err(|e| sftp_error("check ~/.ssh", e))?
+    {
+        sftp.create_dir(&ssh_dir)
+            .await
+            .map_err(|e| sftp_error("create ~/.ssh", e))?;
+    }
+    set_mode(sftp, &ssh_dir, 0o700).await?;
+
+    let existing = if sftp
+        .try_exists(&authorized_keys_path)
+        .await
+        .map_err(|e| sftp_error("check authorized_keys", e))?
+    {
+        let content = sftp
+            .read(&authorized_keys_path)
+            .await
+            .map_err(|e| sftp_error("read authorized_keys", e))?;
+        String::from_utf8_lossy(&content).to_string()
+    } else {
+        String::new()
+    };
+
+    if is_authorized(&existing, public_key) {
+        return Ok(InstalledKey {
+            authorized_keys_path,
+            already_present: true,
+        });
+    }
+
+    let mut entry = String::new();
+    if !existing.is_empty() && !existing.ends_with('\n') {
+        entry.push('\n');
+    }
+    entry.push_str(public_key);
+    entry.push('\n');
+
+    let mut file = sftp
+        .open_with_flags(
+            &authorized_keys_path,
+            OpenFlags::CREATE | OpenFlags::APPEND | OpenFlags::WRITE,
+        )
+        .await
+        .map_err(|e| sftp_error("open authorized_keys for writing", e))?;
+    file.write_all(entry.as_bytes())
+        .await
+        .map_err(|e| sftp_error("write authorized_keys", e))?;
+    file.shutdown()
+        .await
+        .map_err(|e| sftp_error("write authorized_keys", e))?;
+
+    set_mode(sftp, &authorized_keys_path, 0o600).await?;
+
+    Ok(InstalledKey {
+        authorized_keys_path,
+        already_present: false,
+    })
+}
+
+async fn set_mode(sftp: &SftpSession, path: &str, mode: u32) -> Result<(), AppError> {
+    let mut attrs = sftp
+        .metadata(path)
+        .await
+        .map_err(|e| sftp_error("read permissions", e))?;
+    attrs.permissions = Some(mode);
+    sftp.set_metadata(path, attrs)
+        .await
+        .map_err(|e| sftp_error("set permissions", e))
+}
+
+fn sftp_error(action: &str, e: impl std::fmt::Display) -> AppError {
+    AppError::General(format!("Failed to {}: {}", action, e))
+}
+
+/// Key type and base64 blob of an OpenSSH public key line
+fn public_key_fields(public_key: &str) -> Option<(&str, &str)> {
+    let mut fields = public_key.split_whitespace();
+    let key_type = fields.next()?;
+    let blob = fields.next()?;
+    Some((key_type, blob))
+}
+
+/// Whether authorized_keys already contains the key, ignoring options and comments
+fn is_authorized(authorized_keys: &str, public_key: &str) -> bool {
+    let Some((key_type, blob)) = public_key_fields(public_key) else {
+        return false;
+    };
+
+    authorized_keys
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .any(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            fields.windows(2).any(|w| w[0] == key_type && w[1] == blob)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIAxsAdlcXCOGbY2R2krn6MB7jdgFEkt3L8Jf0ggPdCb2 user@laptop";
+
+    #[test]
+    fn test_is_authorized_ignores_comment_and_options() {
+        let authorized_keys = "# managed by hand\n\
+            ssh-rsa AAAAB3NzaC1yc2EAAAADAQABAAABAQC7 other\n\
+            from=\"10.0.0.0/8\",no-pty ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIAxsAdlcXCOGbY2R2krn6MB7jdgFEkt3L8Jf0ggPdCb2 old-comment\n";
+
+        assert!(is_authorized(authorized_keys, KEY));
+        assert!(!is_authorized(
+            "ssh-rsa AAAAB3NzaC1yc2EAAAADAQABAAABAQC7 other\n",
+            KEY
+        ));
+        assert!(!is_authorized(
+            "# ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIAxsAdlcXCOGbY2R2krn6MB7jdgFEkt3L8Jf0ggPdCb2\n",
+            KEY
+        ));
+    }
+
+    #[test]
+    fn test_public_key_fields() {
+        assert_eq!(
+            public_key_fields(KEY),
+            Some((
+                "ssh-ed25519",
+                "AAAAC3NzaC1lZDI1NTE5AAAAIAxsAdlcXCOGbY2R2krn6MB7jdgFEkt3L8Jf0ggPdCb2"
+            ))
+        );
+        assert_eq!(public_key_fields("ssh-ed25519"), None);
+    }
+}
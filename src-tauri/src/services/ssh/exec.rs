@@ -0,0 +1,344 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use russh::{ChannelMsg, Sig};
+use tokio::sync::{mpsc, Mutex};
+use tokio_util::sync::CancellationToken;
+
+use crate::error::AppError;
+use crate::models::ssh::{
+    AuthData, ExecExportFormat, ExecOutputStream, HostExecOutput, HostExecResult, HostExecStatus,
+    MultiHostExecEvent, MultiHostExecResult, SSHProfile,
+};
+use crate::services::ssh::connection_pool::{PooledSession, SSHConnectionPool};
+use crate::services::ssh::SSHKeyService;
+
+/// Captured output kept per stream and host, the rest is only streamed
+const MAX_CAPTURED_OUTPUT: usize = 1024 * 1024;
+/// Time left to a timed out command for stopping it and closing its channel
+const CANCEL_GRACE: Duration = Duration::from_secs(2);
+
+/// Output of a command run on one host
+#[derive(Debug, Default)]
+pub struct CommandOutput {
+    pub exit_status: Option<u32>,
+    pub stdout: String,
+    pub stderr: String,
+    pub truncated: bool,
+    /// Set when the command was killed by a signal
+    pub signal: Option<String>,
+}
+
+/// Where the output of a host is streamed to while the command runs
+pub struct OutputSink {
+    pub exec_id: String,
+    pub profile_id: String,
+    pub events: Option<mpsc::UnboundedSender<MultiHostExecEvent>>,
+}
+
+impl OutputSink {
+    fn send(&self, stream: ExecOutputStream, data: &[u8]) {
+        if let Some(events) = &self.events {
+            let _ = events.send(MultiHostExecEvent::Output(HostExecOutput {
+                exec_id: self.exec_id.clone(),
+                profile_id: self.profile_id.clone(),
+                stream,
+                data: String::from_utf8_lossy(data).into_owned(),
+            }));
+        }
+    }
+}
+
+/// Connect to the profile (through its jump hosts, with its stored key) and run the
+/// command, all within the timeout
+pub async fn exec_on_host(
+    ssh_key_service: &Mutex<SSHKeyService>,
+    connection_pool: &SSHConnectionPool,
+    profile: &SSHProfile,
+    command: &str,
+    timeout: Duration,
+    sink: &OutputSink,
+) -> HostExecResult {
+    let started = Instant::now();
+    let cancel = CancellationToken::new();
+
+    let run = async {
+        let resolved_key = match &profile.auth_data {
+            AuthData::KeyReference { key_id } => {
+                let key_service = ssh_key_service.lock().await;
+                Some(
+                    key_service
+                        .resolve_key_for_auth(key_id)
+                        .await
+                        .map_err(|e| AppError::authentication_failed(e.to_string()))?,
+                )
+            }
+            _ => None,
+        };
+
+        let connection = connection_pool.acquire(profile, resolved_key).await?;
+        run_command(&connection, command, sink, &cancel).await
+    };
+
+    let mut result = HostExecResult {
+        profile_id: profile.base.id.clone(),
+        profile_name: profile.name.clone(),
+        host: profile.host.clone(),
+        status: HostExecStatus::Completed,
+        exit_status: None,
+        stdout: String::new(),
+        stderr: String::new(),
+        truncated: false,
+        error: None,
+        duration_ms: 0,
+    };
+
+    match run_with_timeout(timeout, &cancel, run).await {
+        Some(Ok(output)) => {
+            result.exit_status = output.exit_status;
+            result.stdout = output.stdout;
+            result.stderr = output.stderr;
+            result.truncated = output.truncated;
+            result.error = output
+                .signal
+                .map(|signal| format!("Terminated by signal {}", signal));
+        }
+        Some(Err(e)) => {
+            result.status = HostExecStatus::Failed;
+            result.error = Some(e.to_string());
+        }
+        None => {
+            result.status = HostExecStatus::TimedOut;
+            result.error = Some(format!("Timed out after {}s", timeout.as_secs()));
+        }
+    }
+
+    result.duration_ms = started.elapsed().as_millis() as u64;
+    result
+}
+
+/// Await `run` for at most `timeout`. Once it elapses, `cancel` is triggered and `run` gets
+/// a short grace period to stop the remote command, then `None` is returned.
+pub async fn run_with_timeout<F: Future>(
+    timeout: Duration,
+    cancel: &CancellationToken,
+    run: F,
+) -> Option<F::Output> {
+    tokio::pin!(run);
+    match tokio::time::timeout(timeout, &mut run).await {
+        Ok(output) => Some(output),
+        Err(_) => {
+            cancel.cancel();
+            let _ = tokio::time::timeout(CANCEL_GRACE, run).await;
+            None
+        }
+    }
+}
+
+/// Run a command in an exec channel of the connection and collect its output.
+/// When `cancel` is triggered the command is sent SIGTERM and its channel closed.
+pub async fn run_command(
+    connection: &PooledSession,
+    command: &str,
+    sink: &OutputSink,
+    cancel: &CancellationToken,
+) -> Result<CommandOutput, AppError> {
+    let mut channel = connection.open_session_channel().await?;
+    channel
+        .exec(true, command)
+        .await
+        .map_err(|e| AppError::connection_failed(format!("Failed to start command: {}", e)))?;
+
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let mut output = CommandOutput::default();
+
+    loop {
+        let msg = tokio::select! {
+            msg = channel.wait() => msg,
+            _ = cancel.cancelled() => {
+                let _ = channel.signal(Sig::TERM).await;
+                let _ = channel.close().await;
+                return Err(AppError::connection_failed("Command cancelled".to_string()));
+            }
+        };
+        let Some(msg) = msg else {
+            break;
+        };
+
+        match msg {
+            ChannelMsg::Data { data } => {
+                sink.send(ExecOutputStream::Stdout, &data);
+                output.truncated |= capture(&mut stdout, &data);
+            }
+            ChannelMsg::ExtendedData { data, ext: 1 } => {
+                sink.send(ExecOutputStream::Stderr, &data);
+                output.truncated |= capture(&mut stderr, &data);
+            }
+            ChannelMsg::ExitStatus { exit_status } => {
+                output.exit_status = Some(exit_status);
+            }
+            ChannelMsg::ExitSignal { signal_name, .. } => {
+                output.signal = Some(format!("{:?}", signal_name));
+            }
+            ChannelMsg::Failure => {
+                return Err(AppError::connection_failed(
+                    "Server refused to run the command".to_string(),
+                ));
+            }
+            ChannelMsg::Close => break,
+            _ => {}
+        }
+    }
+
+    output.stdout = String::from_utf8_lossy(&stdout).into_owned();
+    output.stderr = String::from_utf8_lossy(&stderr).into_owned();
+    Ok(output)
+}
+
+/// Append data up to the capture limit, returning whether anything was dropped
+fn capture(buffer: &mut Vec<u8>, data: &[u8]) -> bool {
+    let room = MAX_CAPTURED_OUTPUT.saturating_sub(buffer.len());
+    buffer.extend_from_slice(&data[..data.len().min(room)]);
+    data.len() > room
+}
+
+/// Render the result of a multi-host run in the given format
+pub fn export_result(
+    result: &MultiHostExecResult,
+    format: ExecExportFormat,
+) -> Result<String, AppError> {
+    match format {
+        ExecExportFormat::Json => serde_json::to_string_pretty(result)
+            .map_err(|e| AppError::internal_error(format!("Failed to export result: {}", e))),
+        ExecExportFormat::Csv => Ok(to_csv(result)),
+    }
+}
+
+fn to_csv(result: &MultiHostExecResult) -> String {
+    let mut csv = String::from(
+        "profile_id,profile_name,host,status,exit_status,duration_ms,stdout,stderr,error\r\n",
+    );
+
+    for host in &result.hosts {
+        let fields = [
+            host.profile_id.clone(),
+            host.profile_name.clone(),
+            host.host.clone(),
+            format!("{:?}", host.status),
+            host.exit_status.map(|s| s.to_string()).unwrap_or_default(),
+            host.duration_ms.to_string(),
+            host.stdout.clone(),
+            host.stderr.clone(),
+            host.error.clone().unwrap_or_default(),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&row.join(","));
+        csv.push_str("\r\n");
+    }
+
+    csv
+}
+
+/// Quote a CSV field when it contains a separator, quote or line break (RFC 4180)
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ssh::{HostExecResult, HostExecStatus};
+
+    #[tokio::test]
+    async fn test_timeout_cancels_run_and_waits_for_cleanup() {
+        let cancel = CancellationToken::new();
+        let closed = std::sync::atomic::AtomicBool::new(false);
+
+        let output = run_with_timeout(Duration::from_millis(50), &cancel, async {
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(60)) => "finished",
+                _ = cancel.cancelled() => {
+                    // Closing the channel takes a round trip to the server
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    closed.store(true, std::sync::atomic::Ordering::SeqCst);
+                    "cancelled"
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(output, None);
+        assert!(cancel.is_cancelled());
+        assert!(closed.load(std::sync::atomic::Ordering::SeqCst));
+
+        let cancel = CancellationToken::new();
+        let output = run_with_timeout(Duration::from_secs(1), &cancel, async { 0 }).await;
+        assert_eq!(output, Some(0));
+        assert!(!cancel.is_cancelled());
+    }
+
+    #[test]
+    fn test_capture_stops_at_limit() {
+        let mut buffer = vec![0u8; MAX_CAPTURED_OUTPUT - 2];
+        assert!(capture(&mut buffer, b"abcd"));
+        assert_eq!(buffer.len(), MAX_CAPTURED_OUTPUT);
+        assert!(buffer.ends_with(b"ab"));
+        assert!(capture(&mut buffer, b"e"));
+    }
+
+    #[test]
+    fn test_export_csv_quotes_fields() {
+        let result = MultiHostExecResult {
+            exec_id: "exec".to_string(),
+            command: "uptime".to_string(),
+            started_at: chrono::Utc::now(),
+            finished_at: chrono::Utc::now(),
+            hosts: vec![HostExecResult {
+                profile_id: "p1".to_string(),
+                profile_name: "web, eu".to_string(),
+                host: "10.0.0.1".to_string(),
+                status: HostExecStatus::Completed,
+                exit_status: Some(0),
+                stdout: "line \"one\"\nline two\n".to_string(),
+                stderr: String::new(),
+                truncated: false,
+                error: None,
+                duration_ms: 12,
+            }],
+        };
+
+        let csv = export_result(&result, ExecExportFormat::Csv).unwrap();
+        let mut lines = csv.split("\r\n");
+        assert!(lines.next().unwrap().starts_with("profile_id,"));
+        assert_eq!(
+            lines.next().unwrap(),
+            "p1,\"web, eu\",10.0.0.1,Completed,0,12,\"line \"\"one\"\"\nline two\n\",,"
+        );
+
+        let json = export_result(&result, ExecExportFormat::Json).unwrap();
+        assert!(json.contains("\"exitStatus\": 0"));
+    }
+}
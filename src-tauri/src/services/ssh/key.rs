@@ -21,7 +21,8 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use crate::database::{error::DatabaseResult, service::DatabaseService};
-use crate::models::ssh::{CreateSSHKeyRequest, SSHKey, UpdateSSHKeyRequest};
+use crate::models::ssh::{CreateSSHKeyRequest, GenerateSSHKeyRequest, SSHKey, UpdateSSHKeyRequest};
+use crate::services::ssh::keygen;
 
 /// SSH Key service for centralized key management with caching
 pub struct SSHKeyService {
@@ -146,6 +147,53 @@ impl SSHKeyService {
         self.create_ssh_key(request).await
     }
 
+    /// Generate a new keypair, store it and optionally export it to OpenSSH files
+    pub async fn generate_ssh_key(&self, request: GenerateSSHKeyRequest) -> DatabaseResult<SSHKey> {
+        let key_type = request.key_type.clone();
+        let bits = request.bits;
+        let passphrase = request.passphrase.clone();
+        let comment = request.comment.clone().unwrap_or_default();
+
+        // RSA generation takes a while, keep it off the async runtime
+        let keypair = tokio::task::spawn_blocking(move || {
+            keygen::generate_keypair(&key_type, bits, passphrase.as_deref(), &comment)
+        })
+        .await
+        .map_err(|e| crate::database::error::DatabaseError::Internal(anyhow::anyhow!(e)))?
+        .map_err(crate::database::error::DatabaseError::ValidationError)?;
+
+        if let Some(export_path) = request.export_path.as_deref().filter(|p| !p.is_empty()) {
+            let expanded_path = if export_path.starts_with("~/") {
+                let home = std::env::var("HOME").map_err(|_| {
+                    crate::database::error::DatabaseError::Internal(anyhow::anyhow!(
+                        "Cannot determine HOME directory"
+                    ))
+                })?;
+                export_path.replacen("~", &home, 1)
+            } else {
+                export_path.to_string()
+            };
+
+            keygen::export_key_files(
+                std::path::Path::new(&expanded_path),
+                &keypair.private_key,
+                &keypair.public_key,
+            )
+            .map_err(|e| crate::database::error::DatabaseError::Internal(anyhow::anyhow!(e)))?;
+        }
+
+        let create_request = CreateSSHKeyRequest {
+            name: request.name,
+            key_type: Some(request.key_type),
+            private_key: keypair.private_key,
+            public_key: Some(keypair.public_key),
+            passphrase: request.passphrase.filter(|p| !p.is_empty()),
+            description: request.description,
+        };
+
+        self.create_ssh_key(create_request).await
+    }
+
     /// Detect SSH key type from key content
     fn detect_key_type(key_content: &str) -> DatabaseResult<crate::models::ssh::profile::KeyType> {
         use crate::models::ssh::profile::KeyType;
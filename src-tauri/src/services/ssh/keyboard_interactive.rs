@@ -0,0 +1,274 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use log::warn;
+use russh::client::{Handle, Handler, KeyboardInteractiveAuthResponse};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::oneshot;
+
+use crate::error::AppError;
+use crate::models::ssh::{KeyboardInteractivePrompt, KeyboardInteractiveRequest};
+use crate::services::ssh::pending::PendingRequests;
+
+/// How long to wait for the user to answer a prompt round (OTP apps and push approvals are slow)
+const PROMPT_RESPONSE_TIMEOUT: Duration = Duration::from_secs(180);
+
+/// Keyboard-interactive authentication: relays server prompts to the frontend
+pub struct KeyboardInteractiveService {
+    app_handle: Option<AppHandle>,
+    pending_prompts: PendingRequests<Option<Vec<String>>>,
+}
+
+impl KeyboardInteractiveService {
+    /// Create new KeyboardInteractiveService instance
+    pub fn new(app_handle: Option<AppHandle>) -> Self {
+        Self {
+            app_handle,
+            pending_prompts: PendingRequests::default(),
+        }
+    }
+
+    /// Run keyboard-interactive authentication, sending each prompt round to the frontend.
+    ///
+    /// Returns `Ok(false)` when the server rejects the answers, which is also what a
+    /// partial success looks like when another method is still required.
+    pub async fn authenticate<H: Handler>(
+        &self,
+        session: &mut Handle<H>,
+        username: &str,
+        host: &str,
+        port: u16,
+    ) -> Result<bool, AppError> {
+        let mut response = session
+            .authenticate_keyboard_interactive_start(username, None::<String>)
+            .await
+            .map_err(|e| {
+                AppError::authentication_failed(format!(
+                    "Keyboard-interactive authentication error for '{}@{}': {}",
+                    username, host, e
+                ))
+            })?;
+
+        loop {
+            let (name, instructions, prompts) = match response {
+                KeyboardInteractiveAuthResponse::Success => return Ok(true),
+                KeyboardInteractiveAuthResponse::Failure => return Ok(false),
+                KeyboardInteractiveAuthResponse::InfoRequest {
+                    name,
+                    instructions,
+                    prompts,
+                } => (name, instructions, prompts),
+            };
+
+            // Servers may send empty rounds (e.g. after the last answer), reply without asking
+            let answers = if prompts.is_empty() {
+                Vec::new()
+            } else {
+                let request = KeyboardInteractiveRequest {
+                    request_id: uuid::Uuid::new_v4().to_string(),
+                    host: host.to_string(),
+                    port,
+                    username: username.to_string(),
+                    name,
+                    instructions,
+                    prompts: prompts
+                        .into_iter()
+                        .map(|p| KeyboardInteractivePrompt {
+                            prompt: p.prompt,
+                            echo: p.echo,
+                        })
+                        .collect(),
+                };
+                self.ask(request).await?
+            };
+
+            response = session
+                .authenticate_keyboard_interactive_respond(answers)
+                .await
+                .map_err(|e| {
+                    AppError::authentication_failed(format!(
+                        "Keyboard-interactive authentication error for '{}@{}': {}",
+                        username, host, e
+                    ))
+                })?;
+        }
+    }
+
+    /// Emit a prompt round to the frontend and wait for the answers
+    async fn ask(&self, request: KeyboardInteractiveRequest) -> Result<Vec<String>, AppError> {
+        let Some(app_handle) = &self.app_handle else {
+            return Err(AppError::authentication_failed(format!(
+                "Keyboard-interactive authentication for {}:{} needs user input but no UI is available",
+                request.host, request.port
+            )));
+        };
+
+        let receiver = self.pending_prompts.register(&request.request_id).await;
+
+        if let Err(e) = app_handle.emit("ssh-keyboard-interactive", &request) {
+            warn!("Failed to emit keyboard-interactive request: {}", e);
+            self.pending_prompts.cancel(&request.request_id).await;
+            return Err(AppError::authentication_failed(
+                "Failed to request keyboard-interactive input".to_string(),
+            ));
+        }
+
+        self.collect_answers(&request, receiver, PROMPT_RESPONSE_TIMEOUT)
+            .await
+    }
+
+    /// Wait for the answers of an emitted prompt round, checking there is one per prompt
+    async fn collect_answers(
+        &self,
+        request: &KeyboardInteractiveRequest,
+        receiver: oneshot::Receiver<Option<Vec<String>>>,
+        timeout: Duration,
+    ) -> Result<Vec<String>, AppError> {
+        let expected = request.prompts.len();
+
+        match self
+            .pending_prompts
+            .wait(&request.request_id, receiver, timeout)
+            .await
+        {
+            Some(Some(answers)) if answers.len() == expected => Ok(answers),
+            Some(Some(answers)) => Err(AppError::authentication_failed(format!(
+                "Expected {} keyboard-interactive answers, got {}",
+                expected,
+                answers.len()
+            ))),
+            Some(None) => Err(AppError::authentication_failed(
+                "Keyboard-interactive authentication cancelled".to_string(),
+            )),
+            None => Err(AppError::authentication_failed(
+                "Timed out waiting for keyboard-interactive input".to_string(),
+            )),
+        }
+    }
+
+    /// Deliver the user's answers to a pending prompt round (None cancels authentication)
+    pub async fn respond(
+        &self,
+        request_id: &str,
+        responses: Option<Vec<String>>,
+    ) -> Result<(), AppError> {
+        if self.pending_prompts.answer(request_id, responses).await {
+            Ok(())
+        } else {
+            Err(AppError::NotFound(format!(
+                "Keyboard-interactive request {} not found or expired",
+                request_id
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn otp_request(request_id: &str) -> KeyboardInteractiveRequest {
+        KeyboardInteractiveRequest {
+            request_id: request_id.to_string(),
+            host: "example.com".to_string(),
+            port: 22,
+            username: "alice".to_string(),
+            name: String::new(),
+            instructions: String::new(),
+            prompts: vec![
+                KeyboardInteractivePrompt {
+                    prompt: "Password: ".to_string(),
+                    echo: false,
+                },
+                KeyboardInteractivePrompt {
+                    prompt: "Verification code: ".to_string(),
+                    echo: false,
+                },
+            ],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prompt_round_trip() {
+        let service = Arc::new(KeyboardInteractiveService::new(None));
+        let request = otp_request("round");
+        let receiver = service.pending_prompts.register("round").await;
+
+        let responder = service.clone();
+        tokio::spawn(async move {
+            responder
+                .respond(
+                    "round",
+                    Some(vec!["secret".to_string(), "123456".to_string()]),
+                )
+                .await
+                .unwrap();
+        });
+
+        let answers = service
+            .collect_answers(&request, receiver, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(answers, vec!["secret", "123456"]);
+
+        // The round is answered only once
+        assert!(service.respond("round", None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_prompt_cancelled_or_incomplete() {
+        let service = Arc::new(KeyboardInteractiveService::new(None));
+
+        let request = otp_request("cancel");
+        let receiver = service.pending_prompts.register("cancel").await;
+        service.respond("cancel", None).await.unwrap();
+        let error = service
+            .collect_answers(&request, receiver, Duration::from_secs(5))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("cancelled"));
+
+        let request = otp_request("short");
+        let receiver = service.pending_prompts.register("short").await;
+        service
+            .respond("short", Some(vec!["secret".to_string()]))
+            .await
+            .unwrap();
+        let error = service
+            .collect_answers(&request, receiver, Duration::from_secs(5))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Expected 2"));
+
+        let request = otp_request("late");
+        let receiver = service.pending_prompts.register("late").await;
+        let error = service
+            .collect_answers(&request, receiver, Duration::from_millis(20))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Timed out"));
+    }
+
+    #[tokio::test]
+    async fn test_prompt_without_ui_fails() {
+        let service = KeyboardInteractiveService::new(None);
+        assert!(service.ask(otp_request("no-ui")).await.is_err());
+    }
+}
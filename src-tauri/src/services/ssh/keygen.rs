@@ -0,0 +1,248 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use rand::rngs::OsRng;
+use ssh_key::{Algorithm, EcdsaCurve, HashAlg, LineEnding, PrivateKey, PublicKey};
+use std::io::Write;
+use std::path::Path;
+
+use crate::models::ssh::profile::KeyType;
+
+/// A freshly generated keypair in OpenSSH format
+pub struct GeneratedKeyPair {
+    pub private_key: String,
+    pub public_key: String,
+    /// `SHA256:...` fingerprint as printed by ssh-keygen
+    pub fingerprint: String,
+}
+
+/**
+ * Generate a new keypair, optionally encrypted with a passphrase
+ *
+ * @param key_type - Ed25519, ECDSA or RSA
+ * @param bits - ECDSA curve size (256, 384, 521) or RSA modulus (3072, 4096)
+ * @param passphrase - Encrypts the private key when set and not empty
+ * @param comment - Comment stored with the key, e.g. user@host
+ */
+pub fn generate_keypair(
+    key_type: &KeyType,
+    bits: Option<u32>,
+    passphrase: Option<&str>,
+    comment: &str,
+) -> Result<GeneratedKeyPair, String> {
+    let mut private_key = match key_type {
+        KeyType::Ed25519 => PrivateKey::random(&mut OsRng, Algorithm::Ed25519),
+        KeyType::ECDSA => {
+            let curve = match bits.unwrap_or(256) {
+                256 => EcdsaCurve::NistP256,
+                384 => EcdsaCurve::NistP384,
+                521 => EcdsaCurve::NistP521,
+                other => return Err(format!("Unsupported ECDSA key size: {}", other)),
+            };
+            PrivateKey::random(&mut OsRng, Algorithm::Ecdsa { curve })
+        }
+        KeyType::RSA => {
+            let bits = bits.unwrap_or(3072);
+            if bits != 3072 && bits != 4096 {
+                return Err(format!("Unsupported RSA key size: {}", bits));
+            }
+            ssh_key::private::RsaKeypair::random(&mut OsRng, bits as usize).map(PrivateKey::from)
+        }
+        KeyType::DSA => return Err("DSA keys are no longer supported by OpenSSH".to_string()),
+    }
+    .map_err(|e| format!("Failed to generate key: {}", e))?;
+
+    private_key.set_comment(comment);
+    let public_key = private_key.public_key().clone();
+
+    if let Some(passphrase) = passphrase.filter(|p| !p.is_empty()) {
+        private_key = private_key
+            .encrypt(&mut OsRng, passphrase)
+            .map_err(|e| format!("Failed to encrypt key: {}", e))?;
+    }
+
+    Ok(GeneratedKeyPair {
+        private_key: private_key
+            .to_openssh(LineEnding::LF)
+            .map_err(|e| format!("Failed to encode private key: {}", e))?
+            .to_string(),
+        public_key: public_key_line(&public_key)?,
+        fingerprint: public_key.fingerprint(HashAlg::Sha256).to_string(),
+    })
+}
+
+/**
+ * Derive the OpenSSH public key line from a private key (contents or file path)
+ *
+ * @param private_key - Private key in OpenSSH or PEM format
+ * @param passphrase - Needed for encrypted PEM keys; OpenSSH keys keep the public part in clear
+ */
+pub fn public_key_from_private(
+    private_key: &str,
+    passphrase: Option<&str>,
+) -> Result<String, String> {
+    let content = if Path::new(private_key).exists() {
+        std::fs::read_to_string(private_key)
+            .map_err(|e| format!("Failed to read key file '{}': {}", private_key, e))?
+    } else {
+        private_key.to_string()
+    };
+
+    if let Ok(key) = PrivateKey::from_openssh(content.trim()) {
+        return public_key_line(key.public_key());
+    }
+
+    use russh_keys::PublicKeyBase64;
+    let key = russh_keys::decode_secret_key(&content, passphrase)
+        .map_err(|e| format!("Failed to parse private key: {}", e))?;
+    let public_key = key
+        .clone_public_key()
+        .map_err(|e| format!("Failed to derive public key: {}", e))?;
+    Ok(format!(
+        "{} {}",
+        public_key.name(),
+        public_key.public_key_base64()
+    ))
+}
+
+fn public_key_line(public_key: &PublicKey) -> Result<String, String> {
+    public_key
+        .to_openssh()
+        .map_err(|e| format!("Failed to encode public key: {}", e))
+}
+
+/**
+ * Write a keypair to `path` and `path.pub` like ssh-keygen does: the private key is
+ * only readable by the owner (0600). Existing files are never overwritten.
+ */
+pub fn export_key_files(path: &Path, private_key: &str, public_key: &str) -> Result<(), String> {
+    let public_path = path.with_file_name(format!(
+        "{}.pub",
+        path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    ));
+
+    for existing in [path, public_path.as_path()] {
+        if existing.exists() {
+            return Err(format!("{} already exists", existing.display()));
+        }
+    }
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        if !parent.exists() {
+            create_private_dir(parent)?;
+        }
+    }
+
+    write_new_file(path, private_key.as_bytes(), 0o600)?;
+    let public_line = format!("{}\n", public_key.trim_end());
+    if let Err(e) = write_new_file(&public_path, public_line.as_bytes(), 0o644) {
+        let _ = std::fs::remove_file(path);
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+fn write_new_file(path: &Path, contents: &[u8], mode: u32) -> Result<(), String> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(mode);
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+
+    let mut file = options
+        .open(path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    file.write_all(contents)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn create_private_dir(path: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o700))
+            .map_err(|e| format!("Failed to set permissions on {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_ed25519_with_passphrase() {
+        let keypair =
+            generate_keypair(&KeyType::Ed25519, None, Some("secret"), "test@kerminal").unwrap();
+
+        assert!(keypair.public_key.starts_with("ssh-ed25519 "));
+        assert!(keypair.public_key.ends_with(" test@kerminal"));
+        assert!(keypair.fingerprint.starts_with("SHA256:"));
+
+        let private_key = PrivateKey::from_openssh(&keypair.private_key).unwrap();
+        assert!(private_key.is_encrypted());
+        let decrypted = private_key.decrypt("secret").unwrap();
+        assert_eq!(
+            decrypted
+                .public_key()
+                .fingerprint(HashAlg::Sha256)
+                .to_string(),
+            keypair.fingerprint
+        );
+    }
+
+    #[test]
+    fn test_generate_ecdsa_curves() {
+        let keypair = generate_keypair(&KeyType::ECDSA, Some(384), None, "").unwrap();
+        assert!(keypair.public_key.starts_with("ecdsa-sha2-nistp384 "));
+
+        assert!(generate_keypair(&KeyType::ECDSA, Some(128), None, "").is_err());
+        assert!(generate_keypair(&KeyType::RSA, Some(1024), None, "").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_export_key_files_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("kerminal-keygen-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("id_ed25519");
+        let keypair = generate_keypair(&KeyType::Ed25519, None, None, "").unwrap();
+
+        export_key_files(&path, &keypair.private_key, &keypair.public_key).unwrap();
+        let private_mode = std::fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        let dir_mode = std::fs::metadata(&dir).unwrap().permissions().mode() & 0o777;
+        let public_key = std::fs::read_to_string(dir.join("id_ed25519.pub")).unwrap();
+        let overwrite = export_key_files(&path, &keypair.private_key, &keypair.public_key);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(private_mode, 0o600);
+        assert_eq!(dir_mode, 0o700);
+        assert_eq!(public_key.trim_end(), keypair.public_key);
+        assert!(overwrite.is_err());
+    }
+}
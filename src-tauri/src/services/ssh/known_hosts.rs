@@ -0,0 +1,499 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use log::{info, warn};
+use russh_keys::key::PublicKey;
+use russh_keys::PublicKeyBase64;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{watch, Mutex};
+
+use crate::database::{error::DatabaseResult, service::DatabaseService};
+use crate::error::AppError;
+use crate::models::ssh::known_host::normalize_key_type;
+use crate::models::ssh::{HostKeyVerificationRequest, ImportKnownHostsResult, KnownHost};
+use crate::services::ssh::pending::PendingRequests;
+
+/// How long to wait for the user to accept an unknown host key
+const HOST_KEY_CONFIRM_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Known hosts service: verifies server host keys against the trusted store
+pub struct KnownHostsService {
+    database_service: Arc<Mutex<DatabaseService>>,
+    app_handle: Option<AppHandle>,
+    pending_confirmations: PendingRequests<bool>,
+}
+
+impl KnownHostsService {
+    /// Create new KnownHostsService instance
+    pub fn new(
+        database_service: Arc<Mutex<DatabaseService>>,
+        app_handle: Option<AppHandle>,
+    ) -> Self {
+        Self {
+            database_service,
+            app_handle,
+            pending_confirmations: PendingRequests::default(),
+        }
+    }
+
+    /// Get all trusted host keys
+    pub async fn get_known_hosts(&self) -> DatabaseResult<Vec<KnownHost>> {
+        let db_service = self.database_service.lock().await;
+        db_service.get_known_hosts().await
+    }
+
+    /// Remove a trusted host key
+    pub async fn delete_known_host(&self, id: &str) -> DatabaseResult<()> {
+        let db_service = self.database_service.lock().await;
+        db_service.delete_known_host(id).await
+    }
+
+    /// Verify a server host key.
+    ///
+    /// Returns `Ok(true)` when the key is trusted (or the user just accepted it),
+    /// `Ok(false)` when the user rejected an unknown key and
+    /// `Err(AppError::HostKeyMismatch)` when a different key of the same type is trusted.
+    pub async fn verify_host_key(
+        &self,
+        host: &str,
+        port: u16,
+        jump_chain: Option<&str>,
+        server_key: &PublicKey,
+    ) -> Result<bool, AppError> {
+        let key_type = normalize_key_type(server_key.name()).to_string();
+        let public_key = server_key.public_key_base64();
+
+        let known: Vec<KnownHost> = self
+            .get_known_hosts()
+            .await?
+            .into_iter()
+            .filter(|entry| entry.matches(host, port, jump_chain))
+            .collect();
+
+        if known
+            .iter()
+            .any(|entry| entry.has_key(&key_type, &public_key))
+        {
+            return Ok(true);
+        }
+
+        let fingerprint = KnownHost::calculate_fingerprint(&public_key);
+
+        if let Some(trusted) = known
+            .iter()
+            .find(|entry| normalize_key_type(&entry.key_type) == key_type)
+        {
+            warn!(
+                "Host key mismatch for {}:{}: expected {}, got {}",
+                host, port, trusted.fingerprint, fingerprint
+            );
+            return Err(AppError::HostKeyMismatch(format!(
+                "{} key for {}:{} has changed (trusted {}, received {}). \
+                 Remove the old entry from known hosts if the change is expected",
+                key_type, host, port, trusted.fingerprint, fingerprint
+            )));
+        }
+
+        let request = HostKeyVerificationRequest {
+            request_id: uuid::Uuid::new_v4().to_string(),
+            host: host.to_string(),
+            port,
+            jump_chain: jump_chain.map(|c| c.to_string()),
+            key_type: key_type.clone(),
+            fingerprint,
+            known_key_types: known.iter().map(|entry| entry.key_type.clone()).collect(),
+        };
+
+        if !self.request_confirmation(request).await {
+            return Ok(false);
+        }
+
+        let device_id = {
+            let db_service = self.database_service.lock().await;
+            db_service.get_device_id().to_string()
+        };
+        let known_host = KnownHost::new(
+            device_id,
+            host.to_string(),
+            port,
+            jump_chain.map(|c| c.to_string()),
+            key_type,
+            public_key,
+        );
+        {
+            let db_service = self.database_service.lock().await;
+            db_service.save_known_host(&known_host).await?;
+        }
+        info!(
+            "Trusted new host key {} for {}:{}",
+            known_host.fingerprint, host, port
+        );
+
+        Ok(true)
+    }
+
+    /// Ask the frontend to confirm an unknown host key and wait for the answer
+    async fn request_confirmation(&self, request: HostKeyVerificationRequest) -> bool {
+        let Some(app_handle) = &self.app_handle else {
+            warn!(
+                "Rejecting unknown host key for {}:{}: no UI to confirm it",
+                request.host, request.port
+            );
+            return false;
+        };
+
+        let request_id = request.request_id.clone();
+        let receiver = self.pending_confirmations.register(&request_id).await;
+
+        if let Err(e) = app_handle.emit("ssh-host-key-verification", &request) {
+            warn!("Failed to emit host key verification request: {}", e);
+            self.pending_confirmations.cancel(&request_id).await;
+            return false;
+        }
+
+        self.pending_confirmations
+            .wait(&request_id, receiver, HOST_KEY_CONFIRM_TIMEOUT)
+            .await
+            .unwrap_or(false)
+    }
+
+    /// Deliver the user's answer to a pending host key verification
+    pub async fn respond_host_key_verification(
+        &self,
+        request_id: &str,
+        accept: bool,
+    ) -> Result<(), AppError> {
+        if self.pending_confirmations.answer(request_id, accept).await {
+            Ok(())
+        } else {
+            Err(AppError::NotFound(format!(
+                "Host key verification request {} not found or expired",
+                request_id
+            )))
+        }
+    }
+
+    /// Import entries from an OpenSSH known_hosts file (defaults to ~/.ssh/known_hosts)
+    pub async fn import_openssh_known_hosts(
+        &self,
+        path: Option<PathBuf>,
+    ) -> Result<ImportKnownHostsResult, AppError> {
+        let path = match path {
+            Some(path) => path,
+            None => dirs::home_dir()
+                .ok_or_else(|| AppError::config_error("Could not find home directory"))?
+                .join(".ssh")
+                .join("known_hosts"),
+        };
+
+        let content = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| AppError::General(format!("Failed to read {}: {}", path.display(), e)))?;
+
+        let (entries, mut skipped) = parse_known_hosts(&content);
+
+        let db_service = self.database_service.lock().await;
+        let mut existing = db_service.get_known_hosts().await?;
+        let device_id = db_service.get_device_id().to_string();
+        let mut imported = 0;
+
+        for entry in entries {
+            let duplicate = existing.iter().any(|known| {
+                known.host == entry.host
+                    && known.port == entry.port
+                    && known.jump_chain.is_none()
+                    && known.has_key(&entry.key_type, &entry.public_key)
+            });
+            if duplicate {
+                skipped += 1;
+                continue;
+            }
+
+            let mut known_host = KnownHost::new(
+                device_id.clone(),
+                entry.host,
+                entry.port,
+                None,
+                entry.key_type,
+                entry.public_key,
+            );
+            known_host.comment = Some(format!("Imported from {}", path.display()));
+            db_service.save_known_host(&known_host).await?;
+            existing.push(known_host);
+            imported += 1;
+        }
+
+        info!(
+            "Imported {} known hosts from {} ({} skipped)",
+            imported,
+            path.display(),
+            skipped
+        );
+
+        Ok(ImportKnownHostsResult { imported, skipped })
+    }
+}
+
+/// Host key check for a single connection, shared by the russh client handlers.
+///
+/// `check_server_key` can only answer yes or no, so the reason for a refusal is kept
+/// here and picked up by the caller once `connect` fails.
+#[derive(Clone)]
+pub struct HostKeyVerifier {
+    known_hosts: Arc<KnownHostsService>,
+    host: String,
+    port: u16,
+    jump_chain: Option<String>,
+    failure: Arc<std::sync::Mutex<Option<AppError>>>,
+    checking: Arc<watch::Sender<bool>>,
+}
+
+impl HostKeyVerifier {
+    /// Create a verifier for the given endpoint
+    pub fn new(
+        known_hosts: Arc<KnownHostsService>,
+        host: &str,
+        port: u16,
+        jump_chain: Option<String>,
+    ) -> Self {
+        Self {
+            known_hosts,
+            host: host.to_string(),
+            port,
+            jump_chain,
+            failure: Arc::new(std::sync::Mutex::new(None)),
+            checking: Arc::new(watch::channel(false).0),
+        }
+    }
+
+    /// Whether a host key check is in progress, which may be waiting on the user
+    pub fn checking(&self) -> watch::Receiver<bool> {
+        self.checking.subscribe()
+    }
+
+    /// Check the key presented by the server
+    pub async fn check(&self, server_key: &PublicKey) -> bool {
+        self.checking.send_replace(true);
+        let result = self
+            .known_hosts
+            .verify_host_key(
+                &self.host,
+                self.port,
+                self.jump_chain.as_deref(),
+                server_key,
+            )
+            .await;
+        self.checking.send_replace(false);
+
+        let failure = match result {
+            Ok(true) => return true,
+            Ok(false) => AppError::ssh_connection_failed(format!(
+                "Host key for {}:{} was not accepted",
+                self.host, self.port
+            )),
+            Err(e) => e,
+        };
+
+        if let Ok(mut slot) = self.failure.lock() {
+            *slot = Some(failure);
+        }
+        false
+    }
+
+    /// Take the host key failure recorded during the handshake, if any
+    pub fn take_failure(&self) -> Option<AppError> {
+        self.failure.lock().ok().and_then(|mut slot| slot.take())
+    }
+}
+
+/// Build the jump chain key for a list of hops (`host:port,host:port`)
+pub fn jump_chain_key<'a>(hops: impl IntoIterator<Item = (&'a str, u16)>) -> Option<String> {
+    let chain: Vec<String> = hops
+        .into_iter()
+        .map(|(host, port)| format!("{}:{}", host, port))
+        .collect();
+
+    if chain.is_empty() {
+        None
+    } else {
+        Some(chain.join(","))
+    }
+}
+
+/// Single host/key pair read from an OpenSSH known_hosts file
+#[derive(Debug, Clone, PartialEq)]
+struct KnownHostsEntry {
+    host: String,
+    port: u16,
+    key_type: String,
+    public_key: String,
+}
+
+/// Parse OpenSSH known_hosts content.
+///
+/// Returns the usable entries and the number of skipped ones (markers like
+/// `@cert-authority`/`@revoked`, wildcard and negated patterns).
+fn parse_known_hosts(content: &str) -> (Vec<KnownHostsEntry>, usize) {
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut fields = line.split_whitespace();
+        let (Some(hosts), Some(key_type), Some(public_key)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            skipped += 1;
+            continue;
+        };
+
+        if hosts.starts_with('@') {
+            skipped += 1;
+            continue;
+        }
+
+        for pattern in hosts.split(',') {
+            match parse_host_pattern(pattern) {
+                Some((host, port)) => entries.push(KnownHostsEntry {
+                    host,
+                    port,
+                    key_type: key_type.to_string(),
+                    public_key: public_key.to_string(),
+                }),
+                None => skipped += 1,
+            }
+        }
+    }
+
+    (entries, skipped)
+}
+
+/// Parse one host pattern: `host`, `[host]:port` or a hashed `|1|salt|hash`
+fn parse_host_pattern(pattern: &str) -> Option<(String, u16)> {
+    if pattern.starts_with("|1|") {
+        return Some((pattern.to_string(), 22));
+    }
+
+    if pattern.is_empty() || pattern.contains(['*', '?', '!']) {
+        return None;
+    }
+
+    if let Some(rest) = pattern.strip_prefix('[') {
+        let (host, port) = rest.split_once("]:")?;
+        return Some((host.to_lowercase(), port.parse().ok()?));
+    }
+
+    Some((pattern.to_lowercase(), 22))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ED25519_KEY: &str =
+        "AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl";
+
+    #[test]
+    fn test_parse_known_hosts() {
+        let content = format!(
+            "# comment\n\
+             example.com,192.168.1.10 ssh-ed25519 {key}\n\
+             [git.example.com]:2222 ssh-ed25519 {key} user@host\n\
+             *.internal ssh-ed25519 {key}\n\
+             @cert-authority *.example.com ssh-ed25519 {key}\n",
+            key = ED25519_KEY
+        );
+
+        let (entries, skipped) = parse_known_hosts(&content);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(skipped, 2);
+
+        assert_eq!(entries[0].host, "example.com");
+        assert_eq!(entries[0].port, 22);
+        assert_eq!(entries[1].host, "192.168.1.10");
+        assert_eq!(entries[2].host, "git.example.com");
+        assert_eq!(entries[2].port, 2222);
+        assert_eq!(entries[2].key_type, "ssh-ed25519");
+        assert_eq!(entries[2].public_key, ED25519_KEY);
+    }
+
+    #[test]
+    fn test_hashed_host_matching() {
+        let content = format!(
+            "|1|AAECAwQFBgcICQoLDA0ODxAREhM=|nnUK16ANsXd3hL31YfAkGOluSjU= ssh-ed25519 {key}\n\
+             |1|AAECAwQFBgcICQoLDA0ODxAREhM=|Wgcx+Fm+LmaWwC7rQ80eIf2uHe0= ssh-ed25519 {key}\n",
+            key = ED25519_KEY
+        );
+
+        let (entries, skipped) = parse_known_hosts(&content);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(skipped, 0);
+
+        let to_known_host = |entry: &KnownHostsEntry| {
+            KnownHost::new(
+                "device".to_string(),
+                entry.host.clone(),
+                entry.port,
+                None,
+                entry.key_type.clone(),
+                entry.public_key.clone(),
+            )
+        };
+
+        let default_port = to_known_host(&entries[0]);
+        assert!(default_port.is_hashed());
+        assert!(default_port.matches("example.com", 22, None));
+        assert!(default_port.matches("EXAMPLE.com", 22, Some("bastion:22")));
+        assert!(!default_port.matches("example.com", 2222, None));
+        assert!(!default_port.matches("other.com", 22, None));
+
+        let custom_port = to_known_host(&entries[1]);
+        assert!(custom_port.matches("example.com", 2222, None));
+        assert!(!custom_port.matches("example.com", 22, None));
+    }
+
+    #[test]
+    fn test_jump_chain_matching() {
+        let known_host = KnownHost::new(
+            "device".to_string(),
+            "10.0.0.5".to_string(),
+            22,
+            jump_chain_key([("bastion", 22), ("inner", 2222)]),
+            "ssh-ed25519".to_string(),
+            ED25519_KEY.to_string(),
+        );
+
+        assert_eq!(
+            known_host.jump_chain.as_deref(),
+            Some("bastion:22,inner:2222")
+        );
+        assert!(known_host.matches("10.0.0.5", 22, Some("bastion:22,inner:2222")));
+        assert!(!known_host.matches("10.0.0.5", 22, None));
+        assert!(!known_host.matches("10.0.0.5", 22, Some("bastion:22")));
+        assert!(known_host.has_key("ssh-ed25519", ED25519_KEY));
+        assert!(known_host.fingerprint.starts_with("SHA256:"));
+        assert_eq!(jump_chain_key(std::iter::empty()), None);
+    }
+}
@@ -16,26 +16,55 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+pub mod agent;
+pub mod certificate;
+pub mod client_config;
 pub mod connection_pool;
+pub mod copy_id;
+pub mod exec;
 pub mod key;
+pub mod keyboard_interactive;
+pub mod keygen;
+pub mod known_hosts;
+pub mod pending;
+pub mod x11;
 
 use anyhow;
+use chrono::Utc;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex, Semaphore};
+use tokio::task::JoinSet;
 
 pub use connection_pool::SSHConnectionPool;
 pub use key::SSHKeyService;
+pub use keyboard_interactive::KeyboardInteractiveService;
+pub use known_hosts::{HostKeyVerifier, KnownHostsService};
 
-use crate::database::{error::DatabaseResult, service::DatabaseService};
+use crate::database::{
+    error::{DatabaseError, DatabaseResult},
+    service::DatabaseService,
+};
+use crate::models::ssh::key::ResolvedSSHKey;
+use crate::models::ssh::profile::{AuthData, AuthMethod};
 use crate::models::ssh::{
-    CreateSSHGroupRequest, CreateSSHProfileRequest, DeleteGroupAction, SSHGroup, SSHProfile,
-    TestSSHConnectionRequest, UpdateSSHGroupRequest, UpdateSSHProfileRequest,
+    CreateSSHGroupRequest, CreateSSHProfileRequest, DeleteGroupAction, DeployPublicKeyRequest,
+    DeployPublicKeyResult, HostExecFinished, MultiHostExecEvent, MultiHostExecRequest,
+    MultiHostExecResult, SSHGroup, SSHProfile, TestSSHConnectionRequest, UpdateSSHGroupRequest,
+    UpdateSSHProfileRequest,
 };
 
+/// Hosts running a multi-host command at the same time, unless the request says otherwise
+const DEFAULT_EXEC_CONCURRENCY: usize = 10;
+/// Per-host time limit of a multi-host command, unless the request says otherwise
+const DEFAULT_EXEC_TIMEOUT_SECS: u64 = 60;
+
 /// SSH service for handling SSH profiles and groups
 pub struct SSHService {
     database_service: Arc<Mutex<DatabaseService>>,
     ssh_key_service: Arc<Mutex<SSHKeyService>>,
+    known_hosts: Arc<KnownHostsService>,
+    connection_pool: Arc<SSHConnectionPool>,
 }
 
 impl SSHService {
@@ -43,13 +72,27 @@ impl SSHService {
     pub fn new(
         database_service: Arc<Mutex<DatabaseService>>,
         ssh_key_service: Arc<Mutex<SSHKeyService>>,
+        known_hosts: Arc<KnownHostsService>,
+        connection_pool: Arc<SSHConnectionPool>,
     ) -> Self {
         Self {
             database_service,
             ssh_key_service,
+            known_hosts,
+            connection_pool,
         }
     }
 
+    /// Get the known hosts service used to verify server host keys
+    pub fn known_hosts(&self) -> Arc<KnownHostsService> {
+        self.known_hosts.clone()
+    }
+
+    /// Get the pool of authenticated connections shared with terminals and tunnels
+    pub fn connection_pool(&self) -> Arc<SSHConnectionPool> {
+        self.connection_pool.clone()
+    }
+
     /// Create new SSH group
     pub async fn create_ssh_group(
         &self,
@@ -94,8 +137,11 @@ impl SSHService {
     /// Create new SSH profile
     pub async fn create_ssh_profile(
         &self,
-        request: CreateSSHProfileRequest,
+        mut request: CreateSSHProfileRequest,
     ) -> DatabaseResult<SSHProfile> {
+        certificate::fill_certificate_validity(&mut request.auth_data)
+            .map_err(|e| DatabaseError::ValidationError(e.to_string()))?;
+
         let db_service = self.database_service.lock().await;
         db_service.create_ssh_profile(request).await
     }
@@ -116,8 +162,13 @@ impl SSHService {
     pub async fn update_ssh_profile(
         &self,
         id: &str,
-        request: UpdateSSHProfileRequest,
+        mut request: UpdateSSHProfileRequest,
     ) -> DatabaseResult<SSHProfile> {
+        if let Some(auth_data) = request.auth_data.as_mut() {
+            certificate::fill_certificate_validity(auth_data)
+                .map_err(|e| DatabaseError::ValidationError(e.to_string()))?;
+        }
+
         let db_service = self.database_service.lock().await;
         db_service.update_ssh_profile(id, request).await
     }
@@ -153,10 +204,6 @@ impl SSHService {
         &self,
         request: TestSSHConnectionRequest,
     ) -> DatabaseResult<()> {
-        use crate::core::terminal::ssh::SSHTerminal;
-        use crate::models::ssh::profile::AuthData;
-        use crate::models::terminal::{TerminalConfig, TerminalType};
-
         let device_id = {
             let db_service = self.database_service.lock().await;
             db_service.get_device_id().to_string()
@@ -178,42 +225,204 @@ impl SSHService {
                         })?,
                 )
             }
-            AuthData::Password { .. } | AuthData::Certificate { .. } => None,
+            _ => None,
+        };
+
+        self.connection_pool
+            .test_connection(&profile, resolved_key)
+            .await
+            .map_err(|e| {
+                crate::database::error::DatabaseError::Internal(anyhow::anyhow!(e.to_string()))
+            })?;
+
+        Ok(())
+    }
+
+    /// Install a stored key's public key on the host of a profile (ssh-copy-id), connecting
+    /// with the profile's current authentication. Optionally switches the profile to the
+    /// key once a fresh login with it succeeds.
+    pub async fn deploy_public_key(
+        &self,
+        request: DeployPublicKeyRequest,
+    ) -> DatabaseResult<DeployPublicKeyResult> {
+        let profile = self.get_ssh_profile(&request.profile_id).await?;
+        let key = {
+            let db_service = self.database_service.lock().await;
+            db_service.get_ssh_key(&request.key_id).await?
         };
 
-        let config = TerminalConfig {
-            terminal_type: TerminalType::SSH,
-            local_config: None,
-            ssh_profile_id: Some(profile.base.id.clone()),
-            ssh_config_host: None,
-            ssh_config_password: None,
-            terminal_profile_id: None,
+        let public_key = match key.public_key.as_deref().filter(|k| !k.trim().is_empty()) {
+            Some(public_key) => public_key.trim().to_string(),
+            None => keygen::public_key_from_private(&key.private_key, key.passphrase.as_deref())
+                .map_err(DatabaseError::ValidationError)?,
         };
 
-        let mut ssh_terminal = SSHTerminal::new(
-            "test-connection".to_string(),
-            config,
-            profile,
-            Some(self.database_service.clone()),
-        )
-        .map_err(|e| {
-            crate::database::error::DatabaseError::Internal(anyhow::anyhow!(e.to_string()))
-        })?;
-
-        let connect_result = if let Some(resolved_key) = resolved_key {
-            ssh_terminal
-                .connect_with_resolved_data(Some(resolved_key))
+        let resolved_key = match &profile.auth_data {
+            AuthData::KeyReference { key_id } => {
+                let key_service = self.ssh_key_service.lock().await;
+                Some(key_service.resolve_key_for_auth(key_id).await?)
+            }
+            _ => None,
+        };
+
+        let installed = {
+            let connection = self
+                .connection_pool
+                .acquire(&profile, resolved_key)
+                .await
+                .map_err(|e| DatabaseError::Internal(anyhow::anyhow!(e.to_string())))?;
+            copy_id::install_public_key(&connection, &public_key)
                 .await
-        } else {
-            ssh_terminal.connect().await
+                .map_err(|e| DatabaseError::Internal(anyhow::anyhow!(e.to_string())))?
         };
 
-        let _ = ssh_terminal.disconnect().await;
+        let mut profile_updated = false;
+        if request.switch_profile_auth {
+            let auth_data = AuthData::KeyReference {
+                key_id: key.base.id.clone(),
+            };
+            let mut key_profile = profile.clone();
+            key_profile.auth_method = AuthMethod::KeyReference;
+            key_profile.auth_data = auth_data.clone();
 
-        connect_result.map_err(|e| {
-            crate::database::error::DatabaseError::Internal(anyhow::anyhow!(e.to_string()))
-        })?;
+            self.connection_pool
+                .test_connection(
+                    &key_profile,
+                    Some(ResolvedSSHKey {
+                        private_key: key.private_key,
+                        passphrase: key.passphrase,
+                    }),
+                )
+                .await
+                .map_err(|e| {
+                    DatabaseError::Internal(anyhow::anyhow!(
+                        "Key was installed but logging in with it failed: {}",
+                        e
+                    ))
+                })?;
 
-        Ok(())
+            self.update_ssh_profile(
+                &profile.base.id,
+                UpdateSSHProfileRequest {
+                    auth_method: Some(AuthMethod::KeyReference),
+                    auth_data: Some(auth_data),
+                    ..Default::default()
+                },
+            )
+            .await?;
+            profile_updated = true;
+        }
+
+        Ok(DeployPublicKeyResult {
+            authorized_keys_path: installed.authorized_keys_path,
+            already_present: installed.already_present,
+            profile_updated,
+        })
+    }
+
+    /// Run one command on several profiles (or every profile of a group) over exec
+    /// channels, a limited number of hosts at a time. Partial output and each finished
+    /// host are sent to `events` as they happen.
+    pub async fn exec_on_hosts(
+        &self,
+        request: MultiHostExecRequest,
+        events: Option<mpsc::UnboundedSender<MultiHostExecEvent>>,
+    ) -> DatabaseResult<MultiHostExecResult> {
+        if request.command.trim().is_empty() {
+            return Err(DatabaseError::ValidationError(
+                "Command must not be empty".to_string(),
+            ));
+        }
+
+        let profiles = {
+            let db_service = self.database_service.lock().await;
+            let mut profiles: Vec<SSHProfile> = Vec::new();
+            for id in &request.profile_ids {
+                profiles.push(db_service.get_ssh_profile(id).await?);
+            }
+            if let Some(group_id) = &request.group_id {
+                for profile in db_service.get_ssh_profiles(Some(group_id)).await? {
+                    if !profiles.iter().any(|p| p.base.id == profile.base.id) {
+                        profiles.push(profile);
+                    }
+                }
+            }
+            profiles
+        };
+
+        if profiles.is_empty() {
+            return Err(DatabaseError::ValidationError(
+                "No hosts selected to run the command on".to_string(),
+            ));
+        }
+
+        let exec_id = request
+            .exec_id
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let command = Arc::new(request.command);
+        let timeout = Duration::from_secs(
+            request
+                .timeout_secs
+                .unwrap_or(DEFAULT_EXEC_TIMEOUT_SECS)
+                .max(1),
+        );
+        let limit = Arc::new(Semaphore::new(
+            request
+                .concurrency
+                .unwrap_or(DEFAULT_EXEC_CONCURRENCY)
+                .max(1),
+        ));
+        let started_at = Utc::now();
+        let host_count = profiles.len();
+
+        let mut tasks = JoinSet::new();
+        for (index, profile) in profiles.into_iter().enumerate() {
+            let limit = limit.clone();
+            let ssh_key_service = self.ssh_key_service.clone();
+            let connection_pool = self.connection_pool.clone();
+            let command = command.clone();
+            let sink = exec::OutputSink {
+                exec_id: exec_id.clone(),
+                profile_id: profile.base.id.clone(),
+                events: events.clone(),
+            };
+
+            tasks.spawn(async move {
+                let _permit = limit.acquire_owned().await;
+                let result = exec::exec_on_host(
+                    &ssh_key_service,
+                    &connection_pool,
+                    &profile,
+                    &command,
+                    timeout,
+                    &sink,
+                )
+                .await;
+
+                if let Some(events) = &sink.events {
+                    let _ = events.send(MultiHostExecEvent::Finished(HostExecFinished {
+                        exec_id: sink.exec_id.clone(),
+                        result: result.clone(),
+                    }));
+                }
+                (index, result)
+            });
+        }
+
+        let mut results = vec![None; host_count];
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((index, result)) => results[index] = Some(result),
+                Err(e) => log::error!("Multi-host exec task failed: {}", e),
+            }
+        }
+
+        Ok(MultiHostExecResult {
+            exec_id,
+            command: command.to_string(),
+            started_at,
+            finished_at: Utc::now(),
+            hosts: results.into_iter().flatten().collect(),
+        })
     }
 }
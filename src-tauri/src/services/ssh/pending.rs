@@ -0,0 +1,131 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::{oneshot, Mutex};
+
+/// Requests sent to the frontend that wait for the user's answer, keyed by request id
+pub struct PendingRequests<T> {
+    senders: Mutex<HashMap<String, oneshot::Sender<T>>>,
+}
+
+impl<T> Default for PendingRequests<T> {
+    fn default() -> Self {
+        Self {
+            senders: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<T> PendingRequests<T> {
+    /// Register a request before it is sent, returning the receiver of its answer
+    pub async fn register(&self, request_id: &str) -> oneshot::Receiver<T> {
+        let (sender, receiver) = oneshot::channel();
+        self.senders
+            .lock()
+            .await
+            .insert(request_id.to_string(), sender);
+        receiver
+    }
+
+    /// Forget a request, e.g. when it could not be sent
+    pub async fn cancel(&self, request_id: &str) {
+        self.senders.lock().await.remove(request_id);
+    }
+
+    /// Wait for the answer of a registered request. `None` when it timed out or was
+    /// cancelled; the request is forgotten either way.
+    pub async fn wait(
+        &self,
+        request_id: &str,
+        receiver: oneshot::Receiver<T>,
+        timeout: Duration,
+    ) -> Option<T> {
+        let answer = tokio::time::timeout(timeout, receiver).await;
+        self.cancel(request_id).await;
+        answer.ok().and_then(Result::ok)
+    }
+
+    /// Deliver the answer to a pending request, returning false when it is unknown or expired
+    pub async fn answer(&self, request_id: &str, answer: T) -> bool {
+        let sender = self.senders.lock().await.remove(request_id);
+        match sender {
+            Some(sender) => sender.send(answer).is_ok(),
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_answer_accepts_and_rejects() {
+        let pending = Arc::new(PendingRequests::<bool>::default());
+
+        let receiver = pending.register("accept").await;
+        let responder = pending.clone();
+        tokio::spawn(async move { assert!(responder.answer("accept", true).await) });
+        assert_eq!(
+            pending
+                .wait("accept", receiver, Duration::from_secs(5))
+                .await,
+            Some(true)
+        );
+
+        let receiver = pending.register("reject").await;
+        let responder = pending.clone();
+        tokio::spawn(async move { assert!(responder.answer("reject", false).await) });
+        assert_eq!(
+            pending
+                .wait("reject", receiver, Duration::from_secs(5))
+                .await,
+            Some(false)
+        );
+
+        // Answered requests are forgotten
+        assert!(!pending.answer("accept", true).await);
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_request_expires() {
+        let pending = PendingRequests::<bool>::default();
+
+        let receiver = pending.register("late").await;
+        assert_eq!(
+            pending
+                .wait("late", receiver, Duration::from_millis(20))
+                .await,
+            None
+        );
+        assert!(!pending.answer("late", true).await);
+
+        let receiver = pending.register("cancelled").await;
+        pending.cancel("cancelled").await;
+        assert_eq!(
+            pending
+                .wait("cancelled", receiver, Duration::from_secs(5))
+                .await,
+            None
+        );
+        assert!(!pending.answer("unknown", true).await);
+    }
+}
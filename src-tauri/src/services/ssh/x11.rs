@@ -0,0 +1,423 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use log::{debug, warn};
+use russh::client::Msg;
+use russh::Channel;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::error::AppError;
+
+/// Environment variable naming the local X display
+pub const DISPLAY: &str = "DISPLAY";
+/// Only authentication protocol offered to the server
+pub const X11_AUTH_PROTOCOL: &str = "MIT-MAGIC-COOKIE-1";
+
+/// Directory of the local X server sockets (`X<display>`)
+const X11_UNIX_SOCKET_DIR: &str = "/tmp/.X11-unix";
+/// TCP port of display 0, display N listens on the base plus N
+const X11_TCP_PORT_BASE: u32 = 6000;
+/// Length of the fixed part of the X11 connection setup request
+const X11_SETUP_HEADER_LEN: usize = 12;
+
+/// Where the local X server listens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum X11Target {
+    Unix(PathBuf),
+    Tcp { host: String, port: u16 },
+}
+
+/// Parsed `DISPLAY` value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct X11Display {
+    pub target: X11Target,
+    pub screen: u32,
+}
+
+impl X11Display {
+    /// Parse a display name of the form `[protocol/][host]:display[.screen]`, or a socket
+    /// path ending in `:display` (as set by XQuartz)
+    pub fn parse(display: &str) -> Result<Self, AppError> {
+        let invalid = || AppError::invalid_config(format!("Invalid X11 display '{}'", display));
+
+        let (host, number) = display.rsplit_once(':').ok_or_else(invalid)?;
+        let (number, screen) = match number.split_once('.') {
+            Some((number, screen)) => (number, screen.parse::<u32>().map_err(|_| invalid())?),
+            None => (number, 0),
+        };
+        let number = number.parse::<u32>().map_err(|_| invalid())?;
+
+        if display.starts_with('/') {
+            return Ok(Self {
+                target: X11Target::Unix(PathBuf::from(display)),
+                screen,
+            });
+        }
+
+        let (protocol, host) = match host.split_once('/') {
+            Some((protocol, host)) => (Some(protocol), host),
+            None => (None, host),
+        };
+
+        let target = if protocol != Some("tcp") && (host.is_empty() || host == "unix") {
+            X11Target::Unix(PathBuf::from(X11_UNIX_SOCKET_DIR).join(format!("X{}", number)))
+        } else {
+            let port = u16::try_from(X11_TCP_PORT_BASE + number).map_err(|_| invalid())?;
+            let host = if host.is_empty() { "localhost" } else { host };
+            X11Target::Tcp {
+                host: host.to_string(),
+                port,
+            }
+        };
+
+        Ok(Self { target, screen })
+    }
+}
+
+/// X11 forwarding state of one connection.
+///
+/// The server is given a generated cookie rather than the real one, and each forwarded
+/// connection is only passed to the local display after presenting it. The cookie is then
+/// replaced with the local display's own cookie (if it has one).
+pub struct X11Forwarder {
+    display: X11Display,
+    fake_cookie: [u8; 16],
+    real_cookie: Option<Vec<u8>>,
+}
+
+impl X11Forwarder {
+    pub fn new(display: X11Display, real_cookie: Option<Vec<u8>>) -> Self {
+        Self {
+            display,
+            fake_cookie: rand::random(),
+            real_cookie,
+        }
+    }
+
+    /// Forward to the display named by `DISPLAY`, using the cookie `xauth` has for it
+    pub async fn from_env() -> Result<Self, AppError> {
+        let name = match std::env::var(DISPLAY) {
+            Ok(name) if !name.is_empty() => name,
+            _ => {
+                return Err(AppError::invalid_config(
+                    "X11 forwarding requested but DISPLAY is not set".to_string(),
+                ))
+            }
+        };
+
+        let display = X11Display::parse(&name)?;
+        let real_cookie = local_cookie(&name).await;
+        if real_cookie.is_none() {
+            debug!(
+                "No xauth cookie for display {}, forwarding without one",
+                name
+            );
+        }
+
+        Ok(Self::new(display, real_cookie))
+    }
+
+    /// Cookie sent to the server in `x11-req`, hex encoded
+    pub fn cookie_hex(&self) -> String {
+        self.fake_cookie
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    /// Screen number sent to the server in `x11-req`
+    pub fn screen(&self) -> u32 {
+        self.display.screen
+    }
+
+    /// Serve an `x11` channel opened by the server from the local display
+    pub fn spawn_forward(self: &Arc<Self>, channel: Channel<Msg>) {
+        let forwarder = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = forwarder.forward(channel.into_stream()).await {
+                warn!("X11 forwarding failed: {}", e);
+            }
+        });
+    }
+
+    /// Check the cookie of the connection setup, swap in the local one and copy traffic
+    /// between the stream and the local display until either side closes
+    async fn forward<S>(&self, mut stream: S) -> Result<(), AppError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut header = [0u8; X11_SETUP_HEADER_LEN];
+        stream.read_exact(&mut header).await.map_err(setup_error)?;
+
+        let read_u16 = |offset: usize| {
+            let bytes = [header[offset], header[offset + 1]];
+            match header[0] {
+                b'B' => Ok(u16::from_be_bytes(bytes)),
+                b'l' => Ok(u16::from_le_bytes(bytes)),
+                _ => Err(AppError::connection_failed(
+                    "X11 connection rejected: invalid byte order".to_string(),
+                )),
+            }
+        };
+        let name_len = read_u16(6)? as usize;
+        let data_len = read_u16(8)? as usize;
+
+        let mut name = vec![0u8; name_len + padding(name_len)];
+        stream.read_exact(&mut name).await.map_err(setup_error)?;
+        let mut data = vec![0u8; data_len + padding(data_len)];
+        stream.read_exact(&mut data).await.map_err(setup_error)?;
+
+        if &name[..name_len] != X11_AUTH_PROTOCOL.as_bytes() || data[..data_len] != self.fake_cookie
+        {
+            return Err(AppError::connection_failed(
+                "X11 connection rejected: cookie does not match the forwarded one".to_string(),
+            ));
+        }
+
+        let (name, data): (&[u8], &[u8]) = match &self.real_cookie {
+            Some(cookie) => (X11_AUTH_PROTOCOL.as_bytes(), cookie),
+            None => (&[], &[]),
+        };
+        let write_u16 = |value: u16| match header[0] {
+            b'B' => value.to_be_bytes(),
+            _ => value.to_le_bytes(),
+        };
+
+        let mut setup = Vec::with_capacity(X11_SETUP_HEADER_LEN + name.len() + data.len() + 6);
+        setup.extend_from_slice(&header[..6]);
+        setup.extend_from_slice(&write_u16(name.len() as u16));
+        setup.extend_from_slice(&write_u16(data.len() as u16));
+        setup.extend_from_slice(&header[10..]);
+        setup.extend_from_slice(name);
+        setup.resize(setup.len() + padding(name.len()), 0);
+        setup.extend_from_slice(data);
+        setup.resize(setup.len() + padding(data.len()), 0);
+
+        match &self.display.target {
+            X11Target::Unix(path) => {
+                #[cfg(unix)]
+                {
+                    let local = tokio::net::UnixStream::connect(path).await.map_err(|e| {
+                        AppError::connection_failed(format!(
+                            "Failed to connect to X display at {}: {}",
+                            path.display(),
+                            e
+                        ))
+                    })?;
+                    bridge(stream, local, &setup).await
+                }
+                #[cfg(not(unix))]
+                {
+                    Err(AppError::connection_failed(format!(
+                        "X display socket {} is not supported on this platform",
+                        path.display()
+                    )))
+                }
+            }
+            X11Target::Tcp { host, port } => {
+                let local = tokio::net::TcpStream::connect((host.as_str(), *port))
+                    .await
+                    .map_err(|e| {
+                        AppError::connection_failed(format!(
+                            "Failed to connect to X display at {}:{}: {}",
+                            host, port, e
+                        ))
+                    })?;
+                bridge(stream, local, &setup).await
+            }
+        }
+    }
+}
+
+/// Send the rewritten setup request to the display, then copy traffic both ways
+async fn bridge<S, L>(mut stream: S, mut local: L, setup: &[u8]) -> Result<(), AppError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    L: AsyncRead + AsyncWrite + Unpin,
+{
+    local
+        .write_all(setup)
+        .await
+        .map_err(|e| AppError::connection_failed(format!("X11 forwarding error: {}", e)))?;
+
+    tokio::io::copy_bidirectional(&mut stream, &mut local)
+        .await
+        .map_err(|e| AppError::connection_failed(format!("X11 forwarding error: {}", e)))?;
+
+    Ok(())
+}
+
+fn setup_error(e: std::io::Error) -> AppError {
+    AppError::connection_failed(format!("Failed to read X11 connection setup: {}", e))
+}
+
+/// Bytes needed to pad a field of the setup request to a multiple of four
+fn padding(len: usize) -> usize {
+    (4 - len % 4) % 4
+}
+
+/// Look up the MIT-MAGIC-COOKIE-1 of the display with `xauth list`
+async fn local_cookie(display: &str) -> Option<Vec<u8>> {
+    let output = tokio::process::Command::new("xauth")
+        .arg("list")
+        .arg(display)
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| {
+            let mut fields = line.split_whitespace().skip(1);
+            match (fields.next(), fields.next()) {
+                (Some(X11_AUTH_PROTOCOL), Some(cookie)) => decode_hex(cookie),
+                _ => None,
+            }
+        })
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_display() {
+        assert_eq!(
+            X11Display::parse(":0").unwrap(),
+            X11Display {
+                target: X11Target::Unix(PathBuf::from("/tmp/.X11-unix/X0")),
+                screen: 0,
+            }
+        );
+        assert_eq!(
+            X11Display::parse("unix:1.2").unwrap(),
+            X11Display {
+                target: X11Target::Unix(PathBuf::from("/tmp/.X11-unix/X1")),
+                screen: 2,
+            }
+        );
+        assert_eq!(
+            X11Display::parse("localhost:10.0").unwrap().target,
+            X11Target::Tcp {
+                host: "localhost".to_string(),
+                port: 6010,
+            }
+        );
+        assert_eq!(
+            X11Display::parse("tcp/:3").unwrap().target,
+            X11Target::Tcp {
+                host: "localhost".to_string(),
+                port: 6003,
+            }
+        );
+        assert_eq!(
+            X11Display::parse("/private/tmp/com.apple.launchd.abc/org.xquartz:0")
+                .unwrap()
+                .target,
+            X11Target::Unix(PathBuf::from(
+                "/private/tmp/com.apple.launchd.abc/org.xquartz:0"
+            ))
+        );
+        assert!(X11Display::parse("localhost").is_err());
+        assert!(X11Display::parse(":x").is_err());
+    }
+
+    /// Setup request of a little-endian X11 client presenting the given cookie
+    fn setup_request(cookie: &[u8]) -> Vec<u8> {
+        let mut setup = vec![b'l', 0, 11, 0, 0, 0];
+        setup.extend_from_slice(&(X11_AUTH_PROTOCOL.len() as u16).to_le_bytes());
+        setup.extend_from_slice(&(cookie.len() as u16).to_le_bytes());
+        setup.extend_from_slice(&[0, 0]);
+        setup.extend_from_slice(X11_AUTH_PROTOCOL.as_bytes());
+        setup.resize(setup.len() + padding(X11_AUTH_PROTOCOL.len()), 0);
+        setup.extend_from_slice(cookie);
+        setup.resize(setup.len() + padding(cookie.len()), 0);
+        setup
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_forward_swaps_cookie_for_local_display() {
+        let path = std::env::temp_dir().join(format!("kerminal-x11-{}.sock", uuid::Uuid::new_v4()));
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+        let real_cookie = vec![0xab; 16];
+
+        // Stand-in X server: accept one client, check its setup and answer
+        let expected = setup_request(&real_cookie);
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut received = vec![0u8; expected.len()];
+            socket.read_exact(&mut received).await.unwrap();
+            socket.write_all(b"welcome").await.unwrap();
+            received == expected
+        });
+
+        let forwarder = Arc::new(X11Forwarder::new(
+            X11Display {
+                target: X11Target::Unix(path.clone()),
+                screen: 0,
+            },
+            Some(real_cookie),
+        ));
+        let (mut client, remote) = tokio::io::duplex(1024);
+        let task = {
+            let forwarder = forwarder.clone();
+            tokio::spawn(async move { forwarder.forward(remote).await })
+        };
+
+        client
+            .write_all(&setup_request(&forwarder.fake_cookie))
+            .await
+            .unwrap();
+        let mut reply = [0u8; 7];
+        client.read_exact(&mut reply).await.unwrap();
+        assert_eq!(&reply, b"welcome");
+        assert!(server.await.unwrap());
+
+        drop(client);
+        let _ = task.await;
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_forward_rejects_unknown_cookie() {
+        let forwarder = X11Forwarder::new(X11Display::parse(":0").unwrap(), None);
+        assert_eq!(forwarder.cookie_hex().len(), 32);
+
+        let (mut client, remote) = tokio::io::duplex(1024);
+        client.write_all(&setup_request(&[0u8; 16])).await.unwrap();
+
+        assert!(forwarder.forward(remote).await.is_err());
+    }
+}
@@ -18,35 +18,347 @@
 
 use crate::error::AppError;
 use crate::models::ssh::SSHConfigHost;
-use std::path::PathBuf;
+use log::warn;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Maximum nesting of Include directives, as in OpenSSH
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Keywords whose values accumulate instead of the first one winning
+const MULTI_VALUE_KEYWORDS: &[&str] = &[
+    "identityfile",
+    "certificatefile",
+    "localforward",
+    "remoteforward",
+    "dynamicforward",
+    "sendenv",
+];
+
+/// Condition of a config section
+#[derive(Debug, Clone)]
+enum Criteria {
+    /// `Host` patterns; options before the first section behave like `Host *`
+    Host(Vec<String>),
+    /// `Match` conditions, all of which must hold
+    Match(Vec<MatchCondition>),
+}
+
+#[derive(Debug, Clone)]
+struct MatchCondition {
+    negated: bool,
+    keyword: String,
+    argument: Option<String>,
+}
+
+/// A `Host` or `Match` section and the options given under it, in file order
+#[derive(Debug, Clone)]
+struct ConfigSection {
+    criteria: Criteria,
+    options: Vec<(String, String)>,
+}
+
+/// Values `Match` conditions and `%` tokens are evaluated against
+struct TokenContext<'a> {
+    alias: &'a str,
+    hostname: String,
+    port: u16,
+    user: String,
+    local_user: &'a str,
+    home: String,
+}
+
+/**
+ * Parsed SSH config with Include directives expanded in place
+ */
+pub struct SSHConfig {
+    sections: Vec<ConfigSection>,
+}
+
+impl SSHConfig {
+    /**
+     * Load a config file and everything it includes
+     *
+     * @param path - Path to the config file
+     * @param include_dir - Directory relative Include paths are resolved against
+     */
+    pub fn load(path: &Path, include_dir: &Path) -> Result<Self, AppError> {
+        let mut config = Self::empty();
+        config.read_file(path, include_dir, 0)?;
+        Ok(config)
+    }
+
+    /**
+     * Parse config content, resolving relative Include paths against `include_dir`
+     */
+    pub fn parse_str(content: &str, include_dir: &Path) -> Result<Self, AppError> {
+        let mut config = Self::empty();
+        config.read_content(content, include_dir, 0)?;
+        Ok(config)
+    }
+
+    fn empty() -> Self {
+        Self {
+            sections: vec![ConfigSection {
+                criteria: Criteria::Host(vec!["*".to_string()]),
+                options: Vec::new(),
+            }],
+        }
+    }
+
+    fn read_file(&mut self, path: &Path, include_dir: &Path, depth: usize) -> Result<(), AppError> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            AppError::config_error(format!(
+                "Failed to read SSH config {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        self.read_content(&content, include_dir, depth)
+    }
+
+    fn read_content(
+        &mut self,
+        content: &str,
+        include_dir: &Path,
+        depth: usize,
+    ) -> Result<(), AppError> {
+        for line in content.lines() {
+            let Some((keyword, value)) = split_keyword(line) else {
+                continue;
+            };
+
+            match keyword.as_str() {
+                "host" => self.sections.push(ConfigSection {
+                    criteria: Criteria::Host(split_args(value)),
+                    options: Vec::new(),
+                }),
+                "match" => self.sections.push(ConfigSection {
+                    criteria: Criteria::Match(parse_match(value)?),
+                    options: Vec::new(),
+                }),
+                "include" => {
+                    if depth >= MAX_INCLUDE_DEPTH {
+                        return Err(AppError::config_error(
+                            "SSH config Include nesting is too deep",
+                        ));
+                    }
+
+                    let outer = self.sections.len() - 1;
+                    for pattern in split_args(value) {
+                        for path in expand_include(&pattern, include_dir) {
+                            if let Err(e) = self.read_file(&path, include_dir, depth + 1) {
+                                warn!("Skipping included SSH config: {}", e);
+                            }
+                        }
+                    }
+
+                    // Lines after the Include belong to the section it appeared in again
+                    if self.sections.len() - 1 != outer {
+                        let criteria = self.sections[outer].criteria.clone();
+                        self.sections.push(ConfigSection {
+                            criteria,
+                            options: Vec::new(),
+                        });
+                    }
+                }
+                _ => {
+                    if let Some(section) = self.sections.last_mut() {
+                        section.options.push((keyword, unquote(value).to_string()));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Concrete host names declared in `Host` lines (no wildcards or negations), in order
+     */
+    pub fn host_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for section in &self.sections {
+            if let Criteria::Host(patterns) = &section.criteria {
+                for pattern in patterns {
+                    if pattern.contains('*') || pattern.contains('?') || pattern.starts_with('!') {
+                        continue;
+                    }
+                    if !names.contains(pattern) {
+                        names.push(pattern.clone());
+                    }
+                }
+            }
+        }
+        names
+    }
+
+    /**
+     * Effective settings for a host, computed the way `ssh -G` does: sections are
+     * evaluated in order and the first value obtained for each keyword wins
+     *
+     * @param alias - Host name as it would be typed on the command line
+     */
+    pub fn resolve(&self, alias: &str) -> SSHConfigHost {
+        let local_user = local_username();
+        let mut single: HashMap<String, String> = HashMap::new();
+        let mut multi: HashMap<String, Vec<String>> = HashMap::new();
+
+        for section in &self.sections {
+            let applies = match &section.criteria {
+                Criteria::Host(patterns) => host_patterns_match(patterns, alias),
+                Criteria::Match(conditions) => {
+                    let ctx = token_context(alias, &single, &local_user);
+                    conditions.iter().all(|c| c.matches(&ctx))
+                }
+            };
+            if !applies {
+                continue;
+            }
+
+            for (keyword, value) in &section.options {
+                if MULTI_VALUE_KEYWORDS.contains(&keyword.as_str()) {
+                    multi
+                        .entry(keyword.clone())
+                        .or_default()
+                        .push(value.clone());
+                } else {
+                    single
+                        .entry(keyword.clone())
+                        .or_insert_with(|| value.clone());
+                }
+            }
+        }
+
+        let ctx = token_context(alias, &single, &local_user);
+        let identity_files: Vec<String> = multi
+            .get("identityfile")
+            .map(|files| {
+                files
+                    .iter()
+                    .map(|f| expand_tilde(&expand_tokens(f, &ctx)))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut options: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (keyword, value) in &single {
+            options.insert(keyword.clone(), vec![value.clone()]);
+        }
+        for (keyword, values) in &multi {
+            options.insert(keyword.clone(), values.clone());
+        }
+        options.insert("hostname".to_string(), vec![ctx.hostname.clone()]);
+        options.insert("port".to_string(), vec![ctx.port.to_string()]);
+        options.insert("user".to_string(), vec![ctx.user.clone()]);
+        if !identity_files.is_empty() {
+            options.insert("identityfile".to_string(), identity_files.clone());
+        }
+
+        SSHConfigHost {
+            name: alias.to_string(),
+            hostname: ctx.hostname.clone(),
+            port: ctx.port,
+            user: Some(ctx.user.clone()),
+            identity_file: identity_files.first().cloned(),
+            identity_files,
+            identities_only: single.get("identitiesonly").and_then(|v| parse_yes_no(v)),
+            proxy_jump: single.get("proxyjump").cloned(),
+            proxy_command: single.get("proxycommand").cloned(),
+            forward_agent: single.get("forwardagent").and_then(|v| parse_yes_no(v)),
+            forward_x11: single.get("forwardx11").and_then(|v| parse_yes_no(v)),
+            server_alive_interval: single
+                .get("serveraliveinterval")
+                .and_then(|v| v.parse().ok()),
+            server_alive_count_max: single
+                .get("serveralivecountmax")
+                .and_then(|v| v.parse().ok()),
+            connect_timeout: single.get("connecttimeout").and_then(|v| v.parse().ok()),
+            compression: single.get("compression").and_then(|v| parse_yes_no(v)),
+            options,
+            other_options: None,
+        }
+    }
+
+    /**
+     * Resolve every concrete host declared in the config
+     */
+    pub fn hosts(&self) -> Vec<SSHConfigHost> {
+        self.host_names()
+            .iter()
+            .map(|name| self.resolve(name))
+            .collect()
+    }
+}
+
+impl MatchCondition {
+    fn matches(&self, ctx: &TokenContext) -> bool {
+        let result = match (self.keyword.as_str(), self.argument.as_deref()) {
+            ("all", _) => true,
+            // Only a single, final pass is made, so both always apply
+            ("canonical", _) | ("final", _) => true,
+            ("host", Some(patterns)) => pattern_list_matches(patterns, &ctx.hostname, true),
+            ("originalhost", Some(patterns)) => pattern_list_matches(patterns, ctx.alias, true),
+            ("user", Some(patterns)) => pattern_list_matches(patterns, &ctx.user, false),
+            ("localuser", Some(patterns)) => pattern_list_matches(patterns, ctx.local_user, false),
+            ("exec", Some(command)) => run_match_exec(&expand_tokens(command, ctx)),
+            (keyword, _) => {
+                warn!("Unsupported SSH config Match criteria '{}'", keyword);
+                false
+            }
+        };
+        result != self.negated
+    }
+}
 
 /**
  * Parse SSH config file and extract host configurations
  *
  * @param config_path - Path to SSH config file (defaults to ~/.ssh/config)
- * @returns Vector of SSHConfigHost entries
+ * @returns Vector of SSHConfigHost entries with their effective settings
  */
 pub async fn parse_ssh_config(
     config_path: Option<PathBuf>,
 ) -> Result<Vec<SSHConfigHost>, AppError> {
-    let path = match config_path {
-        Some(p) => p,
-        None => {
-            let home = dirs::home_dir()
-                .ok_or_else(|| AppError::config_error("Could not determine home directory"))?;
-            home.join(".ssh").join("config")
-        }
-    };
+    match load_ssh_config(config_path).await? {
+        Some(config) => Ok(config.hosts()),
+        None => Ok(Vec::new()),
+    }
+}
+
+/**
+ * Resolve the effective settings for a single host, like `ssh -G <host>`
+ *
+ * @param host - Host name or alias
+ * @param config_path - Path to SSH config file (defaults to ~/.ssh/config)
+ */
+pub async fn resolve_ssh_config_host(
+    host: &str,
+    config_path: Option<PathBuf>,
+) -> Result<SSHConfigHost, AppError> {
+    let host = host.to_string();
+    match load_ssh_config(config_path).await? {
+        Some(config) => tokio::task::spawn_blocking(move || config.resolve(&host))
+            .await
+            .map_err(|e| AppError::internal_error(e.to_string())),
+        None => Ok(SSHConfig::empty().resolve(&host)),
+    }
+}
+
+/// Load the config off the async runtime, since `Match exec` runs commands
+async fn load_ssh_config(config_path: Option<PathBuf>) -> Result<Option<SSHConfig>, AppError> {
+    let ssh_dir = dirs::home_dir()
+        .ok_or_else(|| AppError::config_error("Could not determine home directory"))?
+        .join(".ssh");
+    let path = config_path.unwrap_or_else(|| ssh_dir.join("config"));
 
     if !path.exists() {
-        return Ok(Vec::new());
+        return Ok(None);
     }
 
-    let config_content = tokio::fs::read_to_string(&path)
+    tokio::task::spawn_blocking(move || SSHConfig::load(&path, &ssh_dir).map(Some))
         .await
-        .map_err(|e| AppError::config_error(format!("Failed to read SSH config: {}", e)))?;
-
-    parse_config_content(&config_content)
+        .map_err(|e| AppError::internal_error(e.to_string()))?
 }
 
 /**
@@ -55,95 +367,302 @@ pub async fn parse_ssh_config(
  * @param content - SSH config file content
  * @returns Vector of SSHConfigHost entries
  */
+#[cfg(test)]
 fn parse_config_content(content: &str) -> Result<Vec<SSHConfigHost>, AppError> {
-    let mut hosts = Vec::new();
-    let mut current_host: Option<SSHConfigHost> = None;
+    let include_dir = dirs::home_dir()
+        .map(|home| home.join(".ssh"))
+        .unwrap_or_default();
+    Ok(SSHConfig::parse_str(content, &include_dir)?.hosts())
+}
 
-    for line in content.lines() {
-        let trimmed = line.trim();
+/// Split a config line into its lowercased keyword and value (`Keyword value` or `Keyword=value`)
+fn split_keyword(line: &str) -> Option<(String, &str)> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+        return None;
+    }
 
-        if trimmed.is_empty() || trimmed.starts_with('#') {
-            continue;
-        }
+    let end = trimmed
+        .find(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or(trimmed.len());
+    let keyword = trimmed[..end].to_lowercase();
 
-        let parts: Vec<&str> = trimmed.splitn(2, char::is_whitespace).collect();
-        if parts.len() < 2 {
-            continue;
-        }
+    let rest = trimmed[end..].trim_start();
+    let value = rest.strip_prefix('=').unwrap_or(rest).trim();
+    if value.is_empty() {
+        return None;
+    }
 
-        let keyword = parts[0].to_lowercase();
-        let value = parts[1].trim();
+    Some((keyword, value))
+}
 
-        match keyword.as_str() {
-            "host" => {
-                if let Some(host) = current_host.take() {
-                    hosts.push(host);
-                }
+/// Split arguments on whitespace, keeping double-quoted arguments together
+fn split_args(value: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
 
-                for pattern in value.split_whitespace() {
-                    if !pattern.contains('*') && !pattern.contains('?') {
-                        current_host = Some(SSHConfigHost {
-                            name: pattern.to_string(),
-                            hostname: pattern.to_string(),
-                            port: 22,
-                            user: None,
-                            identity_file: None,
-                            proxy_jump: None,
-                            proxy_command: None,
-                            forward_agent: None,
-                            other_options: None,
-                        });
-                        break;
-                    }
+    for c in value.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
                 }
             }
-            "hostname" => {
-                if let Some(ref mut host) = current_host {
-                    host.hostname = value.to_string();
-                }
-            }
-            "port" => {
-                if let Some(ref mut host) = current_host {
-                    if let Ok(port) = value.parse::<u16>() {
-                        host.port = port;
-                    }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+
+    args
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+fn parse_match(value: &str) -> Result<Vec<MatchCondition>, AppError> {
+    let mut args = split_args(value).into_iter();
+    let mut conditions = Vec::new();
+
+    while let Some(arg) = args.next() {
+        let (negated, keyword) = match arg.strip_prefix('!') {
+            Some(keyword) => (true, keyword.to_lowercase()),
+            None => (false, arg.to_lowercase()),
+        };
+
+        let argument = match keyword.as_str() {
+            "all" | "canonical" | "final" => None,
+            _ => Some(args.next().ok_or_else(|| {
+                AppError::config_error(format!("Match '{}' is missing an argument", keyword))
+            })?),
+        };
+
+        conditions.push(MatchCondition {
+            negated,
+            keyword,
+            argument,
+        });
+    }
+
+    Ok(conditions)
+}
+
+/// `Host` line: matches when any pattern matches and no negated pattern does
+fn host_patterns_match(patterns: &[String], host: &str) -> bool {
+    let host = host.to_lowercase();
+    let mut matched = false;
+
+    for pattern in patterns {
+        match pattern.strip_prefix('!') {
+            Some(negated) => {
+                if wildcard_match(&negated.to_lowercase(), &host) {
+                    return false;
                 }
             }
-            "user" => {
-                if let Some(ref mut host) = current_host {
-                    host.user = Some(value.to_string());
+            None => {
+                if wildcard_match(&pattern.to_lowercase(), &host) {
+                    matched = true;
                 }
             }
-            "identityfile" => {
-                if let Some(ref mut host) = current_host {
-                    let expanded_path = expand_tilde(value);
-                    host.identity_file = Some(expanded_path);
-                }
+        }
+    }
+
+    matched
+}
+
+/// Comma-separated pattern list of `Match` arguments
+fn pattern_list_matches(list: &str, value: &str, case_insensitive: bool) -> bool {
+    let patterns: Vec<String> = list
+        .split(',')
+        .map(|p| {
+            if case_insensitive {
+                p.to_lowercase()
+            } else {
+                p.to_string()
             }
-            "proxyjump" => {
-                if let Some(ref mut host) = current_host {
-                    host.proxy_jump = Some(value.to_string());
-                }
+        })
+        .collect();
+    let value = if case_insensitive {
+        value.to_lowercase()
+    } else {
+        value.to_string()
+    };
+
+    let mut matched = false;
+    for pattern in &patterns {
+        match pattern.strip_prefix('!') {
+            Some(negated) if wildcard_match(negated, &value) => return false,
+            Some(_) => {}
+            None if wildcard_match(pattern, &value) => matched = true,
+            None => {}
+        }
+    }
+
+    matched
+}
+
+/// Glob matching with `*` and `?`
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+    let (mut p, mut v) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while v < value.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == value[v]) {
+            p += 1;
+            v += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, v));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            v = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Expand an Include argument (with `~` and wildcards) into existing files, sorted
+fn expand_include(pattern: &str, include_dir: &Path) -> Vec<PathBuf> {
+    let expanded = expand_tilde(pattern);
+    let path = Path::new(&expanded);
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        include_dir.join(path)
+    };
+
+    let mut candidates = vec![PathBuf::new()];
+    for component in path.components() {
+        let part = component.as_os_str().to_string_lossy().to_string();
+        if !part.contains('*') && !part.contains('?') {
+            for candidate in &mut candidates {
+                candidate.push(component);
             }
-            "proxycommand" => {
-                if let Some(ref mut host) = current_host {
-                    host.proxy_command = Some(value.to_string());
+            continue;
+        }
+
+        let mut next = Vec::new();
+        for candidate in &candidates {
+            let Ok(entries) = std::fs::read_dir(candidate) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if !name.starts_with('.') && wildcard_match(&part, &name) {
+                    next.push(candidate.join(name));
                 }
             }
-            "forwardagent" => {
-                if let Some(ref mut host) = current_host {
-                    host.forward_agent = Some(value.to_lowercase() == "yes");
-                }
+        }
+        next.sort();
+        candidates = next;
+    }
+
+    candidates.into_iter().filter(|p| p.is_file()).collect()
+}
+
+fn token_context<'a>(
+    alias: &'a str,
+    single: &HashMap<String, String>,
+    local_user: &'a str,
+) -> TokenContext<'a> {
+    let home = dirs::home_dir()
+        .map(|h| h.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let hostname = single
+        .get("hostname")
+        .map(|h| h.replace("%h", alias))
+        .unwrap_or_else(|| alias.to_string());
+
+    TokenContext {
+        alias,
+        hostname,
+        port: single
+            .get("port")
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(22),
+        user: single
+            .get("user")
+            .cloned()
+            .unwrap_or_else(|| local_user.to_string()),
+        local_user,
+        home,
+    }
+}
+
+/// Expand the `%h %n %p %r %u %d %%` tokens
+fn expand_tokens(value: &str, ctx: &TokenContext) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('h') => result.push_str(&ctx.hostname),
+            Some('n') => result.push_str(ctx.alias),
+            Some('p') => result.push_str(&ctx.port.to_string()),
+            Some('r') => result.push_str(&ctx.user),
+            Some('u') => result.push_str(ctx.local_user),
+            Some('d') => result.push_str(&ctx.home),
+            Some('%') => result.push('%'),
+            Some(other) => {
+                result.push('%');
+                result.push(other);
             }
-            _ => {}
+            None => result.push('%'),
         }
     }
 
-    if let Some(host) = current_host {
-        hosts.push(host);
+    result
+}
+
+/// `Match exec`: the condition holds when the command exits successfully
+fn run_match_exec(command: &str) -> bool {
+    #[cfg(unix)]
+    let status = std::process::Command::new("sh")
+        .args(["-c", command])
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+    #[cfg(windows)]
+    let status = std::process::Command::new("cmd")
+        .args(["/C", command])
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+
+    status.map(|s| s.success()).unwrap_or(false)
+}
+
+fn parse_yes_no(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "yes" | "true" => Some(true),
+        "no" | "false" => Some(false),
+        _ => None,
     }
+}
 
-    Ok(hosts)
+fn local_username() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
 }
 
 /**
@@ -207,4 +726,172 @@ Host specific
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].name, "specific");
     }
+
+    #[test]
+    fn test_proxy_jump_resolves_config_aliases() {
+        let config = r#"
+Host bastion
+    HostName bastion.example.com
+    Port 2200
+    User jump
+
+Host internal
+    HostName 10.0.0.5
+    User admin
+    ProxyJump bastion,ops@[fd00::7]:2222,gw.example.com:22
+    ProxyCommand nc %h %p
+"#;
+
+        let hosts = parse_config_content(config).unwrap();
+        let profile = hosts[1]
+            .to_temporary_profile(Some("secret".to_string()), &hosts)
+            .unwrap();
+
+        // ProxyJump wins over ProxyCommand
+        assert!(profile.proxy_command.is_none());
+
+        let jump_hosts = profile.jump_hosts.unwrap();
+        assert_eq!(jump_hosts.len(), 3);
+        assert_eq!(jump_hosts[0].host.as_deref(), Some("bastion.example.com"));
+        assert_eq!(jump_hosts[0].port, Some(2200));
+        assert_eq!(jump_hosts[0].username.as_deref(), Some("jump"));
+        assert_eq!(jump_hosts[1].host.as_deref(), Some("fd00::7"));
+        assert_eq!(jump_hosts[1].port, Some(2222));
+        assert_eq!(jump_hosts[1].username.as_deref(), Some("ops"));
+        assert_eq!(jump_hosts[2].host.as_deref(), Some("gw.example.com"));
+        assert_eq!(jump_hosts[2].port, Some(22));
+    }
+
+    #[test]
+    fn test_proxy_command_kept_without_proxy_jump() {
+        let config = r#"
+Host behind-proxy
+    HostName 10.1.2.3
+    User root
+    ProxyCommand ssh -W %h:%p gateway
+"#;
+
+        let hosts = parse_config_content(config).unwrap();
+        let profile = hosts[0]
+            .to_temporary_profile(Some("secret".to_string()), &hosts)
+            .unwrap();
+
+        assert!(profile.jump_hosts.is_none());
+        assert_eq!(
+            profile.proxy_command.as_deref(),
+            Some("ssh -W %h:%p gateway")
+        );
+    }
+
+    #[test]
+    fn test_wildcard_defaults_first_match_wins() {
+        let config = r#"
+Host web
+    HostName web.internal
+    IdentityFile ~/.ssh/web_key
+
+Host *
+    User deploy
+    Port 2200
+    HostName ignored.example.com
+    IdentityFile ~/.ssh/default_key
+    ServerAliveInterval 30
+    IdentitiesOnly yes
+"#;
+
+        let config = SSHConfig::parse_str(config, Path::new("/nonexistent")).unwrap();
+        let host = config.resolve("web");
+
+        assert_eq!(host.hostname, "web.internal");
+        assert_eq!(host.user.as_deref(), Some("deploy"));
+        assert_eq!(host.port, 2200);
+        assert_eq!(host.identity_files.len(), 2);
+        assert!(host.identity_files[0].ends_with(".ssh/web_key"));
+        assert!(host.identity_files[1].ends_with(".ssh/default_key"));
+        assert_eq!(host.identities_only, Some(true));
+        assert_eq!(host.server_alive_interval, Some(30));
+        assert_eq!(host.options["serveraliveinterval"], vec!["30"]);
+    }
+
+    #[test]
+    fn test_host_negation_and_match() {
+        let config = r#"
+Host *.corp !legacy.corp
+    User corp
+
+Match originalhost legacy.corp
+    User old
+
+Match host db-* !user nobody-here
+    Port=5022
+
+Match all
+    Compression yes
+"#;
+
+        let config = SSHConfig::parse_str(config, Path::new("/nonexistent")).unwrap();
+
+        let app = config.resolve("app.corp");
+        assert_eq!(app.user.as_deref(), Some("corp"));
+        assert_eq!(app.compression, Some(true));
+
+        let legacy = config.resolve("legacy.corp");
+        assert_eq!(legacy.user.as_deref(), Some("old"));
+
+        assert_eq!(config.resolve("db-1").port, 5022);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_match_exec() {
+        let config = r#"
+Match exec "test %h = exec-yes"
+    User matched
+
+Host *
+    User fallback
+"#;
+
+        let config = SSHConfig::parse_str(config, Path::new("/nonexistent")).unwrap();
+        assert_eq!(config.resolve("exec-yes").user.as_deref(), Some("matched"));
+        assert_eq!(config.resolve("exec-no").user.as_deref(), Some("fallback"));
+    }
+
+    #[test]
+    fn test_include_expands_globs_in_place() {
+        let dir =
+            std::env::temp_dir().join(format!("kerminal-ssh-config-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("config.d")).unwrap();
+        std::fs::write(
+            dir.join("config.d/10-app"),
+            "Host app\n    HostName app.example.com\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("config.d/20-db"),
+            "Host db\n    HostName db.example.com\n    User dba\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("config"),
+            "Host app\n    Include config.d/*\n    Port 2022\n\nHost *\n    User ops\n",
+        )
+        .unwrap();
+
+        let config = SSHConfig::load(&dir.join("config"), &dir).unwrap();
+        let hosts = config.hosts();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let names: Vec<&str> = hosts.iter().map(|h| h.name.as_str()).collect();
+        assert_eq!(names, vec!["app", "db"]);
+
+        // Port follows the Include but still belongs to the outer `Host app` block
+        assert_eq!(hosts[0].hostname, "app.example.com");
+        assert_eq!(hosts[0].port, 2022);
+        assert_eq!(hosts[0].user.as_deref(), Some("ops"));
+
+        assert_eq!(hosts[1].hostname, "db.example.com");
+        assert_eq!(hosts[1].port, 22);
+        assert_eq!(hosts[1].user.as_deref(), Some("dba"));
+    }
 }
@@ -161,6 +161,18 @@ impl SyncEngine {
             stats.total_synced += count;
         }
 
+        let known_hosts = local_guard.find_all_known_hosts().await?;
+        let json_known_hosts: Vec<_> = known_hosts
+            .iter()
+            .filter_map(|h| h.to_json().ok())
+            .collect();
+        if !json_known_hosts.is_empty() {
+            let count = remote
+                .push_records("ssh_known_hosts", json_known_hosts)
+                .await?;
+            stats.total_synced += count;
+        }
+
         let cmd_groups = local_guard.find_all_saved_command_groups().await?;
         let json_cmd_groups: Vec<_> = cmd_groups.iter().filter_map(|g| g.to_json().ok()).collect();
         if !json_cmd_groups.is_empty() {
@@ -220,6 +232,14 @@ impl SyncEngine {
             }
         }
 
+        let json_known_hosts = remote.pull_records("ssh_known_hosts", last_sync).await?;
+        for json in json_known_hosts {
+            if let Ok(known_host) = crate::models::ssh::KnownHost::from_json(&json) {
+                local_guard.save_known_host(&known_host).await?;
+                stats.total_synced += 1;
+            }
+        }
+
         let json_cmd_groups = remote
             .pull_records("saved_command_groups", last_sync)
             .await?;
@@ -265,10 +285,11 @@ impl SyncEngine {
 
         // Parallel sync: SSH tables can be synced together, command tables can be synced together
         // Using try_join! to run syncs in parallel and fail fast on first error
-        let (profile_stats, group_stats, key_stats) = tokio::try_join!(
+        let (profile_stats, group_stats, key_stats, known_host_stats) = tokio::try_join!(
             self.sync_table_bidirectional(&remote, "ssh_profiles", last_sync, strategy),
             self.sync_table_bidirectional(&remote, "ssh_groups", last_sync, strategy),
             self.sync_table_bidirectional(&remote, "ssh_keys", last_sync, strategy),
+            self.sync_table_bidirectional(&remote, "ssh_known_hosts", last_sync, strategy),
         )?;
 
         let (cmd_group_stats, cmd_stats) = tokio::try_join!(
@@ -281,6 +302,7 @@ impl SyncEngine {
         stats.merge(profile_stats);
         stats.merge(group_stats);
         stats.merge(key_stats);
+        stats.merge(known_host_stats);
         stats.merge(cmd_group_stats);
         stats.merge(cmd_stats);
 
@@ -329,6 +351,13 @@ impl SyncEngine {
                         .filter_map(|k| k.to_json().ok())
                         .collect::<Vec<_>>()
                 }
+                "ssh_known_hosts" => {
+                    let known_hosts = local_guard.find_all_known_hosts().await?;
+                    known_hosts
+                        .iter()
+                        .filter_map(|h| h.to_json().ok())
+                        .collect::<Vec<_>>()
+                }
                 "saved_command_groups" => {
                     let groups = local_guard.find_all_saved_command_groups().await?;
                     groups
@@ -471,6 +500,14 @@ impl SyncEngine {
                                 stats.total_synced += 1;
                             }
                         }
+                        "ssh_known_hosts" => {
+                            if let Ok(known_host) =
+                                crate::models::ssh::KnownHost::from_json(&remote_record)
+                            {
+                                local_guard.save_known_host(&known_host).await?;
+                                stats.total_synced += 1;
+                            }
+                        }
                         "saved_command_groups" => {
                             if let Ok(group) =
                                 crate::models::saved_command::SavedCommandGroup::from_json(
@@ -20,7 +20,7 @@ use serde_json::Value;
 
 use crate::database::error::{DatabaseError, DatabaseResult};
 use crate::models::saved_command::{SavedCommand, SavedCommandGroup};
-use crate::models::ssh::{SSHGroup, SSHKey, SSHProfile, SSHTunnel};
+use crate::models::ssh::{KnownHost, SSHGroup, SSHKey, SSHProfile, SSHTunnel};
 
 /// Helper trait for converting models to/from sync records
 pub trait SyncSerializable {
@@ -70,6 +70,16 @@ impl SyncSerializable for SSHTunnel {
     }
 }
 
+impl SyncSerializable for KnownHost {
+    fn to_json(&self) -> DatabaseResult<Value> {
+        serde_json::to_value(self).map_err(DatabaseError::SerializationError)
+    }
+
+    fn from_json(value: &Value) -> DatabaseResult<Self> {
+        serde_json::from_value(value.clone()).map_err(DatabaseError::SerializationError)
+    }
+}
+
 impl SyncSerializable for SavedCommand {
     fn to_json(&self) -> DatabaseResult<Value> {
         serde_json::to_value(self).map_err(DatabaseError::SerializationError)
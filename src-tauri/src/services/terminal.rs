@@ -16,15 +16,32 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+#[cfg(unix)]
+use crate::core::session_daemon::{self, DaemonMessage, DaemonRequest};
+#[cfg(unix)]
+use crate::core::terminal::persistent::PersistentTerminal;
 use crate::core::terminal::{TerminalFactory, TerminalWrapper};
 use crate::database::service::DatabaseService;
 use crate::error::AppError;
+use crate::models::recording::RecordingEnv;
+#[cfg(unix)]
 use crate::models::terminal::{
-    CreateTerminalRequest, CreateTerminalResponse, ResizeTerminalRequest, TerminalData,
-    TerminalExited, TerminalInfo, TerminalLatency, TerminalTitleChanged, WriteTerminalRequest,
+    AttachPersistentSessionRequest, LocalConfig, PersistentSessionInfo, TerminalType,
 };
+use crate::models::terminal::{
+    BroadcastGroup, BroadcastMembersRequest, BroadcastWriteFailure, CreateBroadcastGroupRequest,
+    CreateTerminalRequest, CreateTerminalResponse, DumpScrollbackRequest, DumpScrollbackResult,
+    ResizeTerminalRequest, ScrollbackConfig, ScrollbackMatch, ScrollbackStats,
+    SearchScrollbackRequest, SessionLogConfig, TerminalConfig, TerminalData, TerminalExited,
+    TerminalInfo, TerminalLatency, TerminalStateChanged, TerminalTitleChanged,
+    WriteTerminalRequest,
+};
+use crate::services::input_broadcast::InputBroadcast;
 use crate::services::recording::SessionRecorder;
-use crate::services::ssh::SSHKeyService;
+use crate::services::scrollback::ScrollbackBuffer;
+use crate::services::session_log::SessionLogger;
+use crate::services::ssh::{SSHConnectionPool, SSHKeyService};
+use log::warn;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tauri::{AppHandle, Emitter};
@@ -39,14 +56,52 @@ pub struct TerminalManager {
     output_sender: mpsc::UnboundedSender<TerminalData>,
     database_service: Arc<Mutex<DatabaseService>>,
     ssh_key_service: Option<Arc<Mutex<SSHKeyService>>>,
+    connection_pool: Arc<SSHConnectionPool>,
     pub recorders: Arc<RwLock<HashMap<String, Arc<SessionRecorder>>>>,
+    session_loggers: Arc<RwLock<HashMap<String, Arc<SessionLogger>>>>,
     titles: Arc<RwLock<HashMap<String, String>>>,
+    broadcast: Arc<RwLock<InputBroadcast>>,
+    scrollbacks: Arc<RwLock<HashMap<String, Arc<Mutex<ScrollbackBuffer>>>>>,
+    scrollback_config: Arc<RwLock<ScrollbackConfig>>,
+    /// Session logging of terminals opened from ~/.ssh/config hosts, which have no profile
+    ssh_config_session_log: Arc<RwLock<Option<SessionLogConfig>>>,
+}
+
+/// Consumers of a terminal's output besides the frontend, fed chunk by chunk
+struct OutputHooks {
+    terminal_id: String,
+    scrollback: Arc<Mutex<ScrollbackBuffer>>,
+    recorders: Arc<RwLock<HashMap<String, Arc<SessionRecorder>>>>,
+    session_loggers: Arc<RwLock<HashMap<String, Arc<SessionLogger>>>>,
+}
+
+impl OutputHooks {
+    async fn process(&self, data: &[u8]) {
+        self.scrollback.lock().await.push(data);
+
+        // Record output if recording is active (always record raw output)
+        if let Some(recorder) = self.recorders.read().await.get(&self.terminal_id) {
+            let _ = recorder.record_output(data).await;
+        }
+
+        if let Some(logger) = self.session_loggers.read().await.get(&self.terminal_id) {
+            if let Err(e) = logger.log_output(data).await {
+                if logger.mark_failed() {
+                    warn!(
+                        "Failed to write session log of terminal {}, further errors are not reported: {}",
+                        self.terminal_id, e
+                    );
+                }
+            }
+        }
+    }
 }
 
 impl TerminalManager {
     pub fn new_with_ssh_key_service(
         database_service: Arc<Mutex<DatabaseService>>,
         ssh_key_service: Arc<Mutex<SSHKeyService>>,
+        connection_pool: Arc<SSHConnectionPool>,
     ) -> Self {
         let (output_sender, output_receiver) = mpsc::unbounded_channel();
 
@@ -57,8 +112,14 @@ impl TerminalManager {
             output_sender,
             database_service,
             ssh_key_service: Some(ssh_key_service),
+            connection_pool,
             recorders: Arc::new(RwLock::new(HashMap::new())),
+            session_loggers: Arc::new(RwLock::new(HashMap::new())),
             titles: Arc::new(RwLock::new(HashMap::new())),
+            broadcast: Arc::new(RwLock::new(InputBroadcast::new())),
+            scrollbacks: Arc::new(RwLock::new(HashMap::new())),
+            scrollback_config: Arc::new(RwLock::new(ScrollbackConfig::default())),
+            ssh_config_session_log: Arc::new(RwLock::new(None)),
         }
     }
 
@@ -110,6 +171,7 @@ impl TerminalManager {
             terminal_id.clone(),
             config.clone(),
             Some(self.database_service.clone()),
+            self.connection_pool.clone(),
         )
         .await?;
 
@@ -181,10 +243,185 @@ impl TerminalManager {
                 let _ = handle.emit("ssh-connected", &success_event);
             }
         }
+
+        if let Some((log_config, profile_name)) = self.session_log_config(&request.config).await {
+            match SessionLogger::start(log_config, &profile_name, &terminal_id).await {
+                Ok(logger) => {
+                    let mut loggers = self.session_loggers.write().await;
+                    loggers.insert(terminal_id.clone(), Arc::new(logger));
+                }
+                Err(e) => warn!(
+                    "Failed to start session log for terminal {}: {}",
+                    terminal_id, e
+                ),
+            }
+        }
+
+        self.start_terminal(
+            terminal_id,
+            terminal,
+            request.config,
+            request.title,
+            app_handle,
+        )
+        .await
+    }
+
+    /// Session logging settings of the SSH or terminal profile a terminal is created from,
+    /// for ~/.ssh/config hosts the setting shared by all of them
+    async fn session_log_config(
+        &self,
+        config: &TerminalConfig,
+    ) -> Option<(SessionLogConfig, String)> {
+        let (log_config, profile_name) = match config.terminal_type {
+            crate::models::terminal::TerminalType::SSH => {
+                let db_service = self.database_service.lock().await;
+                let profile = db_service
+                    .get_ssh_profile(config.ssh_profile_id.as_deref()?)
+                    .await
+                    .ok()?;
+                (profile.session_log, profile.name)
+            }
+            crate::models::terminal::TerminalType::Local => {
+                let db_service = self.database_service.lock().await;
+                let profile = db_service
+                    .get_terminal_profile(config.terminal_profile_id.as_deref()?)
+                    .await
+                    .ok()?;
+                (profile.session_log, profile.name)
+            }
+            crate::models::terminal::TerminalType::SSHConfig => (
+                self.ssh_config_session_log.read().await.clone(),
+                config.ssh_config_host.as_ref()?.name.clone(),
+            ),
+        };
+
+        log_config
+            .filter(|log_config| log_config.enabled)
+            .map(|log_config| (log_config, profile_name))
+    }
+
+    async fn stop_session_log(&self, terminal_id: &str) {
+        let logger = self.session_loggers.write().await.remove(terminal_id);
+        if let Some(logger) = logger {
+            if let Err(e) = logger.stop().await {
+                warn!(
+                    "Failed to close session log for terminal {}: {}",
+                    terminal_id, e
+                );
+            }
+        }
+    }
+
+    /// Reattach to a shell owned by the session daemon, replaying its recent output
+    #[cfg(unix)]
+    pub async fn attach_persistent_session(
+        &self,
+        request: AttachPersistentSessionRequest,
+        app_handle: Option<AppHandle>,
+    ) -> Result<CreateTerminalResponse, AppError> {
+        if self
+            .terminals
+            .read()
+            .await
+            .contains_key(&request.session_id)
+        {
+            return Err(AppError::validation_error(format!(
+                "Session {} is already attached",
+                request.session_id
+            )));
+        }
+
+        let config = TerminalConfig {
+            terminal_type: TerminalType::Local,
+            local_config: Some(LocalConfig {
+                persistent: true,
+                ..Default::default()
+            }),
+            ssh_profile_id: None,
+            terminal_profile_id: None,
+            ssh_config_host: None,
+            ssh_config_password: None,
+            reconnect_policy: None,
+        };
+
+        let mut terminal = TerminalWrapper::Persistent(Box::new(PersistentTerminal::reattach(
+            request.session_id.clone(),
+            config.clone(),
+        )));
+        terminal.connect().await?;
+
+        self.start_terminal(
+            request.session_id,
+            terminal,
+            config,
+            request.title,
+            app_handle,
+        )
+        .await
+    }
+
+    /// List the shells owned by the session daemon
+    #[cfg(unix)]
+    pub async fn list_persistent_sessions(&self) -> Result<Vec<PersistentSessionInfo>, AppError> {
+        let (reader, mut writer) = session_daemon::connect().await?.into_split();
+        let mut reader = tokio::io::BufReader::new(reader);
+        session_daemon::send_message(&mut writer, &DaemonRequest::List).await?;
+
+        match session_daemon::read_message(&mut reader).await? {
+            Some(DaemonMessage::Sessions { sessions }) => Ok(sessions),
+            Some(DaemonMessage::Error { message }) => Err(AppError::terminal_error(message)),
+            _ => Err(AppError::terminal_error(
+                "Unexpected response from session daemon".to_string(),
+            )),
+        }
+    }
+
+    /// Kill a shell owned by the session daemon, closing its terminal if attached here
+    #[cfg(unix)]
+    pub async fn kill_persistent_session(&self, session_id: String) -> Result<(), AppError> {
+        if self.terminals.read().await.contains_key(&session_id) {
+            return self.close_terminal(session_id).await;
+        }
+
+        let mut stream = session_daemon::connect().await?;
+        session_daemon::send_message(&mut stream, &DaemonRequest::Kill { session_id }).await
+    }
+
+    /// Close a persistent terminal without killing its shell, so it can be reattached later
+    pub async fn detach_terminal(&self, terminal_id: String) -> Result<(), AppError> {
+        let terminal = {
+            let terminals = self.terminals.read().await;
+            terminals
+                .get(&terminal_id)
+                .cloned()
+                .ok_or_else(|| AppError::TerminalNotFound(terminal_id.clone()))?
+        };
+        terminal.lock().await.detach().await?;
+
+        self.terminals.write().await.remove(&terminal_id);
+        self.output_senders.write().await.remove(&terminal_id);
+        self.titles.write().await.remove(&terminal_id);
+        self.broadcast.write().await.remove_terminal(&terminal_id);
+        self.scrollbacks.write().await.remove(&terminal_id);
+        self.stop_session_log(&terminal_id).await;
+        Ok(())
+    }
+
+    /// Start forwarding a connected terminal's output and events, then register it
+    async fn start_terminal(
+        &self,
+        terminal_id: String,
+        mut terminal: TerminalWrapper,
+        config: TerminalConfig,
+        title: Option<String>,
+        app_handle: Option<AppHandle>,
+    ) -> Result<CreateTerminalResponse, AppError> {
         let (tx, mut rx) = mpsc::unbounded_channel::<Vec<u8>>();
         let (title_tx, mut title_rx) = mpsc::unbounded_channel::<String>();
         let (exit_tx, mut exit_rx) = mpsc::unbounded_channel::<TerminalExited>();
         let (latency_tx, mut latency_rx) = mpsc::unbounded_channel::<TerminalLatency>();
+        let (state_tx, mut state_rx) = mpsc::unbounded_channel::<TerminalStateChanged>();
 
         {
             let mut senders = self.output_senders.write().await;
@@ -192,13 +429,32 @@ impl TerminalManager {
         }
 
         terminal
-            .start_read_loop(tx, Some(title_tx), Some(exit_tx), Some(latency_tx))
+            .start_read_loop(
+                tx,
+                Some(title_tx),
+                Some(exit_tx),
+                Some(latency_tx),
+                Some(state_tx),
+            )
             .await?;
 
+        let scrollback = Arc::new(Mutex::new(ScrollbackBuffer::new(
+            self.scrollback_config.read().await.clone(),
+        )));
+        {
+            let mut scrollbacks = self.scrollbacks.write().await;
+            scrollbacks.insert(terminal_id.clone(), scrollback.clone());
+        }
+
         let terminal_id_clone = terminal_id.clone();
         let output_sender = self.output_sender.clone();
         let app_handle_clone = app_handle.clone();
-        let recorders_clone = self.recorders.clone();
+        let hooks = OutputHooks {
+            terminal_id: terminal_id.clone(),
+            scrollback,
+            recorders: self.recorders.clone(),
+            session_loggers: self.session_loggers.clone(),
+        };
 
         tokio::spawn(async move {
             while let Some(data) = rx.recv().await {
@@ -207,10 +463,7 @@ impl TerminalManager {
                     data: data.clone(),
                 };
 
-                // Record output if recording is active (always record raw output)
-                if let Some(recorder) = recorders_clone.read().await.get(&terminal_id_clone) {
-                    let _ = recorder.record_output(&data).await;
-                }
+                hooks.process(&data).await;
 
                 if let Some(handle) = &app_handle_clone {
                     let _ = handle.emit("terminal-output", &terminal_data);
@@ -225,7 +478,7 @@ impl TerminalManager {
         let terminal_id_clone = terminal_id.clone();
         let app_handle_clone = app_handle.clone();
         let titles_clone = self.titles.clone();
-        let should_lock_title = request.config.terminal_profile_id.is_some();
+        let should_lock_title = config.terminal_profile_id.is_some();
 
         tokio::spawn(async move {
             while let Some(new_title) = title_rx.recv().await {
@@ -266,12 +519,21 @@ impl TerminalManager {
                 }
             }
         });
+
+        let app_handle_clone = app_handle.clone();
+        tokio::spawn(async move {
+            while let Some(state_event) = state_rx.recv().await {
+                if let Some(handle) = &app_handle_clone {
+                    let _ = handle.emit("terminal-state-changed", &state_event);
+                }
+            }
+        });
         let terminal_info = TerminalInfo {
             id: terminal_id.clone(),
-            config: request.config,
+            config,
             state: terminal.get_state(),
             created_at: chrono::Utc::now(),
-            title: request.title,
+            title,
         };
 
         {
@@ -290,16 +552,57 @@ impl TerminalManager {
         })
     }
 
-    pub async fn write_to_terminal(&self, request: WriteTerminalRequest) -> Result<(), AppError> {
-        let terminals = self.terminals.read().await;
+    /// Write to a terminal and mirror the input to its broadcast group members. Failed
+    /// mirrored writes are returned rather than aborting the others.
+    pub async fn write_to_terminal(
+        &self,
+        request: WriteTerminalRequest,
+    ) -> Result<Vec<BroadcastWriteFailure>, AppError> {
+        let (terminal, mirrors) = {
+            let terminals = self.terminals.read().await;
+            let terminal = terminals
+                .get(&request.terminal_id)
+                .cloned()
+                .ok_or_else(|| AppError::TerminalNotFound(request.terminal_id.clone()))?;
+            let mirrors: Vec<_> = self
+                .broadcast
+                .read()
+                .await
+                .mirror_targets(&request.terminal_id)
+                .into_iter()
+                .map(|terminal_id| {
+                    let terminal = terminals.get(&terminal_id).cloned();
+                    (terminal_id, terminal)
+                })
+                .collect();
+            (terminal, mirrors)
+        };
 
-        if let Some(terminal) = terminals.get(&request.terminal_id) {
-            let mut terminal_guard = terminal.lock().await;
-            terminal_guard.write(request.data.as_bytes()).await?;
-            Ok(())
-        } else {
-            Err(AppError::TerminalNotFound(request.terminal_id))
-        }
+        let data = request.data.as_bytes();
+        let secret = Self::write_locked(&terminal, data).await?;
+        self.record_input(&request.terminal_id, data, secret).await;
+
+        let writes = mirrors
+            .into_iter()
+            .map(|(terminal_id, terminal)| async move {
+                let result = match terminal {
+                    Some(terminal) => Self::write_locked(&terminal, data).await,
+                    None => Err(AppError::TerminalNotFound(terminal_id.clone())),
+                };
+                if let Ok(secret) = result {
+                    self.record_input(&terminal_id, data, secret).await;
+                }
+                result.err().map(|e| BroadcastWriteFailure {
+                    terminal_id,
+                    error: e.to_string(),
+                })
+            });
+
+        Ok(futures::future::join_all(writes)
+            .await
+            .into_iter()
+            .flatten()
+            .collect())
     }
 
     pub async fn resize_terminal(&self, request: ResizeTerminalRequest) -> Result<(), AppError> {
@@ -308,12 +611,60 @@ impl TerminalManager {
         if let Some(terminal) = terminals.get(&request.terminal_id) {
             let mut terminal_guard = terminal.lock().await;
             terminal_guard.resize(request.cols, request.rows).await?;
+
+            let recorder = self
+                .recorders
+                .read()
+                .await
+                .get(&request.terminal_id)
+                .cloned();
+            if let Some(recorder) = recorder {
+                if let Err(e) = recorder.record_resize(request.cols, request.rows).await {
+                    warn!("Failed to record resize of {}: {}", request.terminal_id, e);
+                }
+            }
             Ok(())
         } else {
             Err(AppError::TerminalNotFound(request.terminal_id))
         }
     }
 
+    /// Write to a terminal, returning whether it was reading a secret when the input
+    /// arrived
+    async fn write_locked(
+        terminal: &Arc<Mutex<TerminalWrapper>>,
+        data: &[u8],
+    ) -> Result<Option<bool>, AppError> {
+        let mut terminal = terminal.lock().await;
+        let secret = terminal.reads_secret();
+        terminal.write(data).await?;
+        Ok(secret)
+    }
+
+    /// Add typed input to the terminal's recording, if it records input
+    async fn record_input(&self, terminal_id: &str, data: &[u8], secret: Option<bool>) {
+        let recorder = self.recorders.read().await.get(terminal_id).cloned();
+        if let Some(recorder) = recorder {
+            if let Err(e) = recorder.record_input(data, secret).await {
+                warn!("Failed to record input of {}: {}", terminal_id, e);
+            }
+        }
+    }
+
+    /// TERM and SHELL of the process behind a terminal, for its recording header
+    pub async fn recording_env(&self, terminal_id: &str) -> Result<RecordingEnv, AppError> {
+        let terminal = self
+            .terminals
+            .read()
+            .await
+            .get(terminal_id)
+            .cloned()
+            .ok_or_else(|| AppError::TerminalNotFound(terminal_id.to_string()))?;
+        // The remote shell lookup must not keep the terminal locked against writes
+        let lookup = terminal.lock().await.recording_env();
+        Ok(lookup.await)
+    }
+
     pub async fn close_terminal(&self, terminal_id: String) -> Result<(), AppError> {
         let terminal = {
             let mut terminals = self.terminals.write().await;
@@ -330,6 +681,10 @@ impl TerminalManager {
             titles.remove(&terminal_id);
         }
 
+        self.broadcast.write().await.remove_terminal(&terminal_id);
+        self.scrollbacks.write().await.remove(&terminal_id);
+        self.stop_session_log(&terminal_id).await;
+
         if let Some(terminal) = terminal {
             let mut terminal_guard = terminal.lock().await;
             terminal_guard.disconnect().await?;
@@ -378,4 +733,225 @@ impl TerminalManager {
 
         Ok(terminal_infos)
     }
+
+    async fn scrollback(
+        &self,
+        terminal_id: &str,
+    ) -> Result<Arc<Mutex<ScrollbackBuffer>>, AppError> {
+        self.scrollbacks
+            .read()
+            .await
+            .get(terminal_id)
+            .cloned()
+            .ok_or_else(|| AppError::TerminalNotFound(terminal_id.to_string()))
+    }
+
+    /// Search a terminal's ANSI-stripped scrollback by regex
+    pub async fn search_scrollback(
+        &self,
+        request: SearchScrollbackRequest,
+    ) -> Result<Vec<ScrollbackMatch>, AppError> {
+        let regex = regex::RegexBuilder::new(&request.pattern)
+            .case_insensitive(request.case_insensitive)
+            .build()
+            .map_err(|e| AppError::validation_error(format!("Invalid search pattern: {}", e)))?;
+
+        let scrollback = self.scrollback(&request.terminal_id).await?;
+        let snapshot = scrollback.lock().await.snapshot();
+
+        // Matching a large buffer takes a while, keep it off the async runtime
+        let context_lines = request.context_lines;
+        let max_results = request.max_results.unwrap_or(1000);
+        tokio::task::spawn_blocking(move || snapshot.search(&regex, context_lines, max_results))
+            .await
+            .map_err(|e| AppError::internal_error(e.to_string()))
+    }
+
+    /// Write a range of a terminal's scrollback to a file
+    pub async fn dump_scrollback(
+        &self,
+        request: DumpScrollbackRequest,
+    ) -> Result<DumpScrollbackResult, AppError> {
+        let scrollback = self.scrollback(&request.terminal_id).await?;
+        let lines = scrollback
+            .lock()
+            .await
+            .range(request.start_line, request.end_line)?;
+
+        let mut content = lines.join("\n");
+        if !content.is_empty() {
+            content.push('\n');
+        }
+        tokio::fs::write(&request.path, &content).await?;
+
+        Ok(DumpScrollbackResult {
+            path: request.path,
+            line_count: lines.len(),
+            bytes: content.len(),
+        })
+    }
+
+    pub async fn get_scrollback_stats(
+        &self,
+        terminal_id: String,
+    ) -> Result<ScrollbackStats, AppError> {
+        let scrollback = self.scrollback(&terminal_id).await?;
+        let scrollback = scrollback.lock().await;
+        Ok(ScrollbackStats {
+            first_line: scrollback.first_line(),
+            line_count: scrollback.line_count(),
+            bytes: scrollback.bytes(),
+            terminal_id,
+        })
+    }
+
+    pub async fn get_scrollback_config(&self) -> ScrollbackConfig {
+        self.scrollback_config.read().await.clone()
+    }
+
+    /// Change the scrollback limits of new and open terminals
+    pub async fn set_scrollback_config(&self, config: ScrollbackConfig) -> Result<(), AppError> {
+        if config.max_lines == 0 || config.max_bytes == 0 {
+            return Err(AppError::validation_error(
+                "Scrollback limits must be greater than zero",
+            ));
+        }
+
+        *self.scrollback_config.write().await = config.clone();
+        for scrollback in self.scrollbacks.read().await.values() {
+            scrollback.lock().await.set_config(config.clone());
+        }
+        Ok(())
+    }
+
+    /// Session logging of terminals opened from ~/.ssh/config hosts
+    pub async fn get_ssh_config_session_log(&self) -> Option<SessionLogConfig> {
+        self.ssh_config_session_log.read().await.clone()
+    }
+
+    /// Set the session logging of terminals opened from ~/.ssh/config hosts from now on
+    pub async fn set_ssh_config_session_log(&self, config: Option<SessionLogConfig>) {
+        *self.ssh_config_session_log.write().await = config;
+    }
+
+    pub async fn create_broadcast_group(
+        &self,
+        request: CreateBroadcastGroupRequest,
+    ) -> Result<BroadcastGroup, AppError> {
+        self.ensure_terminals_exist(&request.terminal_ids).await?;
+        self.broadcast
+            .write()
+            .await
+            .create_group(&request.name, &request.terminal_ids)
+    }
+
+    pub async fn delete_broadcast_group(&self, name: &str) -> Result<(), AppError> {
+        self.broadcast.write().await.delete_group(name)
+    }
+
+    pub async fn add_broadcast_members(
+        &self,
+        request: BroadcastMembersRequest,
+    ) -> Result<BroadcastGroup, AppError> {
+        self.ensure_terminals_exist(&request.terminal_ids).await?;
+        self.broadcast
+            .write()
+            .await
+            .add_members(&request.name, &request.terminal_ids)
+    }
+
+    pub async fn remove_broadcast_members(
+        &self,
+        request: BroadcastMembersRequest,
+    ) -> Result<BroadcastGroup, AppError> {
+        self.broadcast
+            .write()
+            .await
+            .remove_members(&request.name, &request.terminal_ids)
+    }
+
+    pub async fn set_broadcast_member_paused(
+        &self,
+        name: &str,
+        terminal_id: &str,
+        paused: bool,
+    ) -> Result<BroadcastGroup, AppError> {
+        self.broadcast
+            .write()
+            .await
+            .set_paused(name, terminal_id, paused)
+    }
+
+    pub async fn list_broadcast_groups(&self) -> Vec<BroadcastGroup> {
+        self.broadcast.read().await.groups()
+    }
+
+    async fn ensure_terminals_exist(&self, terminal_ids: &[String]) -> Result<(), AppError> {
+        let terminals = self.terminals.read().await;
+        match terminal_ids.iter().find(|id| !terminals.contains_key(*id)) {
+            Some(missing) => Err(AppError::TerminalNotFound(missing.clone())),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hooks(terminal_id: &str) -> OutputHooks {
+        OutputHooks {
+            terminal_id: terminal_id.to_string(),
+            scrollback: Arc::new(Mutex::new(ScrollbackBuffer::new(
+                ScrollbackConfig::default(),
+            ))),
+            recorders: Arc::new(RwLock::new(HashMap::new())),
+            session_loggers: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_output_hooks_feed_scrollback() {
+        let hooks = hooks("t1");
+        hooks.process(b"$ make\r\n\x1b[31merr").await;
+        hooks.process(b"or: boom\x1b[0m\r\n$ ").await;
+
+        let scrollback = hooks.scrollback.lock().await;
+        assert_eq!(scrollback.line_count(), 3);
+        let matches = scrollback.search(&regex::Regex::new("^error").unwrap(), 0, 10);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].line, "error: boom");
+    }
+
+    #[tokio::test]
+    async fn test_output_hooks_write_session_log() {
+        let dir = std::env::temp_dir().join(format!("kerminal-log-{}", Uuid::new_v4()));
+        let log_config = SessionLogConfig {
+            enabled: true,
+            path_template: dir.join("{profile}.log").to_string_lossy().into_owned(),
+            raw: false,
+            timestamps: false,
+            rotation: Default::default(),
+        };
+        let logger = SessionLogger::start(log_config, "web", "t1").await.unwrap();
+
+        let hooks = hooks("t1");
+        hooks
+            .session_loggers
+            .write()
+            .await
+            .insert("t1".to_string(), Arc::new(logger));
+        hooks.process(b"\x1b[1mhello\x1b[0m\r\nwor").await;
+        hooks.process(b"ld").await;
+
+        let logger = hooks.session_loggers.write().await.remove("t1").unwrap();
+        logger.stop().await.unwrap();
+        let log = std::fs::read_to_string(dir.join("web.log")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(log, "hello\nworld\n");
+
+        // Write errors are only reported for the first failure
+        assert!(logger.mark_failed());
+        assert!(!logger.mark_failed());
+    }
 }
@@ -17,67 +17,120 @@
  */
 
 use anyhow::Result;
-use async_trait::async_trait;
-use log::{error, info};
-use russh::client::{Config, Handle};
-use russh_keys::key;
+use log::{error, info, warn};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::future::Future;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpListener;
 use tokio::sync::{Mutex, RwLock};
 use tokio_util::sync::CancellationToken;
 
 use crate::database::{error::DatabaseResult, service::DatabaseService};
+use crate::models::ssh::tunnel::ip_literal;
 use crate::models::ssh::{
-    AuthData, CreateSSHTunnelRequest, SSHProfile, SSHTunnel, TunnelStatus, TunnelType,
+    CreateSSHTunnelRequest, RestartMode, SSHProfile, SSHTunnel, TunnelAccessControl,
+    TunnelEndpoint, TunnelFailure, TunnelHealthCheck, TunnelHealthProbe, TunnelMetrics,
+    TunnelRestartPolicy, TunnelSocksAuth, TunnelStatus, TunnelStatusInfo, TunnelType,
     TunnelWithStatus, UpdateSSHTunnelRequest,
 };
+use crate::services::ssh::connection_pool::{PooledHandle, PooledSession};
+use crate::services::ssh::exec::{run_command, run_with_timeout, OutputSink};
+use crate::services::ssh::SSHConnectionPool;
+use crate::services::tunnel_http::{self, HttpProxyError};
+use crate::services::tunnel_socks::{self, SocksReply};
+use crate::services::tunnel_traffic::{ConnectionGuard, TunnelTraffic};
+
+/// How often a running tunnel checks whether its SSH connection was lost
+const CONNECTION_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A run at least this long resets the restart backoff
+const STABLE_RUN: Duration = Duration::from_secs(60);
+
+/// How often metrics of the running tunnels are emitted to the UI
+const METRICS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long a SOCKS or HTTP proxy client may take to send its request
+const PROXY_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long resolving a proxy destination for its access control check may take
+const DNS_LOOKUP_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// SSH Tunnel service for managing port forwarding and SOCKS proxy
 #[derive(Clone)]
 pub struct TunnelService {
     database_service: Arc<Mutex<DatabaseService>>,
     active_tunnels: Arc<RwLock<HashMap<String, TunnelHandle>>>,
-    ssh_sessions: Arc<RwLock<HashMap<String, Arc<Mutex<Handle<SSHClientHandler>>>>>>,
+    connection_pool: Arc<SSHConnectionPool>,
 }
 
 /// Handle for an active tunnel
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct TunnelHandle {
     cancel_token: CancellationToken,
     status: Arc<RwLock<TunnelStatus>>,
     error_message: Arc<RwLock<Option<String>>>,
+    restart_count: Arc<AtomicU32>,
+    last_failure: Arc<RwLock<Option<TunnelFailure>>>,
+    traffic: Arc<TunnelTraffic>,
 }
 
-/// SSH Client Handler for russh
-#[derive(Clone)]
-pub struct SSHClientHandler;
-
-#[async_trait]
-impl russh::client::Handler for SSHClientHandler {
-    type Error = russh::Error;
-
-    async fn check_server_key(
-        &mut self,
-        _server_public_key: &key::PublicKey,
-    ) -> Result<bool, Self::Error> {
-        Ok(true)
+impl TunnelHandle {
+    fn new() -> Self {
+        Self {
+            cancel_token: CancellationToken::new(),
+            status: Arc::new(RwLock::new(TunnelStatus::Starting)),
+            error_message: Arc::new(RwLock::new(None)),
+            restart_count: Arc::new(AtomicU32::new(0)),
+            last_failure: Arc::new(RwLock::new(None)),
+            traffic: Arc::new(TunnelTraffic::new()),
+        }
     }
 }
 
+/// Backoff before restarting a tunnel that ended, or `None` when its policy doesn't
+/// restart it. `failures_in_row` counts this run.
+fn restart_delay(
+    policy: &TunnelRestartPolicy,
+    failed: bool,
+    failures_in_row: u32,
+) -> Option<Duration> {
+    let restart = match policy.mode {
+        RestartMode::Never => false,
+        RestartMode::OnFailure => {
+            failed
+                && policy
+                    .max_restarts
+                    .is_none_or(|max_restarts| failures_in_row <= max_restarts)
+        }
+        RestartMode::Always => true,
+    };
+    restart.then(|| policy.delay(failures_in_row))
+}
+
 impl TunnelService {
     /// Create new TunnelService instance
-    pub fn new(database_service: Arc<Mutex<DatabaseService>>) -> Self {
+    pub fn new(
+        database_service: Arc<Mutex<DatabaseService>>,
+        connection_pool: Arc<SSHConnectionPool>,
+    ) -> Self {
         Self {
             database_service,
             active_tunnels: Arc::new(RwLock::new(HashMap::new())),
-            ssh_sessions: Arc::new(RwLock::new(HashMap::new())),
+            connection_pool,
         }
     }
 
     /// Create new TunnelService instance and start auto-start tunnels
-    pub async fn new_with_auto_start(database_service: Arc<Mutex<DatabaseService>>) -> Self {
-        let service = Self::new(database_service);
+    pub async fn new_with_auto_start(
+        database_service: Arc<Mutex<DatabaseService>>,
+        connection_pool: Arc<SSHConnectionPool>,
+    ) -> Self {
+        let service = Self::new(database_service, connection_pool);
 
         let service_clone = service.clone();
         tokio::spawn(async move {
@@ -149,10 +202,23 @@ impl TunnelService {
             }
         };
 
+        let (restart_count, last_failure) = {
+            let active_tunnels = self.active_tunnels.read().await;
+            match active_tunnels.get(id) {
+                Some(handle) => (
+                    handle.restart_count.load(Ordering::Relaxed),
+                    handle.last_failure.read().await.clone(),
+                ),
+                None => (0, None),
+            }
+        };
+
         Ok(TunnelWithStatus {
             tunnel,
             status,
             error_message,
+            restart_count,
+            last_failure,
         })
     }
 
@@ -184,7 +250,7 @@ impl TunnelService {
             if let Some(handle) = active_tunnels.get(&tunnel_id) {
                 let status = handle.status.read().await;
                 match *status {
-                    TunnelStatus::Running | TunnelStatus::Starting => {
+                    TunnelStatus::Running | TunnelStatus::Starting | TunnelStatus::Reconnecting => {
                         return Err("Tunnel is already running".to_string());
                     }
                     TunnelStatus::Error | TunnelStatus::Stopped => {
@@ -195,10 +261,12 @@ impl TunnelService {
             }
         }
 
-        // Remove existing handle if in error/stopped state
+        // Remove existing handle if in error/stopped state, stopping a pending restart
         {
             let mut active_tunnels = self.active_tunnels.write().await;
-            active_tunnels.remove(&tunnel_id);
+            if let Some(handle) = active_tunnels.remove(&tunnel_id) {
+                handle.cancel_token.cancel();
+            }
         }
 
         let tunnel = {
@@ -217,38 +285,21 @@ impl TunnelService {
                 .map_err(|e| format!("Failed to get SSH profile: {}", e))?
         };
 
-        let cancel_token = CancellationToken::new();
-        let status = Arc::new(RwLock::new(TunnelStatus::Starting));
-        let error_message = Arc::new(RwLock::new(None));
-
-        let handle = TunnelHandle {
-            cancel_token: cancel_token.clone(),
-            status: status.clone(),
-            error_message: error_message.clone(),
-        };
+        let handle = TunnelHandle::new();
 
         {
             let mut active_tunnels = self.active_tunnels.write().await;
-            active_tunnels.insert(tunnel_id.clone(), handle);
+            active_tunnels.insert(tunnel_id.clone(), handle.clone());
         }
 
-        let tunnel_clone = tunnel.clone();
-        let profile_clone = profile.clone();
-        let sessions_arc = self.ssh_sessions.clone();
+        let connection_pool = self.connection_pool.clone();
         let tunnel_id_clone = tunnel_id.clone();
         let active_tunnels_arc = self.active_tunnels.clone();
+        let status = handle.status.clone();
+        let error_message = handle.error_message.clone();
 
         tokio::spawn(async move {
-            match Self::run_tunnel(
-                tunnel_clone,
-                profile_clone,
-                cancel_token,
-                status.clone(),
-                error_message.clone(),
-                sessions_arc,
-            )
-            .await
-            {
+            match Self::supervise_tunnel(tunnel, profile, handle, connection_pool).await {
                 Err(e) => {
                     error!("Tunnel {} failed: {}", tunnel_id_clone, e);
 
@@ -299,18 +350,86 @@ impl TunnelService {
         }
     }
 
-    /// Get tunnel status
-    pub async fn get_tunnel_status(&self, tunnel_id: String) -> Result<TunnelStatus, String> {
+    /// Get tunnel status with its restart count and last failure
+    pub async fn get_tunnel_status(&self, tunnel_id: String) -> Result<TunnelStatusInfo, String> {
         let active_tunnels = self.active_tunnels.read().await;
 
         if let Some(handle) = active_tunnels.get(&tunnel_id) {
-            let status = handle.status.read().await;
-            Ok(status.clone())
+            Ok(TunnelStatusInfo {
+                status: handle.status.read().await.clone(),
+                error_message: handle.error_message.read().await.clone(),
+                restart_count: handle.restart_count.load(Ordering::Relaxed),
+                last_failure: handle.last_failure.read().await.clone(),
+            })
         } else {
-            Ok(TunnelStatus::Stopped)
+            Ok(TunnelStatusInfo {
+                status: TunnelStatus::Stopped,
+                error_message: None,
+                restart_count: 0,
+                last_failure: None,
+            })
         }
     }
 
+    /// Traffic counters and active client connections of a running tunnel
+    pub async fn get_tunnel_metrics(&self, tunnel_id: String) -> Result<TunnelMetrics, String> {
+        let active_tunnels = self.active_tunnels.read().await;
+        active_tunnels
+            .get(&tunnel_id)
+            .map(|handle| handle.traffic.snapshot(&tunnel_id))
+            .ok_or_else(|| "Tunnel is not running".to_string())
+    }
+
+    /// Close one client connection of a running tunnel
+    pub async fn close_tunnel_connection(
+        &self,
+        tunnel_id: String,
+        connection_id: String,
+    ) -> Result<(), String> {
+        let active_tunnels = self.active_tunnels.read().await;
+        let handle = active_tunnels
+            .get(&tunnel_id)
+            .ok_or_else(|| "Tunnel is not running".to_string())?;
+        if handle.traffic.close_connection(&connection_id) {
+            Ok(())
+        } else {
+            Err(format!("Connection {} not found", connection_id))
+        }
+    }
+
+    /// Periodically emit `tunnel_metrics` with the metrics of every running tunnel,
+    /// until the service is dropped
+    pub fn start_metrics_events(&self, app_handle: AppHandle) {
+        let active_tunnels = Arc::downgrade(&self.active_tunnels);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(METRICS_INTERVAL);
+            let mut was_empty = true;
+            loop {
+                interval.tick().await;
+                let Some(active_tunnels) = Weak::upgrade(&active_tunnels) else {
+                    break;
+                };
+
+                let metrics: Vec<TunnelMetrics> = active_tunnels
+                    .read()
+                    .await
+                    .iter()
+                    .map(|(tunnel_id, handle)| handle.traffic.snapshot(tunnel_id))
+                    .collect();
+
+                // One empty update lets the UI clear the last stopped tunnel
+                if metrics.is_empty() && was_empty {
+                    continue;
+                }
+                was_empty = metrics.is_empty();
+
+                if let Err(e) = app_handle.emit("tunnel_metrics", &metrics) {
+                    warn!("Failed to emit tunnel metrics: {}", e);
+                }
+            }
+        });
+    }
+
     /// Start auto-start tunnels on application launch
     pub async fn start_auto_start_tunnels(&self) -> Result<(), String> {
         let tunnels = {
@@ -330,6 +449,90 @@ impl TunnelService {
         Ok(())
     }
 
+    /// Run the tunnel, restarting it according to its restart policy until it is
+    /// stopped or gives up
+    async fn supervise_tunnel(
+        tunnel: SSHTunnel,
+        profile: SSHProfile,
+        handle: TunnelHandle,
+        connection_pool: Arc<SSHConnectionPool>,
+    ) -> Result<()> {
+        Self::supervise(
+            &tunnel.name,
+            &tunnel.restart_policy,
+            &handle,
+            |cancel_token| {
+                Self::run_tunnel(
+                    tunnel.clone(),
+                    profile.clone(),
+                    cancel_token,
+                    handle.status.clone(),
+                    handle.error_message.clone(),
+                    connection_pool.clone(),
+                    handle.traffic.clone(),
+                )
+            },
+        )
+        .await
+    }
+
+    /// Call `run` with a child token of the handle until the policy stops restarting
+    /// it or the handle is cancelled
+    async fn supervise<F, Fut>(
+        name: &str,
+        policy: &TunnelRestartPolicy,
+        handle: &TunnelHandle,
+        mut run: F,
+    ) -> Result<()>
+    where
+        F: FnMut(CancellationToken) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let mut failures_in_row = 0u32;
+
+        loop {
+            let started = std::time::Instant::now();
+            let result = run(handle.cancel_token.child_token()).await;
+
+            if handle.cancel_token.is_cancelled() {
+                return Ok(());
+            }
+
+            let reason = match &result {
+                Err(e) => e.to_string(),
+                Ok(()) => "Tunnel stopped".to_string(),
+            };
+            if result.is_err() || policy.mode == RestartMode::Always {
+                *handle.last_failure.write().await = Some(TunnelFailure {
+                    reason: reason.clone(),
+                    failed_at: chrono::Utc::now(),
+                });
+            }
+
+            if started.elapsed() >= STABLE_RUN {
+                failures_in_row = 0;
+            }
+            failures_in_row += 1;
+
+            let Some(delay) = restart_delay(policy, result.is_err(), failures_in_row) else {
+                return result;
+            };
+            warn!(
+                "Tunnel {} stopped ({}), restarting in {:?}",
+                name, reason, delay
+            );
+            *handle.error_message.write().await = Some(reason);
+            *handle.status.write().await = TunnelStatus::Reconnecting;
+            handle.restart_count.fetch_add(1, Ordering::Relaxed);
+
+            tokio::select! {
+                _ = handle.cancel_token.cancelled() => return Ok(()),
+                _ = tokio::time::sleep(delay) => {}
+            }
+            *handle.status.write().await = TunnelStatus::Starting;
+        }
+    }
+
     /// Run tunnel implementation
     async fn run_tunnel(
         tunnel: SSHTunnel,
@@ -337,55 +540,102 @@ impl TunnelService {
         cancel_token: CancellationToken,
         status: Arc<RwLock<TunnelStatus>>,
         error_message: Arc<RwLock<Option<String>>>,
-        sessions: Arc<RwLock<HashMap<String, Arc<Mutex<Handle<SSHClientHandler>>>>>>,
+        connection_pool: Arc<SSHConnectionPool>,
+        traffic: Arc<TunnelTraffic>,
     ) -> Result<()> {
-        let session = match Self::get_or_create_ssh_session(&profile, sessions).await {
-            Ok(s) => s,
+        // The lease keeps the pooled connection open for as long as the tunnel runs
+        let connection = match connection_pool.acquire(&profile, None).await {
+            Ok(connection) => connection,
             Err(e) => {
                 let mut error_msg = error_message.write().await;
                 *error_msg = Some(format!("Failed to create SSH session: {}", e));
                 let mut status_guard = status.write().await;
                 *status_guard = TunnelStatus::Error;
-                return Err(e);
+                return Err(anyhow::anyhow!(e.to_string()));
             }
         };
+        let session = connection.handle();
 
         {
             let mut status_guard = status.write().await;
             *status_guard = TunnelStatus::Running;
         }
+        {
+            let mut error_msg = error_message.write().await;
+            *error_msg = None;
+        }
 
-        let result = match &tunnel.tunnel_type {
-            TunnelType::Local => {
-                Self::start_local_forward(
-                    tunnel.local_host.clone(),
-                    tunnel.local_port,
-                    tunnel.remote_host.clone().unwrap_or_default(),
-                    tunnel.remote_port.unwrap_or(22),
-                    session,
-                    cancel_token,
-                )
-                .await
-            }
-            TunnelType::Remote => {
-                Self::start_remote_forward(
-                    tunnel.local_host.clone(),
-                    tunnel.local_port,
-                    tunnel.remote_host.clone().unwrap_or_default(),
-                    tunnel.remote_port.unwrap_or(22),
-                    session,
-                    cancel_token,
-                )
-                .await
+        let forward_token = cancel_token.clone();
+        let forward = async {
+            match &tunnel.tunnel_type {
+                TunnelType::Local => {
+                    Self::start_local_forward(
+                        tunnel.local_endpoint(),
+                        tunnel.remote_endpoint(),
+                        session,
+                        forward_token,
+                        traffic.clone(),
+                    )
+                    .await
+                }
+                TunnelType::Remote if tunnel.remote_socket_path.is_some() => {
+                    Self::start_remote_socket_forward(
+                        &connection,
+                        tunnel.remote_socket_path.clone().unwrap_or_default(),
+                        tunnel.local_endpoint(),
+                        forward_token,
+                        traffic.clone(),
+                    )
+                    .await
+                }
+                TunnelType::Remote => {
+                    Self::start_remote_forward(
+                        &connection,
+                        tunnel.remote_host.clone().unwrap_or_default(),
+                        tunnel.remote_port.unwrap_or(22),
+                        tunnel.local_endpoint(),
+                        forward_token,
+                        traffic.clone(),
+                    )
+                    .await
+                }
+                TunnelType::Dynamic => {
+                    Self::start_dynamic_forward(
+                        tunnel.local_host.clone(),
+                        tunnel.local_port,
+                        session,
+                        forward_token,
+                        traffic.clone(),
+                        tunnel.socks_auth.clone(),
+                        tunnel.access_control.clone(),
+                    )
+                    .await
+                }
+                TunnelType::HttpProxy => {
+                    Self::start_http_proxy_forward(
+                        tunnel.local_host.clone(),
+                        tunnel.local_port,
+                        session,
+                        forward_token,
+                        traffic.clone(),
+                        tunnel.access_control.clone(),
+                    )
+                    .await
+                }
             }
-            TunnelType::Dynamic => {
-                Self::start_dynamic_forward(
-                    tunnel.local_host.clone(),
-                    tunnel.local_port,
-                    session,
-                    cancel_token,
-                )
-                .await
+        };
+
+        tokio::pin!(forward);
+        let result = tokio::select! {
+            result = &mut forward => result,
+            reason = Self::monitor_health(&tunnel, &connection) => {
+                // Stops the connections still being proxied, and lets the forward cancel
+                // its listener on the server before the connection is released
+                cancel_token.cancel();
+                if let Err(e) = forward.await {
+                    warn!("Tunnel {} did not stop cleanly: {}", tunnel.name, e);
+                }
+                Err(anyhow::anyhow!(reason))
             }
         };
 
@@ -405,74 +655,179 @@ impl TunnelService {
         Ok(())
     }
 
-    /// Get or create SSH session for a profile
-    async fn get_or_create_ssh_session(
-        profile: &SSHProfile,
-        sessions: Arc<RwLock<HashMap<String, Arc<Mutex<Handle<SSHClientHandler>>>>>>,
-    ) -> Result<Arc<Mutex<Handle<SSHClientHandler>>>> {
-        let session_key = format!("{}:{}@{}", profile.username, profile.port, profile.host);
+    /// Wait until the tunnel's connection is lost or its health probe fails too often
+    /// in a row, returning why
+    async fn monitor_health(tunnel: &SSHTunnel, connection: &PooledSession) -> String {
+        use tokio::time::{interval_at, Instant, MissedTickBehavior};
 
-        {
-            let sessions_guard = sessions.read().await;
-            if let Some(session) = sessions_guard.get(&session_key) {
-                return Ok(session.clone());
-            }
-        }
+        let mut connection_check = interval_at(
+            Instant::now() + CONNECTION_CHECK_INTERVAL,
+            CONNECTION_CHECK_INTERVAL,
+        );
+        let mut probe_timer = tunnel.health_probe.as_ref().map(|probe| {
+            let period = Duration::from_secs(probe.interval_secs.max(1));
+            let mut timer = interval_at(Instant::now() + period, period);
+            timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            timer
+        });
+        let mut failures = 0u32;
 
-        let config = Arc::new(Config::default());
-        let handler = SSHClientHandler;
-        let mut session =
-            russh::client::connect(config, (&profile.host as &str, profile.port), handler).await?;
+        loop {
+            let probe_due = async {
+                match probe_timer.as_mut() {
+                    Some(timer) => {
+                        timer.tick().await;
+                    }
+                    None => std::future::pending().await,
+                }
+            };
 
-        let authenticated = match &profile.auth_data {
-            AuthData::Password { password } => {
-                match session
-                    .authenticate_password(&profile.username, password)
-                    .await
-                {
-                    Ok(auth_result) => auth_result,
-                    Err(e) => {
-                        return Err(anyhow::anyhow!("Password authentication failed: {}", e));
+            tokio::select! {
+                _ = connection_check.tick() => {
+                    if let Some(loss) = connection.connection_loss() {
+                        return format!("SSH connection lost: {}", loss.message);
+                    }
+                }
+                _ = probe_due => {
+                    let Some(probe) = &tunnel.health_probe else {
+                        continue;
+                    };
+                    match Self::run_health_probe(tunnel, probe, connection).await {
+                        Ok(()) => failures = 0,
+                        Err(e) => {
+                            let threshold = probe.failure_threshold.max(1);
+                            failures += 1;
+                            warn!(
+                                "Health probe of tunnel {} failed ({}/{}): {}",
+                                tunnel.name, failures, threshold, e
+                            );
+                            if failures >= threshold {
+                                return format!("Health probe failed: {}", e);
+                            }
+                        }
                     }
                 }
             }
-            AuthData::KeyReference { .. } => {
-                return Err(anyhow::anyhow!(
-                    "Key-based authentication not supported for tunnels"
-                ));
-            }
-            AuthData::Certificate { .. } => {
-                return Err(anyhow::anyhow!(
-                    "Certificate-based authentication not supported for tunnels"
-                ));
+        }
+    }
+
+    /// Run one health check of the tunnel
+    async fn run_health_probe(
+        tunnel: &SSHTunnel,
+        probe: &TunnelHealthProbe,
+        connection: &PooledSession,
+    ) -> Result<()> {
+        let timeout = Duration::from_secs(probe.timeout_secs.max(1));
+        let cancel = CancellationToken::new();
+        let check = async {
+            match &probe.check {
+                TunnelHealthCheck::TcpConnect => match tunnel.tunnel_type {
+                    TunnelType::Remote => {
+                        // The forwarded port or socket listens on the SSH server
+                        let channel = match &tunnel.remote_socket_path {
+                            Some(path) => connection.open_direct_streamlocal(path).await?,
+                            None => {
+                                connection
+                                    .open_direct_tcpip(
+                                        "127.0.0.1",
+                                        tunnel.remote_port.unwrap_or(22),
+                                    )
+                                    .await?
+                            }
+                        };
+                        let _ = channel.close().await;
+                        Ok(())
+                    }
+                    TunnelType::Local | TunnelType::Dynamic | TunnelType::HttpProxy => {
+                        connect_local(&tunnel.local_endpoint()).await?;
+                        Ok(())
+                    }
+                },
+                TunnelHealthCheck::Exec { command } => {
+                    let sink = OutputSink {
+                        exec_id: tunnel.base.id.clone(),
+                        profile_id: tunnel.profile_id.clone(),
+                        events: None,
+                    };
+                    let output = run_command(connection, command, &sink, &cancel).await?;
+                    match output.exit_status {
+                        Some(0) => Ok(()),
+                        Some(status) => {
+                            Err(anyhow::anyhow!("Command exited with status {}", status))
+                        }
+                        None => Err(anyhow::anyhow!("Command ended without an exit status")),
+                    }
+                }
             }
         };
 
-        if !authenticated {
-            return Err(anyhow::anyhow!(
-                "Authentication failed: Invalid credentials"
-            ));
-        }
+        run_with_timeout(timeout, &cancel, check)
+            .await
+            .ok_or_else(|| anyhow::anyhow!("Timed out after {}s", timeout.as_secs()))?
+    }
 
-        let session_arc = Arc::new(Mutex::new(session));
-        {
-            let mut sessions_guard = sessions.write().await;
-            sessions_guard.insert(session_key, session_arc.clone());
+    /// Start local port forwarding, listening on a TCP port or a unix socket
+    async fn start_local_forward(
+        local: TunnelEndpoint,
+        remote: TunnelEndpoint,
+        session: PooledHandle,
+        cancel_token: CancellationToken,
+        traffic: Arc<TunnelTraffic>,
+    ) -> Result<()> {
+        let (host, port) = match local {
+            TunnelEndpoint::Tcp { host, port } => (host, port),
+            TunnelEndpoint::Socket { path } => {
+                return Self::start_local_socket_forward(
+                    path,
+                    remote,
+                    session,
+                    cancel_token,
+                    traffic,
+                )
+                .await;
+            }
+        };
+        let listener = TcpListener::bind(format!("{}:{}", host, port)).await?;
+
+        loop {
+            tokio::select! {
+                _ = cancel_token.cancelled() => {
+                    break;
+                }
+                result = listener.accept() => {
+                    match result {
+                        Ok((stream, peer_address)) => {
+                            let connection = traffic.register(peer_address.to_string(), &cancel_token);
+                            Self::spawn_local_connection(
+                                stream,
+                                session.clone(),
+                                remote.clone(),
+                                host.clone(),
+                                port,
+                                connection,
+                            );
+                        }
+                        Err(e) => {
+                            error!("Failed to accept connection: {}", e);
+                        }
+                    }
+                }
+            }
         }
 
-        Ok(session_arc)
+        Ok(())
     }
 
-    /// Start local port forwarding
-    async fn start_local_forward(
-        local_host: String,
-        local_port: u16,
-        remote_host: String,
-        remote_port: u16,
-        session: Arc<Mutex<Handle<SSHClientHandler>>>,
+    /// Accept connections on a local unix socket, removing the socket file once stopped
+    #[cfg(unix)]
+    async fn start_local_socket_forward(
+        path: String,
+        remote: TunnelEndpoint,
+        session: PooledHandle,
         cancel_token: CancellationToken,
+        traffic: Arc<TunnelTraffic>,
     ) -> Result<()> {
-        let listener = TcpListener::bind(format!("{}:{}", local_host, local_port)).await?;
+        let listener = bind_local_socket(&path)?;
 
         loop {
             tokio::select! {
@@ -482,37 +837,106 @@ impl TunnelService {
                 result = listener.accept() => {
                     match result {
                         Ok((stream, _)) => {
-                            let channel = {
-                                let session_guard = session.lock().await;
-                                session_guard.channel_open_direct_tcpip(
-                                    &remote_host,
-                                    remote_port as u32,
-                                    &local_host,
-                                    local_port as u32,
-                                ).await?
-                            };
-
-                            tokio::spawn(Self::proxy_connection(stream, session.clone(), channel, cancel_token.clone()));
+                            let connection = traffic.register(path.clone(), &cancel_token);
+                            Self::spawn_local_connection(
+                                stream,
+                                session.clone(),
+                                remote.clone(),
+                                "127.0.0.1".to_string(),
+                                0,
+                                connection,
+                            );
                         }
                         Err(e) => {
-                            error!("Failed to accept connection: {}", e);
+                            error!("Failed to accept connection on {}: {}", path, e);
                         }
                     }
                 }
             }
         }
 
+        if let Err(e) = std::fs::remove_file(&path) {
+            warn!("Failed to remove forwarded socket {}: {}", path, e);
+        }
         Ok(())
     }
 
-    /// Start remote port forwarding
+    #[cfg(not(unix))]
+    async fn start_local_socket_forward(
+        path: String,
+        _remote: TunnelEndpoint,
+        _session: PooledHandle,
+        _cancel_token: CancellationToken,
+        _traffic: Arc<TunnelTraffic>,
+    ) -> Result<()> {
+        Err(anyhow::anyhow!(
+            "Unix socket forwarding is not supported on this platform ({})",
+            path
+        ))
+    }
+
+    /// Open the channel of a forwarded connection to the remote end of a local forward
+    async fn open_forward_channel(
+        session: &PooledHandle,
+        remote: &TunnelEndpoint,
+        originator_host: &str,
+        originator_port: u16,
+    ) -> Result<russh::Channel<russh::client::Msg>> {
+        let session_guard = session.lock().await;
+        let channel = match remote {
+            TunnelEndpoint::Tcp { host, port } => {
+                session_guard
+                    .channel_open_direct_tcpip(
+                        host,
+                        *port as u32,
+                        originator_host,
+                        originator_port as u32,
+                    )
+                    .await?
+            }
+            TunnelEndpoint::Socket { path } => {
+                session_guard.channel_open_direct_streamlocal(path).await?
+            }
+        };
+        Ok(channel)
+    }
+
+    /// Open the channel of a connection accepted by a local forward and proxy it. Runs
+    /// in its own task, so a channel the server refuses only closes this connection
+    /// and doesn't hold up the next accept.
+    fn spawn_local_connection<S>(
+        stream: S,
+        session: PooledHandle,
+        remote: TunnelEndpoint,
+        originator_host: String,
+        originator_port: u16,
+        connection: ConnectionGuard,
+    ) where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        connection.set_target(remote.to_string());
+        tokio::spawn(async move {
+            let channel =
+                Self::open_forward_channel(&session, &remote, &originator_host, originator_port)
+                    .await;
+            match channel {
+                Ok(channel) => Self::proxy_connection(stream, session, channel, connection).await,
+                Err(e) => {
+                    error!("Failed to open forwarding channel to {}: {}", remote, e);
+                    Ok(())
+                }
+            }
+        });
+    }
+
+    /// Start remote port forwarding from a port on the SSH server to a local port or socket
     async fn start_remote_forward(
-        local_host: String,
-        local_port: u16,
+        connection: &PooledSession,
         remote_host: String,
         remote_port: u16,
-        session: Arc<Mutex<Handle<SSHClientHandler>>>,
+        local: TunnelEndpoint,
         cancel_token: CancellationToken,
+        traffic: Arc<TunnelTraffic>,
     ) -> Result<()> {
         // For remote forwarding, bind address should be empty string or "0.0.0.0"
         // to allow SSH server to bind on appropriate interface
@@ -522,81 +946,140 @@ impl TunnelService {
             "" // Empty string lets SSH server decide
         };
 
-        let forwarded_port = {
-            let mut session_guard = session.lock().await;
-            match session_guard
-                .tcpip_forward(bind_address, remote_port as u32)
-                .await
-            {
-                Ok(port) => port,
-                Err(e) => {
-                    return Err(anyhow::anyhow!(
-                        "Failed to request remote port forwarding: {}",
-                        e
-                    ));
+        let (port, mut channels) = connection
+            .forward_tcpip(bind_address, remote_port as u32)
+            .await
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        let listen_address = format!("{}:{}", bind_address, port);
+
+        info!(
+            "✅ Remote port forwarding established: {} -> {}",
+            listen_address, local
+        );
+
+        loop {
+            tokio::select! {
+                _ = cancel_token.cancelled() => {
+                    break;
+                }
+                channel = channels.recv() => {
+                    let Some(channel) = channel else {
+                        break;
+                    };
+                    let guard = traffic.register(listen_address.clone(), &cancel_token);
+                    Self::spawn_forwarded_connection(connection, channel, &local, guard);
                 }
             }
-        };
+        }
 
-        let actual_port = if remote_port == 0 {
-            forwarded_port as u16
-        } else {
-            remote_port
-        };
+        match connection.cancel_tcpip_forward(bind_address, port).await {
+            Ok(()) => info!("✅ Remote port forwarding cancelled: {}", listen_address),
+            Err(e) => error!("❌ {}", e),
+        }
 
-        info!(
-            "✅ Remote port forwarding established: {}:{} -> {}:{}",
-            bind_address, actual_port, local_host, local_port
-        );
+        Ok(())
+    }
 
-        let bind_address_clone = bind_address.to_string();
+    /// Start remote forwarding of a unix socket on the SSH server to a local port or socket
+    async fn start_remote_socket_forward(
+        connection: &PooledSession,
+        remote_path: String,
+        local: TunnelEndpoint,
+        cancel_token: CancellationToken,
+        traffic: Arc<TunnelTraffic>,
+    ) -> Result<()> {
+        let mut channels = connection
+            .forward_streamlocal(&remote_path)
+            .await
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
 
-        let mut heartbeat_counter = 0u32;
-        let heartbeat_interval = 120; // 60 seconds (500ms * 120)
+        info!(
+            "✅ Remote socket forwarding established: {} -> {}",
+            remote_path, local
+        );
 
         loop {
             tokio::select! {
                 _ = cancel_token.cancelled() => {
-                    let cancel_result = {
-                        let session_guard = session.lock().await;
-                        session_guard.cancel_tcpip_forward(&bind_address_clone, actual_port as u32).await
+                    break;
+                }
+                channel = channels.recv() => {
+                    let Some(channel) = channel else {
+                        break;
                     };
+                    let guard = traffic.register(remote_path.clone(), &cancel_token);
+                    Self::spawn_forwarded_connection(connection, channel, &local, guard);
+                }
+            }
+        }
 
-                    match cancel_result {
-                        Ok(_) => {
-                            info!("✅ Remote port forwarding cancelled: {}:{}", bind_address_clone, actual_port);
-                        },
-                        Err(e) => {
-                            error!("❌ Failed to cancel remote port forwarding: {}", e);
-                        }
-                    }
-                    break;
+        match connection.cancel_streamlocal_forward(&remote_path).await {
+            Ok(()) => info!("✅ Remote socket forwarding cancelled: {}", remote_path),
+            Err(e) => error!("❌ {}", e),
+        }
+
+        Ok(())
+    }
+
+    /// Connect a channel the server opened for a remote forward to the local end
+    fn spawn_forwarded_connection(
+        connection: &PooledSession,
+        channel: russh::Channel<russh::client::Msg>,
+        local: &TunnelEndpoint,
+        guard: ConnectionGuard,
+    ) {
+        guard.set_target(local.to_string());
+        let session = connection.handle();
+        let local = local.clone();
+        tokio::spawn(async move {
+            match connect_local(&local).await {
+                Ok(LocalStream::Tcp(stream)) => {
+                    Self::proxy_connection(stream, session, channel, guard).await
                 }
-                _ = tokio::time::sleep(tokio::time::Duration::from_millis(500)) => {
-                    heartbeat_counter += 1;
-
-                    // Health check every 5 minutes
-                    if heartbeat_counter.is_multiple_of(heartbeat_interval * 5) {
-                        let health_check_result = {
-                            let session_guard = session.lock().await;
-                            tokio::time::timeout(
-                                tokio::time::Duration::from_secs(5),
-                                session_guard.channel_open_session()
-                            ).await
-                        };
+                #[cfg(unix)]
+                Ok(LocalStream::Unix(stream)) => {
+                    Self::proxy_connection(stream, session, channel, guard).await
+                }
+                Err(e) => {
+                    error!("Failed to connect to {}: {}", local, e);
+                    let _ = channel.close().await;
+                    Ok(())
+                }
+            }
+        });
+    }
 
-                        match health_check_result {
-                            Ok(Ok(test_channel)) => {
-                                let _ = test_channel.close().await;
-                            }
-                            Ok(Err(e)) => {
-                                error!("❌ SSH session health check failed: {}", e);
-                                return Err(anyhow::anyhow!("SSH session unhealthy: {}", e));
-                            }
-                            Err(_) => {
-                                error!("❌ SSH session health check timed out");
-                                return Err(anyhow::anyhow!("SSH session timeout"));
-                            }
+    /// Start dynamic port forwarding (SOCKS proxy)
+    async fn start_dynamic_forward(
+        local_host: String,
+        local_port: u16,
+        session: PooledHandle,
+        cancel_token: CancellationToken,
+        traffic: Arc<TunnelTraffic>,
+        socks_auth: Option<TunnelSocksAuth>,
+        access_control: TunnelAccessControl,
+    ) -> Result<()> {
+        let listener = TcpListener::bind(format!("{}:{}", local_host, local_port)).await?;
+
+        loop {
+            tokio::select! {
+                _ = cancel_token.cancelled() => {
+                    break;
+                }
+                result = listener.accept() => {
+                    match result {
+                        Ok((stream, peer_address)) => {
+                            let connection = traffic.register(peer_address.to_string(), &cancel_token);
+                            tokio::spawn(Self::handle_socks_connection(
+                                stream,
+                                session.clone(),
+                                connection,
+                                socks_auth.clone(),
+                                access_control.clone(),
+                            ));
+                        }
+                        Err(e) => {
+                            error!("Failed to accept SOCKS connection: {}", e);
                         }
                     }
                 }
@@ -606,12 +1089,14 @@ impl TunnelService {
         Ok(())
     }
 
-    /// Start dynamic port forwarding (SOCKS proxy)
-    async fn start_dynamic_forward(
+    /// Start an HTTP proxy forwarding through the SSH server
+    async fn start_http_proxy_forward(
         local_host: String,
         local_port: u16,
-        session: Arc<Mutex<Handle<SSHClientHandler>>>,
+        session: PooledHandle,
         cancel_token: CancellationToken,
+        traffic: Arc<TunnelTraffic>,
+        access_control: TunnelAccessControl,
     ) -> Result<()> {
         let listener = TcpListener::bind(format!("{}:{}", local_host, local_port)).await?;
 
@@ -622,11 +1107,17 @@ impl TunnelService {
                 }
                 result = listener.accept() => {
                     match result {
-                        Ok((stream, _)) => {
-                            tokio::spawn(Self::handle_socks_connection(stream, session.clone(), cancel_token.clone()));
+                        Ok((stream, peer_address)) => {
+                            let connection = traffic.register(peer_address.to_string(), &cancel_token);
+                            tokio::spawn(Self::handle_http_proxy_connection(
+                                stream,
+                                session.clone(),
+                                connection,
+                                access_control.clone(),
+                            ));
                         }
                         Err(e) => {
-                            error!("Failed to accept SOCKS connection: {}", e);
+                            error!("Failed to accept HTTP proxy connection: {}", e);
                         }
                     }
                 }
@@ -637,21 +1128,24 @@ impl TunnelService {
     }
 
     /// Proxy connection between local and remote
-    async fn proxy_connection(
-        mut local_stream: tokio::net::TcpStream,
-        _session: Arc<Mutex<Handle<SSHClientHandler>>>,
+    async fn proxy_connection<S>(
+        local_stream: S,
+        _session: PooledHandle,
         mut channel: russh::Channel<russh::client::Msg>,
-        cancel_token: CancellationToken,
-    ) -> Result<()> {
+        connection: ConnectionGuard,
+    ) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
         use russh::ChannelMsg;
         use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
-        let (mut local_reader, mut local_writer) = local_stream.split();
+        let (mut local_reader, mut local_writer) = tokio::io::split(local_stream);
         let mut buffer = [0u8; 8192];
 
         loop {
             tokio::select! {
-                _ = cancel_token.cancelled() => {
+                _ = connection.cancel_token().cancelled() => {
                     break;
                 }
                 result = local_reader.read(&mut buffer) => {
@@ -662,6 +1156,7 @@ impl TunnelService {
                                 error!("Failed to send data to remote: {}", e);
                                 break;
                             }
+                            connection.record_out(n);
                         }
                         Err(e) => {
                             error!("Failed to read from local stream: {}", e);
@@ -680,6 +1175,7 @@ impl TunnelService {
                                 error!("Failed to flush local stream: {}", e);
                                 break;
                             }
+                            connection.record_in(data.len());
                         }
                         Some(ChannelMsg::Eof) | None => {
                             break;
@@ -699,100 +1195,97 @@ impl TunnelService {
     /// Handle SOCKS proxy connection
     async fn handle_socks_connection(
         mut local_stream: tokio::net::TcpStream,
-        session: Arc<Mutex<Handle<SSHClientHandler>>>,
-        cancel_token: CancellationToken,
+        session: PooledHandle,
+        connection: ConnectionGuard,
+        socks_auth: Option<TunnelSocksAuth>,
+        access_control: TunnelAccessControl,
     ) -> Result<()> {
-        use std::net::{Ipv4Addr, Ipv6Addr};
-        use tokio::io::{AsyncReadExt, AsyncWriteExt};
-
-        let mut buffer = [0u8; 512];
-
-        let n = local_stream.read(&mut buffer).await?;
-        if n < 3 || buffer[0] != 0x05 {
-            return Err(anyhow::anyhow!("Invalid SOCKS5 greeting"));
-        }
-
-        local_stream.write_all(&[0x05, 0x00]).await?;
-
-        let n = local_stream.read(&mut buffer).await?;
-        if n < 4 || buffer[0] != 0x05 || buffer[1] != 0x01 {
-            return Err(anyhow::anyhow!("Invalid SOCKS5 request"));
-        }
-
-        let (target_host, target_port) = match buffer[3] {
-            0x01 => {
-                if n < 10 {
-                    return Err(anyhow::anyhow!("Invalid IPv4 request"));
-                }
-                let ip = Ipv4Addr::new(buffer[4], buffer[5], buffer[6], buffer[7]);
-                let port = u16::from_be_bytes([buffer[8], buffer[9]]);
-                (ip.to_string(), port)
-            }
-            0x03 => {
-                if n < 7 {
-                    return Err(anyhow::anyhow!("Invalid domain name request"));
-                }
-                let domain_len = buffer[4] as usize;
-                if n < 7 + domain_len {
-                    return Err(anyhow::anyhow!("Incomplete domain name request"));
-                }
-                let domain = String::from_utf8_lossy(&buffer[5..5 + domain_len]).to_string();
-                let port = u16::from_be_bytes([buffer[5 + domain_len], buffer[6 + domain_len]]);
-                (domain, port)
-            }
-            0x04 => {
-                if n < 22 {
-                    return Err(anyhow::anyhow!("Invalid IPv6 request"));
-                }
-                let mut ipv6_bytes = [0u8; 16];
-                ipv6_bytes.copy_from_slice(&buffer[4..20]);
-                let ip = Ipv6Addr::from(ipv6_bytes);
-                let port = u16::from_be_bytes([buffer[20], buffer[21]]);
-                (ip.to_string(), port)
-            }
-            _ => {
-                return Err(anyhow::anyhow!("Unsupported address type"));
-            }
+        let Some((request, host)) = accept_socks_request(
+            &mut local_stream,
+            &connection,
+            socks_auth.as_ref(),
+            &access_control,
+        )
+        .await?
+        else {
+            return Ok(());
         };
 
         let channel_result = {
             let session_guard = session.lock().await;
             session_guard
-                .channel_open_direct_tcpip(&target_host, target_port as u32, "127.0.0.1", 0)
+                .channel_open_direct_tcpip(&host, request.port as u32, "127.0.0.1", 0)
                 .await
         };
 
         match channel_result {
             Ok(channel) => {
-                let response = [
-                    0x05, 0x00, 0x00, 0x01, // SOCKS5, success, reserved, IPv4
-                    0x00, 0x00, 0x00, 0x00, // Bind IP (0.0.0.0)
-                    0x00, 0x00, // Bind port (0)
-                ];
-                local_stream.write_all(&response).await?;
+                tunnel_socks::send_reply(&mut local_stream, request.version, SocksReply::Succeeded)
+                    .await?;
 
-                Self::proxy_socks_connection(local_stream, session, channel, cancel_token).await?;
+                Self::proxy_socks_connection(local_stream, session, channel, connection).await?;
             }
             Err(e) => {
                 error!("Failed to establish SSH channel: {}", e);
-                let response = [
-                    0x05, 0x05, 0x00, 0x01, // SOCKS5, connection refused, reserved, IPv4
-                    0x00, 0x00, 0x00, 0x00, // Bind IP (0.0.0.0)
-                    0x00, 0x00, // Bind port (0)
-                ];
-                let _ = local_stream.write_all(&response).await;
+                let _ = tunnel_socks::send_reply(
+                    &mut local_stream,
+                    request.version,
+                    SocksReply::ConnectionRefused,
+                )
+                .await;
             }
         }
 
         Ok(())
     }
 
+    /// Handle HTTP proxy connection
+    async fn handle_http_proxy_connection(
+        mut local_stream: tokio::net::TcpStream,
+        session: PooledHandle,
+        connection: ConnectionGuard,
+        access_control: TunnelAccessControl,
+    ) -> Result<()> {
+        let Some((request, host)) =
+            accept_http_proxy_request(&mut local_stream, &connection, &access_control).await?
+        else {
+            return Ok(());
+        };
+
+        let channel_result = {
+            let session_guard = session.lock().await;
+            session_guard
+                .channel_open_direct_tcpip(&host, request.port as u32, "127.0.0.1", 0)
+                .await
+        };
+
+        let channel = match channel_result {
+            Ok(channel) => channel,
+            Err(e) => {
+                error!("Failed to establish SSH channel: {}", e);
+                let _ =
+                    tunnel_http::send_error(&mut local_stream, HttpProxyError::BadGateway).await;
+                return Ok(());
+            }
+        };
+
+        if request.tunnel {
+            tunnel_http::send_established(&mut local_stream).await?;
+        }
+        if !request.initial_data.is_empty() {
+            channel.data(&request.initial_data[..]).await?;
+            connection.record_out(request.initial_data.len());
+        }
+
+        Self::proxy_connection(local_stream, session, channel, connection).await
+    }
+
     /// Proxy SOCKS connection between local stream and SSH channel
     async fn proxy_socks_connection(
         mut local_stream: tokio::net::TcpStream,
-        _session: Arc<Mutex<Handle<SSHClientHandler>>>,
+        _session: PooledHandle,
         mut channel: russh::Channel<russh::client::Msg>,
-        cancel_token: CancellationToken,
+        connection: ConnectionGuard,
     ) -> Result<()> {
         use russh::ChannelMsg;
         use tokio::io::{AsyncReadExt, AsyncWriteExt};
@@ -802,7 +1295,7 @@ impl TunnelService {
 
         loop {
             tokio::select! {
-                _ = cancel_token.cancelled() => {
+                _ = connection.cancel_token().cancelled() => {
                     break;
                 }
                 result = local_reader.read(&mut buffer) => {
@@ -813,6 +1306,7 @@ impl TunnelService {
                                 error!("Failed to send data to remote via SOCKS: {}", e);
                                 break;
                             }
+                            connection.record_out(n);
                         }
                         Err(e) => {
                             error!("Failed to read from local SOCKS stream: {}", e);
@@ -831,6 +1325,7 @@ impl TunnelService {
                                 error!("Failed to flush local SOCKS stream: {}", e);
                                 break;
                             }
+                            connection.record_in(data.len());
                         }
                         Some(ChannelMsg::Eof) | None => {
                             break;
@@ -847,3 +1342,555 @@ impl TunnelService {
         Ok(())
     }
 }
+
+/// Address to reach a listener bound to the given host from this machine
+fn connect_host(bind_host: &str) -> &str {
+    match bind_host {
+        "0.0.0.0" | "" => "127.0.0.1",
+        "::" => "::1",
+        host => host,
+    }
+}
+
+/// Check the host of a proxy request against the access control, returning the host
+/// to open the channel to. Names that are checked by their addresses are replaced by
+/// the first of them, so the server can't resolve them to an address that wasn't
+/// checked, and shorthand IPv4 forms are passed on as the address they stand for.
+async fn check_destination(
+    access_control: &TunnelAccessControl,
+    host: &str,
+    port: u16,
+) -> Option<String> {
+    if !access_control.needs_resolution(host) {
+        return checked_host(access_control, host, &[]);
+    }
+
+    let addresses: Vec<IpAddr> =
+        match tokio::time::timeout(DNS_LOOKUP_TIMEOUT, tokio::net::lookup_host((host, port))).await
+        {
+            Ok(Ok(addresses)) => addresses.map(|address| address.ip()).collect(),
+            Ok(Err(e)) => {
+                warn!("Failed to resolve {} for access control: {}", host, e);
+                Vec::new()
+            }
+            Err(_) => {
+                warn!("Resolving {} for access control timed out", host);
+                Vec::new()
+            }
+        };
+    checked_host(access_control, host, &addresses)
+}
+
+/// Host to open the channel to when the access control allows the host with the
+/// addresses it resolved to
+fn checked_host(
+    access_control: &TunnelAccessControl,
+    host: &str,
+    addresses: &[IpAddr],
+) -> Option<String> {
+    if !access_control.allows(host, addresses) {
+        return None;
+    }
+    Some(
+        match ip_literal(host).or_else(|| addresses.first().copied()) {
+            Some(address) => address.to_string(),
+            None => host.to_string(),
+        },
+    )
+}
+
+/// Read the CONNECT request of a SOCKS client and check its destination, answering
+/// the client when it is denied. Returns the request with the host to open the
+/// channel to, or nothing when the connection is done.
+async fn accept_socks_request<S>(
+    stream: &mut S,
+    connection: &ConnectionGuard,
+    socks_auth: Option<&TunnelSocksAuth>,
+    access_control: &TunnelAccessControl,
+) -> Result<Option<(tunnel_socks::SocksRequest, String)>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let handshake = tokio::time::timeout(
+        PROXY_HANDSHAKE_TIMEOUT,
+        tunnel_socks::accept(stream, socks_auth),
+    );
+    let request = tokio::select! {
+        _ = connection.cancel_token().cancelled() => return Ok(None),
+        result = handshake => match result {
+            Ok(Ok(request)) => request,
+            Ok(Err(e)) => {
+                warn!("SOCKS handshake failed: {}", e);
+                return Err(e);
+            }
+            Err(_) => return Err(anyhow::anyhow!("SOCKS handshake timed out")),
+        },
+    };
+    connection.set_target(request.target());
+
+    let Some(host) = check_destination(access_control, &request.host, request.port).await else {
+        warn!(
+            "SOCKS connection to {} denied by access control",
+            request.target()
+        );
+        tunnel_socks::send_reply(stream, request.version, SocksReply::NotAllowed).await?;
+        return Ok(None);
+    };
+    Ok(Some((request, host)))
+}
+
+/// Read the request of an HTTP proxy client and check its destination, answering
+/// the client when it is denied. Returns the request with the host to open the
+/// channel to, or nothing when the connection is done.
+async fn accept_http_proxy_request<S>(
+    stream: &mut S,
+    connection: &ConnectionGuard,
+    access_control: &TunnelAccessControl,
+) -> Result<Option<(tunnel_http::HttpProxyRequest, String)>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let handshake = tokio::time::timeout(PROXY_HANDSHAKE_TIMEOUT, tunnel_http::accept(stream));
+    let request = tokio::select! {
+        _ = connection.cancel_token().cancelled() => return Ok(None),
+        result = handshake => match result {
+            Ok(Ok(request)) => request,
+            Ok(Err(e)) => {
+                warn!("HTTP proxy request failed: {}", e);
+                return Err(e);
+            }
+            Err(_) => return Err(anyhow::anyhow!("HTTP proxy request timed out")),
+        },
+    };
+    connection.set_target(request.target());
+
+    let Some(host) = check_destination(access_control, &request.host, request.port).await else {
+        warn!(
+            "HTTP proxy connection to {} denied by access control",
+            request.target()
+        );
+        tunnel_http::send_error(stream, HttpProxyError::Forbidden).await?;
+        return Ok(None);
+    };
+    Ok(Some((request, host)))
+}
+
+/// Local connection to the target of a forwarded channel
+enum LocalStream {
+    Tcp(tokio::net::TcpStream),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixStream),
+}
+
+/// Connect to a local TCP port or unix socket
+async fn connect_local(endpoint: &TunnelEndpoint) -> std::io::Result<LocalStream> {
+    match endpoint {
+        TunnelEndpoint::Tcp { host, port } => {
+            let stream = tokio::net::TcpStream::connect((connect_host(host), *port)).await?;
+            Ok(LocalStream::Tcp(stream))
+        }
+        #[cfg(unix)]
+        TunnelEndpoint::Socket { path } => {
+            let stream = tokio::net::UnixStream::connect(path).await?;
+            Ok(LocalStream::Unix(stream))
+        }
+        #[cfg(not(unix))]
+        TunnelEndpoint::Socket { .. } => Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Unix sockets are not supported on this platform",
+        )),
+    }
+}
+
+/// Listen on a local unix socket
+#[cfg(unix)]
+fn bind_local_socket(path: &str) -> std::io::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::FileTypeExt;
+
+    // A socket left behind by a previous run would make the bind fail, but one
+    // that still accepts connections belongs to a running process
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if metadata.file_type().is_socket()
+            && std::os::unix::net::UnixStream::connect(path).is_err()
+        {
+            std::fs::remove_file(path)?;
+        }
+    }
+    tokio::net::UnixListener::bind(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn policy(mode: RestartMode, max_restarts: Option<u32>) -> TunnelRestartPolicy {
+        TunnelRestartPolicy {
+            mode,
+            max_restarts,
+            initial_delay_ms: 1,
+            max_delay_ms: 4,
+        }
+    }
+
+    /// Supervise runs that end with the given results in turn, cancelling the handle
+    /// once they run out. Returns the supervisor's result and the number of runs.
+    async fn supervise_runs(
+        policy: &TunnelRestartPolicy,
+        handle: &TunnelHandle,
+        results: Vec<Result<()>>,
+    ) -> (Result<()>, usize) {
+        let mut results = results.into_iter();
+        let mut runs = 0;
+        let cancel_token = handle.cancel_token.clone();
+        let result = TunnelService::supervise("test", policy, handle, |_| {
+            runs += 1;
+            let result = results.next();
+            if results.as_slice().is_empty() {
+                cancel_token.cancel();
+            }
+            async move { result.unwrap_or(Ok(())) }
+        })
+        .await;
+        (result, runs)
+    }
+
+    fn tunnel(tunnel_type: TunnelType) -> SSHTunnel {
+        SSHTunnel::new(
+            "device".to_string(),
+            "tunnel".to_string(),
+            "profile".to_string(),
+            tunnel_type,
+            "127.0.0.1".to_string(),
+            8080,
+            Some("db.internal".to_string()),
+            Some(5432),
+        )
+    }
+
+    #[cfg(unix)]
+    fn socket_path() -> String {
+        std::env::temp_dir()
+            .join(format!("tunnel-{}.sock", uuid::Uuid::new_v4()))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn test_endpoints_prefer_socket_paths() {
+        let mut tunnel = tunnel(TunnelType::Local);
+        assert_eq!(
+            tunnel.local_endpoint(),
+            TunnelEndpoint::Tcp {
+                host: "127.0.0.1".to_string(),
+                port: 8080
+            }
+        );
+        assert_eq!(
+            tunnel.remote_endpoint(),
+            TunnelEndpoint::Tcp {
+                host: "db.internal".to_string(),
+                port: 5432
+            }
+        );
+
+        tunnel.local_socket_path = Some("/tmp/db.sock".to_string());
+        tunnel.remote_socket_path = Some("/run/postgresql/.s.PGSQL.5432".to_string());
+        assert_eq!(
+            tunnel.local_endpoint(),
+            TunnelEndpoint::Socket {
+                path: "/tmp/db.sock".to_string()
+            }
+        );
+        assert_eq!(
+            tunnel.remote_endpoint(),
+            TunnelEndpoint::Socket {
+                path: "/run/postgresql/.s.PGSQL.5432".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_validate_socket_paths() {
+        // A socket stands in for the port, or the host and port, of its end
+        let mut local = tunnel(TunnelType::Local);
+        local.local_port = 0;
+        assert!(local.validate().is_err());
+        local.local_socket_path = Some("/tmp/db.sock".to_string());
+        assert!(local.validate().is_ok());
+        local.remote_host = None;
+        local.remote_port = None;
+        assert!(local.validate().is_err());
+        local.remote_socket_path = Some("/run/db.sock".to_string());
+        assert!(local.validate().is_ok());
+
+        // Remote ports and sockets forward to local sockets as well
+        let mut remote = tunnel(TunnelType::Remote);
+        remote.local_socket_path = Some("/tmp/app.sock".to_string());
+        assert!(remote.validate().is_ok());
+        remote.remote_socket_path = Some("/run/app.sock".to_string());
+        assert!(remote.validate().is_ok());
+
+        remote.remote_socket_path = Some("run/app.sock".to_string());
+        assert_eq!(
+            remote.validate().unwrap_err(),
+            "Socket path 'run/app.sock' must be absolute"
+        );
+
+        for tunnel_type in [TunnelType::Dynamic, TunnelType::HttpProxy] {
+            let mut proxy = tunnel(tunnel_type);
+            proxy.local_socket_path = Some("/tmp/proxy.sock".to_string());
+            assert!(proxy.validate().is_err());
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_bind_local_socket_replaces_only_stale_sockets() {
+        let path = socket_path();
+
+        // Dropping a listener leaves its socket file behind
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        assert!(std::path::Path::new(&path).exists());
+        let listener = bind_local_socket(&path).unwrap();
+
+        // A socket that still accepts connections is left alone
+        assert!(bind_local_socket(&path).is_err());
+        drop(listener);
+        std::fs::remove_file(&path).unwrap();
+
+        // So is anything that isn't a socket
+        std::fs::write(&path, "data").unwrap();
+        assert!(bind_local_socket(&path).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "data");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_check_destination_dials_the_checked_address() {
+        let open = TunnelAccessControl::default();
+        assert_eq!(
+            check_destination(&open, "localhost", 80).await.as_deref(),
+            Some("localhost")
+        );
+        assert_eq!(
+            check_destination(&open, "2130706433", 80).await.as_deref(),
+            Some("127.0.0.1")
+        );
+
+        let no_loopback = TunnelAccessControl {
+            allow: Vec::new(),
+            deny: vec!["127.0.0.0/8".to_string(), "::1".to_string()],
+        };
+        assert_eq!(check_destination(&no_loopback, "0x7f.1", 80).await, None);
+
+        // Names are dialled by the first address they resolved to, as checked
+        let dual_stack: [IpAddr; 2] = ["::1".parse().unwrap(), "127.0.0.1".parse().unwrap()];
+        assert_eq!(checked_host(&no_loopback, "localhost", &dual_stack), None);
+        let loopback_only = TunnelAccessControl {
+            allow: vec!["127.0.0.0/8".to_string(), "::1".to_string()],
+            deny: Vec::new(),
+        };
+        assert_eq!(
+            checked_host(&loopback_only, "localhost", &dual_stack).as_deref(),
+            Some("::1")
+        );
+        assert_eq!(checked_host(&loopback_only, "localhost", &[]), None);
+    }
+
+    fn deny_private() -> TunnelAccessControl {
+        TunnelAccessControl {
+            allow: Vec::new(),
+            deny: vec!["10.0.0.0/8".to_string()],
+        }
+    }
+
+    /// Target recorded for the only connection of `traffic`
+    fn recorded_target(traffic: &TunnelTraffic) -> Option<String> {
+        traffic.snapshot("test").connections[0].target.clone()
+    }
+
+    #[tokio::test]
+    async fn test_socks_requests_are_checked_before_connecting() {
+        let traffic = Arc::new(TunnelTraffic::new());
+        let cancel_token = CancellationToken::new();
+
+        // SOCKS5 without authentication, CONNECT to the name "167772161" port 80
+        let mut input = vec![0x05, 0x01, 0x00, 0x05, 0x01, 0x00, 0x03, 9];
+        input.extend_from_slice(b"167772161");
+        input.extend_from_slice(&[0, 80]);
+
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        client.write_all(&input).await.unwrap();
+        let connection = traffic.register("client".to_string(), &cancel_token);
+        let accepted = accept_socks_request(
+            &mut server,
+            &connection,
+            None,
+            &TunnelAccessControl::default(),
+        )
+        .await
+        .unwrap();
+        let (request, host) = accepted.unwrap();
+        assert_eq!(request.host, "167772161");
+        assert_eq!(host, "10.0.0.1");
+        assert_eq!(recorded_target(&traffic).as_deref(), Some("167772161:80"));
+        drop(connection);
+
+        // Denied requests are answered with "not allowed" and never connected
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        client.write_all(&input).await.unwrap();
+        let connection = traffic.register("client".to_string(), &cancel_token);
+        let accepted = accept_socks_request(&mut server, &connection, None, &deny_private())
+            .await
+            .unwrap();
+        assert!(accepted.is_none());
+        drop(server);
+        let mut output = Vec::new();
+        client.read_to_end(&mut output).await.unwrap();
+        assert_eq!(
+            output,
+            [0x05, 0x00, 0x05, 0x02, 0x00, 0x01, 0, 0, 0, 0, 0, 0]
+        );
+        drop(connection);
+
+        // A closed connection stops waiting for the handshake
+        let (_client, mut server) = tokio::io::duplex(1024);
+        let connection = traffic.register("client".to_string(), &cancel_token);
+        cancel_token.cancel();
+        let accepted = accept_socks_request(&mut server, &connection, None, &deny_private())
+            .await
+            .unwrap();
+        assert!(accepted.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_http_proxy_requests_are_checked_before_connecting() {
+        let traffic = Arc::new(TunnelTraffic::new());
+        let cancel_token = CancellationToken::new();
+
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        client
+            .write_all(b"CONNECT 10.0.0.1:443 HTTP/1.1\r\nHost: 10.0.0.1:443\r\n\r\n")
+            .await
+            .unwrap();
+        let connection = traffic.register("client".to_string(), &cancel_token);
+        let accepted = accept_http_proxy_request(&mut server, &connection, &deny_private())
+            .await
+            .unwrap();
+        assert!(accepted.is_none());
+        assert_eq!(recorded_target(&traffic).as_deref(), Some("10.0.0.1:443"));
+        drop(server);
+        let mut output = Vec::new();
+        client.read_to_end(&mut output).await.unwrap();
+        assert!(output.starts_with(b"HTTP/1.1 403 Forbidden\r\n"));
+        drop(connection);
+
+        // Allowed requests are left to be answered once the channel is open
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        client
+            .write_all(b"CONNECT 192.168.1.5:22 HTTP/1.1\r\n\r\n")
+            .await
+            .unwrap();
+        let connection = traffic.register("client".to_string(), &cancel_token);
+        let accepted = accept_http_proxy_request(&mut server, &connection, &deny_private())
+            .await
+            .unwrap();
+        let (request, host) = accepted.unwrap();
+        assert!(request.tunnel);
+        assert_eq!(host, "192.168.1.5");
+        drop(server);
+        let mut output = Vec::new();
+        client.read_to_end(&mut output).await.unwrap();
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn test_restart_delay_backs_off_up_to_max() {
+        let policy = TunnelRestartPolicy {
+            mode: RestartMode::Always,
+            max_restarts: None,
+            initial_delay_ms: 1000,
+            max_delay_ms: 5000,
+        };
+        let delays: Vec<u64> = [1, 2, 3, 4, 100]
+            .into_iter()
+            .map(|restart| policy.delay(restart).as_millis() as u64)
+            .collect();
+        assert_eq!(delays, vec![1000, 2000, 4000, 5000, 5000]);
+    }
+
+    #[test]
+    fn test_restart_decision_follows_mode() {
+        let never = policy(RestartMode::Never, None);
+        assert_eq!(restart_delay(&never, true, 1), None);
+
+        let on_failure = policy(RestartMode::OnFailure, Some(2));
+        assert_eq!(restart_delay(&on_failure, false, 1), None);
+        assert_eq!(
+            restart_delay(&on_failure, true, 2),
+            Some(Duration::from_millis(2))
+        );
+        assert_eq!(restart_delay(&on_failure, true, 3), None);
+        let unlimited = policy(RestartMode::OnFailure, None);
+        assert!(restart_delay(&unlimited, true, 1000).is_some());
+
+        let always = policy(RestartMode::Always, Some(0));
+        assert_eq!(
+            restart_delay(&always, false, 5),
+            Some(Duration::from_millis(4))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_supervisor_gives_up_after_max_restarts() {
+        let handle = TunnelHandle::new();
+        let failures = (0..5)
+            .map(|i| Err(anyhow::anyhow!("failure {}", i)))
+            .collect();
+        let (result, runs) =
+            supervise_runs(&policy(RestartMode::OnFailure, Some(2)), &handle, failures).await;
+
+        assert_eq!(result.unwrap_err().to_string(), "failure 2");
+        assert_eq!(runs, 3);
+        assert_eq!(handle.restart_count.load(Ordering::Relaxed), 2);
+        let last_failure = handle.last_failure.read().await.clone().unwrap();
+        assert_eq!(last_failure.reason, "failure 2");
+    }
+
+    #[tokio::test]
+    async fn test_supervisor_restarts_by_mode_until_cancelled() {
+        // A tunnel that stops without an error is only restarted in Always mode
+        let handle = TunnelHandle::new();
+        let (result, runs) = supervise_runs(
+            &policy(RestartMode::OnFailure, None),
+            &handle,
+            vec![Ok(()), Ok(())],
+        )
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(runs, 1);
+        assert!(handle.last_failure.read().await.is_none());
+
+        let handle = TunnelHandle::new();
+        let (result, runs) = supervise_runs(
+            &policy(RestartMode::Never, None),
+            &handle,
+            vec![Err(anyhow::anyhow!("lost")), Ok(())],
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(runs, 1);
+
+        let handle = TunnelHandle::new();
+        let (result, runs) = supervise_runs(
+            &policy(RestartMode::Always, Some(1)),
+            &handle,
+            vec![Ok(()), Err(anyhow::anyhow!("lost")), Ok(())],
+        )
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(runs, 3);
+        assert_eq!(handle.restart_count.load(Ordering::Relaxed), 2);
+    }
+}
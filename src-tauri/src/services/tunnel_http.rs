@@ -0,0 +1,384 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Server side of the HTTP proxy used by HttpProxy tunnels: CONNECT requests and plain
+//! requests with an absolute `http://` URI. Plain requests are sent with
+//! `Connection: close`, so every client connection carries a single request.
+
+use anyhow::{anyhow, Result};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Largest request line and headers accepted from a client
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+
+/// Headers that only concern the connection to the proxy
+const HOP_BY_HOP_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authorization",
+    "proxy-connection",
+    "te",
+    "upgrade",
+];
+
+/// Request of a client, with what has to be sent to its destination
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpProxyRequest {
+    pub host: String,
+    pub port: u16,
+    /// CONNECT tunnel rather than a single plain request
+    pub tunnel: bool,
+    /// Bytes for the destination before relaying: the rewritten request of a plain
+    /// request and anything the client sent after the request head
+    pub initial_data: Vec<u8>,
+}
+
+impl HttpProxyRequest {
+    /// `host:port`, with IPv6 addresses in brackets
+    pub fn target(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// Error responses of the proxy
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HttpProxyError {
+    BadRequest,
+    Forbidden,
+    BadGateway,
+}
+
+impl HttpProxyError {
+    fn status_line(self) -> &'static str {
+        match self {
+            HttpProxyError::BadRequest => "400 Bad Request",
+            HttpProxyError::Forbidden => "403 Forbidden",
+            HttpProxyError::BadGateway => "502 Bad Gateway",
+        }
+    }
+}
+
+/// Read and parse the request head of a client. Malformed requests are answered
+/// with 400 before the error is returned.
+pub async fn accept<S>(stream: &mut S) -> Result<HttpProxyRequest>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (head, rest) = read_head(stream).await?;
+    match parse_request(&head, rest) {
+        Ok(request) => Ok(request),
+        Err(e) => {
+            let _ = send_error(stream, HttpProxyError::BadRequest).await;
+            Err(e)
+        }
+    }
+}
+
+/// Tell a CONNECT client that its tunnel is open
+pub async fn send_established<S>(stream: &mut S) -> Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    stream
+        .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+        .await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+pub async fn send_error<S>(stream: &mut S, error: HttpProxyError) -> Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        error.status_line()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+/// Read up to the end of the request head, returning the head and any bytes after it
+async fn read_head<S>(stream: &mut S) -> Result<(String, Vec<u8>)>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            let rest = buffer.split_off(end + 4);
+            let head = String::from_utf8(buffer)
+                .map_err(|_| anyhow!("HTTP request head is not valid UTF-8"))?;
+            return Ok((head, rest));
+        }
+        if buffer.len() > MAX_HEAD_SIZE {
+            let _ = send_error(stream, HttpProxyError::BadRequest).await;
+            return Err(anyhow!("HTTP request head is too large"));
+        }
+
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(anyhow!("Connection closed before the HTTP request head"));
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+    }
+}
+
+fn parse_request(head: &str, rest: Vec<u8>) -> Result<HttpProxyRequest> {
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().unwrap_or_default();
+    let mut parts = request_line.split(' ');
+    let (Some(method), Some(target), Some(version), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(anyhow!("Invalid HTTP request line '{}'", request_line));
+    };
+    if !version.starts_with("HTTP/1.") {
+        return Err(anyhow!("Unsupported HTTP version '{}'", version));
+    }
+
+    if method.eq_ignore_ascii_case("CONNECT") {
+        let (host, port) = parse_authority(target, None)?;
+        return Ok(HttpProxyRequest {
+            host,
+            port,
+            tunnel: true,
+            initial_data: rest,
+        });
+    }
+
+    let Some(scheme_end) = target.find("://") else {
+        return Err(anyhow!("Expected an absolute URI, got '{}'", target));
+    };
+    if !target[..scheme_end].eq_ignore_ascii_case("http") {
+        return Err(anyhow!("Only http:// URIs can be proxied, use CONNECT"));
+    }
+    let after_scheme = &target[scheme_end + 3..];
+    let authority_end = after_scheme
+        .find(['/', '?', '#'])
+        .unwrap_or(after_scheme.len());
+    let authority = &after_scheme[..authority_end];
+    let path = match &after_scheme[authority_end..] {
+        "" => "/".to_string(),
+        path if path.starts_with('/') => path.to_string(),
+        query => format!("/{}", query),
+    };
+    // Fragments are never sent to servers
+    let path = path.split('#').next().unwrap_or("/");
+    // Credentials in the URI are not forwarded
+    let authority = authority.rsplit('@').next().unwrap_or(authority);
+    let (host, port) = parse_authority(authority, Some(80))?;
+
+    let mut request = format!("{} {} {}\r\n", method, path, version);
+    let mut has_host = false;
+    for line in lines.filter(|line| !line.is_empty()) {
+        let name = line.split(':').next().unwrap_or_default().trim();
+        if HOP_BY_HOP_HEADERS
+            .iter()
+            .any(|header| name.eq_ignore_ascii_case(header))
+        {
+            continue;
+        }
+        has_host |= name.eq_ignore_ascii_case("host");
+        request.push_str(line);
+        request.push_str("\r\n");
+    }
+    if !has_host {
+        request.push_str(&format!("Host: {}\r\n", authority));
+    }
+    request.push_str("Connection: close\r\n\r\n");
+
+    let mut initial_data = request.into_bytes();
+    initial_data.extend_from_slice(&rest);
+
+    Ok(HttpProxyRequest {
+        host,
+        port,
+        tunnel: false,
+        initial_data,
+    })
+}
+
+/// Split `host:port`, where IPv6 hosts are in brackets
+fn parse_authority(authority: &str, default_port: Option<u16>) -> Result<(String, u16)> {
+    let invalid = || anyhow!("Invalid host '{}'", authority);
+
+    let (host, port) = if let Some(bracketed) = authority.strip_prefix('[') {
+        let (host, after) = bracketed.split_once(']').ok_or_else(invalid)?;
+        match after.strip_prefix(':') {
+            Some(port) => (host, Some(port)),
+            None if after.is_empty() => (host, None),
+            None => return Err(invalid()),
+        }
+    } else {
+        match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        }
+    };
+
+    let port = match port {
+        Some(port) => port.parse::<u16>().map_err(|_| invalid())?,
+        None => default_port.ok_or_else(invalid)?,
+    };
+    if host.is_empty() || port == 0 {
+        return Err(invalid());
+    }
+    Ok((host.to_string(), port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// Origin server that answers every connection with the request head it received,
+    /// or echoes the data of CONNECT tunnels
+    async fn start_origin() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut buffer = vec![0u8; 4096];
+                    let n = stream.read(&mut buffer).await.unwrap();
+                    let received = String::from_utf8_lossy(&buffer[..n]).to_string();
+                    if received.starts_with("GET ") {
+                        let response = format!(
+                            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                            received.len(),
+                            received
+                        );
+                        stream.write_all(response.as_bytes()).await.unwrap();
+                    } else {
+                        stream.write_all(&buffer[..n]).await.unwrap();
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    /// Proxy that connects to destinations directly, standing in for SSH channels
+    async fn start_proxy() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut client, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let Ok(request) = accept(&mut client).await else {
+                        return;
+                    };
+                    let mut origin = TcpStream::connect((request.host.as_str(), request.port))
+                        .await
+                        .unwrap();
+                    if request.tunnel {
+                        send_established(&mut client).await.unwrap();
+                    }
+                    origin.write_all(&request.initial_data).await.unwrap();
+                    let _ = tokio::io::copy_bidirectional(&mut client, &mut origin).await;
+                });
+            }
+        });
+        port
+    }
+
+    async fn exchange(proxy_port: u16, request: &[u8]) -> String {
+        let mut client = TcpStream::connect(("127.0.0.1", proxy_port)).await.unwrap();
+        client.write_all(request).await.unwrap();
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.unwrap();
+        String::from_utf8(response).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_forwards_absolute_uri_requests() {
+        let origin = start_origin().await;
+        let proxy = start_proxy().await;
+
+        let request = format!(
+            "GET http://127.0.0.1:{}/path?q=1 HTTP/1.1\r\nHost: 127.0.0.1:{}\r\n\
+             Proxy-Connection: keep-alive\r\nAccept: */*\r\n\r\n",
+            origin, origin
+        );
+        let response = exchange(proxy, request.as_bytes()).await;
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("GET /path?q=1 HTTP/1.1\r\n"));
+        assert!(response.contains("Accept: */*\r\n"));
+        assert!(response.contains("Connection: close\r\n"));
+        assert!(!response.contains("Proxy-Connection"));
+    }
+
+    #[tokio::test]
+    async fn test_tunnels_connect_requests() {
+        let origin = start_origin().await;
+        let proxy = start_proxy().await;
+
+        let mut client = TcpStream::connect(("127.0.0.1", proxy)).await.unwrap();
+        let request = format!("CONNECT 127.0.0.1:{} HTTP/1.1\r\n\r\n", origin);
+        client.write_all(request.as_bytes()).await.unwrap();
+
+        let established = b"HTTP/1.1 200 Connection Established\r\n\r\n";
+        let mut response = vec![0u8; established.len()];
+        client.read_exact(&mut response).await.unwrap();
+        assert_eq!(response, established);
+
+        client.write_all(b"ping").await.unwrap();
+        let mut echoed = [0u8; 4];
+        client.read_exact(&mut echoed).await.unwrap();
+        assert_eq!(&echoed, b"ping");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_malformed_requests() {
+        let proxy = start_proxy().await;
+
+        for request in [
+            "GET /relative HTTP/1.1\r\n\r\n",
+            "GET https://example.com/ HTTP/1.1\r\n\r\n",
+            "CONNECT example.com HTTP/1.1\r\n\r\n",
+        ] {
+            let response = exchange(proxy, request.as_bytes()).await;
+            assert!(
+                response.starts_with("HTTP/1.1 400 Bad Request"),
+                "{}",
+                request
+            );
+        }
+    }
+
+    #[test]
+    fn test_parses_authorities() {
+        assert_eq!(
+            parse_authority("[::1]:8080", None).unwrap(),
+            ("::1".to_string(), 8080)
+        );
+        assert_eq!(
+            parse_authority("example.com", Some(80)).unwrap(),
+            ("example.com".to_string(), 80)
+        );
+        assert!(parse_authority("example.com:http", Some(80)).is_err());
+    }
+}
@@ -0,0 +1,451 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Server side of the SOCKS4, SOCKS4a and SOCKS5 handshakes used by Dynamic tunnels.
+//! Only CONNECT is supported, as SSH can only forward TCP streams.
+
+use anyhow::{anyhow, Result};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::models::ssh::TunnelSocksAuth;
+
+const SOCKS4: u8 = 0x04;
+const SOCKS5: u8 = 0x05;
+const CONNECT: u8 = 0x01;
+
+const NO_AUTH: u8 = 0x00;
+const USERNAME_PASSWORD: u8 = 0x02;
+const NO_ACCEPTABLE_METHODS: u8 = 0xFF;
+const AUTH_VERSION: u8 = 0x01;
+
+/// Longest user id or host name accepted from a SOCKS4 client
+const MAX_SOCKS4_FIELD: usize = 255;
+
+/// CONNECT request of a client that completed the handshake
+#[derive(Debug, Clone, PartialEq)]
+pub struct SocksRequest {
+    pub version: u8,
+    pub host: String,
+    pub port: u16,
+}
+
+impl SocksRequest {
+    /// `host:port`, with IPv6 addresses in brackets
+    pub fn target(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// Outcome sent back to the client for its CONNECT request
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SocksReply {
+    Succeeded,
+    NotAllowed,
+    ConnectionRefused,
+    CommandNotSupported,
+    AddressTypeNotSupported,
+}
+
+impl SocksReply {
+    fn socks5_code(self) -> u8 {
+        match self {
+            SocksReply::Succeeded => 0x00,
+            SocksReply::NotAllowed => 0x02,
+            SocksReply::ConnectionRefused => 0x05,
+            SocksReply::CommandNotSupported => 0x07,
+            SocksReply::AddressTypeNotSupported => 0x08,
+        }
+    }
+
+    fn socks4_code(self) -> u8 {
+        match self {
+            SocksReply::Succeeded => 0x5A,
+            _ => 0x5B,
+        }
+    }
+}
+
+/// Run the handshake up to the client's CONNECT request. Failures that the protocol
+/// can report are answered before the error is returned.
+pub async fn accept<S>(stream: &mut S, auth: Option<&TunnelSocksAuth>) -> Result<SocksRequest>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    match stream.read_u8().await? {
+        SOCKS5 => accept_socks5(stream, auth).await,
+        SOCKS4 => accept_socks4(stream, auth).await,
+        version => Err(anyhow!("Unsupported SOCKS version {}", version)),
+    }
+}
+
+/// Answer the CONNECT request of a client
+pub async fn send_reply<S>(stream: &mut S, version: u8, reply: SocksReply) -> Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    if version == SOCKS4 {
+        // Version 0, status, then a port and address that clients ignore
+        stream
+            .write_all(&[0x00, reply.socks4_code(), 0, 0, 0, 0, 0, 0])
+            .await?;
+    } else {
+        // Bound to 0.0.0.0:0, as the real socket is on the SSH server
+        stream
+            .write_all(&[SOCKS5, reply.socks5_code(), 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+            .await?;
+    }
+    stream.flush().await?;
+    Ok(())
+}
+
+async fn accept_socks5<S>(stream: &mut S, auth: Option<&TunnelSocksAuth>) -> Result<SocksRequest>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let method_count = stream.read_u8().await? as usize;
+    let mut methods = vec![0u8; method_count];
+    stream.read_exact(&mut methods).await?;
+
+    let method = if auth.is_some() {
+        USERNAME_PASSWORD
+    } else {
+        NO_AUTH
+    };
+    if !methods.contains(&method) {
+        stream.write_all(&[SOCKS5, NO_ACCEPTABLE_METHODS]).await?;
+        return Err(anyhow!("SOCKS5 client offered no acceptable auth method"));
+    }
+    stream.write_all(&[SOCKS5, method]).await?;
+
+    if let Some(auth) = auth {
+        authenticate(stream, auth).await?;
+    }
+
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).await?;
+    let [version, command, _, address_type] = header;
+    if version != SOCKS5 {
+        return Err(anyhow!("Invalid SOCKS5 request"));
+    }
+
+    let host = match address_type {
+        0x01 => {
+            let mut octets = [0u8; 4];
+            stream.read_exact(&mut octets).await?;
+            Ipv4Addr::from(octets).to_string()
+        }
+        0x03 => {
+            let len = stream.read_u8().await? as usize;
+            let mut domain = vec![0u8; len];
+            stream.read_exact(&mut domain).await?;
+            String::from_utf8(domain).map_err(|_| anyhow!("Invalid SOCKS5 domain name"))?
+        }
+        0x04 => {
+            let mut octets = [0u8; 16];
+            stream.read_exact(&mut octets).await?;
+            Ipv6Addr::from(octets).to_string()
+        }
+        _ => {
+            send_reply(stream, SOCKS5, SocksReply::AddressTypeNotSupported).await?;
+            return Err(anyhow!("Unsupported SOCKS5 address type {}", address_type));
+        }
+    };
+    let port = stream.read_u16().await?;
+
+    if command != CONNECT {
+        send_reply(stream, SOCKS5, SocksReply::CommandNotSupported).await?;
+        return Err(anyhow!("Unsupported SOCKS5 command {}", command));
+    }
+
+    Ok(SocksRequest {
+        version: SOCKS5,
+        host,
+        port,
+    })
+}
+
+/// Username/password sub-negotiation (RFC 1929)
+async fn authenticate<S>(stream: &mut S, auth: &TunnelSocksAuth) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    if stream.read_u8().await? != AUTH_VERSION {
+        return Err(anyhow!("Invalid SOCKS5 authentication request"));
+    }
+    let username = read_short_field(stream).await?;
+    let password = read_short_field(stream).await?;
+
+    let accepted = constant_time_eq(&username, auth.username.as_bytes())
+        & constant_time_eq(&password, auth.password.as_bytes());
+    stream
+        .write_all(&[AUTH_VERSION, if accepted { 0x00 } else { 0x01 }])
+        .await?;
+    if accepted {
+        Ok(())
+    } else {
+        Err(anyhow!("SOCKS5 authentication failed"))
+    }
+}
+
+async fn accept_socks4<S>(stream: &mut S, auth: Option<&TunnelSocksAuth>) -> Result<SocksRequest>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let command = stream.read_u8().await?;
+    let port = stream.read_u16().await?;
+    let mut octets = [0u8; 4];
+    stream.read_exact(&mut octets).await?;
+    // The user id is not checked, SOCKS4 has no password
+    read_null_terminated(stream).await?;
+
+    // SOCKS4a: 0.0.0.x with x != 0 means a host name follows the user id
+    let host = if octets[..3] == [0, 0, 0] && octets[3] != 0 {
+        let domain = read_null_terminated(stream).await?;
+        String::from_utf8(domain).map_err(|_| anyhow!("Invalid SOCKS4a domain name"))?
+    } else {
+        Ipv4Addr::from(octets).to_string()
+    };
+
+    if auth.is_some() {
+        send_reply(stream, SOCKS4, SocksReply::NotAllowed).await?;
+        return Err(anyhow!("SOCKS4 clients cannot authenticate"));
+    }
+    if command != CONNECT {
+        send_reply(stream, SOCKS4, SocksReply::CommandNotSupported).await?;
+        return Err(anyhow!("Unsupported SOCKS4 command {}", command));
+    }
+
+    Ok(SocksRequest {
+        version: SOCKS4,
+        host,
+        port,
+    })
+}
+
+/// Field with a one byte length prefix
+async fn read_short_field<S>(stream: &mut S) -> Result<Vec<u8>>
+where
+    S: AsyncRead + Unpin,
+{
+    let len = stream.read_u8().await? as usize;
+    let mut field = vec![0u8; len];
+    stream.read_exact(&mut field).await?;
+    Ok(field)
+}
+
+async fn read_null_terminated<S>(stream: &mut S) -> Result<Vec<u8>>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut field = Vec::new();
+    loop {
+        match stream.read_u8().await? {
+            0 => return Ok(field),
+            _ if field.len() >= MAX_SOCKS4_FIELD => {
+                return Err(anyhow!("SOCKS4 request field is too long"));
+            }
+            byte => field.push(byte),
+        }
+    }
+}
+
+/// Compare credentials without returning early on the first differing byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ssh::TunnelAccessControl;
+    use tokio::io::duplex;
+
+    fn credentials() -> TunnelSocksAuth {
+        TunnelSocksAuth {
+            username: "user".to_string(),
+            password: "secret".to_string(),
+        }
+    }
+
+    async fn handshake(
+        input: &[u8],
+        auth: Option<&TunnelSocksAuth>,
+    ) -> (Result<SocksRequest>, Vec<u8>) {
+        let (mut client, mut server) = duplex(1024);
+        client.write_all(input).await.unwrap();
+        let result = accept(&mut server, auth).await;
+        drop(server);
+        let mut output = Vec::new();
+        client.read_to_end(&mut output).await.unwrap();
+        (result, output)
+    }
+
+    #[tokio::test]
+    async fn test_socks5_with_password() {
+        let mut input = vec![0x05, 0x02, 0x00, 0x02];
+        input.extend_from_slice(&[0x01, 4, b'u', b's', b'e', b'r', 6]);
+        input.extend_from_slice(b"secret");
+        input.extend_from_slice(&[0x05, 0x01, 0x00, 0x03, 11]);
+        input.extend_from_slice(b"example.com");
+        input.extend_from_slice(&443u16.to_be_bytes());
+
+        let (result, output) = handshake(&input, Some(&credentials())).await;
+        let request = result.unwrap();
+        assert_eq!(request.target(), "example.com:443");
+        assert_eq!(output, [0x05, 0x02, 0x01, 0x00]);
+
+        input[11] = b'X';
+        let (result, output) = handshake(&input, Some(&credentials())).await;
+        assert!(result.is_err());
+        assert_eq!(output, [0x05, 0x02, 0x01, 0x01]);
+    }
+
+    #[tokio::test]
+    async fn test_socks5_rejects_missing_auth_and_udp() {
+        let (result, output) = handshake(&[0x05, 0x01, 0x00], Some(&credentials())).await;
+        assert!(result.is_err());
+        assert_eq!(output, [0x05, 0xFF]);
+
+        let udp_associate = [0x05, 0x01, 0x00, 0x05, 0x03, 0x00, 0x01, 0, 0, 0, 0, 0, 0];
+        let (result, output) = handshake(&udp_associate, None).await;
+        assert!(result.is_err());
+        assert_eq!(output[..2], [0x05, 0x00]);
+        assert_eq!(output[2..4], [0x05, 0x07]);
+    }
+
+    #[tokio::test]
+    async fn test_socks4_and_socks4a() {
+        let mut input = vec![0x04, 0x01, 0x00, 0x50, 10, 0, 0, 1];
+        input.extend_from_slice(b"me\0");
+        let (result, _) = handshake(&input, None).await;
+        assert_eq!(result.unwrap().target(), "10.0.0.1:80");
+
+        let mut input = vec![0x04, 0x01, 0x00, 0x50, 0, 0, 0, 1, 0];
+        input.extend_from_slice(b"example.org\0");
+        let (result, _) = handshake(&input, None).await;
+        assert_eq!(result.unwrap().host, "example.org");
+
+        let (result, output) = handshake(&input, Some(&credentials())).await;
+        assert!(result.is_err());
+        assert_eq!(output[..2], [0x00, 0x5B]);
+    }
+
+    #[test]
+    fn test_access_control_rules() {
+        let access = TunnelAccessControl {
+            allow: vec![
+                "*.example.com".to_string(),
+                "10.0.0.0/8".to_string(),
+                "2001:db8::/32".to_string(),
+            ],
+            deny: vec!["secret.example.com".to_string(), "10.0.0.1".to_string()],
+        };
+        assert!(access.validate().is_ok());
+        let resolved = ["93.184.216.34".parse().unwrap()];
+        assert!(access.allows("www.Example.com", &resolved));
+        assert!(access.allows("10.20.30.40", &[]));
+        assert!(access.allows("::ffff:10.1.2.3", &[]));
+        assert!(access.allows("[2001:db8::1]", &[]));
+        assert!(!access.allows("example.com", &[]));
+        assert!(!access.allows("secret.example.com", &[]));
+        assert!(!access.allows("10.0.0.1", &[]));
+        assert!(!access.allows("192.168.1.1", &[]));
+        assert!(TunnelAccessControl::default().allows("anything", &[]));
+
+        let invalid = TunnelAccessControl {
+            allow: vec!["10.0.0.0/33".to_string()],
+            deny: Vec::new(),
+        };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_access_control_checks_names_by_their_addresses() {
+        let metadata = "169.254.169.254".parse().unwrap();
+        let private = "10.1.2.3".parse().unwrap();
+        let public = "93.184.216.34".parse().unwrap();
+        let access = TunnelAccessControl {
+            allow: Vec::new(),
+            deny: vec!["10.0.0.0/8".to_string(), "169.254.169.254".to_string()],
+        };
+
+        // Shorthand and numeric IPv4 forms are addresses, not names
+        for host in [
+            "10.1",
+            "167772161",
+            "0x0a.0.0.1",
+            "012.0.0.1",
+            "169.254.43518",
+        ] {
+            assert!(!access.needs_resolution(host), "{}", host);
+            assert!(!access.allows(host, &[]), "{}", host);
+        }
+        assert!(access.allows("11.0.0.1", &[]));
+
+        assert!(access.needs_resolution("metadata.internal"));
+        assert!(!access.allows("metadata.internal", &[metadata]));
+        assert!(!access.allows("example.com", &[public, private]));
+        assert!(access.allows("example.com", &[public]));
+        // Names that don't resolve can't be checked against the denied networks
+        assert!(!access.allows("unknown.invalid", &[]));
+
+        let access = TunnelAccessControl {
+            allow: vec!["10.0.0.0/8".to_string(), "*.corp".to_string()],
+            deny: Vec::new(),
+        };
+        assert!(access.allows("db.internal", &[private]));
+        assert!(!access.allows("db.internal", &[private, public]));
+        assert!(!access.allows("db.internal", &[]));
+        assert!(access.allows("wiki.corp", &[]));
+        assert!(!TunnelAccessControl::default().needs_resolution("example.com"));
+
+        // Between them, the allowed networks hold every address of a dual-stack name
+        let access = TunnelAccessControl {
+            allow: vec!["127.0.0.0/8".to_string(), "::1".to_string()],
+            deny: Vec::new(),
+        };
+        let loopback = ["127.0.0.1".parse().unwrap(), "::1".parse().unwrap()];
+        assert!(access.allows("localhost", &loopback));
+        assert!(!access.allows("localhost", &[loopback[0], public]));
+    }
+
+    #[test]
+    fn test_ip_literal_forms() {
+        use crate::models::ssh::tunnel::ip_literal;
+
+        let literal = |host: &str| ip_literal(host).map(|address| address.to_string());
+        assert_eq!(literal("10.1").as_deref(), Some("10.0.0.1"));
+        assert_eq!(literal("10.1.2").as_deref(), Some("10.1.0.2"));
+        assert_eq!(literal("2130706433").as_deref(), Some("127.0.0.1"));
+        assert_eq!(literal("0x7f.1").as_deref(), Some("127.0.0.1"));
+        assert_eq!(literal("0177.0.0.01").as_deref(), Some("127.0.0.1"));
+        assert_eq!(literal("127.0.0.1.").as_deref(), Some("127.0.0.1"));
+        assert_eq!(literal("[::1]").as_deref(), Some("::1"));
+        assert_eq!(literal("1.256.0.0"), None);
+        assert_eq!(literal("10.0.0.0.1"), None);
+        assert_eq!(literal("08.0.0.1"), None);
+        assert_eq!(literal("+1.2.3.4"), None);
+        assert_eq!(literal("1e100.net"), None);
+        assert_eq!(literal("deadbeef"), None);
+    }
+}
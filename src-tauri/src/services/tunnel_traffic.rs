@@ -0,0 +1,274 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Traffic accounting for running tunnels: byte counters, throughput and the client
+//! connections being proxied.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use crate::models::ssh::{TunnelConnectionInfo, TunnelMetrics};
+
+/// Traffic of one tunnel, shared by all its runs
+#[derive(Debug, Default)]
+pub struct TunnelTraffic {
+    total_connections: AtomicU64,
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    connections: Mutex<HashMap<String, Arc<TrackedConnection>>>,
+    last_sample: Mutex<Option<RateSample>>,
+}
+
+#[derive(Debug)]
+struct TrackedConnection {
+    /// Registration order, for listing connections oldest first
+    sequence: u64,
+    id: String,
+    peer_address: String,
+    target: Mutex<Option<String>>,
+    started_at: chrono::DateTime<chrono::Utc>,
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    cancel_token: CancellationToken,
+}
+
+/// Counters at the previous snapshot, to derive throughput from
+#[derive(Debug, Clone, Copy)]
+struct RateSample {
+    at: Instant,
+    bytes_in: u64,
+    bytes_out: u64,
+    bytes_in_per_sec: f64,
+    bytes_out_per_sec: f64,
+}
+
+/// A registered client connection, removed from the tunnel's list when dropped
+pub struct ConnectionGuard {
+    traffic: Arc<TunnelTraffic>,
+    connection: Arc<TrackedConnection>,
+}
+
+impl TunnelTraffic {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track a new client connection, closed when `parent_token` is cancelled or the
+    /// connection is killed
+    pub fn register(
+        self: &Arc<Self>,
+        peer_address: String,
+        parent_token: &CancellationToken,
+    ) -> ConnectionGuard {
+        let sequence = self.total_connections.fetch_add(1, Ordering::Relaxed);
+        let connection = Arc::new(TrackedConnection {
+            sequence,
+            id: uuid::Uuid::new_v4().to_string(),
+            peer_address,
+            target: Mutex::new(None),
+            started_at: chrono::Utc::now(),
+            bytes_in: AtomicU64::new(0),
+            bytes_out: AtomicU64::new(0),
+            cancel_token: parent_token.child_token(),
+        });
+
+        self.connections
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(connection.id.clone(), connection.clone());
+
+        ConnectionGuard {
+            traffic: self.clone(),
+            connection,
+        }
+    }
+
+    /// Close an active connection, returning whether it was found
+    pub fn close_connection(&self, connection_id: &str) -> bool {
+        let connections = self.connections.lock().unwrap_or_else(|e| e.into_inner());
+        match connections.get(connection_id) {
+            Some(connection) => {
+                connection.cancel_token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Current counters and connections. Throughput is averaged since the previous
+    /// snapshot, which is reused when that was less than a second ago.
+    pub fn snapshot(&self, tunnel_id: &str) -> TunnelMetrics {
+        let bytes_in = self.bytes_in.load(Ordering::Relaxed);
+        let bytes_out = self.bytes_out.load(Ordering::Relaxed);
+
+        let (bytes_in_per_sec, bytes_out_per_sec) = {
+            let mut last_sample = self.last_sample.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            let sample = match *last_sample {
+                Some(last) if now.duration_since(last.at).as_secs_f64() < 1.0 => last,
+                Some(last) => {
+                    let elapsed = now.duration_since(last.at).as_secs_f64();
+                    RateSample {
+                        at: now,
+                        bytes_in,
+                        bytes_out,
+                        bytes_in_per_sec: bytes_in.saturating_sub(last.bytes_in) as f64 / elapsed,
+                        bytes_out_per_sec: bytes_out.saturating_sub(last.bytes_out) as f64
+                            / elapsed,
+                    }
+                }
+                None => RateSample {
+                    at: now,
+                    bytes_in,
+                    bytes_out,
+                    bytes_in_per_sec: 0.0,
+                    bytes_out_per_sec: 0.0,
+                },
+            };
+            *last_sample = Some(sample);
+            (sample.bytes_in_per_sec, sample.bytes_out_per_sec)
+        };
+
+        let mut tracked: Vec<Arc<TrackedConnection>> = self
+            .connections
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect();
+        tracked.sort_by_key(|connection| connection.sequence);
+        let connections: Vec<TunnelConnectionInfo> = tracked
+            .iter()
+            .map(|connection| TunnelConnectionInfo {
+                id: connection.id.clone(),
+                peer_address: connection.peer_address.clone(),
+                target: connection
+                    .target
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clone(),
+                started_at: connection.started_at,
+                bytes_in: connection.bytes_in.load(Ordering::Relaxed),
+                bytes_out: connection.bytes_out.load(Ordering::Relaxed),
+            })
+            .collect();
+
+        TunnelMetrics {
+            tunnel_id: tunnel_id.to_string(),
+            active_connections: connections.len(),
+            total_connections: self.total_connections.load(Ordering::Relaxed),
+            bytes_in,
+            bytes_out,
+            bytes_in_per_sec,
+            bytes_out_per_sec,
+            connections,
+        }
+    }
+}
+
+impl ConnectionGuard {
+    /// Token cancelled when the connection should be closed
+    pub fn cancel_token(&self) -> &CancellationToken {
+        &self.connection.cancel_token
+    }
+
+    /// Where the connection goes, once known
+    pub fn set_target(&self, target: String) {
+        *self
+            .connection
+            .target
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Some(target);
+    }
+
+    /// Count bytes received from the remote side
+    pub fn record_in(&self, bytes: usize) {
+        self.connection
+            .bytes_in
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+        self.traffic
+            .bytes_in
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Count bytes sent by the local client
+    pub fn record_out(&self, bytes: usize) {
+        self.connection
+            .bytes_out
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+        self.traffic
+            .bytes_out
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.traffic
+            .connections
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.connection.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_traffic_and_drops_closed_connections() {
+        let traffic = Arc::new(TunnelTraffic::new());
+        let run_token = CancellationToken::new();
+
+        let first = traffic.register("127.0.0.1:5000".to_string(), &run_token);
+        let second = traffic.register("127.0.0.1:5001".to_string(), &run_token);
+        second.set_target("example.com:443".to_string());
+        first.record_out(100);
+        first.record_in(1000);
+        second.record_in(24);
+
+        let metrics = traffic.snapshot("tunnel");
+        assert_eq!(metrics.active_connections, 2);
+        assert_eq!(metrics.total_connections, 2);
+        assert_eq!(metrics.bytes_in, 1024);
+        assert_eq!(metrics.bytes_out, 100);
+        assert_eq!(metrics.connections[0].bytes_in, 1000);
+        assert_eq!(
+            metrics.connections[1].target.as_deref(),
+            Some("example.com:443")
+        );
+
+        assert!(traffic.close_connection(&metrics.connections[0].id));
+        assert!(first.cancel_token().is_cancelled());
+        assert!(!second.cancel_token().is_cancelled());
+        drop(first);
+
+        let metrics = traffic.snapshot("tunnel");
+        assert_eq!(metrics.active_connections, 1);
+        assert_eq!(metrics.total_connections, 2);
+        assert_eq!(metrics.bytes_in, 1024);
+        assert!(!traffic.close_connection("missing"));
+
+        run_token.cancel();
+        assert!(second.cancel_token().is_cancelled());
+    }
+}
@@ -5,7 +5,9 @@ use crate::services::{
     history::HistoryManager,
     saved_command::SavedCommandService,
     sftp::{sync::SyncService as SFTPSyncService, transfer::TransferManager, SFTPService},
-    ssh::{SSHConnectionPool, SSHKeyService, SSHService},
+    ssh::{
+        KeyboardInteractiveService, KnownHostsService, SSHConnectionPool, SSHKeyService, SSHService,
+    },
     sync::SyncService,
     terminal::TerminalManager,
     tunnel::TunnelService,
@@ -21,6 +23,8 @@ pub struct AppState {
     pub ssh_service: Arc<SSHService>,
     pub ssh_key_service: Arc<Mutex<SSHKeyService>>,
     pub ssh_connection_pool: Arc<SSHConnectionPool>,
+    pub known_hosts_service: Arc<KnownHostsService>,
+    pub keyboard_interactive_service: Arc<KeyboardInteractiveService>,
     pub tunnel_service: TunnelService,
     pub saved_command_service: SavedCommandService,
     pub sync_service: Arc<SyncService>,
@@ -34,7 +38,7 @@ pub struct AppState {
 
 impl AppState {
     /// Create new app state with initialized database service
-    pub async fn new(_app_handle: AppHandle) -> Result<Self, String> {
+    pub async fn new(app_handle: AppHandle) -> Result<Self, String> {
         let config = DatabaseServiceConfig::default();
         let database_service = DatabaseService::new(config)
             .await
@@ -45,9 +49,33 @@ impl AppState {
         let auth_service = AuthService::new(database_service_arc.clone());
         let ssh_key_service =
             Arc::new(Mutex::new(SSHKeyService::new(database_service_arc.clone())));
-        let ssh_service = SSHService::new(database_service_arc.clone(), ssh_key_service.clone());
+        let known_hosts_service = Arc::new(KnownHostsService::new(
+            database_service_arc.clone(),
+            Some(app_handle.clone()),
+        ));
+        let keyboard_interactive_service =
+            Arc::new(KeyboardInteractiveService::new(Some(app_handle.clone())));
+        let ssh_connection_pool = Arc::new(SSHConnectionPool::new(
+            30,
+            database_service_arc.clone(),
+            known_hosts_service.clone(),
+            keyboard_interactive_service.clone(),
+            Some(app_handle.clone()),
+        ));
+        ssh_connection_pool.start_idle_cleanup();
+        let ssh_service = SSHService::new(
+            database_service_arc.clone(),
+            ssh_key_service.clone(),
+            known_hosts_service.clone(),
+            ssh_connection_pool.clone(),
+        );
         let ssh_service_arc = Arc::new(ssh_service);
-        let tunnel_service = TunnelService::new_with_auto_start(database_service_arc.clone()).await;
+        let tunnel_service = TunnelService::new_with_auto_start(
+            database_service_arc.clone(),
+            ssh_connection_pool.clone(),
+        )
+        .await;
+        tunnel_service.start_metrics_events(app_handle.clone());
         let saved_command_service = SavedCommandService::new(database_service_arc.clone());
 
         let sync_service = Arc::new(SyncService::new(database_service_arc.clone()));
@@ -59,13 +87,13 @@ impl AppState {
         let terminal_manager = TerminalManager::new_with_ssh_key_service(
             database_service_arc.clone(),
             ssh_key_service.clone(),
+            ssh_connection_pool.clone(),
         );
 
         let auth_session_manager = Arc::new(Mutex::new(AuthSessionManager::new(
             database_service_arc.clone(),
         )));
 
-        let ssh_connection_pool = Arc::new(SSHConnectionPool::default());
         let sftp_service = Arc::new(SFTPService::new(
             ssh_service_arc.clone(),
             ssh_key_service.clone(),
@@ -82,6 +110,8 @@ impl AppState {
             ssh_service: ssh_service_arc,
             ssh_key_service,
             ssh_connection_pool,
+            known_hosts_service,
+            keyboard_interactive_service,
             tunnel_service,
             saved_command_service,
             sync_service,
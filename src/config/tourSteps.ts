@@ -89,7 +89,7 @@ export const TOUR_STEPS: TourStep[] = [
     target: '[data-tour="tunnels-btn"]',
     title: "SSH Tunnel Manager",
     description:
-      "Create and manage SSH tunnels (port forwarding). Supports Local, Remote, and Dynamic (SOCKS) tunnels.",
+      "Create and manage SSH tunnels (port forwarding). Supports Local, Remote, Dynamic (SOCKS) and HTTP proxy tunnels.",
     position: "bottom",
     highlight: true,
     spotlightPadding: 4,
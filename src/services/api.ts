@@ -98,6 +98,8 @@ class ApiClient {
       update_ssh_key: ["id", "request"],
       delete_ssh_key: ["id", "force"],
       count_profiles_using_key: ["keyId"],
+      respond_host_key_verification: ["requestId", "accept"],
+      respond_keyboard_interactive: ["requestId", "responses"],
       import_ssh_key_from_file: [
         "name",
         "filePath",
@@ -110,6 +112,8 @@ class ApiClient {
       start_tunnel: ["id"],
       stop_tunnel: ["id"],
       get_tunnel_status: ["id"],
+      get_tunnel_metrics: ["id"],
+      close_tunnel_connection: ["id", "connectionId"],
       get_saved_command: ["id"],
       update_saved_command: ["id", "request"],
       delete_saved_command: ["id"],
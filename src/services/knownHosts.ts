@@ -0,0 +1,51 @@
+/*
+ * Kerminal - Modern Terminal Emulator & SSH Manager
+ * Copyright (C) 2026 Bùi Thanh Xuân (klpod221)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+
+import { api } from "./api";
+import type { HostKeyVerificationRequest } from "../types/ssh";
+
+/**
+ * Answer a pending host key verification
+ * @param requestId - ID of the "ssh-host-key-verification" event
+ * @param accept - Whether to trust the key and continue connecting
+ */
+export async function respondHostKeyVerification(
+  requestId: string,
+  accept: boolean,
+): Promise<void> {
+  return await api.callRaw<void>(
+    "respond_host_key_verification",
+    requestId,
+    accept,
+  );
+}
+
+/**
+ * Listen for servers presenting a host key that is not trusted yet
+ * @param callback - Called with each verification request
+ * @returns Unlisten function
+ */
+export async function listenHostKeyVerification(
+  callback: (request: HostKeyVerificationRequest) => void,
+): Promise<() => void> {
+  return await api.listen<HostKeyVerificationRequest>(
+    "ssh-host-key-verification",
+    callback,
+  );
+}
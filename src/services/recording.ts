@@ -17,22 +17,35 @@
  */
 
 import { api } from "./api";
-import type { SessionRecording } from "../types/recording";
+import type {
+  RecordingEdit,
+  RecordingOptions,
+  SessionRecording,
+} from "../types/recording";
 
 export async function startRecording(
   terminalId: string,
   sessionName?: string,
   width?: number,
   height?: number,
+  options?: RecordingOptions,
 ): Promise<string> {
   return await api.call("start_recording", {
     terminalId,
     sessionName,
     width,
     height,
+    options,
   });
 }
 
+export async function addRecordingMarker(
+  terminalId: string,
+  label?: string,
+): Promise<void> {
+  return await api.call("add_recording_marker", { terminalId, label });
+}
+
 export async function stopRecording(
   terminalId: string,
 ): Promise<SessionRecording> {
@@ -54,6 +67,26 @@ export async function exportRecording(
   return await api.call("export_recording", { recordingId, exportPath });
 }
 
+export async function editRecording(
+  recordingId: string,
+  edits: RecordingEdit[],
+  sessionName?: string,
+): Promise<SessionRecording> {
+  return await api.call("edit_recording", { recordingId, edits, sessionName });
+}
+
+export async function concatRecordings(
+  recordingIds: string[],
+  sessionName?: string,
+  gapSecs?: number,
+): Promise<SessionRecording> {
+  return await api.call("concat_recordings", {
+    recordingIds,
+    sessionName,
+    gapSecs,
+  });
+}
+
 export async function readCastFile(filePath: string): Promise<string> {
   return await api.call("read_cast_file", { filePath });
 }
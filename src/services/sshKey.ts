@@ -20,6 +20,9 @@ import { api } from "./api";
 import type {
   SSHKey,
   CreateSSHKeyRequest,
+  DeployPublicKeyRequest,
+  DeployPublicKeyResult,
+  GenerateSSHKeyRequest,
   UpdateSSHKeyRequest,
 } from "../types/ssh";
 
@@ -34,6 +37,28 @@ export async function createSSHKey(
   return await api.call<SSHKey>("create_ssh_key", request);
 }
 
+/**
+ * Generate a new SSH keypair
+ * @param request - Key type, size, passphrase and optional export path
+ * @returns Generated SSH key
+ */
+export async function generateSSHKey(
+  request: GenerateSSHKeyRequest,
+): Promise<SSHKey> {
+  return await api.call<SSHKey>("generate_ssh_key", request);
+}
+
+/**
+ * Append a key's public key to a profile host's authorized_keys (ssh-copy-id)
+ * @param request - Profile, key and whether to switch the profile to the key
+ * @returns Where the key was installed and whether the profile changed
+ */
+export async function deployPublicKey(
+  request: DeployPublicKeyRequest,
+): Promise<DeployPublicKeyResult> {
+  return await api.call<DeployPublicKeyResult>("deploy_public_key", request);
+}
+
 /**
  * Get all SSH keys
  * @returns List of all SSH keys
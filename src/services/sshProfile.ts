@@ -26,6 +26,13 @@ import type {
   DeleteGroupAction,
   CreateSSHGroupRequest,
   UpdateSSHGroupRequest,
+  MultiHostExecRequest,
+  MultiHostExecResult,
+  HostExecOutput,
+  HostExecFinished,
+  ExecExportFormat,
+  KeyboardInteractiveRequest,
+  CertificateExpiryWarning,
 } from "../types/ssh";
 
 /**
@@ -185,6 +192,17 @@ export async function getSSHConfigHosts(): Promise<SSHConfigHost[]> {
   return await api.call("get_ssh_config_hosts");
 }
 
+/**
+ * Resolve the effective settings of a host from ~/.ssh/config, like `ssh -G`
+ * @param host - Host name or alias
+ * @returns Resolved SSH config host
+ */
+export async function resolveSSHConfigHost(
+  host: string,
+): Promise<SSHConfigHost> {
+  return await api.call("resolve_ssh_config_host", { host });
+}
+
 /**
  * Test SSH connection
  * @param request - SSH connection test request
@@ -202,3 +220,88 @@ export async function testSSHConnection(request: {
 }): Promise<void> {
   return await api.callRaw("test_ssh_connection", { request });
 }
+
+/**
+ * Run a command on several profiles or a whole group
+ * @param request - Hosts, command, concurrency and per-host timeout
+ * @returns Per-host output and exit status, in request order
+ */
+export async function execOnHosts(
+  request: MultiHostExecRequest,
+): Promise<MultiHostExecResult> {
+  return await api.call<MultiHostExecResult>("exec_on_hosts", request);
+}
+
+/**
+ * Render a multi-host exec result for saving
+ * @param result - Result returned by execOnHosts
+ * @param format - "json" or "csv"
+ * @returns The exported document
+ */
+export async function exportExecResult(
+  result: MultiHostExecResult,
+  format: ExecExportFormat,
+): Promise<string> {
+  return await api.callRaw<string>("export_exec_result", { result, format });
+}
+
+/**
+ * Listen to partial output of multi-host exec runs
+ */
+export async function listenToExecOutput(
+  callback: (data: HostExecOutput) => void,
+): Promise<() => void> {
+  return await api.listen<HostExecOutput>("multi-host-exec-output", callback);
+}
+
+/**
+ * Listen to hosts finishing in multi-host exec runs
+ */
+export async function listenToExecHostFinished(
+  callback: (data: HostExecFinished) => void,
+): Promise<() => void> {
+  return await api.listen<HostExecFinished>(
+    "multi-host-exec-host-finished",
+    callback,
+  );
+}
+
+/**
+ * Answer a keyboard-interactive prompt round
+ * @param requestId - ID of the "ssh-keyboard-interactive" event
+ * @param responses - One answer per prompt, or null to cancel authentication
+ */
+export async function respondKeyboardInteractive(
+  requestId: string,
+  responses: string[] | null,
+): Promise<void> {
+  return await api.callRaw<void>(
+    "respond_keyboard_interactive",
+    requestId,
+    responses,
+  );
+}
+
+/**
+ * Listen to keyboard-interactive prompt rounds (passwords, OTP codes)
+ */
+export async function listenToKeyboardInteractive(
+  callback: (data: KeyboardInteractiveRequest) => void,
+): Promise<() => void> {
+  return await api.listen<KeyboardInteractiveRequest>(
+    "ssh-keyboard-interactive",
+    callback,
+  );
+}
+
+/**
+ * Listen to warnings about certificates that expired or expire soon
+ */
+export async function listenToCertificateExpiry(
+  callback: (data: CertificateExpiryWarning) => void,
+): Promise<() => void> {
+  return await api.listen<CertificateExpiryWarning>(
+    "ssh-certificate-expiry",
+    callback,
+  );
+}
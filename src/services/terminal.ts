@@ -27,12 +27,24 @@ import type {
   TerminalInfo,
   TerminalData,
   TerminalLatency,
+  TerminalStateChanged,
+  BroadcastGroup,
+  BroadcastWriteFailure,
+  PersistentSessionInfo,
+  ScrollbackConfig,
+  ScrollbackStats,
+  ScrollbackMatch,
+  SearchScrollbackRequest,
+  DumpScrollbackRequest,
+  DumpScrollbackResult,
 } from "../types/panel";
+import type { SessionLogConfig } from "../types/terminalProfile";
 
 let outputUnlisten: (() => void) | null = null;
 let titleUnlisten: (() => void) | null = null;
 let exitUnlisten: (() => void) | null = null;
 let latencyUnlisten: (() => void) | null = null;
+let stateUnlisten: (() => void) | null = null;
 
 /**
  * Create a new local terminal
@@ -41,12 +53,14 @@ export async function createTerminal(
   shell?: string,
   workingDir?: string,
   title?: string,
+  persistent = false,
 ): Promise<CreateTerminalResponse> {
   try {
     return await api.call<CreateTerminalResponse>("create_terminal", {
       shell,
       workingDir,
       title,
+      persistent,
     });
   } catch (error) {
     console.error("Failed to create terminal:", error);
@@ -103,9 +117,12 @@ export async function createSSHConfigTerminal(
  */
 export async function writeToTerminal(
   request: WriteTerminalRequest,
-): Promise<void> {
+): Promise<BroadcastWriteFailure[]> {
   try {
-    return await api.call<void>("write_to_terminal", request);
+    return await api.call<BroadcastWriteFailure[]>(
+      "write_to_terminal",
+      request,
+    );
   } catch (error) {
     console.error("Failed to write to terminal:", error);
     throw error;
@@ -117,9 +134,9 @@ export async function writeToTerminal(
  */
 export async function writeBatchToTerminal(
   requests: WriteTerminalRequest[],
-): Promise<void> {
+): Promise<BroadcastWriteFailure[]> {
   try {
-    return await api.call<void>("write_batch_to_terminal", {
+    return await api.call<BroadcastWriteFailure[]>("write_batch_to_terminal", {
       requests,
     });
   } catch (error) {
@@ -128,6 +145,143 @@ export async function writeBatchToTerminal(
   }
 }
 
+/**
+ * Create an input broadcast group: input written to any member is mirrored to the others
+ */
+export async function createBroadcastGroup(
+  name: string,
+  terminalIds: string[] = [],
+): Promise<BroadcastGroup> {
+  return await api.call<BroadcastGroup>("create_broadcast_group", {
+    name,
+    terminalIds,
+  });
+}
+
+/**
+ * Delete an input broadcast group
+ */
+export async function deleteBroadcastGroup(name: string): Promise<void> {
+  return await api.callRaw<void>("delete_broadcast_group", { name });
+}
+
+/**
+ * Add terminals to an input broadcast group
+ */
+export async function addBroadcastMembers(
+  name: string,
+  terminalIds: string[],
+): Promise<BroadcastGroup> {
+  return await api.call<BroadcastGroup>("add_broadcast_members", {
+    name,
+    terminalIds,
+  });
+}
+
+/**
+ * Remove terminals from an input broadcast group
+ */
+export async function removeBroadcastMembers(
+  name: string,
+  terminalIds: string[],
+): Promise<BroadcastGroup> {
+  return await api.call<BroadcastGroup>("remove_broadcast_members", {
+    name,
+    terminalIds,
+  });
+}
+
+/**
+ * Pause or resume a member of an input broadcast group
+ */
+export async function pauseBroadcastMember(
+  name: string,
+  terminalId: string,
+  paused: boolean,
+): Promise<BroadcastGroup> {
+  return await api.call<BroadcastGroup>("pause_broadcast_member", {
+    name,
+    terminalId,
+    paused,
+  });
+}
+
+/**
+ * List the input broadcast groups
+ */
+export async function listBroadcastGroups(): Promise<BroadcastGroup[]> {
+  return await api.call<BroadcastGroup[]>("list_broadcast_groups");
+}
+
+/**
+ * Search a terminal's scrollback (kept by the backend, ANSI stripped) by regex
+ */
+export async function searchTerminalScrollback(
+  request: SearchScrollbackRequest,
+): Promise<ScrollbackMatch[]> {
+  return await api.call<ScrollbackMatch[]>(
+    "search_terminal_scrollback",
+    request,
+  );
+}
+
+/**
+ * Save a range of a terminal's scrollback to a file
+ */
+export async function dumpTerminalScrollback(
+  request: DumpScrollbackRequest,
+): Promise<DumpScrollbackResult> {
+  return await api.call<DumpScrollbackResult>(
+    "dump_terminal_scrollback",
+    request,
+  );
+}
+
+/**
+ * Get the size of a terminal's scrollback
+ */
+export async function getTerminalScrollbackStats(
+  terminalId: string,
+): Promise<ScrollbackStats> {
+  return await api.callRaw<ScrollbackStats>("get_terminal_scrollback_stats", {
+    terminalId,
+  });
+}
+
+/**
+ * Get the scrollback limits
+ */
+export async function getScrollbackConfig(): Promise<ScrollbackConfig> {
+  return await api.call<ScrollbackConfig>("get_scrollback_config");
+}
+
+/**
+ * Set the scrollback limits of new and open terminals
+ */
+export async function setScrollbackConfig(
+  config: ScrollbackConfig,
+): Promise<void> {
+  return await api.callRaw<void>("set_scrollback_config", { config });
+}
+
+/**
+ * Get the session logging of terminals opened from ~/.ssh/config hosts
+ */
+export async function getSSHConfigSessionLog(): Promise<
+  SessionLogConfig | null
+> {
+  return await api.call<SessionLogConfig | null>("get_ssh_config_session_log");
+}
+
+/**
+ * Set the session logging of terminals opened from ~/.ssh/config hosts
+ */
+export async function setSSHConfigSessionLog(
+  config: SessionLogConfig | null,
+): Promise<void> {
+  return await api.callRaw<void>("set_ssh_config_session_log", { config });
+}
+
 /**
  * Resize a terminal
  */
@@ -164,6 +318,44 @@ export async function closeTerminal(terminalId: string): Promise<void> {
   }
 }
 
+/**
+ * Close a persistent terminal but keep its shell running in the session daemon
+ */
+export async function detachTerminal(terminalId: string): Promise<void> {
+  await api.callRaw<void>("detach_terminal", { terminalId });
+  terminalCache.invalidateTerminal(terminalId);
+}
+
+/**
+ * List the shells owned by the session daemon, e.g. to reattach after a restart
+ */
+export async function listPersistentSessions(): Promise<
+  PersistentSessionInfo[]
+> {
+  return await api.call<PersistentSessionInfo[]>("list_persistent_sessions");
+}
+
+/**
+ * Reattach to a shell owned by the session daemon, replaying its recent output
+ */
+export async function attachPersistentSession(
+  sessionId: string,
+  title?: string,
+): Promise<CreateTerminalResponse> {
+  return await api.call<CreateTerminalResponse>("attach_persistent_session", {
+    sessionId,
+    title,
+  });
+}
+
+/**
+ * Kill a shell owned by the session daemon
+ */
+export async function killPersistentSession(sessionId: string): Promise<void> {
+  await api.callRaw<void>("kill_persistent_session", { sessionId });
+  terminalCache.invalidateTerminal(sessionId);
+}
+
 /**
  * Get information about a specific terminal (cached)
  */
@@ -248,6 +440,19 @@ export async function listenToTerminalLatency(
   return latencyUnlisten;
 }
 
+/**
+ * Listen to terminal state changes (e.g. SSH reconnect attempts)
+ */
+export async function listenToTerminalStateChanged(
+  callback: (data: TerminalStateChanged) => void,
+): Promise<() => void> {
+  stateUnlisten = await api.listen<TerminalStateChanged>(
+    "terminal-state-changed",
+    callback,
+  );
+  return stateUnlisten;
+}
+
 /**
  * Cleanup all terminal listeners
  */
@@ -268,4 +473,8 @@ export const cleanupTerminalListeners = (): void => {
     latencyUnlisten();
     latencyUnlisten = null;
   }
+  if (stateUnlisten) {
+    stateUnlisten();
+    stateUnlisten = null;
+  }
 };
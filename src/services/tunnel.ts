@@ -22,7 +22,8 @@ import type {
   TunnelWithStatus,
   CreateSSHTunnelRequest,
   UpdateSSHTunnelRequest,
-  TunnelStatus,
+  TunnelStatusInfo,
+  TunnelMetrics,
 } from "../types/tunnel";
 
 /**
@@ -82,9 +83,23 @@ export const tunnelService = {
   },
 
   /**
-   * Get tunnel status
+   * Get tunnel status with its restart count and last failure
    */
-  async getTunnelStatus(id: string): Promise<TunnelStatus> {
+  async getTunnelStatus(id: string): Promise<TunnelStatusInfo> {
     return await api.callRaw("get_tunnel_status", id);
   },
+
+  /**
+   * Get traffic metrics and active client connections of a running tunnel
+   */
+  async getTunnelMetrics(id: string): Promise<TunnelMetrics> {
+    return await api.callRaw("get_tunnel_metrics", id);
+  },
+
+  /**
+   * Close one client connection of a running tunnel
+   */
+  async closeTunnelConnection(id: string, connectionId: string): Promise<void> {
+    return await api.callRaw("close_tunnel_connection", id, connectionId);
+  },
 };
@@ -22,7 +22,7 @@ import * as recordingService from "../services/recording";
 import { api } from "../services/api";
 import { writeToTerminal, getTerminalInfo } from "../services/terminal";
 import { useWorkspaceStore } from "./workspace";
-import type { SessionRecording } from "../types/recording";
+import type { RecordingOptions, SessionRecording } from "../types/recording";
 import { handleError, type ErrorContext } from "../utils/errorHandler";
 import { message } from "../utils/message";
 
@@ -60,6 +60,7 @@ export const useRecordingStore = defineStore("recording", () => {
    * @param name - Optional session name
    * @param width - Terminal width
    * @param height - Terminal height
+   * @param options - Format version and input recording
    * @returns Recording ID
    */
   async function startRecording(
@@ -67,6 +68,7 @@ export const useRecordingStore = defineStore("recording", () => {
     name?: string,
     width?: number,
     height?: number,
+    options?: RecordingOptions,
   ) {
     const context: ErrorContext = {
       operation: "Start Recording",
@@ -84,6 +86,7 @@ export const useRecordingStore = defineStore("recording", () => {
         name,
         width,
         height,
+        options,
       );
       activeRecordings.value.set(terminalId, recordingId);
 
@@ -101,6 +104,29 @@ export const useRecordingStore = defineStore("recording", () => {
     }
   }
 
+  /**
+   * Add a marker to the active recording of a terminal
+   */
+  async function addMarker(terminalId: string, label?: string) {
+    const context: ErrorContext = {
+      operation: "Add Recording Marker",
+      context: { terminalId, label },
+    };
+
+    try {
+      const workspaceStore = useWorkspaceStore();
+      const backendTerminalId = await getBackendTerminalId(
+        terminalId,
+        workspaceStore,
+      );
+      await recordingService.addRecordingMarker(backendTerminalId, label);
+    } catch (error) {
+      const errorMessage = handleError(error, context);
+      message.error(errorMessage);
+      throw new Error(errorMessage);
+    }
+  }
+
   /**
    * Stop recording a terminal session with error handling
    */
@@ -201,6 +227,7 @@ export const useRecordingStore = defineStore("recording", () => {
     isRecording,
     loadRecordings,
     startRecording,
+    addMarker,
     stopRecording,
     deleteRecording,
     exportRecording,
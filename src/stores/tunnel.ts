@@ -23,6 +23,7 @@ import type {
   TunnelWithStatus,
   CreateSSHTunnelRequest,
   UpdateSSHTunnelRequest,
+  TunnelMetrics,
 } from "../types/tunnel";
 import { tunnelService } from "../services/tunnel";
 import { api } from "../services/api";
@@ -36,10 +37,15 @@ import { message } from "../utils/message";
 export const useTunnelStore = defineStore("tunnel", () => {
   const tunnels = ref<TunnelWithStatus[]>([]);
   const isLoading = ref(false);
+  const metrics = ref<Record<string, TunnelMetrics>>({});
 
   const activeTunnels = computed(() =>
     tunnels.value.filter(
-      (t) => t?.id && (t.status === "running" || t.status === "starting"),
+      (t) =>
+        t?.id &&
+        (t.status === "running" ||
+          t.status === "starting" ||
+          t.status === "reconnecting"),
     ),
   );
 
@@ -302,6 +308,28 @@ export const useTunnelStore = defineStore("tunnel", () => {
     tunnels.value = tunnels.value.filter((t) => t?.id !== id);
   };
 
+  const setMetrics = (list: TunnelMetrics[]) => {
+    metrics.value = Object.fromEntries(list.map((m) => [m.tunnelId, m]));
+  };
+
+  const closeConnection = async (
+    tunnelId: string,
+    connectionId: string,
+  ): Promise<void> => {
+    try {
+      await tunnelService.closeTunnelConnection(tunnelId, connectionId);
+      metrics.value[tunnelId] =
+        await tunnelService.getTunnelMetrics(tunnelId);
+    } catch (err) {
+      const errorMessage = handleError(err, {
+        operation: "Close Tunnel Connection",
+        context: { tunnelId, connectionId },
+      });
+      message.error(errorMessage);
+      throw err;
+    }
+  };
+
   let unsubscribeStatusRealtime: (() => void) | null = null;
   let unsubscribeCrudRealtime: (() => void) | null = null;
 
@@ -318,10 +346,14 @@ export const useTunnelStore = defineStore("tunnel", () => {
         "tunnel_status_changed",
         (t) => upsertTunnel(t),
       );
+      const u4 = await api.listen<TunnelMetrics[]>("tunnel_metrics", (list) =>
+        setMetrics(list),
+      );
       unsubscribeStatusRealtime = () => {
         u1();
         u2();
         u3();
+        u4();
       };
     } catch (e) {
       console.error("Failed to subscribe tunnel status realtime:", e);
@@ -376,6 +408,7 @@ export const useTunnelStore = defineStore("tunnel", () => {
   return {
     tunnels,
     isLoading,
+    metrics,
 
     activeTunnels,
     stoppedTunnels,
@@ -394,6 +427,7 @@ export const useTunnelStore = defineStore("tunnel", () => {
     upsertTunnel,
     setTunnelStatus,
     removeTunnel,
+    closeConnection,
     startRealtimeStatus,
     stopRealtimeStatus,
     startRealtimeCrud,
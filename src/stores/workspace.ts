@@ -30,6 +30,7 @@ import {
   createLocalTerminal,
   createSSHTerminal,
   createSSHConfigTerminal,
+  attachPersistentSession,
   closeTerminal,
   getUserHostname,
   listenToTerminalTitleChanged,
@@ -37,6 +38,7 @@ import {
   resizeTerminal,
   listenToTerminalOutput,
   listenToTerminalLatency,
+  listenToTerminalStateChanged,
 } from "../services/terminal";
 import { invoke } from "@tauri-apps/api/core";
 import { api } from "../services/api";
@@ -48,10 +50,12 @@ import type {
   TerminalTitleChanged,
   TerminalExited,
   TerminalLatency,
+  TerminalStateChanged,
   PanelLayout,
   TerminalInstance,
   Panel,
   Tab,
+  PersistentSessionInfo,
 } from "../types/panel";
 
 /**
@@ -99,7 +103,8 @@ export const useWorkspaceStore = defineStore("workspace", () => {
   let unlistenTitleChanges: (() => void) | null = null;
   let unlistenTerminalExits: (() => void) | null = null;
   let unlistenSSHConnected: (() => void) | null = null;
-  let unlistenTerminalLatency: (() => void) | null = null; /**
+  let unlistenTerminalLatency: (() => void) | null = null;
+  let unlistenTerminalState: (() => void) | null = null; /**
    * Find a panel in the layout tree by ID
    * @param layout - The layout to search in
    * @param panelId - The panel ID to find
@@ -381,6 +386,78 @@ export const useWorkspaceStore = defineStore("workspace", () => {
     tabCounter++;
   };
 
+  /**
+   * Add a local terminal tab whose shell runs in the session daemon, so it survives
+   * app restarts
+   * @param panelId - The panel ID to add the tab to
+   */
+  const addPersistentTab = async (panelId: string): Promise<void> => {
+    const panel = findPanelInLayout(panelLayout.value, panelId);
+    if (!panel) return;
+
+    const newTabId = tabCounter.toString();
+
+    const newTab: Tab = {
+      id: newTabId,
+      title: await getUserHostname(),
+    };
+
+    const newTerminal: TerminalInstance = {
+      id: newTabId,
+      ready: false,
+      shouldFocusOnReady: true,
+      persistent: true,
+    };
+
+    panel.tabs.push(newTab);
+    panel.activeTabId = newTabId;
+
+    terminals.value.push(newTerminal);
+    focusedTerminalId.value = newTabId;
+
+    viewState.setActiveView("workspace");
+
+    tabCounter++;
+  };
+
+  /**
+   * Add a tab reattached to a shell running in the session daemon
+   * @param panelId - The panel ID to add the tab to
+   * @param session - The daemon session to attach to
+   */
+  const attachPersistentTab = (
+    panelId: string,
+    session: PersistentSessionInfo,
+  ): void => {
+    const panel = findPanelInLayout(panelLayout.value, panelId);
+    if (!panel) return;
+
+    const newTabId = tabCounter.toString();
+
+    const newTab: Tab = {
+      id: newTabId,
+      title: session.shell.split("/").pop() || session.shell,
+    };
+
+    const newTerminal: TerminalInstance = {
+      id: newTabId,
+      ready: false,
+      shouldFocusOnReady: true,
+      persistent: true,
+      persistentSessionId: session.sessionId,
+    };
+
+    panel.tabs.push(newTab);
+    panel.activeTabId = newTabId;
+
+    terminals.value.push(newTerminal);
+    focusedTerminalId.value = newTabId;
+
+    viewState.setActiveView("workspace");
+
+    tabCounter++;
+  };
+
   /**
    * Add a new SSH Config tab from ~/.ssh/config host
    * @param panelId - The panel ID to add the tab to
@@ -1323,8 +1400,15 @@ export const useWorkspaceStore = defineStore("workspace", () => {
           command: terminal.command,
         },
       });
+    } else if (terminal.persistentSessionId) {
+      return attachPersistentSession(terminal.persistentSessionId, title);
     } else {
-      return createLocalTerminal(terminal.shell, terminal.workingDir, title);
+      return createLocalTerminal(
+        terminal.shell,
+        terminal.workingDir,
+        title,
+        terminal.persistent,
+      );
     }
   };
 
@@ -1461,13 +1545,16 @@ export const useWorkspaceStore = defineStore("workspace", () => {
 
       if (
         exitEvent.reason === "user-closed" ||
+        exitEvent.reason === "remote-closed" ||
         terminal?.disconnectReason === "user-closed"
       ) {
         closeTab(result.panel.id, result.tab.id);
       } else {
         const reason = exitEvent.reason || "connection-lost";
         if (terminal) {
-          terminal.disconnectReason = reason as any;
+          terminal.disconnectReason = reason;
+          terminal.reconnectAttempt = undefined;
+          terminal.reconnectMaxAttempts = undefined;
           terminal.isSSHConnecting = false;
           terminal.isConnected = false;
           terminal.backendTerminalId = undefined;
@@ -1490,6 +1577,26 @@ export const useWorkspaceStore = defineStore("workspace", () => {
     }
   };
 
+  const handleTerminalStateChanged = (stateEvent: TerminalStateChanged) => {
+    const terminal = terminals.value.find(
+      (t) => t.backendTerminalId === stateEvent.terminalId,
+    );
+    if (!terminal) {
+      return;
+    }
+
+    const { state } = stateEvent;
+    if (typeof state === "object" && "Reconnecting" in state) {
+      terminal.isConnected = false;
+      terminal.reconnectAttempt = state.Reconnecting.attempt;
+      terminal.reconnectMaxAttempts = state.Reconnecting.maxAttempts;
+    } else if (state === "Connected") {
+      terminal.isConnected = true;
+      terminal.reconnectAttempt = undefined;
+      terminal.reconnectMaxAttempts = undefined;
+    }
+  };
+
   const handleSSHConnected = (data: { terminalId: string }) => {
     let terminal = terminals.value.find(
       (t) => t.backendTerminalId === data.terminalId,
@@ -1526,6 +1633,10 @@ export const useWorkspaceStore = defineStore("workspace", () => {
         handleTerminalLatency,
       );
 
+      unlistenTerminalState = await listenToTerminalStateChanged(
+        handleTerminalStateChanged,
+      );
+
       try {
         unlistenSSHConnected = await api.listen<{ terminalId: string }>(
           "ssh-connected",
@@ -1568,6 +1679,10 @@ export const useWorkspaceStore = defineStore("workspace", () => {
       unlistenTerminalLatency();
       unlistenTerminalLatency = null;
     }
+    if (unlistenTerminalState) {
+      unlistenTerminalState();
+      unlistenTerminalState = null;
+    }
   };
 
   return {
@@ -1581,6 +1696,8 @@ export const useWorkspaceStore = defineStore("workspace", () => {
     selectTab,
     addTab,
     addSSHTab,
+    addPersistentTab,
+    attachPersistentTab,
     addSSHConfigTab,
     closeTab,
     splitVertical,
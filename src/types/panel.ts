@@ -31,11 +31,7 @@ export interface TerminalInstance {
   backendTerminalId?: string;
   shouldFocusOnReady?: boolean;
   isClosing?: boolean;
-  disconnectReason?:
-    | "user-closed"
-    | "connection-lost"
-    | "server-disconnect"
-    | "connection-error";
+  disconnectReason?: TerminalExitReason;
   canReconnect?: boolean;
   sshProfileId?: string; // SSH Config
   sshConfigHost?: string;
@@ -52,6 +48,13 @@ export interface TerminalInstance {
   errorMessage?: string;
   hasError?: boolean;
   latency?: number;
+  reconnectAttempt?: number;
+  reconnectMaxAttempts?: number;
+
+  /** Local shell owned by the session daemon */
+  persistent?: boolean;
+  /** Daemon session to reattach to instead of spawning a shell */
+  persistentSessionId?: string;
 }
 
 export type TerminalType = "Local" | "SSH";
@@ -59,6 +62,7 @@ export type TerminalType = "Local" | "SSH";
 export type TerminalState =
   | "Connecting"
   | "Connected"
+  | { Reconnecting: { attempt: number; maxAttempts: number } }
   | "Disconnected"
   | { Error: string };
 
@@ -66,6 +70,8 @@ export interface LocalConfig {
   shell?: string;
   workingDir?: string;
   envVars?: Record<string, string>;
+  /** Run the shell in the session daemon so it survives app restarts (unix only) */
+  persistent?: boolean;
 }
 
 export interface SSHConfig {
@@ -125,10 +131,89 @@ export interface TerminalTitleChanged {
 export interface TerminalExited {
   terminalId: string;
   exitCode?: number;
-  reason?: "user-closed" | "connection-lost" | "error";
+  reason?: TerminalExitReason;
+}
+
+export type TerminalExitReason =
+  | "user-closed"
+  | "remote-closed"
+  | "network-lost"
+  | "server-disconnect"
+  | "connection-lost"
+  | "error";
+
+export interface TerminalStateChanged {
+  terminalId: string;
+  state: TerminalState;
 }
 
 export interface TerminalLatency {
   terminalId: string;
   latencyMs: number;
 }
+
+export interface BroadcastMember {
+  terminalId: string;
+  /** Paused members neither mirror their input nor receive mirrored input */
+  paused: boolean;
+}
+
+export interface BroadcastGroup {
+  name: string;
+  members: BroadcastMember[];
+}
+
+export interface BroadcastWriteFailure {
+  terminalId: string;
+  error: string;
+}
+
+export interface PersistentSessionInfo {
+  sessionId: string;
+  shell: string;
+  workingDir?: string;
+  pid?: number;
+  createdAt: string;
+  attachedClients: number;
+}
+
+export interface ScrollbackConfig {
+  maxLines: number;
+  maxBytes: number;
+}
+
+export interface ScrollbackStats {
+  terminalId: string;
+  /** Number of the oldest line still kept, lines are numbered from the session start */
+  firstLine: number;
+  lineCount: number;
+  bytes: number;
+}
+
+export interface SearchScrollbackRequest {
+  terminalId: string;
+  pattern: string;
+  caseInsensitive?: boolean;
+  contextLines?: number;
+  maxResults?: number;
+}
+
+export interface ScrollbackMatch {
+  lineNumber: number;
+  line: string;
+  before: string[];
+  after: string[];
+}
+
+export interface DumpScrollbackRequest {
+  terminalId: string;
+  path: string;
+  startLine?: number;
+  endLine?: number;
+}
+
+export interface DumpScrollbackResult {
+  path: string;
+  lineCount: number;
+  bytes: number;
+}
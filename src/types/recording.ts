@@ -21,6 +21,19 @@ export interface RecordingMetadata {
   shell?: string;
 }
 
+export interface RecordingOptions {
+  /** Asciicast format version, defaults to 2 */
+  version?: 2 | 3;
+  /** Record typed input, masked at password prompts */
+  recordInput?: boolean;
+}
+
+export type RecordingEdit =
+  | { type: "capIdle"; maxIdleSecs: number }
+  | { type: "speed"; factor: number }
+  | { type: "cut"; startSecs: number; endSecs: number }
+  | { type: "redact"; patterns: string[]; replacement?: string };
+
 export interface AsciicastHeader {
   version: number;
   width: number;
@@ -1,3 +1,5 @@
+import type { SessionLogConfig } from "./terminalProfile";
+
 /**
  * Base model interface from backend
  */
@@ -20,9 +22,18 @@ export interface SSHConfigHost {
   port: number;
   user: string | null;
   identityFile: string | null;
+  identityFiles: string[];
+  identitiesOnly: boolean | null;
   proxyJump: string | null;
   proxyCommand: string | null;
   forwardAgent: boolean | null;
+  forwardX11: boolean | null;
+  serverAliveInterval: number | null;
+  serverAliveCountMax: number | null;
+  connectTimeout: number | null;
+  compression: boolean | null;
+  /** Effective settings as printed by `ssh -G`, keyed by lowercase keyword */
+  options: Record<string, string[]>;
   otherOptions: string | null;
 }
 
@@ -32,7 +43,7 @@ export interface SSHConfigHost {
  * @returns true if password is required (no identity file)
  */
 export function requiresPassword(host: SSHConfigHost): boolean {
-  return !host.identityFile;
+  return host.identityFiles.length === 0;
 }
 
 /**
@@ -70,6 +81,9 @@ export type AuthMethod =
   | "PrivateKeyWithPassphrase"
   | "KeyReference"
   | "Certificate"
+  | "Agent"
+  | "KeyboardInteractive"
+  | "MultiFactor"
   | "Kerberos"
   | "PKCS11";
 
@@ -82,10 +96,63 @@ export type KeyType = "RSA" | "Ed25519" | "ECDSA" | "DSA";
  * Certificate validity information
  */
 export interface CertificateValidity {
+  /** RFC 3339 timestamp, or "always" */
   validFrom: string;
+  /** RFC 3339 timestamp, or "forever" */
   validTo: string;
   serial?: string;
   caFingerprint?: string;
+  keyId?: string;
+  principals?: string[];
+}
+
+/**
+ * Payload of the "ssh-host-key-verification" event, sent when a server presents
+ * a host key that is not trusted yet
+ */
+export interface HostKeyVerificationRequest {
+  requestId: string;
+  host: string;
+  port: number;
+  jumpChain?: string;
+  keyType: string;
+  fingerprint: string;
+  /** Key types already trusted for this endpoint */
+  knownKeyTypes: string[];
+}
+
+/**
+ * Single prompt of a keyboard-interactive round
+ */
+export interface KeyboardInteractivePrompt {
+  prompt: string;
+  /** Whether the answer may be shown while typing (false for passwords and OTP codes) */
+  echo: boolean;
+}
+
+/**
+ * Payload of the "ssh-keyboard-interactive" event, one prompt round of the server
+ */
+export interface KeyboardInteractiveRequest {
+  requestId: string;
+  host: string;
+  port: number;
+  username: string;
+  name: string;
+  instructions: string;
+  prompts: KeyboardInteractivePrompt[];
+}
+
+/**
+ * Payload of the "ssh-certificate-expiry" event
+ */
+export interface CertificateExpiryWarning {
+  profileId: string;
+  profileName: string;
+  host: string;
+  validTo: string;
+  expired: boolean;
+  expiresInSeconds: number;
 }
 
 /**
@@ -111,6 +178,9 @@ export type AuthData =
         validityPeriod?: CertificateValidity;
       };
     }
+  | { Agent: Record<string, never> }
+  | { KeyboardInteractive: Record<string, never> }
+  | { MultiFactor: { factors: AuthData[] } }
   | { Kerberos: { realm: string; principal: string } }
   | {
       PKCS11: {
@@ -144,6 +214,9 @@ export interface SSHProfile extends BaseModel {
   timeout?: number;
   keepAlive: boolean;
   compression: boolean;
+  forwardAgent?: boolean;
+  forwardX11?: boolean;
+  sessionLog?: SessionLogConfig;
   proxy?: ProxyConfig;
   jumpHosts?: JumpHostConfig[];
   color?: string;
@@ -203,6 +276,9 @@ export interface CreateSSHProfileRequest {
   timeout?: number;
   keepAlive?: boolean;
   compression?: boolean;
+  forwardAgent?: boolean;
+  forwardX11?: boolean;
+  sessionLog?: SessionLogConfig;
   proxy?: ProxyConfig;
   jumpHosts?: JumpHostConfig[];
   color?: string;
@@ -227,6 +303,9 @@ export interface UpdateSSHProfileRequest {
   timeout?: number | null;
   keepAlive?: boolean;
   compression?: boolean;
+  forwardAgent?: boolean;
+  forwardX11?: boolean;
+  sessionLog?: SessionLogConfig | null;
   proxy?: ProxyConfig | null;
   jumpHosts?: JumpHostConfig[] | null;
   color?: string | null;
@@ -258,6 +337,40 @@ export interface CreateSSHKeyRequest {
   description?: string;
 }
 
+/**
+ * Generate SSH Key Request - matches backend GenerateSSHKeyRequest
+ * bits is the ECDSA curve (256, 384, 521) or RSA size (3072, 4096)
+ */
+export interface GenerateSSHKeyRequest {
+  name: string;
+  keyType: Exclude<KeyType, "DSA">;
+  bits?: number;
+  passphrase?: string;
+  comment?: string;
+  description?: string;
+  /** Also write the keypair to this path and `<path>.pub` */
+  exportPath?: string;
+}
+
+/**
+ * Deploy Public Key Request - matches backend DeployPublicKeyRequest
+ */
+export interface DeployPublicKeyRequest {
+  profileId: string;
+  keyId: string;
+  /** Switch the profile to the key once logging in with it works */
+  switchProfileAuth?: boolean;
+}
+
+/**
+ * Deploy Public Key Result - matches backend DeployPublicKeyResult
+ */
+export interface DeployPublicKeyResult {
+  authorizedKeysPath: string;
+  alreadyPresent: boolean;
+  profileUpdated: boolean;
+}
+
 /**
  * Update SSH Key Request - matches backend UpdateSSHKeyRequest
  */
@@ -268,3 +381,66 @@ export interface UpdateSSHKeyRequest {
   publicKey?: string | null;
   passphrase?: string | null;
 }
+
+/**
+ * Multi-Host Exec Request - matches backend MultiHostExecRequest
+ */
+export interface MultiHostExecRequest {
+  /** Id used in the events of the run, generated when omitted */
+  execId?: string;
+  profileIds?: string[];
+  /** Run on every profile of the group as well */
+  groupId?: string;
+  command: string;
+  concurrency?: number;
+  timeoutSecs?: number;
+}
+
+export type HostExecStatus = "Completed" | "TimedOut" | "Failed";
+
+/**
+ * Outcome of a command on one host - matches backend HostExecResult
+ */
+export interface HostExecResult {
+  profileId: string;
+  profileName: string;
+  host: string;
+  status: HostExecStatus;
+  exitStatus: number | null;
+  stdout: string;
+  stderr: string;
+  truncated: boolean;
+  error: string | null;
+  durationMs: number;
+}
+
+/**
+ * Multi-Host Exec Result - matches backend MultiHostExecResult
+ */
+export interface MultiHostExecResult {
+  execId: string;
+  command: string;
+  startedAt: string;
+  finishedAt: string;
+  hosts: HostExecResult[];
+}
+
+/**
+ * Partial output of a host (multi-host-exec-output event)
+ */
+export interface HostExecOutput {
+  execId: string;
+  profileId: string;
+  stream: "stdout" | "stderr";
+  data: string;
+}
+
+/**
+ * A host finished (multi-host-exec-host-finished event)
+ */
+export interface HostExecFinished {
+  execId: string;
+  result: HostExecResult;
+}
+
+export type ExecExportFormat = "json" | "csv";
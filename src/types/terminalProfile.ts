@@ -1,3 +1,21 @@
+export type LogRotation =
+  | { type: "none" }
+  | { type: "daily" }
+  | { type: "size"; maxBytes: number };
+
+/**
+ * Plain-text session logging of an SSH or terminal profile
+ */
+export interface SessionLogConfig {
+  enabled: boolean;
+  /** Supports {profile}, {date} and {terminal_id}; relative paths go to the app's logs directory */
+  pathTemplate: string;
+  /** Write the raw output stream instead of ANSI-stripped text */
+  raw?: boolean;
+  timestamps?: boolean;
+  rotation?: LogRotation;
+}
+
 export interface TerminalProfile {
   id: string;
   name: string;
@@ -8,6 +26,7 @@ export interface TerminalProfile {
   color?: string;
   command?: string;
   isDefault?: boolean;
+  sessionLog?: SessionLogConfig;
 }
 
 export interface CreateTerminalProfileRequest {
@@ -18,6 +37,7 @@ export interface CreateTerminalProfileRequest {
   icon?: string;
   color?: string;
   command?: string;
+  sessionLog?: SessionLogConfig;
 }
 
 export interface UpdateTerminalProfileRequest {
@@ -28,4 +48,5 @@ export interface UpdateTerminalProfileRequest {
   icon?: string;
   color?: string;
   command?: string;
+  sessionLog?: SessionLogConfig;
 }
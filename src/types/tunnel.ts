@@ -1,5 +1,64 @@
-export type TunnelType = "Local" | "Remote" | "Dynamic";
-export type TunnelStatus = "stopped" | "starting" | "running" | "error";
+export type TunnelType = "Local" | "Remote" | "Dynamic" | "HttpProxy";
+export type TunnelStatus =
+  | "stopped"
+  | "starting"
+  | "running"
+  | "reconnecting"
+  | "error";
+
+export type RestartMode = "never" | "onFailure" | "always";
+
+export interface TunnelRestartPolicy {
+  mode: RestartMode;
+  /** Restarts in a row before giving up, unlimited when unset */
+  maxRestarts?: number;
+  initialDelayMs: number;
+  maxDelayMs: number;
+}
+
+export type TunnelHealthCheck =
+  | { type: "tcpConnect" }
+  | { type: "exec"; command: string };
+
+export interface TunnelHealthProbe {
+  check: TunnelHealthCheck;
+  intervalSecs: number;
+  timeoutSecs: number;
+  failureThreshold: number;
+}
+
+export interface TunnelFailure {
+  reason: string;
+  failedAt: string;
+}
+
+export interface TunnelStatusInfo {
+  status: TunnelStatus;
+  errorMessage?: string;
+  restartCount: number;
+  lastFailure?: TunnelFailure;
+}
+
+export interface TunnelConnectionInfo {
+  id: string;
+  peerAddress: string;
+  target?: string;
+  startedAt: string;
+  bytesIn: number;
+  bytesOut: number;
+}
+
+/** Live traffic of a running tunnel, emitted as the "tunnel_metrics" event */
+export interface TunnelMetrics {
+  tunnelId: string;
+  activeConnections: number;
+  totalConnections: number;
+  bytesIn: number;
+  bytesOut: number;
+  bytesInPerSec: number;
+  bytesOutPerSec: number;
+  connections: TunnelConnectionInfo[];
+}
 
 export interface BaseModel {
   id: string;
@@ -10,6 +69,22 @@ export interface BaseModel {
   syncStatus: "synced" | "pending" | "conflict";
 }
 
+/** SOCKS5 username and password, SOCKS4 clients are refused while set */
+export interface TunnelSocksAuth {
+  username: string;
+  password: string;
+}
+
+/**
+ * SOCKS destinations: host names, "*.domain" wildcards, IP addresses, CIDRs
+ * or "*". An empty allow list allows everything not denied. Host names are
+ * matched against addresses and CIDRs by what they resolve to locally.
+ */
+export interface TunnelAccessControl {
+  allow: string[];
+  deny: string[];
+}
+
 export interface SSHTunnel extends BaseModel {
   name: string;
   description?: string;
@@ -19,12 +94,20 @@ export interface SSHTunnel extends BaseModel {
   localPort: number;
   remoteHost?: string;
   remotePort?: number;
+  localSocketPath?: string;
+  remoteSocketPath?: string;
   autoStart: boolean;
+  restartPolicy: TunnelRestartPolicy;
+  healthProbe?: TunnelHealthProbe;
+  socksAuth?: TunnelSocksAuth;
+  accessControl: TunnelAccessControl;
 }
 
 export interface TunnelWithStatus extends SSHTunnel {
   status: TunnelStatus;
   errorMessage?: string;
+  restartCount?: number;
+  lastFailure?: TunnelFailure;
 }
 
 export interface CreateSSHTunnelRequest {
@@ -36,7 +119,13 @@ export interface CreateSSHTunnelRequest {
   localPort: number;
   remoteHost?: string;
   remotePort?: number;
+  localSocketPath?: string | null;
+  remoteSocketPath?: string | null;
   autoStart?: boolean;
+  restartPolicy?: TunnelRestartPolicy;
+  healthProbe?: TunnelHealthProbe | null;
+  socksAuth?: TunnelSocksAuth | null;
+  accessControl?: TunnelAccessControl;
 }
 
 export interface UpdateSSHTunnelRequest {
@@ -48,5 +137,11 @@ export interface UpdateSSHTunnelRequest {
   localPort?: number;
   remoteHost?: string;
   remotePort?: number;
+  localSocketPath?: string | null;
+  remoteSocketPath?: string | null;
   autoStart?: boolean;
+  restartPolicy?: TunnelRestartPolicy;
+  healthProbe?: TunnelHealthProbe | null;
+  socksAuth?: TunnelSocksAuth | null;
+  accessControl?: TunnelAccessControl;
 }